@@ -33,6 +33,12 @@ crate::enum_try_from! {
 
         /// Get memory address of base asset ID
         BaseAssetId = 0x06,
+
+        /// Get the number of set state slots of the current contract
+        StateSlotCount = 0x07,
+
+        /// Get the total size in bytes of the state slots of the current contract
+        StateByteSize = 0x08,
     },
     Immediate18
 }
@@ -270,6 +276,8 @@ fn encode_gm_args() {
         GMArgs::GetChainId,
         GMArgs::TxStart,
         GMArgs::BaseAssetId,
+        GMArgs::StateSlotCount,
+        GMArgs::StateByteSize,
     ];
 
     args.into_iter().for_each(|a| {
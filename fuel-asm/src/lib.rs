@@ -26,6 +26,7 @@ pub mod macros;
 pub mod op;
 mod pack;
 mod panic_reason;
+pub mod text;
 mod unpack;
 
 #[cfg(test)]
@@ -1178,6 +1178,24 @@ macro_rules! impl_instructions {
         }
 
         impl Opcode {
+            /// The uppercase assembly mnemonic of the opcode, e.g. `"ADDI"`.
+            pub const fn mnemonic(self) -> &'static str {
+                match self {
+                    $(
+                        Self::$Op => stringify!($Op),
+                    )*
+                }
+            }
+
+            /// The kinds of the operands taken by the opcode, in encoding order.
+            pub const fn operand_kinds(self) -> &'static [text::OperandKind] {
+                match self {
+                    $(
+                        Self::$Op => &[$(text::OperandKind::$field),*],
+                    )*
+                }
+            }
+
             /// Construct the instruction from all possible raw fields, ignoring inapplicable ones.
             #[cfg(test)]
             pub fn test_construct(self, ra: RegId, rb: RegId, rc: RegId, rd: RegId, imm: u32) -> Instruction {
@@ -0,0 +1,751 @@
+//! Textual assembly representation of instructions.
+//!
+//! Every [`Instruction`] can be printed as a single line of assembly and parsed back.
+//! Reserved registers are printed using their aliases (`$pc`, `$sp`, `$hp`, ...), while
+//! general purpose registers are printed as `r16`, `r17` and so on.
+//!
+//! ```rust
+//! use fuel_asm::{
+//!     op,
+//!     Instruction,
+//!     RegId,
+//! };
+//!
+//! let instruction: Instruction = "addi r16 $hp 12".parse().unwrap();
+//! assert_eq!(instruction, op::addi(0x10, RegId::HP, 12));
+//! assert_eq!(instruction.to_string(), "addi r16 $hp 12");
+//! ```
+//!
+//! With the `alloc` feature, whole programs can be assembled with [`assemble`] and
+//! disassembled with [`disassemble`]. Programs may use labels as jump targets, and
+//! words that don't decode into an instruction are represented with the `.word`
+//! directive, so that disassembling and assembling again always yields the original
+//! bytes.
+//!
+//! ```rust
+//! use fuel_asm::{
+//!     op,
+//!     text,
+//!     RegId,
+//! };
+//!
+//! let program = text::assemble(
+//!     r#"
+//!     movi r16 3
+//! loop:
+//!     subi r16 r16 1     ; decrement the counter
+//!     jnzb r16 $zero loop
+//!     ret $one
+//!     "#,
+//! )
+//! .unwrap();
+//!
+//! assert_eq!(
+//!     program,
+//!     vec![
+//!         op::movi(0x10, 3),
+//!         op::subi(0x10, 0x10, 1),
+//!         op::jnzb(0x10, RegId::ZERO, 0),
+//!         op::ret(RegId::ONE),
+//!     ]
+//! );
+//!
+//! let bytes: Vec<u8> = program.into_iter().collect();
+//! let source = text::disassemble(bytes.iter().copied());
+//! assert_eq!(text::assemble_bytes(&source).unwrap(), bytes);
+//! ```
+
+use core::{
+    fmt,
+    str::FromStr,
+};
+
+use crate::{
+    Imm06,
+    Imm12,
+    Imm18,
+    Imm24,
+    Instruction,
+    Opcode,
+    RawInstruction,
+    RegId,
+};
+
+#[cfg(feature = "alloc")]
+use alloc::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    string::String,
+    vec::Vec,
+};
+
+/// The kind of an instruction operand, named after the type used to represent it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OperandKind {
+    /// A register.
+    RegId,
+    /// A 6-bit immediate value.
+    Imm06,
+    /// A 12-bit immediate value.
+    Imm12,
+    /// A 18-bit immediate value.
+    Imm18,
+    /// A 24-bit immediate value.
+    Imm24,
+}
+
+impl OperandKind {
+    /// The largest value representable by the operand.
+    pub const fn max(self) -> u32 {
+        match self {
+            Self::RegId | Self::Imm06 => Imm06::MAX.to_u8() as u32,
+            Self::Imm12 => Imm12::MAX.to_u16() as u32,
+            Self::Imm18 => Imm18::MAX.to_u32(),
+            Self::Imm24 => Imm24::MAX.to_u32(),
+        }
+    }
+}
+
+/// The way a jump instruction interprets its immediate target.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JumpKind {
+    /// The target is an instruction index relative to `$is`.
+    Absolute,
+    /// The target is `$pc + (dynamic + fixed + 1)` instructions.
+    Forwards,
+    /// The target is `$pc - (dynamic + fixed + 1)` instructions.
+    Backwards,
+}
+
+/// Returns the kind of the jump performed by the opcode, if any. The immediate target of
+/// every jump is its last operand, and for the relative jumps the dynamic register is the
+/// one preceding it.
+#[cfg(feature = "alloc")]
+const fn jump_kind(opcode: Opcode) -> Option<JumpKind> {
+    match opcode {
+        Opcode::JI | Opcode::JNEI | Opcode::JNZI => Some(JumpKind::Absolute),
+        Opcode::JMPF | Opcode::JNZF | Opcode::JNEF => Some(JumpKind::Forwards),
+        Opcode::JMPB | Opcode::JNZB | Opcode::JNEB => Some(JumpKind::Backwards),
+        _ => None,
+    }
+}
+
+/// Aliases of the reserved registers, indexed by register id.
+const REGISTER_ALIASES: [&str; 16] = [
+    "zero", "one", "of", "pc", "ssp", "sp", "fp", "hp", "err", "ggas", "cgas", "bal",
+    "is", "ret", "retl", "flag",
+];
+
+/// Offsets of the register operands within a raw instruction, in encoding order.
+const REGISTER_SHIFTS: [u32; 4] = [18, 12, 6, 0];
+
+/// The reason a piece of assembly failed to parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// The input doesn't contain an instruction.
+    Empty,
+    /// The mnemonic doesn't name any opcode.
+    UnknownMnemonic,
+    /// The instruction has fewer operands than its opcode requires.
+    MissingOperand,
+    /// The instruction has more operands than its opcode accepts.
+    UnexpectedOperand,
+    /// The operand isn't a valid register.
+    InvalidRegister,
+    /// The operand isn't a valid immediate value.
+    InvalidImmediate,
+    /// The immediate value doesn't fit into the operand.
+    ImmediateOutOfRange,
+    /// The label isn't defined anywhere in the program.
+    UnknownLabel,
+    /// The label is defined more than once.
+    DuplicateLabel,
+    /// The label name isn't a valid identifier.
+    InvalidLabel,
+    /// The label can't be reached by the jump referring to it.
+    LabelOutOfRange,
+    /// The word given to the `.word` directive isn't a valid instruction.
+    InvalidInstruction,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for RegId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match REGISTER_ALIASES.get(usize::from(*self)) {
+            Some(alias) => write!(f, "${alias}"),
+            None => write!(f, "r{}", self.to_u8()),
+        }
+    }
+}
+
+impl FromStr for RegId {
+    type Err = ParseError;
+
+    /// Parses a register alias such as `$sp`, or a numbered register such as `r16` or
+    /// `$r16`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.strip_prefix('$');
+        if let Some(name) = name {
+            if let Some(reg) = REGISTER_ALIASES
+                .iter()
+                .position(|alias| alias.eq_ignore_ascii_case(name))
+                .and_then(|id| u8::try_from(id).ok())
+            {
+                return Ok(RegId::new(reg))
+            }
+        }
+
+        name.unwrap_or(s)
+            .strip_prefix(['r', 'R'])
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|digits| digits.parse().ok())
+            .and_then(RegId::new_checked)
+            .ok_or(ParseError::InvalidRegister)
+    }
+}
+
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.mnemonic()
+            .chars()
+            .try_for_each(|c| fmt::Write::write_char(f, c.to_ascii_lowercase()))
+    }
+}
+
+impl FromStr for Opcode {
+    type Err = ParseError;
+
+    /// Parses a mnemonic, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        (0..=u8::MAX)
+            .filter_map(|byte| Opcode::try_from(byte).ok())
+            .find(|opcode| opcode.mnemonic().eq_ignore_ascii_case(s))
+            .ok_or(ParseError::UnknownMnemonic)
+    }
+}
+
+impl Instruction {
+    /// Raw values of the instruction operands in encoding order, with registers
+    /// represented by their ids.
+    pub fn operands(&self) -> impl Iterator<Item = (OperandKind, u32)> {
+        let raw = RawInstruction::from(*self);
+        self.opcode()
+            .operand_kinds()
+            .iter()
+            .zip(REGISTER_SHIFTS)
+            .map(move |(kind, shift)| {
+                let value = match kind {
+                    OperandKind::RegId => raw.checked_shr(shift).unwrap_or_default(),
+                    _ => raw,
+                };
+                (*kind, value & kind.max())
+            })
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.opcode().fmt(f)?;
+        self.operands().try_for_each(|(kind, value)| match kind {
+            OperandKind::RegId => write!(f, " {}", register(value)),
+            _ => write!(f, " {value}"),
+        })
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = ParseError;
+
+    /// Parses a single line of assembly, e.g. `addi r16 r16 12`. Operands may be
+    /// separated by whitespace or commas. Labels aren't supported, use [`assemble`]
+    /// for programs that need them.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_instruction(tokens(s), |_, _| Err(ParseError::InvalidImmediate))
+    }
+}
+
+/// Converts a raw operand value into a register, masking it to 6 bits.
+fn register(value: u32) -> RegId {
+    let [.., low] = value.to_be_bytes();
+    RegId::new(low)
+}
+
+/// Splits a line of assembly into whitespace or comma separated tokens.
+fn tokens(line: &str) -> impl Iterator<Item = &str> {
+    line.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+}
+
+/// Parses an unsigned integer in decimal, `0x` hexadecimal or `0b` binary notation.
+/// Underscores may be used as digit separators.
+fn parse_integer(s: &str) -> Option<u32> {
+    let (digits, radix) = if let Some(hex) = s.strip_prefix("0x") {
+        (hex, 16)
+    } else if let Some(bin) = s.strip_prefix("0b") {
+        (bin, 2)
+    } else {
+        (s, 10)
+    };
+
+    let mut digits = digits.chars().filter(|c| *c != '_').peekable();
+    digits.peek()?;
+    digits.try_fold(0u32, |value, c| {
+        value.checked_mul(radix)?.checked_add(c.to_digit(radix)?)
+    })
+}
+
+/// Parses an instruction from its tokens. Immediate operands that aren't integers are
+/// passed to `resolve` together with the kind of the operand.
+fn parse_instruction<'a>(
+    mut tokens: impl Iterator<Item = &'a str>,
+    mut resolve: impl FnMut(&'a str, OperandKind) -> Result<u32, ParseError>,
+) -> Result<Instruction, ParseError> {
+    let opcode: Opcode = tokens.next().ok_or(ParseError::Empty)?.parse()?;
+    let mut raw = RawInstruction::from_be_bytes([opcode as u8, 0, 0, 0]);
+
+    for (kind, shift) in opcode.operand_kinds().iter().zip(REGISTER_SHIFTS) {
+        let token = tokens.next().ok_or(ParseError::MissingOperand)?;
+        let value = match kind {
+            OperandKind::RegId => {
+                let reg: RegId = token.parse()?;
+                u32::from(reg.to_u8())
+                    .checked_shl(shift)
+                    .ok_or(ParseError::InvalidRegister)?
+            }
+            _ => {
+                let value = match parse_integer(token) {
+                    Some(value) => value,
+                    None => resolve(token, *kind)?,
+                };
+                if value > kind.max() {
+                    return Err(ParseError::ImmediateOutOfRange)
+                }
+                value
+            }
+        };
+        raw |= value;
+    }
+
+    if tokens.next().is_some() {
+        return Err(ParseError::UnexpectedOperand)
+    }
+
+    Instruction::try_from(raw).map_err(|_| ParseError::InvalidInstruction)
+}
+
+/// An error produced while assembling a program, along with the line it occurred on.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AssembleError {
+    /// The line of the source the error occurred on, starting from 1.
+    pub line: usize,
+    /// The reason of the error.
+    pub error: ParseError,
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AssembleError {}
+
+/// A single line of a program that produces a word.
+#[cfg(feature = "alloc")]
+struct Item<'a> {
+    line: usize,
+    tokens: Vec<&'a str>,
+}
+
+/// Assembles a program into instructions.
+///
+/// Every line contains an optional list of labels, each followed by a colon, and
+/// an optional instruction. Comments start with `;` or `//` and span to the end of the
+/// line. A label refers to the instruction following it, and can be used as the target
+/// of jump instructions. Relative jumps should use `$zero` as their dynamic register
+/// when jumping to a label.
+///
+/// Fails with [`ParseError::InvalidInstruction`] if the program contains a `.word`
+/// directive that isn't a valid instruction, use [`assemble_bytes`] for such programs.
+#[cfg(feature = "alloc")]
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, AssembleError> {
+    assemble_words(source)?
+        .into_iter()
+        .map(|(line, raw)| {
+            Instruction::try_from(raw).map_err(|_| AssembleError {
+                line,
+                error: ParseError::InvalidInstruction,
+            })
+        })
+        .collect()
+}
+
+/// Assembles a program into bytecode. See [`assemble`] for the syntax.
+///
+/// Unlike [`assemble`], the `.word` directive can be used to emit arbitrary data.
+#[cfg(feature = "alloc")]
+pub fn assemble_bytes(source: &str) -> Result<Vec<u8>, AssembleError> {
+    Ok(assemble_words(source)?
+        .into_iter()
+        .flat_map(|(_, raw)| raw.to_be_bytes())
+        .collect())
+}
+
+/// Assembles a program into raw instructions, paired with their source lines.
+#[cfg(feature = "alloc")]
+fn assemble_words(source: &str) -> Result<Vec<(usize, RawInstruction)>, AssembleError> {
+    let mut labels = BTreeMap::new();
+    let mut items = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index.saturating_add(1);
+        let err = |error| AssembleError {
+            line: line_number,
+            error,
+        };
+
+        let code = line.split(';').next().unwrap_or_default();
+        let code = code.split("//").next().unwrap_or_default();
+        let mut tokens = tokens(code).peekable();
+
+        while let Some(label) = tokens.peek().and_then(|t| t.strip_suffix(':')) {
+            if !is_label(label) {
+                return Err(err(ParseError::InvalidLabel))
+            }
+            if labels.insert(label, items.len()).is_some() {
+                return Err(err(ParseError::DuplicateLabel))
+            }
+            tokens.next();
+        }
+
+        let tokens: Vec<_> = tokens.collect();
+        if !tokens.is_empty() {
+            items.push(Item {
+                line: line_number,
+                tokens,
+            });
+        }
+    }
+
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let err = |error| AssembleError {
+                line: item.line,
+                error,
+            };
+
+            if let [".word", word] = item.tokens.as_slice() {
+                let raw = parse_integer(word)
+                    .ok_or_else(|| err(ParseError::InvalidImmediate))?;
+                return Ok((item.line, raw))
+            }
+
+            let jump = item
+                .tokens
+                .first()
+                .and_then(|mnemonic| mnemonic.parse().ok())
+                .and_then(jump_kind);
+
+            let instruction =
+                parse_instruction(item.tokens.iter().copied(), |token, kind| {
+                    if !is_label(token) {
+                        return Err(ParseError::InvalidImmediate)
+                    }
+                    let target = *labels.get(token).ok_or(ParseError::UnknownLabel)?;
+                    let jump = jump.ok_or(ParseError::InvalidImmediate)?;
+                    let offset = match jump {
+                        JumpKind::Absolute => Some(target),
+                        JumpKind::Forwards => {
+                            target.checked_sub(index).and_then(|o| o.checked_sub(1))
+                        }
+                        JumpKind::Backwards => {
+                            index.checked_sub(target).and_then(|o| o.checked_sub(1))
+                        }
+                    };
+                    offset
+                        .and_then(|offset| u32::try_from(offset).ok())
+                        .filter(|offset| *offset <= kind.max())
+                        .ok_or(ParseError::LabelOutOfRange)
+                })
+                .map_err(err)?;
+
+            Ok((item.line, RawInstruction::from(instruction)))
+        })
+        .collect()
+}
+
+/// Checks that the token can be used as a label, i.e. it's an identifier that doesn't
+/// start with a digit.
+#[cfg(feature = "alloc")]
+fn is_label(token: &str) -> bool {
+    let mut chars = token.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '.')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Returns the index of the instruction targeted by a jump located at `index`, if it
+/// can be determined statically.
+#[cfg(feature = "alloc")]
+fn jump_target(index: usize, instruction: &Instruction) -> Option<usize> {
+    let kind = jump_kind(instruction.opcode())?;
+    let operands: Vec<_> = instruction.operands().map(|(_, value)| value).collect();
+    let (fixed, rest) = operands.split_last()?;
+    let fixed = usize::try_from(*fixed).ok()?;
+
+    if kind != JumpKind::Absolute && rest.last() != Some(&u32::from(RegId::ZERO.to_u8()))
+    {
+        return None
+    }
+
+    match kind {
+        JumpKind::Absolute => Some(fixed),
+        JumpKind::Forwards => index.checked_add(fixed)?.checked_add(1),
+        JumpKind::Backwards => index.checked_sub(fixed)?.checked_sub(1),
+    }
+}
+
+/// Disassembles bytecode into a program that can be assembled back with
+/// [`assemble_bytes`].
+///
+/// Jump targets that are known statically and lie within the program are replaced with
+/// labels named after the index of the target instruction, e.g. `L12`. Words that
+/// aren't valid instructions are emitted with the `.word` directive. Trailing bytes that
+/// don't form a whole word are ignored.
+#[cfg(feature = "alloc")]
+pub fn disassemble<I>(bytes: I) -> String
+where
+    I: IntoIterator<Item = u8>,
+{
+    let mut iter = bytes.into_iter();
+    let words: Vec<RawInstruction> = core::iter::from_fn(|| {
+        Some(RawInstruction::from_be_bytes([
+            iter.next()?,
+            iter.next()?,
+            iter.next()?,
+            iter.next()?,
+        ]))
+    })
+    .collect();
+
+    let instructions: Vec<_> = words
+        .iter()
+        .map(|raw| Instruction::try_from(*raw).ok())
+        .collect();
+
+    let targets: BTreeSet<usize> = instructions
+        .iter()
+        .enumerate()
+        .filter_map(|(index, instruction)| jump_target(index, instruction.as_ref()?))
+        .filter(|target| *target <= words.len())
+        .collect();
+
+    let mut output = String::new();
+    for (index, (raw, instruction)) in words.iter().zip(&instructions).enumerate() {
+        write_label(&mut output, &targets, index);
+        match instruction {
+            Some(instruction) => {
+                write_instruction(&mut output, index, instruction, &targets)
+            }
+            None => output.push_str(&alloc::format!("    .word {raw:#010x}\n")),
+        }
+    }
+    write_label(&mut output, &targets, words.len());
+
+    output
+}
+
+#[cfg(feature = "alloc")]
+fn write_label(output: &mut String, targets: &BTreeSet<usize>, index: usize) {
+    if targets.contains(&index) {
+        output.push_str(&alloc::format!("L{index}:\n"));
+    }
+}
+
+#[cfg(feature = "alloc")]
+fn write_instruction(
+    output: &mut String,
+    index: usize,
+    instruction: &Instruction,
+    targets: &BTreeSet<usize>,
+) {
+    use fmt::Write;
+
+    let target = jump_target(index, instruction).filter(|t| targets.contains(t));
+    let operands: Vec<_> = instruction.operands().collect();
+
+    let _ = write!(output, "    {}", instruction.opcode());
+    for (position, (kind, value)) in operands.iter().enumerate() {
+        let is_last = position.saturating_add(1) == operands.len();
+        let _ = match (kind, target) {
+            (_, Some(target)) if is_last => write!(output, " L{target}"),
+            (OperandKind::RegId, _) => write!(output, " {}", register(*value)),
+            _ => write!(output, " {value}"),
+        };
+    }
+    output.push('\n');
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::op;
+    use alloc::{
+        string::ToString,
+        vec,
+    };
+
+    #[test]
+    fn instruction_display_round_trips_for_all_opcodes() {
+        let r = RegId::new(0x2d);
+        for byte in 0..=u8::MAX {
+            let Ok(opcode) = Opcode::try_from(byte) else {
+                continue
+            };
+            for imm in [0, 0x0bfd, 0x02fffd, 0xbffffd] {
+                let instruction = opcode.test_construct(r, r, r, r, imm);
+                let text = instruction.to_string();
+                assert_eq!(text.parse::<Instruction>(), Ok(instruction), "{text}");
+            }
+        }
+    }
+
+    #[test]
+    fn registers_are_printed_with_aliases() {
+        assert_eq!(RegId::PC.to_string(), "$pc");
+        assert_eq!(RegId::FLAG.to_string(), "$flag");
+        assert_eq!(RegId::WRITABLE.to_string(), "r16");
+        assert_eq!(op::move_(0x10, RegId::SP).to_string(), "move r16 $sp");
+    }
+
+    #[test]
+    fn registers_parse_in_all_notations() {
+        assert_eq!("$hp".parse(), Ok(RegId::HP));
+        assert_eq!("$HP".parse(), Ok(RegId::HP));
+        assert_eq!("r7".parse(), Ok(RegId::HP));
+        assert_eq!("$r63".parse(), Ok(RegId::new(63)));
+        assert_eq!("r64".parse::<RegId>(), Err(ParseError::InvalidRegister));
+        assert_eq!("hp".parse::<RegId>(), Err(ParseError::InvalidRegister));
+        assert_eq!("r+1".parse::<RegId>(), Err(ParseError::InvalidRegister));
+    }
+
+    #[test]
+    fn instruction_parse_accepts_commas_and_number_notations() {
+        let expected = op::addi(0x10, 0x11, 0xabc);
+        assert_eq!("ADDI r16, r17, 0xabc".parse(), Ok(expected));
+        assert_eq!("addi r16 r17 2748".parse(), Ok(expected));
+        assert_eq!("addi r16 r17 0b1010_1011_1100".parse(), Ok(expected));
+    }
+
+    #[test]
+    fn instruction_parse_errors() {
+        let cases = [
+            ("", ParseError::Empty),
+            ("foo r16", ParseError::UnknownMnemonic),
+            ("addi r16 r16", ParseError::MissingOperand),
+            ("addi r16 r16 1 1", ParseError::UnexpectedOperand),
+            ("addi 16 r16 1", ParseError::InvalidRegister),
+            ("addi r16 r16 x", ParseError::InvalidImmediate),
+            ("addi r16 r16 0x1000", ParseError::ImmediateOutOfRange),
+            ("addi r16 r16 0x", ParseError::InvalidImmediate),
+        ];
+        for (text, error) in cases {
+            assert_eq!(text.parse::<Instruction>(), Err(error), "{text}");
+        }
+    }
+
+    #[test]
+    fn assemble_resolves_labels() {
+        let program = assemble(
+            "
+            start: ji end
+                   jmpf $zero end   // skips one instruction
+                   noop
+            end:   jnzb r16 $zero start
+                   jnei r16 r17 start
+                   jneb r16 r17 $zero end
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(
+            program,
+            vec![
+                op::ji(3),
+                op::jmpf(RegId::ZERO, 1),
+                op::noop(),
+                op::jnzb(0x10, RegId::ZERO, 2),
+                op::jnei(0x10, 0x11, 0),
+                op::jneb(0x10, 0x11, RegId::ZERO, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn assemble_reports_line_of_error() {
+        let cases = [
+            ("noop\nji nowhere", 2, ParseError::UnknownLabel),
+            ("a:\na: noop", 2, ParseError::DuplicateLabel),
+            ("1a: noop", 1, ParseError::InvalidLabel),
+            ("\n\na: jmpf $zero a", 3, ParseError::LabelOutOfRange),
+            ("a: noop\nmovi r16 a", 2, ParseError::InvalidImmediate),
+            ("noop\n.word 0xffffffff", 2, ParseError::InvalidInstruction),
+        ];
+        for (source, line, error) in cases {
+            assert_eq!(assemble(source), Err(AssembleError { line, error }));
+        }
+    }
+
+    #[test]
+    fn disassemble_round_trips() {
+        let program = [
+            op::movi(0x10, 3),
+            op::subi(0x10, 0x10, 1),
+            op::jnzb(0x10, RegId::ZERO, 0),
+            op::jnzf(0x10, RegId::ZERO, 0),
+            op::jmpf(0x10, 1),
+            op::ji(6),
+            op::jnei(0x10, 0x11, 100),
+            op::ret(RegId::ONE),
+        ];
+        let mut bytes: Vec<u8> = program.into_iter().collect();
+        bytes.extend([0xff; 4]);
+
+        let source = disassemble(bytes.iter().copied());
+        assert_eq!(
+            source,
+            "    movi r16 3
+L1:
+    subi r16 r16 1
+    jnzb r16 $zero L1
+    jnzf r16 $zero L4
+L4:
+    jmpf r16 1
+    ji L6
+L6:
+    jnei r16 r17 100
+    ret $one
+    .word 0xffffffff
+"
+        );
+        assert_eq!(assemble_bytes(&source), Ok(bytes));
+    }
+}
@@ -1,5 +1,6 @@
 mod hash;
 mod merkle_tree;
+mod multiproof;
 mod node;
 mod primitive;
 mod verify;
@@ -15,8 +16,12 @@ pub use merkle_tree::{
     MerkleTree,
     MerkleTreeError,
 };
+pub use multiproof::MultiProof;
 pub use primitive::Primitive;
 pub mod in_memory;
 pub mod root_calculator;
 
-pub use verify::verify;
+pub use verify::{
+    verify,
+    verify_consistency,
+};
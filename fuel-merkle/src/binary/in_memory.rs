@@ -1,6 +1,7 @@
 use crate::{
     binary::{
         self,
+        MultiProof,
         Primitive,
     },
     common::{
@@ -51,6 +52,17 @@ impl MerkleTree {
         self.tree.prove(proof_index).ok()
     }
 
+    pub fn prove_multiple(&self, leaf_indices: &[u64]) -> Option<(Bytes32, MultiProof)> {
+        self.tree.prove_multiple(leaf_indices).ok()
+    }
+
+    pub fn prove_consistency(
+        &self,
+        old_leaves_count: u64,
+    ) -> Option<(Bytes32, ProofSet)> {
+        self.tree.prove_consistency(old_leaves_count).ok()
+    }
+
     pub fn reset(&mut self) {
         self.tree.reset();
     }
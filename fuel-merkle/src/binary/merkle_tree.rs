@@ -2,6 +2,8 @@ use crate::{
     binary::{
         empty_sum,
         in_memory::NodesTable,
+        node_sum,
+        MultiProof,
         Node,
         Primitive,
     },
@@ -36,6 +38,9 @@ pub enum MerkleTreeError<StorageError> {
     #[display(fmt = "proof index {_0} is not valid")]
     InvalidProofIndex(u64),
 
+    #[display(fmt = "old leaves count {_0} is larger than the leaves count")]
+    InvalidOldLeavesCount(u64),
+
     #[display(fmt = "cannot load node with key {_0}; the key is not found in storage")]
     LoadError(u64),
 
@@ -286,6 +291,161 @@ where
         Ok((root, proof_set))
     }
 
+    /// Proves the inclusion of all the leaves at `leaf_indices`, given in any
+    /// order, with a single [`MultiProof`]. Returns the root of the tree with
+    /// the proof, holding the side nodes shared by the paths of the leaves
+    /// only once.
+    pub fn prove_multiple(
+        &self,
+        leaf_indices: &[u64],
+    ) -> Result<(Bytes32, MultiProof), MerkleTreeError<StorageError>> {
+        let mut leaf_indices = leaf_indices.to_vec();
+        leaf_indices.sort_unstable();
+        leaf_indices.dedup();
+        if let Some(index) = leaf_indices.last().filter(|i| **i >= self.leaves_count) {
+            return Err(MerkleTreeError::InvalidProofIndex(*index))
+        }
+
+        let mut proof_set = ProofSet::new();
+        if self.leaves_count > 0 {
+            self.multiproof_side_nodes(
+                0,
+                self.leaves_count,
+                &leaf_indices,
+                &mut proof_set,
+            )?;
+        }
+
+        let proof = MultiProof {
+            leaf_indices,
+            leaves_count: self.leaves_count,
+            proof_set,
+        };
+        Ok((self.root(), proof))
+    }
+
+    /// Pushes to `proof_set` the roots of the largest subtrees of the leaves
+    /// `start..end` without any of the sorted `leaf_indices`, from left to
+    /// right.
+    fn multiproof_side_nodes(
+        &self,
+        start: u64,
+        end: u64,
+        leaf_indices: &[u64],
+        proof_set: &mut ProofSet,
+    ) -> Result<(), MerkleTreeError<StorageError>> {
+        if leaf_indices.is_empty() {
+            proof_set.push(self.subtree_root(start, end)?);
+            return Ok(())
+        }
+
+        #[allow(clippy::arithmetic_side_effects)] // The range contains a leaf
+        let len = end - start;
+        if len == 1 {
+            return Ok(())
+        }
+
+        // The left subtree is the largest balanced subtree of the range.
+        #[allow(clippy::arithmetic_side_effects)] // len > 1
+        let split = start + (1 << (len - 1).ilog2());
+        let (left, right) =
+            leaf_indices.split_at(leaf_indices.partition_point(|i| *i < split));
+        self.multiproof_side_nodes(start, split, left, proof_set)?;
+        self.multiproof_side_nodes(split, end, right, proof_set)
+    }
+
+    /// Proves that the tree made of the first `old_leaves_count` leaves is a
+    /// prefix of this tree, i.e. that this tree was only appended to since it
+    /// had `old_leaves_count` leaves. Returns the root of this tree and the
+    /// consistency proof described in RFC 6962, verified by
+    /// [`verify_consistency`](crate::binary::verify_consistency).
+    ///
+    /// The proof is empty when `old_leaves_count` is zero or equals the leaves
+    /// count.
+    pub fn prove_consistency(
+        &self,
+        old_leaves_count: u64,
+    ) -> Result<(Bytes32, ProofSet), MerkleTreeError<StorageError>> {
+        if old_leaves_count > self.leaves_count {
+            return Err(MerkleTreeError::InvalidOldLeavesCount(old_leaves_count))
+        }
+
+        let mut proof_set = ProofSet::new();
+        if old_leaves_count > 0 {
+            self.consistency_subproof(
+                old_leaves_count,
+                0,
+                self.leaves_count,
+                true,
+                &mut proof_set,
+            )?;
+        }
+
+        Ok((self.root(), proof_set))
+    }
+
+    /// Pushes to `proof_set` the nodes proving that the leaves
+    /// `start..old_end` are a prefix of the leaves `start..end`. The root of
+    /// the old leaves is omitted from the proof when `is_complete`, as it is
+    /// then known to the verifier.
+    fn consistency_subproof(
+        &self,
+        old_end: u64,
+        start: u64,
+        end: u64,
+        is_complete: bool,
+        proof_set: &mut ProofSet,
+    ) -> Result<(), MerkleTreeError<StorageError>> {
+        if old_end == end {
+            if !is_complete {
+                proof_set.push(self.subtree_root(start, end)?);
+            }
+            return Ok(())
+        }
+
+        // The left subtree is the largest balanced subtree of the range.
+        #[allow(clippy::arithmetic_side_effects)] // start < old_end < end
+        let split = start + (1 << (end - start - 1).ilog2());
+        if old_end <= split {
+            self.consistency_subproof(old_end, start, split, is_complete, proof_set)?;
+            proof_set.push(self.subtree_root(split, end)?);
+        } else {
+            self.consistency_subproof(old_end, split, end, false, proof_set)?;
+            proof_set.push(self.subtree_root(start, split)?);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the root of the subtree of the leaves `start..end`, where the
+    /// range is either a balanced subtree or a suffix of the leaves.
+    fn subtree_root(
+        &self,
+        start: u64,
+        end: u64,
+    ) -> Result<Bytes32, MerkleTreeError<StorageError>> {
+        #[allow(clippy::arithmetic_side_effects)] // start < end
+        let len = end - start;
+
+        if len.is_power_of_two() {
+            // Balanced subtrees are persisted, with the root at the in-order
+            // index following the in-order indices of its left half.
+            #[allow(clippy::arithmetic_side_effects)] // end <= leaves_count
+            let key = start * 2 + len - 1;
+            let primitive = StorageInspect::get(&self.storage, &key)?
+                .ok_or(MerkleTreeError::LoadError(key))?
+                .into_owned();
+            return Ok(*Node::from(primitive).hash())
+        }
+
+        #[allow(clippy::arithmetic_side_effects)] // len > 1
+        let split = start + (1 << (len - 1).ilog2());
+        Ok(node_sum(
+            &self.subtree_root(start, split)?,
+            &self.subtree_root(split, end)?,
+        ))
+    }
+
     pub fn reset(&mut self) {
         self.nodes.clear();
     }
@@ -365,6 +525,7 @@ mod test {
             empty_sum,
             leaf_sum,
             node_sum,
+            verify_consistency,
             Node,
             Primitive,
         },
@@ -836,6 +997,109 @@ mod test {
         }
     }
 
+    #[test]
+    fn prove_consistency_returns_the_subtrees_completing_the_old_tree() {
+        let mut storage_map = StorageMap::<TestTable>::new();
+        let mut tree = MerkleTree::new(&mut storage_map);
+
+        let data = &TEST_DATA[0..7]; // 7 leaves
+        for datum in data.iter() {
+            let _ = tree.push(datum);
+        }
+
+        let leaf_0 = leaf_sum(data[0]);
+        let leaf_1 = leaf_sum(data[1]);
+        let leaf_2 = leaf_sum(data[2]);
+        let leaf_3 = leaf_sum(data[3]);
+        let leaf_4 = leaf_sum(data[4]);
+        let leaf_5 = leaf_sum(data[5]);
+        let leaf_6 = leaf_sum(data[6]);
+
+        let node_1 = node_sum(&leaf_0, &leaf_1);
+        let node_5 = node_sum(&leaf_2, &leaf_3);
+        let node_9 = node_sum(&leaf_4, &leaf_5);
+        let node_3 = node_sum(&node_1, &node_5);
+        let node_11 = node_sum(&node_9, &leaf_6);
+        let node_7 = node_sum(&node_3, &node_11);
+
+        {
+            let (root, proof_set) = tree.prove_consistency(3).unwrap();
+            assert_eq!(root, node_7);
+            assert_eq!(proof_set, vec![leaf_2, leaf_3, node_1, node_11]);
+        }
+        {
+            let (root, proof_set) = tree.prove_consistency(4).unwrap();
+            assert_eq!(root, node_7);
+            assert_eq!(proof_set, vec![node_11]);
+        }
+        {
+            let (root, proof_set) = tree.prove_consistency(6).unwrap();
+            assert_eq!(root, node_7);
+            assert_eq!(proof_set, vec![node_9, leaf_6, node_3]);
+        }
+        {
+            let (root, proof_set) = tree.prove_consistency(7).unwrap();
+            assert_eq!(root, node_7);
+            assert!(proof_set.is_empty());
+        }
+    }
+
+    #[test]
+    fn prove_consistency_is_verified_for_every_old_leaves_count() {
+        for leaves_count in 1..=33u64 {
+            let mut storage_map = StorageMap::<TestTable>::new();
+            let mut tree = MerkleTree::new(&mut storage_map);
+            let mut old_roots = vec![tree.root()];
+            for i in 0..leaves_count {
+                let _ = tree.push(&i.to_be_bytes());
+                old_roots.push(tree.root());
+            }
+
+            for (old_leaves_count, old_root) in (0u64..).zip(old_roots.iter()) {
+                let (root, proof_set) = tree.prove_consistency(old_leaves_count).unwrap();
+                assert!(verify_consistency(
+                    old_root,
+                    &root,
+                    &proof_set,
+                    old_leaves_count,
+                    leaves_count
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn prove_consistency_uses_the_nodes_of_a_loaded_tree() {
+        let mut storage_map = StorageMap::<TestTable>::new();
+        let mut tree = MerkleTree::new(&mut storage_map);
+        for datum in TEST_DATA[0..5].iter() {
+            let _ = tree.push(datum);
+        }
+        let old_root = tree.root();
+        for datum in TEST_DATA[5..10].iter() {
+            let _ = tree.push(datum);
+        }
+
+        let tree = MerkleTree::load(&mut storage_map, 9).unwrap();
+        let (root, proof_set) = tree.prove_consistency(5).unwrap();
+
+        assert!(verify_consistency(&old_root, &root, &proof_set, 5, 9));
+    }
+
+    #[test]
+    fn prove_consistency_returns_an_error_for_a_larger_old_leaves_count() {
+        let mut storage_map = StorageMap::<TestTable>::new();
+        let mut tree = MerkleTree::new(&mut storage_map);
+        for datum in TEST_DATA[0..5].iter() {
+            let _ = tree.push(datum);
+        }
+
+        let err = tree
+            .prove_consistency(6)
+            .expect_err("Expected prove_consistency() to return Error; got Ok");
+        assert_eq!(err, MerkleTreeError::InvalidOldLeavesCount(6));
+    }
+
     #[test]
     fn reset_reverts_tree_to_empty_state() {
         let mut storage_map = StorageMap::<TestTable>::new();
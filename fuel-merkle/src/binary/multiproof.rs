@@ -0,0 +1,268 @@
+use crate::{
+    binary::{
+        empty_sum,
+        leaf_sum,
+        node_sum,
+    },
+    common::{
+        Bytes32,
+        ProofSet,
+    },
+};
+
+use alloc::vec::Vec;
+use core::{
+    fmt,
+    fmt::Debug,
+    slice,
+};
+
+/// The inclusion proof of several leaves of a
+/// [`MerkleTree`](crate::binary::MerkleTree), produced by
+/// [`MerkleTree::prove_multiple`](crate::binary::MerkleTree::prove_multiple).
+///
+/// The paths of the leaves share their side nodes: the proof set only holds the
+/// roots of the largest subtrees without any proven leaf, from left to right.
+#[derive(Clone, Eq, PartialEq)]
+pub struct MultiProof {
+    /// The indices of the proven leaves, in increasing order.
+    pub leaf_indices: Vec<u64>,
+    /// The number of leaves of the tree. It is not bound by the root, and must be
+    /// authenticated along with it.
+    pub leaves_count: u64,
+    /// The roots of the subtrees that contain none of the proven leaves, in the
+    /// order of a depth-first, left-to-right traversal of the tree. A subtree is
+    /// only included when its sibling contains a proven leaf.
+    pub proof_set: ProofSet,
+}
+
+impl MultiProof {
+    /// Verifies that the `leaves` are the data of the leaves at the
+    /// `leaf_indices` of the tree with the `root`.
+    pub fn verify<T: AsRef<[u8]>>(&self, root: &Bytes32, leaves: &[T]) -> bool {
+        let Self {
+            leaf_indices,
+            leaves_count,
+            proof_set,
+        } = self;
+
+        if leaf_indices.len() != leaves.len() {
+            return false
+        }
+        let is_sorted = leaf_indices.windows(2).all(|pair| pair[0] < pair[1]);
+        if !is_sorted || leaf_indices.last().is_some_and(|last| last >= leaves_count) {
+            return false
+        }
+
+        if *leaves_count == 0 {
+            return proof_set.is_empty() && root == empty_sum()
+        }
+
+        let leaves = leaf_indices
+            .iter()
+            .zip(leaves)
+            .map(|(index, data)| (*index, leaf_sum(data.as_ref())))
+            .collect::<Vec<_>>();
+        let mut side_hashes = proof_set.iter();
+        let Some(computed) = subtree_root(0, *leaves_count, &leaves, &mut side_hashes)
+        else {
+            return false
+        };
+
+        side_hashes.next().is_none() && computed == *root
+    }
+}
+
+/// Computes the root of the subtree of the leaves `start..end` from the hashes
+/// of its proven `leaves` and the roots of its other subtrees, taken in order
+/// from `side_hashes`.
+fn subtree_root(
+    start: u64,
+    end: u64,
+    leaves: &[(u64, Bytes32)],
+    side_hashes: &mut slice::Iter<Bytes32>,
+) -> Option<Bytes32> {
+    let Some((_, leaf)) = leaves.first() else {
+        return side_hashes.next().copied()
+    };
+
+    #[allow(clippy::arithmetic_side_effects)] // The range contains a leaf
+    let len = end - start;
+    if len == 1 {
+        return Some(*leaf)
+    }
+
+    // The left subtree is the largest balanced subtree of the range.
+    #[allow(clippy::arithmetic_side_effects)] // len > 1
+    let split = start + (1 << (len - 1).ilog2());
+    let (left, right) = leaves.split_at(leaves.partition_point(|(i, _)| *i < split));
+    let left = subtree_root(start, split, left, side_hashes)?;
+    let right = subtree_root(split, end, right, side_hashes)?;
+
+    Some(node_sum(&left, &right))
+}
+
+impl Debug for MultiProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let proof_set = self.proof_set.iter().map(hex::encode).collect::<Vec<_>>();
+        f.debug_struct("MultiProof")
+            .field("Leaf indices", &self.leaf_indices)
+            .field("Leaves count", &self.leaves_count)
+            .field("Proof set", &proof_set)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        binary::{
+            in_memory,
+            root_calculator::MerkleRootCalculator,
+            MerkleTree,
+            MerkleTreeError,
+            Primitive,
+        },
+        common::StorageMap,
+    };
+    use fuel_merkle_test_helpers::TEST_DATA;
+    use fuel_storage::Mappable;
+
+    #[derive(Debug)]
+    struct TestTable;
+
+    impl Mappable for TestTable {
+        type Key = Self::OwnedKey;
+        type OwnedKey = u64;
+        type OwnedValue = Primitive;
+        type Value = Self::OwnedValue;
+    }
+
+    #[test]
+    fn prove_multiple_returns_the_side_nodes_shared_by_the_leaves() {
+        let mut storage_map = StorageMap::<TestTable>::new();
+        let mut tree = MerkleTree::new(&mut storage_map);
+
+        let data = &TEST_DATA[0..7]; // 7 leaves
+        for datum in data.iter() {
+            tree.push(datum).unwrap();
+        }
+
+        let leaf_0 = leaf_sum(data[0]);
+        let leaf_2 = leaf_sum(data[2]);
+        let leaf_3 = leaf_sum(data[3]);
+        let leaf_5 = leaf_sum(data[5]);
+        let leaf_6 = leaf_sum(data[6]);
+        let node_5 = node_sum(&leaf_2, &leaf_3);
+
+        let (root, proof) = tree.prove_multiple(&[4, 1]).unwrap();
+
+        assert_eq!(root, tree.root());
+        assert_eq!(
+            proof,
+            MultiProof {
+                leaf_indices: vec![1, 4],
+                leaves_count: 7,
+                proof_set: vec![leaf_0, node_5, leaf_5, leaf_6],
+            }
+        );
+        assert!(proof.verify(&root, &[data[1], data[4]]));
+    }
+
+    #[test]
+    fn prove_multiple_is_verified_for_every_set_of_leaves() {
+        for leaves_count in 0..=10u64 {
+            let mut tree = in_memory::MerkleTree::new();
+            for datum in TEST_DATA[0..usize::try_from(leaves_count).unwrap()].iter() {
+                tree.push(datum);
+            }
+
+            for set in 0..1u32 << leaves_count {
+                let leaf_indices = (0..leaves_count)
+                    .filter(|i| set & (1 << i) != 0)
+                    .collect::<Vec<_>>();
+                let leaves = leaf_indices
+                    .iter()
+                    .map(|i| TEST_DATA[usize::try_from(*i).unwrap()])
+                    .collect::<Vec<_>>();
+
+                let (root, proof) = tree.prove_multiple(&leaf_indices).unwrap();
+
+                assert_eq!(proof.leaf_indices, leaf_indices);
+                assert!(proof.verify(&root, &leaves));
+            }
+        }
+    }
+
+    #[test]
+    fn prove_multiple_sorts_and_deduplicates_the_leaf_indices() {
+        let mut storage_map = StorageMap::<TestTable>::new();
+        let mut tree = MerkleTree::new(&mut storage_map);
+        for datum in TEST_DATA[0..5].iter() {
+            tree.push(datum).unwrap();
+        }
+
+        let (_, proof) = tree.prove_multiple(&[3, 0, 3]).unwrap();
+        assert_eq!(proof.leaf_indices, vec![0, 3]);
+
+        let err = tree
+            .prove_multiple(&[0, 5])
+            .expect_err("Expected prove_multiple() to return Error; got Ok");
+        assert_eq!(err, MerkleTreeError::InvalidProofIndex(5));
+    }
+
+    #[test]
+    fn verify_returns_true_for_the_root_of_the_root_calculator() {
+        let mut tree = in_memory::MerkleTree::new();
+        let mut calculator = MerkleRootCalculator::new();
+        for datum in TEST_DATA.iter() {
+            tree.push(datum);
+            calculator.push(datum);
+        }
+
+        let (_, proof) = tree.prove_multiple(&[2, 3, 8]).unwrap();
+
+        assert!(proof.verify(
+            &calculator.root(),
+            &[TEST_DATA[2], TEST_DATA[3], TEST_DATA[8]]
+        ));
+    }
+
+    #[test]
+    fn verify_returns_false_when_the_proof_does_not_match() {
+        let mut tree = in_memory::MerkleTree::new();
+        for datum in TEST_DATA.iter() {
+            tree.push(datum);
+        }
+        let leaves = [TEST_DATA[2], TEST_DATA[5], TEST_DATA[6]];
+        let (root, proof) = tree.prove_multiple(&[2, 5, 6]).unwrap();
+        assert!(proof.verify(&root, &leaves));
+
+        // Other leaves
+        assert!(!proof.verify(&root, &[TEST_DATA[2], TEST_DATA[6], TEST_DATA[5]]));
+        assert!(!proof.verify(&root, &[TEST_DATA[2], TEST_DATA[5]]));
+        // Another root
+        assert!(!proof.verify(&leaf_sum(TEST_DATA[0]), &leaves));
+
+        let verify_with = |update: fn(&mut MultiProof)| {
+            let mut proof = proof.clone();
+            update(&mut proof);
+            proof.verify(&root, &leaves)
+        };
+        // Unsorted, duplicated and out of bounds indices
+        assert!(!verify_with(|proof| proof.leaf_indices = vec![5, 2, 6]));
+        assert!(!verify_with(|proof| proof.leaf_indices = vec![2, 2, 6]));
+        assert!(!verify_with(|proof| proof.leaf_indices = vec![2, 5, 10]));
+        // Another leaves count
+        assert!(!verify_with(|proof| proof.leaves_count = 7));
+        // A tampered, truncated and extended proof set
+        assert!(!verify_with(|proof| proof.proof_set[0] = Default::default()));
+        assert!(!verify_with(|proof| {
+            proof.proof_set.pop();
+        }));
+        assert!(!verify_with(|proof| proof
+            .proof_set
+            .push(Default::default())));
+    }
+}
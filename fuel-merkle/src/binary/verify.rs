@@ -1,5 +1,6 @@
 use crate::{
     binary::{
+        empty_sum,
         leaf_sum,
         node_sum,
     },
@@ -130,9 +131,81 @@ pub fn verify<T: AsRef<[u8]>>(
     sum == *root
 }
 
+/// Verifies the consistency proof produced by
+/// [`MerkleTree::prove_consistency`](crate::binary::MerkleTree::prove_consistency),
+/// i.e. that the tree of `old_leaves_count` leaves with the `old_root` is a
+/// prefix of the tree of `leaves_count` leaves with the `root`.
+///
+/// Follows the verification algorithm of RFC 9162, extended to the empty old
+/// tree, which is consistent with any tree. As with RFC 9162, the leaves counts
+/// are not bound by the roots, and must be authenticated along with them.
+pub fn verify_consistency(
+    old_root: &Bytes32,
+    root: &Bytes32,
+    proof_set: &ProofSet,
+    old_leaves_count: u64,
+    leaves_count: u64,
+) -> bool {
+    if old_leaves_count > leaves_count {
+        return false
+    }
+    if old_leaves_count == 0 {
+        return proof_set.is_empty() && old_root == empty_sum()
+    }
+    if old_leaves_count == leaves_count {
+        return proof_set.is_empty() && old_root == root
+    }
+
+    let mut proof = proof_set.iter();
+    // The root of an old balanced tree is omitted from the proof.
+    let first = if old_leaves_count.is_power_of_two() {
+        old_root
+    } else {
+        match proof.next() {
+            Some(first) => first,
+            None => return false,
+        }
+    };
+
+    // The last leaf indices of both trees, walked up to the root. Checked above.
+    #[allow(clippy::arithmetic_side_effects)]
+    let (mut old_node, mut node) = (old_leaves_count - 1, leaves_count - 1);
+    while old_node & 1 == 1 {
+        old_node >>= 1;
+        node >>= 1;
+    }
+
+    let mut old_sum = *first;
+    let mut sum = *first;
+    for side in proof {
+        if node == 0 {
+            return false
+        }
+
+        if old_node & 1 == 1 || old_node == node {
+            old_sum = node_sum(side, &old_sum);
+            sum = node_sum(side, &sum);
+            while old_node & 1 == 0 && old_node != 0 {
+                old_node >>= 1;
+                node >>= 1;
+            }
+        } else {
+            sum = node_sum(&sum, side);
+        }
+
+        old_node >>= 1;
+        node >>= 1;
+    }
+
+    old_sum == *old_root && sum == *root && node == 0
+}
+
 #[cfg(test)]
 mod test {
-    use super::verify;
+    use super::{
+        verify,
+        verify_consistency,
+    };
     use crate::{
         binary::{
             MerkleTree,
@@ -260,4 +333,107 @@ mod test {
         );
         assert!(!verification);
     }
+
+    #[test]
+    fn verify_consistency_returns_false_when_the_proof_does_not_match() {
+        let mut storage_map = StorageMap::<TestTable>::new();
+        let mut tree = MerkleTree::new(&mut storage_map);
+
+        const OLD_LEAVES_COUNT: u64 = 5;
+        const LEAVES_COUNT: u64 = 10;
+
+        let old_leaves_count = usize::try_from(OLD_LEAVES_COUNT).unwrap();
+        let leaves_count = usize::try_from(LEAVES_COUNT).unwrap();
+
+        for datum in TEST_DATA[0..old_leaves_count].iter() {
+            tree.push(datum).unwrap();
+        }
+        let old_root = tree.root();
+        for datum in TEST_DATA[old_leaves_count..leaves_count].iter() {
+            tree.push(datum).unwrap();
+        }
+
+        let (root, proof_set) = tree.prove_consistency(OLD_LEAVES_COUNT).unwrap();
+        assert!(verify_consistency(
+            &old_root,
+            &root,
+            &proof_set,
+            OLD_LEAVES_COUNT,
+            LEAVES_COUNT
+        ));
+
+        // Another old tree
+        assert!(!verify_consistency(
+            &root,
+            &root,
+            &proof_set,
+            OLD_LEAVES_COUNT,
+            LEAVES_COUNT
+        ));
+        // Other leaves counts
+        assert!(!verify_consistency(
+            &old_root,
+            &root,
+            &proof_set,
+            OLD_LEAVES_COUNT + 1,
+            LEAVES_COUNT
+        ));
+        assert!(!verify_consistency(
+            &old_root,
+            &root,
+            &proof_set,
+            LEAVES_COUNT,
+            OLD_LEAVES_COUNT
+        ));
+        // A tampered proof
+        let mut tampered = proof_set.clone();
+        tampered[1] = Default::default();
+        assert!(!verify_consistency(
+            &old_root,
+            &root,
+            &tampered,
+            OLD_LEAVES_COUNT,
+            LEAVES_COUNT
+        ));
+        // A truncated and an extended proof
+        let mut truncated = proof_set.clone();
+        truncated.pop();
+        assert!(!verify_consistency(
+            &old_root,
+            &root,
+            &truncated,
+            OLD_LEAVES_COUNT,
+            LEAVES_COUNT
+        ));
+        let mut extended = proof_set;
+        extended.push(Default::default());
+        assert!(!verify_consistency(
+            &old_root,
+            &root,
+            &extended,
+            OLD_LEAVES_COUNT,
+            LEAVES_COUNT
+        ));
+    }
+
+    #[test]
+    fn verify_consistency_returns_true_for_the_same_tree_and_the_empty_tree() {
+        let mut storage_map = StorageMap::<TestTable>::new();
+        let mut tree = MerkleTree::new(&mut storage_map);
+        for datum in TEST_DATA[0..3].iter() {
+            tree.push(datum).unwrap();
+        }
+        let root = tree.root();
+
+        assert!(verify_consistency(&root, &root, &vec![], 3, 3));
+        assert!(verify_consistency(
+            MerkleTree::<(), ()>::empty_root(),
+            &root,
+            &vec![],
+            0,
+            3
+        ));
+        assert!(!verify_consistency(&root, &root, &vec![], 0, 3));
+        assert!(!verify_consistency(&root, &root, &vec![root], 3, 3));
+    }
 }
@@ -171,6 +171,14 @@ impl MerkleTree {
         let _ = self.tree.update(key, data);
     }
 
+    pub fn update_batch<I, D>(&mut self, set: I)
+    where
+        I: IntoIterator<Item = (MerkleTreeKey, Option<D>)>,
+        D: AsRef<[u8]>,
+    {
+        let _ = self.tree.update_batch(set);
+    }
+
     pub fn delete(&mut self, key: MerkleTreeKey) {
         let _ = self.tree.delete(key);
     }
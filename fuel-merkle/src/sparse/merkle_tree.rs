@@ -14,19 +14,36 @@ use node::{
 use crate::{
     common::{
         error::DeserializeError,
-        node::ChildError,
+        node::{
+            ChildError,
+            ParentNode,
+        },
+        path::{
+            Path,
+            Side,
+        },
         AsPathIterator,
         Bytes32,
+        Prefix,
+        ProofSet,
     },
     sparse::{
         empty_sum,
         proof::{
+            BatchLeaf,
+            BatchProof,
+            CompressedProofSet,
             ExclusionLeaf,
             ExclusionLeafData,
             ExclusionProof,
             InclusionProof,
             Proof,
+            SideNode,
+            UpdateOperation,
+            UpdateProof,
+            UpdateStep,
         },
+        zero_sum,
         Primitive,
     },
     storage::{
@@ -461,6 +478,227 @@ where
         Ok(())
     }
 
+    /// Sets the data of all the keys of the `set`, or deletes the keys with
+    /// `None` or empty data. This is equivalent to sequentially calling
+    /// [update](Self::update) for each key-value pair, where the last pair of a
+    /// key wins, but the nodes shared by the paths of the keys are only rebuilt
+    /// and written to the storage once.
+    pub fn update_batch<I, D>(
+        &mut self,
+        set: I,
+    ) -> Result<(), MerkleTreeError<StorageError>>
+    where
+        I: IntoIterator<Item = (MerkleTreeKey, Option<D>)>,
+        D: AsRef<[u8]>,
+    {
+        let entries = set
+            .into_iter()
+            .map(|(key, data)| {
+                let leaf = data
+                    .filter(|data| !data.as_ref().is_empty())
+                    .map(|data| Node::create_leaf(&key.0, data));
+                (key.0, leaf)
+            })
+            .collect::<alloc::collections::BTreeMap<_, _>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        let root_node = self.root_node().clone();
+        let root_node = self.update_batch_node(root_node, 0, &entries)?;
+        self.set_root_node(root_node);
+
+        Ok(())
+    }
+
+    /// Applies the sorted `entries`, sharing the path of the `node` at `depth`,
+    /// to the subtree of the `node`, and returns the new root of the subtree.
+    fn update_batch_node(
+        &mut self,
+        node: Node,
+        depth: u32,
+        entries: &[(Bytes32, Option<Node>)],
+    ) -> Result<Node, MerkleTreeError<StorageError>> {
+        if entries.is_empty() {
+            return Ok(node)
+        }
+
+        if !node.is_node() {
+            // The subtree is made of the leaf, if any, and the new leaves.
+            let mut old_leaf = (!node.is_placeholder()).then_some(node);
+            let mut leaves = Vec::with_capacity(entries.len().saturating_add(1));
+            for (key, leaf) in entries {
+                if old_leaf.as_ref().is_some_and(|old| old.leaf_key() < key) {
+                    leaves.extend(old_leaf.take());
+                }
+                if old_leaf.as_ref().is_some_and(|old| old.leaf_key() == key) {
+                    let old = old_leaf.take().expect("The old leaf is set");
+                    if leaf.as_ref() != Some(&old) {
+                        self.storage.remove(old.hash())?;
+                    }
+                }
+                if let Some(leaf) = leaf {
+                    self.storage.insert(leaf.hash(), &leaf.into())?;
+                    leaves.push(leaf.clone());
+                }
+            }
+            leaves.extend(old_leaf);
+
+            return Ok(self.build_subtree(depth, &leaves)?)
+        }
+
+        self.storage.remove(node.hash())?;
+        let split = entries.partition_point(|(key, _)| {
+            matches!(key.get_instruction(depth), Some(Side::Left))
+        });
+        let (left_entries, right_entries) = entries.split_at(split);
+        #[allow(clippy::arithmetic_side_effects)] // Nodes are above the max depth
+        let child_depth = depth + 1;
+
+        let mut left = None;
+        if !left_entries.is_empty() {
+            let child = StorageNode::new(&self.storage, node.clone())
+                .left_child()
+                .map_err(MerkleTreeError::ChildError)?
+                .into_node();
+            left = Some(self.update_batch_node(child, child_depth, left_entries)?);
+        }
+        let mut right = None;
+        if !right_entries.is_empty() {
+            let child = StorageNode::new(&self.storage, node.clone())
+                .right_child()
+                .map_err(MerkleTreeError::ChildError)?
+                .into_node();
+            right = Some(self.update_batch_node(child, child_depth, right_entries)?);
+        }
+
+        let left_hash = left.as_ref().map_or(*node.left_child_key(), |n| *n.hash());
+        let right_hash = right
+            .as_ref()
+            .map_or(*node.right_child_key(), |n| *n.hash());
+        if left_hash == *zero_sum() && right_hash == *zero_sum() {
+            return Ok(Node::create_placeholder())
+        }
+
+        // A leaf without a sibling takes the place of its parent.
+        if left_hash == *zero_sum() || right_hash == *zero_sum() {
+            let (child, hash) = if left_hash == *zero_sum() {
+                (right, right_hash)
+            } else {
+                (left, left_hash)
+            };
+            let child = match child {
+                Some(child) => child,
+                None => self.load_node(&hash)?,
+            };
+            if child.is_leaf() {
+                return Ok(child)
+            }
+        }
+
+        #[allow(clippy::arithmetic_side_effects)] // depth <= max_height
+        let height = Node::max_height() - depth;
+        let node = Node::new(height, Prefix::Node, left_hash, right_hash);
+        self.storage.insert(node.hash(), &node.as_ref().into())?;
+
+        Ok(node)
+    }
+
+    /// Builds the subtree at `depth` of the sorted `leaves`, sharing the path
+    /// to the subtree.
+    fn build_subtree(
+        &mut self,
+        depth: u32,
+        leaves: &[Node],
+    ) -> Result<Node, StorageError> {
+        match leaves {
+            [] => Ok(Node::create_placeholder()),
+            [leaf] => Ok(leaf.clone()),
+            _ => {
+                let split = leaves.partition_point(|leaf| {
+                    matches!(leaf.leaf_key().get_instruction(depth), Some(Side::Left))
+                });
+                let (left, right) = leaves.split_at(split);
+                #[allow(clippy::arithmetic_side_effects)] // Distinct keys diverge
+                let child_depth = depth + 1;
+                let left = self.build_subtree(child_depth, left)?;
+                let right = self.build_subtree(child_depth, right)?;
+
+                #[allow(clippy::arithmetic_side_effects)] // depth < max_height
+                let height = Node::max_height() - depth;
+                let node = Node::create_node(&left, &right, height);
+                self.storage.insert(node.hash(), &node.as_ref().into())?;
+                Ok(node)
+            }
+        }
+    }
+
+    fn load_node(&self, key: &Bytes32) -> Result<Node, MerkleTreeError<StorageError>> {
+        self.storage
+            .get(key)?
+            .ok_or(MerkleTreeError::LoadError(*key))?
+            .into_owned()
+            .try_into()
+            .map_err(MerkleTreeError::DeserializeError)
+    }
+
+    /// Applies the `operations` to the tree in order, and returns the proof of
+    /// the change of the root, verified by
+    /// [`verify_update`](crate::sparse::proof::verify_update).
+    pub fn generate_update_proof<D: AsRef<[u8]>>(
+        &mut self,
+        operations: &[UpdateOperation<D>],
+    ) -> Result<UpdateProof, MerkleTreeError<StorageError>> {
+        let mut steps = Vec::with_capacity(operations.len());
+        for operation in operations {
+            let key = *operation.key();
+            let data = operation.data();
+            steps.push(self.update_step(&key, data.is_none())?);
+            self.update(key, data.unwrap_or_default())?;
+        }
+
+        Ok(UpdateProof { steps })
+    }
+
+    fn update_step(
+        &self,
+        key: &MerkleTreeKey,
+        is_delete: bool,
+    ) -> Result<UpdateStep, MerkleTreeError<StorageError>> {
+        let (path_nodes, side_nodes) = self.path_set(key.as_ref())?;
+        let actual_leaf = &path_nodes[0];
+        let is_included =
+            !actual_leaf.is_placeholder() && *actual_leaf.leaf_key() == key.0;
+
+        let leaf = if actual_leaf.is_placeholder() {
+            ExclusionLeaf::Placeholder
+        } else {
+            ExclusionLeaf::Leaf(ExclusionLeafData {
+                leaf_key: *actual_leaf.leaf_key(),
+                leaf_value: *actual_leaf.leaf_data(),
+            })
+        };
+        // Deleting a leaf depends on whether its side node is a leaf.
+        let side_node = match side_nodes.first() {
+            Some(node) if is_delete && is_included && node.is_node() => Some(
+                SideNode::Node(*node.left_child_key(), *node.right_child_key()),
+            ),
+            Some(node) if is_delete && is_included && !node.is_placeholder() => {
+                Some(SideNode::Leaf(ExclusionLeafData {
+                    leaf_key: *node.leaf_key(),
+                    leaf_value: *node.leaf_data(),
+                }))
+            }
+            _ => None,
+        };
+        let proof_set = side_nodes.iter().map(|node| *node.hash()).collect();
+
+        Ok(UpdateStep {
+            proof_set,
+            leaf,
+            side_node,
+        })
+    }
+
     fn update_with_path_set(
         &mut self,
         requested_leaf_node: &Node,
@@ -675,6 +913,99 @@ where
         };
         Ok(proof)
     }
+
+    /// Generates a single proof of the inclusion or the exclusion of all the
+    /// `keys`, given in any order. The side nodes shared by the paths of the
+    /// keys are only included once.
+    pub fn generate_batch_proof(
+        &self,
+        keys: &[MerkleTreeKey],
+    ) -> Result<BatchProof, MerkleTreeError<StorageError>> {
+        let mut keys = keys.to_vec();
+        keys.sort_unstable_by_key(|key| key.0);
+        keys.dedup();
+
+        let mut leaves = Vec::new();
+        let mut proof_set = ProofSet::new();
+        if keys.is_empty() {
+            proof_set.push(self.root());
+        } else {
+            self.batch_proof_nodes(
+                self.root_node().clone(),
+                0,
+                &keys,
+                &mut leaves,
+                &mut proof_set,
+            )?;
+        }
+
+        Ok(BatchProof {
+            leaves,
+            proof_set: CompressedProofSet::compress(&proof_set),
+        })
+    }
+
+    /// Pushes the leaves ending the paths of the sorted `keys` from the `node`
+    /// at `depth`, and the side nodes of the paths, in depth-first order.
+    fn batch_proof_nodes(
+        &self,
+        node: Node,
+        depth: u32,
+        keys: &[MerkleTreeKey],
+        leaves: &mut Vec<(u32, BatchLeaf)>,
+        proof_set: &mut ProofSet,
+    ) -> Result<(), MerkleTreeError<StorageError>> {
+        if !node.is_node() {
+            let leaf = if node.is_placeholder() {
+                BatchLeaf::Excluded(ExclusionLeaf::Placeholder)
+            } else if keys.iter().any(|key| &key.0 == node.leaf_key()) {
+                BatchLeaf::Included
+            } else {
+                BatchLeaf::Excluded(ExclusionLeaf::Leaf(ExclusionLeafData {
+                    leaf_key: *node.leaf_key(),
+                    leaf_value: *node.leaf_data(),
+                }))
+            };
+            leaves.push((depth, leaf));
+            return Ok(())
+        }
+
+        let split = keys.partition_point(|key| {
+            matches!(key.get_instruction(depth), Some(Side::Left))
+        });
+        let (left_keys, right_keys) = keys.split_at(split);
+        let (left_hash, right_hash) = (*node.left_child_key(), *node.right_child_key());
+        let node = StorageNode::new(&self.storage, node);
+        #[allow(clippy::arithmetic_side_effects)] // Nodes are above the max depth
+        let child_depth = depth + 1;
+
+        if left_keys.is_empty() {
+            proof_set.push(left_hash);
+        } else {
+            let child = node.left_child().map_err(MerkleTreeError::ChildError)?;
+            self.batch_proof_nodes(
+                child.into_node(),
+                child_depth,
+                left_keys,
+                leaves,
+                proof_set,
+            )?;
+        }
+        if right_keys.is_empty() {
+            proof_set.push(right_hash);
+        } else {
+            let child = node.right_child().map_err(MerkleTreeError::ChildError)?;
+            self.batch_proof_nodes(
+                child.into_node(),
+                child_depth,
+                right_keys,
+                leaves,
+                proof_set,
+            )?;
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -1669,4 +2000,119 @@ mod test {
         // Then
         assert!(proof.is_exclusion());
     }
+
+    fn close_key(last: u8) -> MerkleTreeKey {
+        let mut key = [0u8; 32];
+        key[31] = last;
+        MerkleTreeKey::new_without_hash(key)
+    }
+
+    #[test]
+    fn test_update_batch_yields_the_same_tree_as_sequential_updates() {
+        use rand::{
+            rngs::StdRng,
+            SeedableRng,
+        };
+        let rng = &mut StdRng::seed_from_u64(0xDEADBEEF);
+
+        let initial = (0..100u8)
+            .map(|i| key([i]))
+            .chain((0..8).map(close_key))
+            .map(|key| (key, random_bytes32(rng)))
+            .collect::<Vec<_>>();
+
+        let mut batch = Vec::new();
+        // Updated and deleted keys
+        for (i, (key, _)) in initial.iter().enumerate().step_by(3) {
+            let data = match i % 4 {
+                0 => None,
+                1 => Some(vec![]),
+                _ => Some(random_bytes32(rng).to_vec()),
+            };
+            batch.push((*key, data));
+        }
+        // New keys, and absent keys to delete
+        for i in 100..150u8 {
+            batch.push((key([i]), Some(random_bytes32(rng).to_vec())));
+            batch.push((key([i, i]), None));
+        }
+        // Close keys, whose siblings take the place of their parents once deleted
+        for i in 1..8 {
+            batch.push((close_key(i), None));
+        }
+        batch.push((close_key(8), Some(b"DATA".to_vec())));
+        // Duplicated keys, where the last data wins
+        batch.push((key([100]), None));
+        batch.push((key([0]), Some(b"DATA".to_vec())));
+
+        let mut sequential_storage = StorageMap::<TestTable>::new();
+        let mut sequential =
+            MerkleTree::from_set(&mut sequential_storage, initial.iter().cloned())
+                .unwrap();
+        for (key, data) in batch.iter() {
+            match data {
+                Some(data) => sequential.update(*key, data).unwrap(),
+                None => sequential.delete(*key).unwrap(),
+            }
+        }
+
+        let mut batch_storage = StorageMap::<TestTable>::new();
+        let mut tree =
+            MerkleTree::from_set(&mut batch_storage, initial.iter().cloned()).unwrap();
+        tree.update_batch(batch.iter().cloned()).unwrap();
+
+        assert_eq!(tree.root(), sequential.root());
+        let keys = initial.iter().map(|(key, _)| key);
+        for key in keys.chain(batch.iter().map(|(key, _)| key)) {
+            let proof = tree.generate_proof(key).unwrap();
+            assert_eq!(proof, sequential.generate_proof(key).unwrap());
+        }
+        let root = tree.root();
+        let tree = MerkleTree::load(&mut batch_storage, &root).unwrap();
+        assert_eq!(tree.root(), sequential.root());
+        assert_eq!(batch_storage.len(), sequential_storage.len());
+    }
+
+    #[test]
+    fn test_update_batch_on_an_empty_tree_yields_the_same_tree_as_from_set() {
+        let rng = &mut rand::thread_rng();
+        let set = (0..100u32)
+            .map(|i| (key(i.to_be_bytes()), random_bytes32(rng)))
+            .collect::<Vec<_>>();
+
+        let mut storage = StorageMap::<TestTable>::new();
+        let mut tree = MerkleTree::new(&mut storage);
+        tree.update_batch(set.iter().map(|(key, data)| (*key, Some(data))))
+            .unwrap();
+        let root = tree.root();
+
+        let mut expected_storage = StorageMap::<TestTable>::new();
+        let expected_tree =
+            MerkleTree::from_set(&mut expected_storage, set.iter().cloned()).unwrap();
+        assert_eq!(root, expected_tree.root());
+        assert_eq!(storage.len(), expected_storage.len());
+
+        let mut tree = MerkleTree::load(&mut storage, &root).unwrap();
+        tree.update_batch(set.iter().map(|(key, _)| (*key, None::<Bytes32>)))
+            .unwrap();
+        assert_eq!(tree.root(), *empty_sum());
+        assert!(storage.is_empty());
+    }
+
+    #[test]
+    fn test_update_batch_with_an_empty_set_does_not_change_the_tree() {
+        let mut storage = StorageMap::<TestTable>::new();
+        let mut tree = MerkleTree::new(&mut storage);
+        tree.update(key(b"\x00\x00\x00\x00"), b"DATA").unwrap();
+        tree.update(key(b"\x00\x00\x00\x01"), b"DATA").unwrap();
+        let root = tree.root();
+        let len = storage.len();
+
+        let mut tree = MerkleTree::load(&mut storage, &root).unwrap();
+        tree.update_batch(core::iter::empty::<(MerkleTreeKey, Option<Bytes32>)>())
+            .unwrap();
+
+        assert_eq!(tree.root(), root);
+        assert_eq!(storage.len(), len);
+    }
 }
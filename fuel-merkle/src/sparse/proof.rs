@@ -18,10 +18,16 @@ use crate::{
     },
 };
 
-use alloc::vec::Vec;
+use alloc::{
+    vec,
+    vec::Vec,
+};
 use core::{
     fmt,
     fmt::Debug,
+    iter,
+    iter::Peekable,
+    slice,
 };
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -164,6 +170,431 @@ impl Debug for ExclusionProof {
     }
 }
 
+impl Proof {
+    /// Compresses the proof set of the proof, see [`CompressedProofSet`].
+    pub fn compress(&self) -> CompressedProof {
+        match self {
+            Proof::Inclusion(proof) => {
+                CompressedProof::Inclusion(CompressedProofSet::compress(&proof.proof_set))
+            }
+            Proof::Exclusion(proof) => CompressedProof::Exclusion(
+                CompressedProofSet::compress(&proof.proof_set),
+                proof.leaf.clone(),
+            ),
+        }
+    }
+}
+
+/// A [`Proof`] with a compressed proof set.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CompressedProof {
+    Inclusion(CompressedProofSet),
+    Exclusion(CompressedProofSet, ExclusionLeaf),
+}
+
+impl CompressedProof {
+    /// Returns the decompressed proof, or `None` if the proof set is malformed.
+    pub fn decompress(&self) -> Option<Proof> {
+        let proof = match self {
+            CompressedProof::Inclusion(proof_set) => Proof::Inclusion(InclusionProof {
+                proof_set: proof_set.decompress()?,
+            }),
+            CompressedProof::Exclusion(proof_set, leaf) => {
+                Proof::Exclusion(ExclusionProof {
+                    proof_set: proof_set.decompress()?,
+                    leaf: leaf.clone(),
+                })
+            }
+        };
+        Some(proof)
+    }
+}
+
+/// A proof set without its default side nodes, i.e. the placeholders hashing
+/// to the zero sum, which make up most of the side nodes of the sparse Merkle
+/// tree.
+#[derive(Clone, Eq, PartialEq)]
+pub struct CompressedProofSet {
+    /// The number of side nodes of the proof set.
+    pub len: u32,
+    /// The bitmap of the non-default side nodes, where the most significant
+    /// bit of the first byte is the first side node.
+    pub bitmap: Vec<u8>,
+    /// The non-default side nodes, in order.
+    pub side_nodes: ProofSet,
+}
+
+impl CompressedProofSet {
+    pub fn compress(proof_set: &[Bytes32]) -> Self {
+        let mut bitmap = vec![0u8; proof_set.len().div_ceil(8)];
+        let mut side_nodes = ProofSet::new();
+        for (i, side_node) in proof_set.iter().enumerate() {
+            if side_node != zero_sum() {
+                bitmap[i / 8] |= 0x80 >> (i % 8);
+                side_nodes.push(*side_node);
+            }
+        }
+
+        Self {
+            len: u32::try_from(proof_set.len())
+                .expect("The proof set is smaller than the tree"),
+            bitmap,
+            side_nodes,
+        }
+    }
+
+    /// Returns the decompressed proof set, or `None` if the bitmap doesn't
+    /// match the length and the non-default side nodes of the proof set, or if
+    /// it is longer than the path of a leaf.
+    pub fn decompress(&self) -> Option<ProofSet> {
+        self.decompress_at_most(256)
+    }
+
+    /// Decompresses a proof set of at most `max_len` side nodes, which is
+    /// checked before allocating it.
+    fn decompress_at_most(&self, max_len: usize) -> Option<ProofSet> {
+        let Self {
+            len,
+            bitmap,
+            side_nodes,
+        } = self;
+
+        let len = usize::try_from(*len).ok()?;
+        if len > max_len || bitmap.len() != len.div_ceil(8) {
+            return None
+        }
+        let is_set = |i: usize| bitmap[i / 8] & (0x80 >> (i % 8)) != 0;
+        // The bits following the last side node must be unset.
+        let padding = bitmap.len().saturating_mul(8);
+        if (len..padding).any(is_set) {
+            return None
+        }
+        let set_count = bitmap
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .fold(0usize, usize::saturating_add);
+        if set_count != side_nodes.len() {
+            return None
+        }
+
+        let mut side_nodes = side_nodes.iter();
+        let proof_set = (0..len)
+            .map(|i| {
+                if is_set(i) {
+                    side_nodes.next().copied()
+                } else {
+                    Some(*zero_sum())
+                }
+            })
+            .collect::<Option<ProofSet>>()?;
+
+        side_nodes.next().is_none().then_some(proof_set)
+    }
+}
+
+impl Debug for CompressedProofSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side_nodes = self.side_nodes.iter().map(hex::encode).collect::<Vec<_>>();
+        f.debug_struct("CompressedProofSet")
+            .field("Length", &self.len)
+            .field("Bitmap", &hex::encode(&self.bitmap))
+            .field("Side nodes", &side_nodes)
+            .finish()
+    }
+}
+
+/// The leaf ending the paths of some of the keys of a [`BatchProof`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BatchLeaf {
+    /// The leaf of one of the keys, included in the tree. The other keys are
+    /// excluded.
+    Included,
+    /// The leaf or the placeholder found in place of the keys, all excluded.
+    Excluded(ExclusionLeaf),
+}
+
+/// A single proof of the inclusion or the exclusion of several keys, produced
+/// by [`MerkleTree::generate_batch_proof`](crate::sparse::MerkleTree::generate_batch_proof).
+///
+/// The paths of the keys share their side nodes, and the proof set holds only
+/// the side nodes that are not on the path of another key, compressed.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BatchProof {
+    /// The leaves ending the paths of the keys with their depth in the tree,
+    /// from left to right.
+    pub leaves: Vec<(u32, BatchLeaf)>,
+    /// The side nodes of the paths in depth-first order, from left to right.
+    pub proof_set: CompressedProofSet,
+}
+
+impl BatchProof {
+    /// Verifies the `entries` against the `root`, where every key is either
+    /// included in the tree with `Some` value, or excluded from the tree with
+    /// `None`. The entries can be given in any order.
+    pub fn verify<T: AsRef<[u8]>>(
+        &self,
+        root: &Bytes32,
+        entries: &[(MerkleTreeKey, Option<T>)],
+    ) -> bool {
+        // The leaves are at most at the depth of the keys, and each of their
+        // paths has at most as many side nodes, which bounds the recursion and
+        // the decompressed proof set. Without leaves, the proof set is the root.
+        if self.leaves.iter().any(|(depth, _)| *depth > 256) {
+            return false
+        }
+        let max_len = self.leaves.len().saturating_mul(256).max(1);
+        let Some(proof_set) = self.proof_set.decompress_at_most(max_len) else {
+            return false
+        };
+
+        let mut entries = entries
+            .iter()
+            .map(|(key, value)| (*key, value.as_ref().map(sum)))
+            .collect::<Vec<_>>();
+        entries.sort_unstable_by_key(|(key, _)| **key);
+        if entries.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return false
+        }
+
+        let mut leaves = self.leaves.iter().peekable();
+        let mut side_nodes = proof_set.iter();
+        let Some(computed) = batch_root(0, &entries, &mut leaves, &mut side_nodes) else {
+            return false
+        };
+
+        leaves.next().is_none() && side_nodes.next().is_none() && computed == *root
+    }
+}
+
+/// Computes the root of the subtree at `depth` containing the sorted `entries`,
+/// taking its leaves and side nodes in order.
+fn batch_root(
+    depth: u32,
+    entries: &[(MerkleTreeKey, Option<Bytes32>)],
+    leaves: &mut Peekable<slice::Iter<'_, (u32, BatchLeaf)>>,
+    side_nodes: &mut slice::Iter<Bytes32>,
+) -> Option<Bytes32> {
+    if entries.is_empty() {
+        return side_nodes.next().copied()
+    }
+
+    let (leaf_depth, leaf) = leaves.peek()?;
+    if *leaf_depth == depth {
+        let _ = leaves.next();
+        return match leaf {
+            BatchLeaf::Included => {
+                let mut included = entries.iter().filter_map(|(key, value)| {
+                    value.map(|value| calculate_leaf_hash(key, &value))
+                });
+                let leaf_hash = included.next();
+                if included.next().is_some() {
+                    return None
+                }
+                leaf_hash
+            }
+            BatchLeaf::Excluded(leaf) => {
+                let is_excluded = entries.iter().all(|(key, value)| {
+                    value.is_none()
+                        && !matches!(leaf, ExclusionLeaf::Leaf(data) if data.leaf_key == key.as_ref())
+                });
+                is_excluded.then(|| leaf.hash())
+            }
+        }
+    }
+    if *leaf_depth < depth {
+        return None
+    }
+
+    // The entries share the first `depth` bits of their keys, and are sorted by
+    // the next one.
+    let split = entries.partition_point(|(key, _)| {
+        matches!(key.get_instruction(depth), Some(Side::Left))
+    });
+    let (left, right) = entries.split_at(split);
+    let child_depth = depth.checked_add(1)?;
+    let left = batch_root(child_depth, left, leaves, side_nodes)?;
+    let right = batch_root(child_depth, right, leaves, side_nodes)?;
+
+    Some(calculate_node_hash(&left, &right))
+}
+
+/// An operation on a sparse Merkle tree, proven by an [`UpdateProof`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum UpdateOperation<D> {
+    /// Sets the data of the key, or deletes the key if the data is empty, as
+    /// [`MerkleTree::update`](crate::sparse::MerkleTree::update).
+    Update(MerkleTreeKey, D),
+    /// Deletes the key, as [`MerkleTree::delete`](crate::sparse::MerkleTree::delete).
+    Delete(MerkleTreeKey),
+}
+
+impl<D: AsRef<[u8]>> UpdateOperation<D> {
+    pub fn key(&self) -> &MerkleTreeKey {
+        match self {
+            UpdateOperation::Update(key, _) | UpdateOperation::Delete(key) => key,
+        }
+    }
+
+    /// Returns the data set by the operation, or `None` if it deletes the key.
+    pub fn data(&self) -> Option<&[u8]> {
+        match self {
+            UpdateOperation::Update(_, data) if !data.as_ref().is_empty() => {
+                Some(data.as_ref())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The preimage of the first side node of a deleted leaf. When it is a leaf,
+/// it takes the place of their parent.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SideNode {
+    Leaf(ExclusionLeafData),
+    /// An internal node, with the hashes of its left and right children.
+    Node(Bytes32, Bytes32),
+}
+
+impl SideNode {
+    fn hash(&self) -> Bytes32 {
+        match self {
+            SideNode::Leaf(data) => calculate_leaf_hash(&data.leaf_key, &data.leaf_value),
+            SideNode::Node(left, right) => calculate_node_hash(left, right),
+        }
+    }
+}
+
+/// The path of the key of an operation before applying it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UpdateStep {
+    pub proof_set: ProofSet,
+    /// The leaf at the end of the path: the leaf of the key if it is set, or
+    /// the leaf or the placeholder in its place otherwise.
+    pub leaf: ExclusionLeaf,
+    /// The preimage of the first side node when the operation deletes the
+    /// leaf of the key, unless the side node is a placeholder.
+    pub side_node: Option<SideNode>,
+}
+
+/// The proof that applying a sequence of [`UpdateOperation`]s to a sparse
+/// Merkle tree changes its root from one root to another, produced by
+/// [`MerkleTree::generate_update_proof`](crate::sparse::MerkleTree::generate_update_proof)
+/// and verified by [`verify_update`].
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct UpdateProof {
+    /// The steps of the operations, in order.
+    pub steps: Vec<UpdateStep>,
+}
+
+/// Verifies that applying the `operations` to the tree with the `root` results
+/// in the tree with the `new_root`, without holding the tree.
+pub fn verify_update<D: AsRef<[u8]>>(
+    root: &Bytes32,
+    new_root: &Bytes32,
+    operations: &[UpdateOperation<D>],
+    proof: &UpdateProof,
+) -> bool {
+    if operations.len() != proof.steps.len() {
+        return false
+    }
+
+    let mut current = *root;
+    for (operation, step) in operations.iter().zip(proof.steps.iter()) {
+        match apply_step(&current, operation, step) {
+            Some(root) => current = root,
+            None => return false,
+        }
+    }
+
+    current == *new_root
+}
+
+/// Returns the root after applying the `operation` to the tree with the `root`,
+/// or `None` if the `step` doesn't prove the path of the key.
+fn apply_step<D: AsRef<[u8]>>(
+    root: &Bytes32,
+    operation: &UpdateOperation<D>,
+    step: &UpdateStep,
+) -> Option<Bytes32> {
+    let UpdateStep {
+        proof_set,
+        leaf,
+        side_node,
+    } = step;
+    let key = operation.key();
+
+    if path_root(key, leaf.hash(), proof_set)? != *root {
+        return None
+    }
+    let is_included =
+        matches!(leaf, ExclusionLeaf::Leaf(data) if data.leaf_key == key.as_ref());
+
+    match (operation.data(), leaf) {
+        (Some(data), ExclusionLeaf::Leaf(data_leaf)) if !is_included => {
+            // The new leaf and the leaf in its place are joined where their
+            // paths diverge, below placeholders down from the current depth.
+            #[allow(clippy::cast_possible_truncation)] // Key is 32 bytes
+            let ancestor_depth = key.common_path_length(&data_leaf.leaf_key) as usize;
+            let placeholders_count = ancestor_depth.checked_sub(proof_set.len())?;
+            let path = iter::once(leaf.hash())
+                .chain((0..placeholders_count).map(|_| *zero_sum()))
+                .chain(proof_set.iter().copied())
+                .collect::<Vec<_>>();
+            path_root(key, calculate_leaf_hash(key, &sum(data)), &path)
+        }
+        (Some(data), _) => {
+            path_root(key, calculate_leaf_hash(key, &sum(data)), proof_set)
+        }
+        (None, _) if !is_included => Some(*root),
+        (None, _) => {
+            let Some(first) = proof_set.first() else {
+                return Some(*zero_sum())
+            };
+            let is_leaf = match side_node {
+                _ if first == zero_sum() => true,
+                Some(side_node) if side_node.hash() == *first => {
+                    matches!(side_node, SideNode::Leaf(_))
+                }
+                _ => return None,
+            };
+
+            if is_leaf {
+                // The side leaf moves up to the next non-placeholder side node.
+                match proof_set.iter().skip(1).position(|node| node != zero_sum()) {
+                    #[allow(clippy::arithmetic_side_effects)] // position < len - 1
+                    Some(position) => path_root(key, *first, &proof_set[position + 1..]),
+                    None => Some(*first),
+                }
+            } else {
+                path_root(key, *zero_sum(), proof_set)
+            }
+        }
+    }
+}
+
+/// Computes the root from the `leaf` at the end of the path of the `key`, and
+/// the side nodes of the path from the leaf to the root.
+fn path_root(
+    key: &MerkleTreeKey,
+    leaf: Bytes32,
+    proof_set: &[Bytes32],
+) -> Option<Bytes32> {
+    if proof_set.len() > 256usize {
+        return None;
+    }
+
+    let mut current = leaf;
+    for (i, side_hash) in proof_set.iter().enumerate() {
+        #[allow(clippy::arithmetic_side_effects)] // Cannot underflow
+        let index = u32::try_from(proof_set.len() - 1 - i).ok()?;
+        current = match key.get_instruction(index)? {
+            Side::Left => calculate_node_hash(&current, side_hash),
+            Side::Right => calculate_node_hash(side_hash, &current),
+        };
+    }
+    Some(current)
+}
+
 #[cfg(test)]
 #[allow(non_snake_case)]
 mod test {
@@ -618,7 +1049,18 @@ mod test_random {
             StorageMap,
         },
         sparse::{
-            proof::Proof,
+            proof::{
+                verify_update,
+                BatchLeaf,
+                BatchProof,
+                CompressedProof,
+                CompressedProofSet,
+                ExclusionLeaf,
+                Proof,
+                SideNode,
+                UpdateOperation,
+            },
+            zero_sum,
             MerkleTree,
             MerkleTreeKey,
             Primitive,
@@ -797,4 +1239,370 @@ mod test_random {
         // Then
         assert!(exclusion);
     }
+
+    #[test]
+    fn compressed_proof__decompress__returns_the_proof() {
+        let mut rng = StdRng::seed_from_u64(0xDEADBEEF);
+        let mut storage = StorageMap::<TestTable>::new();
+        let mut tree = MerkleTree::new(&mut storage);
+
+        for _ in 0..1_000 {
+            let key = random_bytes32(&mut rng);
+            let value = random_bytes32(&mut rng);
+            tree.update(key.into(), &value).unwrap();
+        }
+        // The keys only differ by their last bits, with default side nodes on
+        // most of their common path.
+        let key = |last: u8| {
+            let mut key = [0u8; 32];
+            key[31] = last;
+            MerkleTreeKey::new_without_hash(key)
+        };
+        tree.update(key(0), &random_bytes32(&mut rng)).unwrap();
+        tree.update(key(1), &random_bytes32(&mut rng)).unwrap();
+
+        for key in [key(0), key(2)] {
+            // Given
+            let proof = tree.generate_proof(&key).unwrap();
+
+            // When
+            let compressed = proof.compress();
+
+            // Then
+            let proof_set = match &compressed {
+                CompressedProof::Inclusion(proof_set) => proof_set,
+                CompressedProof::Exclusion(proof_set, _) => proof_set,
+            };
+            let defaults = proof
+                .proof_set()
+                .iter()
+                .filter(|side_node| *side_node == zero_sum())
+                .count();
+            assert_eq!(proof_set.len as usize, proof.proof_set().len());
+            assert_eq!(
+                proof_set.side_nodes.len(),
+                proof.proof_set().len() - defaults
+            );
+            assert!(defaults > 200);
+            assert_eq!(compressed.decompress(), Some(proof));
+        }
+    }
+
+    #[test]
+    fn compressed_proof_set__decompress__returns_none_for_malformed_proof_sets() {
+        let mut rng = StdRng::seed_from_u64(0xDEADBEEF);
+        let mut proof_set = vec![*zero_sum(); 10];
+        proof_set[2] = random_bytes32(&mut rng);
+        proof_set[9] = random_bytes32(&mut rng);
+
+        let compressed = CompressedProofSet::compress(&proof_set);
+        assert_eq!(compressed.bitmap, vec![0b0010_0000, 0b0100_0000]);
+        assert_eq!(compressed.decompress(), Some(proof_set));
+
+        let decompress_with = |update: fn(&mut CompressedProofSet)| {
+            let mut compressed = compressed.clone();
+            update(&mut compressed);
+            compressed.decompress()
+        };
+        // Another length
+        assert_eq!(decompress_with(|proof_set| proof_set.len = 17), None);
+        // A bit set after the last side node
+        assert_eq!(decompress_with(|proof_set| proof_set.bitmap[1] |= 1), None);
+        // A missing and an extra side node
+        assert_eq!(
+            decompress_with(|proof_set| {
+                proof_set.side_nodes.pop();
+            }),
+            None
+        );
+        assert_eq!(
+            decompress_with(|proof_set| proof_set.side_nodes.push([1; 32])),
+            None
+        );
+    }
+
+    #[test]
+    fn batch_proof__verify__returns_true_for_included_and_excluded_keys() {
+        let mut rng = StdRng::seed_from_u64(0xDEADBEEF);
+        let mut storage = StorageMap::<TestTable>::new();
+        let mut tree = MerkleTree::new(&mut storage);
+
+        let mut entries = Vec::new();
+        for i in 0..1_000 {
+            let key = random_bytes32(&mut rng).into();
+            let value = random_bytes32(&mut rng);
+            tree.update(key, &value).unwrap();
+            if i % 50 == 0 {
+                entries.push((key, Some(value)));
+            }
+        }
+        for _ in 0..20 {
+            entries.push((random_bytes32(&mut rng).into(), None));
+        }
+        let root = tree.root();
+
+        // Given
+        let keys = entries.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+        let proof = tree.generate_batch_proof(&keys).unwrap();
+
+        // When
+        let verification = proof.verify(&root, &entries);
+
+        // Then
+        assert!(verification);
+        assert!(proof
+            .leaves
+            .iter()
+            .any(|(_, leaf)| matches!(leaf, BatchLeaf::Excluded(_))));
+        let side_nodes = keys
+            .iter()
+            .map(|key| tree.generate_proof(key).unwrap().compress())
+            .map(|proof| match proof {
+                CompressedProof::Inclusion(proof_set) => proof_set.side_nodes.len(),
+                CompressedProof::Exclusion(proof_set, _) => proof_set.side_nodes.len(),
+            })
+            .sum::<usize>();
+        assert!(proof.proof_set.side_nodes.len() < side_nodes);
+
+        // The entries can be given in any order
+        entries.reverse();
+        assert!(proof.verify(&root, &entries));
+    }
+
+    #[test]
+    fn batch_proof__verify__returns_false_for_incorrect_entries() {
+        let mut rng = StdRng::seed_from_u64(0xDEADBEEF);
+        let mut storage = StorageMap::<TestTable>::new();
+        let mut tree = MerkleTree::new(&mut storage);
+
+        let included: MerkleTreeKey = random_bytes32(&mut rng).into();
+        let value = random_bytes32(&mut rng);
+        tree.update(included, &value).unwrap();
+        for _ in 0..100 {
+            let key = random_bytes32(&mut rng);
+            let value = random_bytes32(&mut rng);
+            tree.update(key.into(), &value).unwrap();
+        }
+        let excluded: MerkleTreeKey = random_bytes32(&mut rng).into();
+        let other: MerkleTreeKey = random_bytes32(&mut rng).into();
+        let root = tree.root();
+
+        let proof = tree.generate_batch_proof(&[excluded, included]).unwrap();
+        assert!(proof.verify(&root, &[(included, Some(value)), (excluded, None)]));
+
+        // Incorrect value
+        assert!(!proof.verify(
+            &root,
+            &[(included, Some(random_bytes32(&mut rng))), (excluded, None)]
+        ));
+        // Included key claimed to be excluded, and conversely
+        assert!(!proof.verify(&root, &[(included, None::<Bytes32>), (excluded, None)]));
+        assert!(!proof.verify(&root, &[(included, Some(value)), (excluded, Some(value))]));
+        // Missing, duplicated and other keys
+        assert!(!proof.verify(&root, &[(included, Some(value))]));
+        assert!(!proof.verify(
+            &root,
+            &[(included, Some(value)), (excluded, None), (excluded, None)]
+        ));
+        assert!(!proof.verify(&root, &[(included, Some(value)), (other, None)]));
+        // Another root
+        assert!(!proof.verify(
+            &random_bytes32(&mut rng),
+            &[(included, Some(value)), (excluded, None)]
+        ));
+    }
+
+    #[test]
+    fn batch_proof__verify__returns_false_for_oversized_proofs() {
+        let key: MerkleTreeKey = [1u8; 32].into();
+        let entries = [(key, None::<Bytes32>)];
+
+        // A leaf deeper than the keys
+        let proof = BatchProof {
+            leaves: vec![(u32::MAX, BatchLeaf::Excluded(ExclusionLeaf::Placeholder))],
+            proof_set: CompressedProofSet {
+                len: 2_000_000,
+                bitmap: vec![0; 250_000],
+                side_nodes: vec![],
+            },
+        };
+        assert!(!proof.verify(zero_sum(), &entries));
+
+        // A proof set longer than the paths of the leaves
+        let proof = BatchProof {
+            leaves: vec![(257, BatchLeaf::Excluded(ExclusionLeaf::Placeholder))],
+            ..proof
+        };
+        assert!(!proof.verify(zero_sum(), &entries));
+        let proof = BatchProof {
+            leaves: vec![(256, BatchLeaf::Excluded(ExclusionLeaf::Placeholder))],
+            ..proof
+        };
+        assert!(!proof.verify(zero_sum(), &entries));
+        assert_eq!(proof.proof_set.decompress(), None);
+    }
+
+    #[test]
+    fn batch_proof__verify__returns_true_for_small_trees() {
+        let mut rng = StdRng::seed_from_u64(0xDEADBEEF);
+        let mut storage = StorageMap::<TestTable>::new();
+        let mut tree = MerkleTree::new(&mut storage);
+        let key: MerkleTreeKey = random_bytes32(&mut rng).into();
+        let other: MerkleTreeKey = random_bytes32(&mut rng).into();
+
+        // Empty tree
+        let proof = tree.generate_batch_proof(&[key, other]).unwrap();
+        assert_eq!(
+            proof.leaves,
+            vec![(0, BatchLeaf::Excluded(ExclusionLeaf::Placeholder))]
+        );
+        assert!(proof.verify(&tree.root(), &[(key, None::<Bytes32>), (other, None)]));
+
+        // Tree with a single leaf
+        let value = random_bytes32(&mut rng);
+        tree.update(key, &value).unwrap();
+        let proof = tree.generate_batch_proof(&[key, other]).unwrap();
+        assert_eq!(proof.leaves, vec![(0, BatchLeaf::Included)]);
+        assert!(proof.verify(&tree.root(), &[(key, Some(value)), (other, None)]));
+
+        // No keys
+        let proof = tree.generate_batch_proof(&[]).unwrap();
+        assert!(proof.verify::<Bytes32>(&tree.root(), &[]));
+    }
+
+    /// A key only differing from the other keys built with this function by
+    /// its last byte.
+    fn close_key(last: u8) -> MerkleTreeKey {
+        let mut key = [0u8; 32];
+        key[31] = last;
+        MerkleTreeKey::new_without_hash(key)
+    }
+
+    #[test]
+    fn update_proof__verify_update__returns_true_for_the_applied_operations() {
+        let mut rng = StdRng::seed_from_u64(0xDEADBEEF);
+        let mut storage = StorageMap::<TestTable>::new();
+        let mut tree = MerkleTree::new(&mut storage);
+
+        let mut keys = Vec::new();
+        for _ in 0..200 {
+            let key: MerkleTreeKey = random_bytes32(&mut rng).into();
+            tree.update(key, &random_bytes32(&mut rng)).unwrap();
+            keys.push(key);
+        }
+        for last in 0..4 {
+            tree.update(close_key(last), &random_bytes32(&mut rng))
+                .unwrap();
+        }
+        let root = tree.root();
+
+        // Given
+        let mut operations = Vec::new();
+        for (i, key) in keys.iter().take(40).enumerate() {
+            let operation = match i % 4 {
+                0 => UpdateOperation::Update(*key, random_bytes32(&mut rng).to_vec()),
+                1 => UpdateOperation::Delete(*key),
+                2 => UpdateOperation::Update(*key, vec![]),
+                _ => UpdateOperation::Delete(random_bytes32(&mut rng).into()),
+            };
+            operations.push(operation);
+            operations.push(UpdateOperation::Update(
+                random_bytes32(&mut rng).into(),
+                random_bytes32(&mut rng).to_vec(),
+            ));
+        }
+        operations.extend([
+            UpdateOperation::Delete(close_key(1)),
+            UpdateOperation::Delete(close_key(0)),
+            UpdateOperation::Update(close_key(0), b"DATA_0".to_vec()),
+            UpdateOperation::Update(close_key(4), b"DATA_4".to_vec()),
+            UpdateOperation::Delete(close_key(2)),
+            UpdateOperation::Delete(close_key(0)),
+            UpdateOperation::Delete(keys[100]),
+        ]);
+
+        // When
+        let proof = tree.generate_update_proof(&operations).unwrap();
+
+        // Then
+        assert!(proof.steps.iter().any(|step| step.side_node.is_some()));
+        assert!(verify_update(&root, &tree.root(), &operations, &proof));
+    }
+
+    #[test]
+    fn update_proof__verify_update__returns_true_from_and_to_the_empty_tree() {
+        let mut storage = StorageMap::<TestTable>::new();
+        let mut tree = MerkleTree::new(&mut storage);
+        let root = tree.root();
+
+        // Given
+        let operations = [
+            UpdateOperation::Update(close_key(0), b"DATA_0".to_vec()),
+            UpdateOperation::Update(close_key(1), b"DATA_1".to_vec()),
+            UpdateOperation::Update(close_key(3), b"DATA_3".to_vec()),
+            UpdateOperation::Delete(close_key(0)),
+            UpdateOperation::Delete(close_key(3)),
+            UpdateOperation::Delete(close_key(1)),
+        ];
+
+        // When
+        let proof = tree.generate_update_proof(&operations).unwrap();
+
+        // Then
+        assert_eq!(tree.root(), root);
+        assert!(verify_update(&root, &root, &operations, &proof));
+        assert!(verify_update(
+            &root,
+            &root,
+            &operations[..0],
+            &Default::default()
+        ));
+    }
+
+    #[test]
+    fn update_proof__verify_update__returns_false_for_other_operations_or_roots() {
+        let mut rng = StdRng::seed_from_u64(0xDEADBEEF);
+        let mut storage = StorageMap::<TestTable>::new();
+        let mut tree = MerkleTree::new(&mut storage);
+        for last in 0..3 {
+            tree.update(close_key(last), &random_bytes32(&mut rng))
+                .unwrap();
+        }
+        for _ in 0..20 {
+            let key = random_bytes32(&mut rng).into();
+            tree.update(key, &random_bytes32(&mut rng)).unwrap();
+        }
+        let root = tree.root();
+
+        let operations = [
+            UpdateOperation::Update(close_key(5), b"DATA_5".to_vec()),
+            UpdateOperation::Delete(close_key(1)),
+        ];
+        let proof = tree.generate_update_proof(&operations).unwrap();
+        let new_root = tree.root();
+        assert!(verify_update(&root, &new_root, &operations, &proof));
+        assert!(matches!(proof.steps[1].side_node, Some(SideNode::Leaf(_))));
+
+        // Other roots
+        assert!(!verify_update(&root, &root, &operations, &proof));
+        assert!(!verify_update(&new_root, &new_root, &operations, &proof));
+        // Other operations
+        let other_data = [
+            UpdateOperation::Update(close_key(5), b"DATA_6".to_vec()),
+            operations[1].clone(),
+        ];
+        assert!(!verify_update(&root, &new_root, &other_data, &proof));
+        let other_key = [
+            UpdateOperation::Update(close_key(6), b"DATA_5".to_vec()),
+            operations[1].clone(),
+        ];
+        assert!(!verify_update(&root, &new_root, &other_key, &proof));
+        assert!(!verify_update(&root, &new_root, &operations[..1], &proof));
+        // A side node claimed to be an internal node
+        let mut tampered = proof.clone();
+        tampered.steps[1].side_node = Some(SideNode::Node([1; 32], [2; 32]));
+        assert!(!verify_update(&root, &new_root, &operations, &tampered));
+        tampered.steps[1].side_node = None;
+        assert!(!verify_update(&root, &new_root, &operations, &tampered));
+    }
 }
@@ -68,6 +68,7 @@ pub use builder::{
 
 #[cfg(feature = "alloc")]
 pub use receipt::{
+    PanicContext,
     Receipt,
     ScriptExecutionResult,
 };
@@ -18,10 +18,12 @@ use fuel_types::{
     Word,
 };
 
+mod panic_context;
 mod receipt_repr;
 mod script_result;
 
 use crate::input::message::compute_message_id;
+pub use panic_context::PanicContext;
 pub use script_result::ScriptExecutionResult;
 
 #[derive(Clone, Derivative)]
@@ -69,6 +71,10 @@ pub enum Receipt {
         #[derivative(PartialEq = "ignore", Hash = "ignore")]
         #[canonical(skip)]
         contract_id: Option<ContractId>,
+        #[derivative(PartialEq = "ignore", Hash = "ignore")]
+        #[cfg_attr(feature = "serde", serde(default, with = "panic_context::versioned"))]
+        #[canonical(skip)]
+        panic_context: PanicContext,
     },
 
     Revert {
@@ -235,6 +241,7 @@ impl Receipt {
             pc,
             is,
             contract_id: None,
+            panic_context: PanicContext::None,
         }
     }
 
@@ -249,6 +256,19 @@ impl Receipt {
         self
     }
 
+    /// Attaches the `context` to a [`Receipt::Panic`]. Other receipts are returned
+    /// unchanged.
+    pub fn with_panic_context(mut self, context: PanicContext) -> Self {
+        if let Receipt::Panic {
+            ref mut panic_context,
+            ..
+        } = self
+        {
+            *panic_context = context;
+        }
+        self
+    }
+
     pub const fn revert(id: ContractId, ra: Word, pc: Word, is: Word) -> Self {
         Self::Revert { id, ra, pc, is }
     }
@@ -700,6 +720,16 @@ impl Receipt {
             _ => None,
         }
     }
+
+    /// Returns the context of a [`Receipt::Panic`], or `None` for other receipts.
+    /// The context is [`PanicContext::None`] when the VM has no information about
+    /// the panic.
+    pub const fn panic_context(&self) -> Option<&PanicContext> {
+        match self {
+            Self::Panic { panic_context, .. } => Some(panic_context),
+            _ => None,
+        }
+    }
 }
 
 fn trim_contract_id(id: Option<&ContractId>) -> Option<&ContractId> {
@@ -714,7 +744,16 @@ fn trim_contract_id(id: Option<&ContractId>) -> Option<&ContractId> {
 
 #[cfg(test)]
 mod tests {
+    #![allow(non_snake_case)]
+
+    #[cfg(feature = "serde")]
+    use crate::PanicContext;
     use crate::Receipt;
+    #[cfg(feature = "serde")]
+    use fuel_asm::{
+        PanicInstruction,
+        PanicReason,
+    };
     use fuel_types::ContractId;
 
     // TODO: Rewrite the test cases when `Receipt` will have its struct for
@@ -813,4 +852,39 @@ mod tests {
     fn receipt_to(#[case] receipt: Receipt, #[case] expected_to: Option<ContractId>) {
         assert_eq!(receipt.to(), expected_to.as_ref());
     }
+
+    #[cfg(feature = "serde")]
+    fn panic_receipt() -> Receipt {
+        let reason = PanicInstruction::error(PanicReason::MemoryOverflow, 0);
+        Receipt::panic(Default::default(), reason, 0, 0)
+            .with_panic_context(PanicContext::MemoryRange { start: 1, len: 2 })
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn panic_context__serde_roundtrip_keeps_the_context() {
+        let receipt = panic_receipt();
+
+        let json = serde_json::to_value(&receipt).unwrap();
+        assert_eq!(
+            json["Panic"]["panic_context"][0],
+            u64::from(PanicContext::VERSION)
+        );
+
+        let decoded: Receipt = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.panic_context(), receipt.panic_context());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn panic_context__other_versions_are_deserialized_as_none() {
+        let mut json = serde_json::to_value(panic_receipt()).unwrap();
+        json["Panic"]["panic_context"] = serde_json::json!([
+            PanicContext::VERSION.wrapping_add(1),
+            { "UnknownVariant": 3 }
+        ]);
+
+        let decoded: Receipt = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.panic_context(), Some(&PanicContext::None));
+    }
 }
@@ -0,0 +1,125 @@
+use fuel_types::{
+    AssetId,
+    ContractId,
+    Word,
+};
+
+/// Additional information about the cause of a panic, carried by [`Receipt::Panic`]
+/// to simplify debugging.
+///
+/// The context is meta information: it is not part of the canonical encoding of the
+/// receipt and is ignored when comparing receipts. New variants may be added in
+/// future versions, in which case [`PanicContext::VERSION`] is increased.
+///
+/// [`Receipt::Panic`]: crate::Receipt::Panic
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum PanicContext {
+    /// No additional information.
+    #[default]
+    None,
+    /// `ContractId` retrieved during instruction execution.
+    ContractId(ContractId),
+    /// The memory range accessed by the instruction that caused the panic.
+    MemoryRange {
+        /// Start address of the range.
+        start: Word,
+        /// Length of the range.
+        len: Word,
+    },
+    /// The asset of which the balance was not enough.
+    Balance {
+        /// The asset id.
+        asset_id: AssetId,
+        /// The amount that was required.
+        amount: Word,
+    },
+    /// The operands of the arithmetic operation that overflowed.
+    Arithmetic {
+        /// The left-hand side operand.
+        lhs: Word,
+        /// The right-hand side operand.
+        rhs: Word,
+    },
+    /// The error code returned by the ECAL handler.
+    Ecal {
+        /// The handler-specific code.
+        code: Word,
+    },
+}
+
+impl PanicContext {
+    /// The version of the panic context format. `Receipt::Panic` is serialized with
+    /// it, and contexts of other versions are deserialized as [`PanicContext::None`].
+    pub const VERSION: u8 = 1;
+
+    /// Returns `true` if the context doesn't contain any information.
+    pub const fn is_none(&self) -> bool {
+        matches!(self, Self::None)
+    }
+}
+
+/// Serializes the panic context of a receipt as a `(version, context)` tuple.
+#[cfg(feature = "serde")]
+pub(crate) mod versioned {
+    use super::PanicContext;
+    use core::fmt;
+    use serde::{
+        de::{
+            self,
+            IgnoredAny,
+            SeqAccess,
+            Visitor,
+        },
+        ser::SerializeTuple,
+        Deserializer,
+        Serializer,
+    };
+
+    pub fn serialize<S>(context: &PanicContext, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&PanicContext::VERSION)?;
+        tuple.serialize_element(context)?;
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<PanicContext, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple(2, VersionedVisitor)
+    }
+
+    struct VersionedVisitor;
+
+    impl<'de> Visitor<'de> for VersionedVisitor {
+        type Value = PanicContext;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a versioned panic context")
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let version: u8 = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+
+            if version == PanicContext::VERSION {
+                seq.next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))
+            } else {
+                // The context of another version may use unknown variants.
+                seq.next_element::<IgnoredAny>()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                Ok(PanicContext::None)
+            }
+        }
+    }
+}
@@ -274,7 +274,16 @@ impl From<ConsensusParametersV1> for ConsensusParameters {
 
 /// The versioned fee parameters.
 #[derive(
-    Copy, Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    fuel_types::canonical::Deserialize,
+    fuel_types::canonical::Serialize,
 )]
 pub enum FeeParameters {
     V1(FeeParametersV1),
@@ -336,7 +345,16 @@ impl From<FeeParametersV1> for FeeParameters {
 
 /// Consensus configurable parameters used for verifying transactions
 #[derive(
-    Copy, Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    fuel_types::canonical::Deserialize,
+    fuel_types::canonical::Serialize,
 )]
 pub struct FeeParametersV1 {
     /// Factor to convert between gas and transaction assets value.
@@ -45,6 +45,47 @@ impl serde::Serialize for GasCosts {
     }
 }
 
+#[cfg(feature = "alloc")]
+impl fuel_types::canonical::Serialize for GasCosts {
+    fn size_static(&self) -> usize {
+        self.0.size_static()
+    }
+
+    fn size_dynamic(&self) -> usize {
+        self.0.size_dynamic()
+    }
+
+    fn encode_static<O: fuel_types::canonical::Output + ?Sized>(
+        &self,
+        buffer: &mut O,
+    ) -> Result<(), fuel_types::canonical::Error> {
+        self.0.encode_static(buffer)
+    }
+
+    fn encode_dynamic<O: fuel_types::canonical::Output + ?Sized>(
+        &self,
+        buffer: &mut O,
+    ) -> Result<(), fuel_types::canonical::Error> {
+        self.0.encode_dynamic(buffer)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fuel_types::canonical::Deserialize for GasCosts {
+    fn decode_static<I: fuel_types::canonical::Input + ?Sized>(
+        buffer: &mut I,
+    ) -> Result<Self, fuel_types::canonical::Error> {
+        Ok(GasCosts(Arc::new(GasCostsValues::decode_static(buffer)?)))
+    }
+
+    fn decode_dynamic<I: fuel_types::canonical::Input + ?Sized>(
+        &mut self,
+        buffer: &mut I,
+    ) -> Result<(), fuel_types::canonical::Error> {
+        Arc::make_mut(&mut self.0).decode_dynamic(buffer)
+    }
+}
+
 #[cfg(feature = "alloc")]
 impl GasCosts {
     /// Create new cost values wrapped in an [`Arc`].
@@ -69,7 +110,17 @@ impl Default for GasCostsValues {
 }
 
 /// The versioned gas costs for every op.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    fuel_types::canonical::Deserialize,
+    fuel_types::canonical::Serialize,
+)]
 pub enum GasCostsValues {
     /// Version 1 of the gas costs.
     V1(GasCostsValuesV1),
@@ -855,7 +906,17 @@ impl GasCostsValues {
 
 /// Gas costs for every op.
 #[allow(missing_docs)]
-#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    fuel_types::canonical::Deserialize,
+    fuel_types::canonical::Serialize,
+)]
 #[serde(default = "GasCostsValuesV1::unit")]
 pub struct GasCostsValuesV1 {
     pub add: Word,
@@ -983,7 +1044,17 @@ pub struct GasCostsValuesV1 {
 /// The difference with [`GasCostsValuesV1`]:
 /// - `aloc` is a [`DependentCost`] instead of a [`Word`]
 #[allow(missing_docs)]
-#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    fuel_types::canonical::Deserialize,
+    fuel_types::canonical::Serialize,
+)]
 #[serde(default = "GasCostsValuesV2::unit")]
 pub struct GasCostsValuesV2 {
     pub add: Word,
@@ -1109,7 +1180,16 @@ pub struct GasCostsValuesV2 {
 
 /// Dependent cost is a cost that depends on the number of units.
 #[derive(
-    Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    fuel_types::canonical::Deserialize,
+    fuel_types::canonical::Serialize,
 )]
 pub enum DependentCost {
     /// When an operation is dependent on the magnitude of its inputs, and the
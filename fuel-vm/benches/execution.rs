@@ -175,5 +175,65 @@ fn execution(c: &mut Criterion) {
     group_execution.finish();
 }
 
-criterion_group!(benches, execution);
+/// Compares the `add` loop with and without record mode. Without the `record-mode`
+/// feature, the record mode hooks aren't compiled in the execution loop.
+///
+/// The benchmarks are built with the profiling features enabled by the dev-dependency
+/// on `fuel-vm`, so the execution loop of both groups also runs the profiling hooks.
+#[cfg(feature = "record-mode")]
+fn recording(c: &mut Criterion) {
+    let mut interpreter = Interpreter::<_, _, Script>::with_storage(
+        MemoryInstance::new(),
+        MemoryStorage::default(),
+        InterpreterParams {
+            gas_costs: GasCosts::free(),
+            ..Default::default()
+        },
+    )
+    .add_recording();
+    let script = TransactionBuilder::script(
+        vec![
+            op::add(RegId::WRITABLE, RegId::ZERO, RegId::ONE),
+            op::jmpb(RegId::ZERO, 0),
+        ]
+        .into_iter()
+        .collect(),
+        vec![],
+    )
+    .max_fee_limit(0)
+    .add_random_fee_input()
+    .finalize();
+    let script = script
+        .into_checked_basic(Default::default(), &Default::default())
+        .unwrap();
+    let script = script.test_into_ready();
+    black_box(interpreter.init_script(script)).unwrap();
+
+    let mut group_recording = c.benchmark_group("recording");
+
+    group_recording.bench_function("Infinite `add` loop not recording", |b| {
+        b.iter(|| {
+            for _ in 0..1000 {
+                black_box(interpreter.execute()).unwrap();
+            }
+        })
+    });
+
+    group_recording.bench_function("Infinite `add` loop recording", |b| {
+        b.iter(|| {
+            // Discards the history of the previous iteration.
+            interpreter.set_recording(true);
+            for _ in 0..1000 {
+                black_box(interpreter.execute()).unwrap();
+            }
+        })
+    });
+
+    group_recording.finish();
+}
+
+#[cfg(not(feature = "record-mode"))]
+fn recording(_: &mut Criterion) {}
+
+criterion_group!(benches, execution, recording);
 criterion_main!(benches);
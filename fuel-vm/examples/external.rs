@@ -35,6 +35,7 @@ use fuel_vm::{
     error::SimpleResult,
     interpreter::{
         EcalHandler,
+        EcalRegistry,
         Memory,
     },
     prelude::{
@@ -79,7 +80,7 @@ impl EcalHandler for FileReadEcal {
         // Allocate the buffer in the vm memory and read directly from the file into it
         vm.allocate(b)?;
         let hp = vm.registers()[RegId::HP];
-        file.read(vm.memory_mut().write_noownerchecks(hp, b)?)
+        file.read(vm.memory_write(hp, b)?)
             .map_err(|_| PanicReason::EcalError)?;
 
         Ok(())
@@ -250,8 +251,54 @@ fn example_shared_counter() {
     assert_eq!(*rd, 8);
 }
 
+fn example_registry() {
+    const ADD: u64 = 1;
+
+    let messages = Arc::new(Mutex::new(Vec::new()));
+    let output = messages.clone();
+    let registry = EcalRegistry::new()
+        // Writes `lhs + rhs` into the first argument register.
+        .with_function(ADD, 1, |ctx, (result, lhs, rhs): (RegId, u64, u64)| {
+            ctx.set_register(result, lhs.saturating_add(rhs))
+        })
+        .with_debug_functions(move |message| {
+            output.lock().expect("poisoned").push(message.to_string())
+        });
+    let vm: Interpreter<_, MemoryStorage, Script, EcalRegistry> =
+        Interpreter::with_memory_storage_and_ecal(registry);
+
+    let script = vec![
+        op::movi(0x10, ADD as u32),
+        op::movi(0x21, 2),
+        op::movi(0x22, 3),
+        op::ecal(0x10, 0x20, 0x21, 0x22), // $r32 = 2 + 3
+        op::movi(0x10, EcalRegistry::PRINT_REGISTER as u32),
+        op::ecal(0x10, 0x20, RegId::ZERO, RegId::ZERO), // Prints "r32 = 5"
+        op::ret(RegId::ONE),
+    ]
+    .into_iter()
+    .collect();
+
+    let mut client = MemoryClient::from_txtor(vm.into());
+    let consensus_params = ConsensusParameters::standard();
+    let tx = TransactionBuilder::script(script, vec![])
+        .script_gas_limit(1_000_000)
+        .maturity(Default::default())
+        .add_random_fee_input()
+        .finalize()
+        .into_checked(Default::default(), &consensus_params)
+        .expect("failed to generate a checked tx");
+    client.transact(tx);
+
+    assert_eq!(
+        *messages.lock().expect("poisoned"),
+        vec!["r32 = 5".to_string()]
+    );
+}
+
 fn main() {
     example_file_read();
     example_counter();
     example_shared_counter();
+    example_registry();
 }
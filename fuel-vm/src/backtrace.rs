@@ -4,12 +4,16 @@
 
 use alloc::{
     borrow::ToOwned,
+    sync::Arc,
     vec::Vec,
 };
+use core::fmt;
 
 use crate::{
     call::CallFrame,
+    constraints::InstructionLocation,
     consts::*,
+    debug_info::DebugInfo,
     interpreter::{
         InitialBalances,
         Interpreter,
@@ -21,6 +25,7 @@ use crate::interpreter::{
     Memory,
     MemoryInstance,
 };
+use fuel_asm::RegId;
 use fuel_tx::ScriptExecutionResult;
 use fuel_types::{
     ContractId,
@@ -37,6 +42,7 @@ pub struct Backtrace {
     memory: MemoryInstance,
     result: ScriptExecutionResult,
     initial_balances: InitialBalances,
+    debug_info: Option<Arc<DebugInfo>>,
 }
 
 impl Backtrace {
@@ -54,6 +60,7 @@ impl Backtrace {
         let contract = vm.internal_contract().unwrap_or_default();
         let memory = vm.memory().clone();
         let initial_balances = vm.initial_balances().clone();
+        let debug_info = vm.debugger().debug_info_shared();
         let mut registers = [0; VM_REGISTER_COUNT];
 
         registers.copy_from_slice(vm.registers());
@@ -65,6 +72,7 @@ impl Backtrace {
             memory,
             result,
             initial_balances,
+            debug_info,
         }
     }
 
@@ -98,6 +106,22 @@ impl Backtrace {
         &self.initial_balances
     }
 
+    /// Source-level debug information loaded into the VM when the error occurred.
+    pub fn debug_info(&self) -> Option<&DebugInfo> {
+        self.debug_info.as_deref()
+    }
+
+    /// Location of the instruction that caused the error.
+    pub fn location(&self) -> InstructionLocation {
+        location(&self.contract, &self.registers)
+    }
+
+    /// Locations of the instructions on the call stack, starting from the one that
+    /// caused the error and followed by the `CALL` instructions of its callers.
+    pub fn locations(&self) -> Vec<InstructionLocation> {
+        call_stack_locations(&self.contract, &self.registers, &self.call_stack)
+    }
+
     /// Expose the internal attributes of the backtrace.
     pub fn into_inner(
         self,
@@ -116,6 +140,7 @@ impl Backtrace {
             memory,
             result,
             initial_balances,
+            ..
         } = self;
 
         (
@@ -128,3 +153,47 @@ impl Backtrace {
         )
     }
 }
+
+/// Location of the current instruction given the registers of a context.
+fn location(contract: &ContractId, registers: &[Word]) -> InstructionLocation {
+    let context = (*contract != ContractId::zeroed()).then_some(*contract);
+    let offset = registers[RegId::PC].saturating_sub(registers[RegId::IS]);
+    InstructionLocation::new(context, offset)
+}
+
+/// Location of the instruction pointed to by `registers`, followed by the locations
+/// of the `CALL` instructions recorded in `call_stack`, innermost first.
+pub(crate) fn call_stack_locations(
+    contract: &ContractId,
+    registers: &[Word],
+    call_stack: &[CallFrame],
+) -> Vec<InstructionLocation> {
+    let callers = call_stack.iter().enumerate().rev().map(|(i, frame)| {
+        let caller = i
+            .checked_sub(1)
+            .and_then(|i| call_stack.get(i))
+            .map(CallFrame::to)
+            .copied()
+            .unwrap_or_default();
+        location(&caller, frame.registers())
+    });
+
+    core::iter::once(location(contract, registers))
+        .chain(callers)
+        .collect()
+}
+
+impl fmt::Display for Backtrace {
+    /// Renders the result and the call stack, innermost first, with the source
+    /// locations of the instructions if debug information was loaded.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:?}", self.result)?;
+        for (i, location) in self.locations().into_iter().enumerate() {
+            match self.debug_info() {
+                Some(info) => writeln!(f, "{i:>4}: {}", info.display(location))?,
+                None => writeln!(f, "{i:>4}: {location}")?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,305 @@
+//! Optimistic parallel execution of the transactions of a block.
+//!
+//! The [`BlockExecutor`] first executes the `Script` and `Create` transactions of the
+//! block in parallel, each one against a snapshot of the storage before the block,
+//! recording the storage entries it accessed with an [`AccessRecorder`]. It then
+//! commits the transactions in the block order: a speculative execution is kept if
+//! none of the transactions committed before it wrote an entry that it accessed,
+//! otherwise the transaction is executed again on top of the committed state. Other
+//! transactions are always executed in order.
+//!
+//! The resulting receipts, transactions and storage are the same as if the
+//! transactions were executed one after another with a [`Transactor`] on the same
+//! storage.
+//!
+//! [`Transactor`]: crate::transactor::Transactor
+
+use crate::{
+    checked_transaction::CheckedTransaction,
+    error::InterpreterError,
+    interpreter::{
+        EcalHandler,
+        Interpreter,
+        InterpreterParams,
+        MemoryInstance,
+        NotSupportedEcal,
+    },
+    state::ProgramState,
+    storage::{
+        AccessList,
+        AccessRecorder,
+        Changeset,
+        InterpreterStorage,
+        OverlayStorage,
+        WriteSet,
+    },
+};
+
+use alloc::{
+    vec,
+    vec::Vec,
+};
+use core::{
+    num::NonZeroUsize,
+    sync::atomic::{
+        AtomicUsize,
+        Ordering,
+    },
+};
+use fuel_tx::{
+    Receipt,
+    Script,
+    Transaction,
+};
+
+/// A transaction executed by the [`BlockExecutor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutedTransaction {
+    /// The transaction with all the modifications made by the execution.
+    pub transaction: Transaction,
+    /// The receipts of a `Script` transaction, empty for other transactions.
+    pub receipts: Vec<Receipt>,
+    /// The final state of a `Script` transaction, `None` for other transactions.
+    pub state: Option<ProgramState>,
+}
+
+/// The result of the execution of a block by the [`BlockExecutor`].
+#[derive(Debug)]
+pub struct BlockExecution<E> {
+    /// The result of every transaction, in the block order.
+    pub transactions: Vec<Result<ExecutedTransaction, InterpreterError<E>>>,
+    /// The indices of the transactions whose speculative execution conflicted with
+    /// the transactions before them, and which were executed again.
+    pub reexecuted: Vec<usize>,
+}
+
+/// The speculative execution of a transaction against the state before the block.
+struct Speculation<E> {
+    access_list: AccessList,
+    changeset: Changeset,
+    result: Result<ExecutedTransaction, InterpreterError<E>>,
+}
+
+/// Executes the transactions of a block in parallel, with the same results as a
+/// sequential execution.
+///
+/// The `Mint` transactions are not executed by the VM, and are returned unchanged.
+#[derive(Debug, Clone)]
+pub struct BlockExecutor<Ecal = NotSupportedEcal> {
+    interpreter_params: InterpreterParams,
+    ecal: Ecal,
+    threads: NonZeroUsize,
+}
+
+impl BlockExecutor {
+    /// Create an executor running the transactions with the `interpreter_params`, on
+    /// as many threads as the available parallelism.
+    pub fn new(interpreter_params: InterpreterParams) -> Self {
+        Self {
+            interpreter_params,
+            ecal: NotSupportedEcal,
+            threads: std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+        }
+    }
+}
+
+impl<Ecal> BlockExecutor<Ecal> {
+    /// Run the speculative executions on `threads` threads.
+    pub fn with_threads(mut self, threads: NonZeroUsize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Handle the ECAL instructions with `ecal`, cloned for every transaction.
+    pub fn with_ecal<E>(self, ecal: E) -> BlockExecutor<E> {
+        BlockExecutor {
+            interpreter_params: self.interpreter_params,
+            ecal,
+            threads: self.threads,
+        }
+    }
+}
+
+impl<Ecal> BlockExecutor<Ecal>
+where
+    Ecal: EcalHandler + Clone + Send + Sync,
+{
+    /// Execute the `transactions` in order on top of the `storage`, writing all their
+    /// changes into it.
+    ///
+    /// Each thread clones the `storage` once to get a snapshot of the state before
+    /// the block, so cloning should be cheap, for example a handle to a database.
+    ///
+    /// Fails only if the storage fails while committing the changes, in which case
+    /// some of the changes may be written already.
+    pub fn execute<S>(
+        &self,
+        storage: &mut S,
+        transactions: Vec<CheckedTransaction>,
+    ) -> Result<BlockExecution<S::DataError>, S::DataError>
+    where
+        S: InterpreterStorage + Clone + Send + Sync,
+        S::DataError: Send,
+    {
+        let mut speculations = self.speculate(storage, &transactions);
+
+        let mut committed = OverlayStorage::new(&mut *storage);
+        let mut written = WriteSet::default();
+        let mut results = Vec::with_capacity(transactions.len());
+        let mut reexecuted = Vec::new();
+
+        for (index, tx) in transactions.into_iter().enumerate() {
+            let (access_list, result) = match speculations[index].take() {
+                Some(speculation)
+                    if !written.conflicts_with(&speculation.access_list) =>
+                {
+                    speculation.changeset.apply(&mut committed)?;
+                    (speculation.access_list, speculation.result)
+                }
+                speculation => {
+                    if speculation.is_some() {
+                        reexecuted.push(index);
+                    }
+                    self.execute_recorded(tx, &mut committed)
+                }
+            };
+
+            written.extend(access_list.writes);
+            results.push(result);
+        }
+
+        committed.flush()?;
+
+        Ok(BlockExecution {
+            transactions: results,
+            reexecuted,
+        })
+    }
+
+    /// Execute the `Script` and `Create` transactions in parallel against snapshots
+    /// of the `storage`.
+    fn speculate<S>(
+        &self,
+        storage: &S,
+        transactions: &[CheckedTransaction],
+    ) -> Vec<Option<Speculation<S::DataError>>>
+    where
+        S: InterpreterStorage + Clone + Send + Sync,
+        S::DataError: Send,
+    {
+        let next = AtomicUsize::new(0);
+        let mut speculations: Vec<_> = transactions.iter().map(|_| None).collect();
+
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.threads.get())
+                .map(|_| {
+                    let mut snapshot = OverlayStorage::new(storage.clone());
+                    let next = &next;
+                    scope.spawn(move || {
+                        let mut done = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(tx) = transactions.get(index) else {
+                                break
+                            };
+                            if !matches!(
+                                tx,
+                                CheckedTransaction::Script(_)
+                                    | CheckedTransaction::Create(_)
+                            ) {
+                                continue
+                            }
+                            let (access_list, result) =
+                                self.execute_recorded(tx.clone(), &mut snapshot);
+                            let changeset = snapshot.take_changeset();
+                            done.push((
+                                index,
+                                Speculation {
+                                    access_list,
+                                    changeset,
+                                    result,
+                                },
+                            ));
+                        }
+                        done
+                    })
+                })
+                .collect();
+
+            for worker in workers {
+                let done = worker
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+                for (index, speculation) in done {
+                    speculations[index] = Some(speculation);
+                }
+            }
+        });
+
+        speculations
+    }
+
+    /// Execute the transaction on the `storage`, recording the accessed entries.
+    fn execute_recorded<S>(
+        &self,
+        tx: CheckedTransaction,
+        storage: S,
+    ) -> (
+        AccessList,
+        Result<ExecutedTransaction, InterpreterError<S::DataError>>,
+    )
+    where
+        S: InterpreterStorage,
+    {
+        let mut vm = Interpreter::<_, _, Script, Ecal>::with_storage_and_ecal(
+            MemoryInstance::new(),
+            AccessRecorder::new(storage),
+            self.interpreter_params.clone(),
+            self.ecal.clone(),
+        );
+        let gas_price = vm.gas_price();
+        let gas_costs = vm.gas_costs().clone();
+        let fee_params = *vm.fee_params();
+
+        let result = match tx {
+            CheckedTransaction::Script(checked) => checked
+                .into_ready(gas_price, &gas_costs, &fee_params)
+                .map_err(InterpreterError::CheckError)
+                .and_then(|ready| {
+                    vm.transact(ready).map(|state| ExecutedTransaction {
+                        transaction: state.tx().clone().into(),
+                        receipts: state.receipts().to_vec(),
+                        state: Some(*state.state()),
+                    })
+                }),
+            CheckedTransaction::Create(checked) => checked
+                .into_ready(gas_price, &gas_costs, &fee_params)
+                .map_err(InterpreterError::CheckError)
+                .and_then(|ready| vm.deploy(ready))
+                .map(|tx| executed(tx.into())),
+            CheckedTransaction::Upgrade(checked) => checked
+                .into_ready(gas_price, &gas_costs, &fee_params)
+                .map_err(InterpreterError::CheckError)
+                .and_then(|ready| vm.upgrade(ready))
+                .map(|tx| executed(tx.into())),
+            CheckedTransaction::Upload(checked) => checked
+                .into_ready(gas_price, &gas_costs, &fee_params)
+                .map_err(InterpreterError::CheckError)
+                .and_then(|ready| vm.upload(ready))
+                .map(|tx| executed(tx.into())),
+            CheckedTransaction::Mint(checked) => {
+                Ok(executed(checked.transaction().clone().into()))
+            }
+        };
+
+        (vm.as_mut().take_access_list(), result)
+    }
+}
+
+fn executed(transaction: Transaction) -> ExecutedTransaction {
+    ExecutedTransaction {
+        transaction,
+        receipts: vec![],
+        state: None,
+    }
+}
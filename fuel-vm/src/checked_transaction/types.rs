@@ -232,6 +232,38 @@ pub mod script {
             Ok(Checked::basic(self, metadata))
         }
     }
+
+    #[cfg(feature = "replay")]
+    impl Checked<Script> {
+        /// Check the script of a replayed execution with the parameters of the
+        /// interpreter. The consensus parameters of the original execution are not
+        /// known, so only the metadata is computed.
+        pub(crate) fn replayed(
+            mut tx: Script,
+            block_height: BlockHeight,
+            params: &crate::interpreter::InterpreterParams,
+        ) -> Result<Self, CheckError> {
+            tx.precompute(&params.chain_id)?;
+
+            let AvailableBalances {
+                non_retryable_balances,
+                retryable_balance,
+            } = initial_free_balances(&tx, &params.base_asset_id)?;
+
+            let metadata = CheckedMetadata {
+                non_retryable_balances: NonRetryableFreeBalances(non_retryable_balances),
+                retryable_balance: RetryableAmount {
+                    amount: retryable_balance,
+                    base_asset_id: params.base_asset_id,
+                },
+                block_height,
+                min_gas: tx.min_gas(&params.gas_costs, &params.fee_params),
+                max_gas: tx.max_gas(&params.gas_costs, &params.fee_params),
+            };
+
+            Ok(Checked::basic(tx, metadata))
+        }
+    }
 }
 
 /// For [`fuel_tx::Upgrade`]
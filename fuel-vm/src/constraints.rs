@@ -1,5 +1,15 @@
 //! Types to help constrain inputs to functions to only what is used.
 
+use alloc::{
+    format,
+    string::{
+        String,
+        ToString,
+    },
+};
+use core::fmt;
+
+use fuel_tx::Receipt;
 use fuel_types::ContractId;
 
 pub mod reg_key;
@@ -12,3 +22,54 @@ pub struct InstructionLocation {
     /// Offset from the IS register
     pub offset: u64,
 }
+
+impl InstructionLocation {
+    /// New location from context and offset
+    pub const fn new(context: Option<ContractId>, offset: u64) -> Self {
+        Self { context, offset }
+    }
+
+    /// Location of the instruction that produced a `Panic` or `Revert` receipt.
+    ///
+    /// The zeroed contract id of the receipts produced by scripts maps to the `None`
+    /// context.
+    pub fn from_receipt(receipt: &Receipt) -> Option<Self> {
+        match receipt {
+            Receipt::Panic { id, pc, is, .. } | Receipt::Revert { id, pc, is, .. } => {
+                let context = (*id != ContractId::zeroed()).then_some(*id);
+                Some(Self::new(context, pc.saturating_sub(*is)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Context, i.e. current contract
+    pub const fn context(&self) -> Option<ContractId> {
+        self.context
+    }
+
+    /// Offset from the IS register
+    pub const fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl fmt::Display for InstructionLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Location({}, offset={})",
+            self.context
+                .map(|contract_id| format!(
+                    "contract_id={}",
+                    contract_id.iter().fold(String::new(), |mut output, b| {
+                        use core::fmt::Write;
+                        let _ = write!(output, "{b:02x?}");
+                        output
+                    })
+                ),)
+                .unwrap_or_else(|| "script".to_string()),
+            self.offset
+        )
+    }
+}
@@ -0,0 +1,269 @@
+//! Source-level debug information of the executed programs.
+//!
+//! Compilers can emit a side table that maps the offsets of instructions within the
+//! bytecode to the source locations they were generated from. Once the table is loaded
+//! into the [`Interpreter`](crate::interpreter::Interpreter), breakpoints, backtraces,
+//! panic receipts and gas profiles can be rendered with the source locations of the
+//! compiled program.
+
+use alloc::{
+    collections::BTreeMap,
+    string::String,
+};
+use core::fmt;
+
+use fuel_tx::Receipt;
+use fuel_types::{
+    ContractId,
+    Word,
+};
+
+use crate::constraints::InstructionLocation;
+
+/// A location in the source code of a program.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceLocation {
+    /// Path of the source file.
+    pub file: String,
+    /// Line within the source file, starting from 1.
+    pub line: u32,
+    /// Name of the function the location belongs to, if known.
+    pub function: Option<String>,
+}
+
+impl SourceLocation {
+    /// Create a new source location.
+    pub fn new<F: Into<String>>(file: F, line: u32) -> Self {
+        Self {
+            file: file.into(),
+            line,
+            function: None,
+        }
+    }
+
+    /// Set the name of the function the location belongs to.
+    pub fn with_function<F: Into<String>>(mut self, function: F) -> Self {
+        self.function = Some(function.into());
+        self
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)?;
+        if let Some(function) = &self.function {
+            write!(f, " in {function}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Mapping of the instruction offsets of a single program to source locations.
+///
+/// Every entry covers the instructions from its offset up to the offset of the next
+/// entry, so only the first instruction of each source line has to be recorded.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProgramDebugInfo {
+    locations: BTreeMap<Word, SourceLocation>,
+}
+
+impl ProgramDebugInfo {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map the instructions starting at `offset` bytes from `$is` to `location`.
+    pub fn insert(&mut self, offset: Word, location: SourceLocation) {
+        self.locations.insert(offset, location);
+    }
+
+    /// Source location of the instruction at `offset` bytes from `$is`.
+    pub fn resolve(&self, offset: Word) -> Option<&SourceLocation> {
+        self.locations
+            .range(..=offset)
+            .next_back()
+            .map(|(_, location)| location)
+    }
+
+    /// Iterate through the recorded offsets and their locations in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Word, &SourceLocation)> {
+        self.locations.iter()
+    }
+}
+
+impl FromIterator<(Word, SourceLocation)> for ProgramDebugInfo {
+    fn from_iter<I: IntoIterator<Item = (Word, SourceLocation)>>(iter: I) -> Self {
+        Self {
+            locations: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// Debug information for the script and the contracts executed by a transaction.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DebugInfo {
+    script: ProgramDebugInfo,
+    contracts: BTreeMap<ContractId, ProgramDebugInfo>,
+}
+
+impl DebugInfo {
+    /// Create an empty set of debug information.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the debug information of the script.
+    pub fn set_script(&mut self, info: ProgramDebugInfo) {
+        self.script = info;
+    }
+
+    /// Set the debug information of a contract.
+    pub fn set_contract(&mut self, contract: ContractId, info: ProgramDebugInfo) {
+        self.contracts.insert(contract, info);
+    }
+
+    /// Debug information of the script.
+    pub const fn script(&self) -> &ProgramDebugInfo {
+        &self.script
+    }
+
+    /// Debug information of a contract, if loaded.
+    pub fn contract(&self, contract: &ContractId) -> Option<&ProgramDebugInfo> {
+        self.contracts.get(contract)
+    }
+
+    /// Debug information of the program running in the given context. The script is
+    /// represented either by `None` or by the zeroed contract id, as used by
+    /// breakpoints and receipts.
+    pub fn program(&self, context: Option<&ContractId>) -> Option<&ProgramDebugInfo> {
+        match context {
+            Some(contract) if *contract != ContractId::zeroed() => {
+                self.contract(contract)
+            }
+            _ => Some(&self.script),
+        }
+    }
+
+    /// Source location of the instruction.
+    pub fn resolve(&self, location: &InstructionLocation) -> Option<&SourceLocation> {
+        self.program(location.context.as_ref())?
+            .resolve(location.offset)
+    }
+
+    /// Source location of the instruction that produced a `Panic` or `Revert` receipt.
+    pub fn resolve_receipt(&self, receipt: &Receipt) -> Option<&SourceLocation> {
+        self.resolve(&InstructionLocation::from_receipt(receipt)?)
+    }
+
+    /// Render the instruction location along with its source location, if known.
+    pub fn display(&self, location: InstructionLocation) -> SourcedLocation<'_> {
+        SourcedLocation {
+            location,
+            source: self.resolve(&location),
+        }
+    }
+}
+
+/// An instruction location paired with its source location, rendered as
+/// `Location(...) at file:line in function`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourcedLocation<'a> {
+    /// Location of the instruction.
+    pub location: InstructionLocation,
+    /// Source location of the instruction, if known.
+    pub source: Option<&'a SourceLocation>,
+}
+
+impl fmt::Display for SourcedLocation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.location)?;
+        if let Some(source) = self.source {
+            write!(f, " at {source}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use fuel_asm::{
+        PanicInstruction,
+        PanicReason,
+    };
+
+    fn sample() -> DebugInfo {
+        let mut info = DebugInfo::new();
+        info.set_script(
+            [
+                (0, SourceLocation::new("main.sw", 3).with_function("main")),
+                (8, SourceLocation::new("main.sw", 4).with_function("main")),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        info.set_contract(
+            ContractId::from([1; 32]),
+            [(4, SourceLocation::new("lib.sw", 10))]
+                .into_iter()
+                .collect(),
+        );
+        info
+    }
+
+    #[test]
+    fn resolve__uses_closest_preceding_offset() {
+        let info = sample();
+
+        let resolve = |context, offset| {
+            info.resolve(&InstructionLocation::new(context, offset))
+                .map(|l| l.line)
+        };
+
+        assert_eq!(resolve(None, 0), Some(3));
+        assert_eq!(resolve(None, 4), Some(3));
+        assert_eq!(resolve(None, 8), Some(4));
+        assert_eq!(resolve(None, 400), Some(4));
+        assert_eq!(resolve(Some(ContractId::zeroed()), 8), Some(4));
+        assert_eq!(resolve(Some(ContractId::from([1; 32])), 0), None);
+        assert_eq!(resolve(Some(ContractId::from([1; 32])), 4), Some(10));
+        assert_eq!(resolve(Some(ContractId::from([2; 32])), 4), None);
+    }
+
+    #[test]
+    fn resolve_receipt__maps_panic_to_source() {
+        let info = sample();
+        let reason = PanicInstruction::error(
+            PanicReason::ArithmeticOverflow,
+            fuel_asm::op::noop().into(),
+        );
+        let receipt = Receipt::panic(ContractId::zeroed(), reason, 1000 + 12, 1000);
+
+        assert_eq!(
+            info.resolve_receipt(&receipt),
+            Some(&SourceLocation::new("main.sw", 4).with_function("main"))
+        );
+    }
+
+    #[test]
+    fn display__includes_source_location() {
+        let info = sample();
+
+        assert_eq!(
+            alloc::format!("{}", info.display(InstructionLocation::new(None, 8))),
+            "Location(script, offset=8) at main.sw:4 in main"
+        );
+        assert_eq!(
+            alloc::format!(
+                "{}",
+                info.display(InstructionLocation::new(Some([2; 32].into()), 8))
+            ),
+            "Location(contract_id=0202020202020202020202020202020202020202020202020202020202020202, offset=8)"
+        );
+    }
+}
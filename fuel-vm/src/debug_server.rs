@@ -0,0 +1,547 @@
+//! Remote debugger protocol server.
+//!
+//! Exposes an [`Interpreter`] running in debug mode to an external debugger, such as
+//! an IDE extension, over a local TCP socket or stdio. Messages are JSON objects
+//! framed with a `Content-Length` header, following the conventions of the Debug
+//! Adapter Protocol:
+//!
+//! ```text
+//! Content-Length: 46\r\n
+//! \r\n
+//! {"seq":1,"type":"request","command":"continue"}
+//! ```
+//!
+//! Every request is answered with a response carrying the same `seq` as its
+//! `request_seq`. Whenever the execution stops on a breakpoint or after a single step
+//! the server emits a `stopped` event, and a `terminated` event once the program has
+//! finished.
+
+use std::{
+    fmt::Write as _,
+    io::{
+        self,
+        BufRead,
+        BufReader,
+        Read,
+        Write,
+    },
+    net::{
+        TcpListener,
+        ToSocketAddrs,
+    },
+};
+
+use fuel_asm::{
+    Instruction,
+    RegId,
+};
+use fuel_types::{
+    ContractId,
+    Word,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    consts::VM_MAX_RAM,
+    debug_info::SourceLocation,
+    interpreter::{
+        EcalHandler,
+        ExecutableTransaction,
+        Interpreter,
+        Memory,
+    },
+    state::{
+        Breakpoint,
+        DebugEval,
+        ProgramState,
+    },
+    storage::InterpreterStorage,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Maximum number of bytes returned by a single [`Command::ReadMemory`] request.
+pub const MAX_READ_MEMORY_LENGTH: Word = 64 * 1024;
+
+/// The maximum `Content-Length` of a message read by the debug server.
+pub const MAX_MESSAGE_LENGTH: usize = 1024 * 1024;
+
+/// A request sent by the debugger client.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Request {
+    /// Sequence number of the request, echoed back in the response.
+    pub seq: u64,
+    /// The command to execute.
+    #[serde(flatten)]
+    pub command: Command,
+}
+
+/// Commands supported by the debug server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", content = "arguments", rename_all = "camelCase")]
+pub enum Command {
+    /// Replace all breakpoints with the given set.
+    SetBreakpoints {
+        /// The new breakpoints.
+        breakpoints: Vec<BreakpointLocation>,
+    },
+    /// Remove all breakpoints.
+    ClearBreakpoints,
+    /// Run until the next breakpoint or the end of the program.
+    Continue,
+    /// Execute a single instruction.
+    Step,
+    /// Read the values of all registers.
+    Registers,
+    /// Read a range of the VM memory.
+    ReadMemory {
+        /// Address of the first byte to read.
+        address: Word,
+        /// Number of bytes to read, at most [`MAX_READ_MEMORY_LENGTH`].
+        length: Word,
+    },
+    /// Read the locations of the call frames, innermost first.
+    StackTrace,
+    /// End the debugging session.
+    Disconnect,
+}
+
+/// Location of a breakpoint as sent by the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BreakpointLocation {
+    /// Contract to break in, or the script if omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract: Option<ContractId>,
+    /// Index of the instruction to break at.
+    pub pc: Word,
+}
+
+/// A message sent by the debug server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Message {
+    /// Result of a request.
+    Response(Response),
+    /// Notification about a change of the execution state.
+    Event(Event),
+}
+
+/// Result of a [`Request`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Response {
+    /// Sequence number of the request this response belongs to.
+    pub request_seq: u64,
+    /// Whether the request was executed successfully.
+    pub success: bool,
+    /// Error description of a failed request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Data returned by the request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<ResponseBody>,
+}
+
+impl Response {
+    fn success(request_seq: u64, body: Option<ResponseBody>) -> Self {
+        Self {
+            request_seq,
+            success: true,
+            message: None,
+            body,
+        }
+    }
+
+    fn error<E: ToString>(request_seq: u64, error: E) -> Self {
+        Self {
+            request_seq,
+            success: false,
+            message: Some(error.to_string()),
+            body: None,
+        }
+    }
+}
+
+/// Data returned by a successful request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ResponseBody {
+    /// Values of the registers, in register order.
+    Registers {
+        /// The registers.
+        registers: Vec<Register>,
+    },
+    /// Content of a memory range.
+    Memory {
+        /// Address of the first byte.
+        address: Word,
+        /// Hex-encoded bytes.
+        data: String,
+    },
+    /// The call frames, innermost first.
+    StackTrace {
+        /// The frames.
+        frames: Vec<StackFrame>,
+    },
+}
+
+/// Value of a single register.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Register {
+    /// Name of the register, as used by the assembly syntax.
+    pub name: String,
+    /// Value of the register.
+    pub value: Word,
+}
+
+/// A call frame of the running program.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackFrame {
+    /// Contract executing the frame, or `None` for the script.
+    pub contract: Option<ContractId>,
+    /// Offset in bytes of the current instruction of the frame from `$is`.
+    pub pc: Word,
+    /// Source location of the instruction, if debug information was loaded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceLocation>,
+}
+
+/// Notification about a change of the execution state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", content = "body", rename_all = "camelCase")]
+pub enum Event {
+    /// The execution was interrupted.
+    Stopped {
+        /// Why the execution was interrupted.
+        reason: StopReason,
+        /// Location of the next instruction to be executed.
+        frame: StackFrame,
+    },
+    /// The program finished.
+    Terminated {
+        /// Final state of the program.
+        state: ProgramState,
+    },
+}
+
+/// Reason of a [`Event::Stopped`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StopReason {
+    /// A breakpoint set by the client was hit.
+    Breakpoint,
+    /// A single step was executed.
+    Step,
+}
+
+/// Serves debugger requests for an interpreter that was started in debug mode.
+///
+/// Breakpoints or single-stepping have to be configured on the interpreter before the
+/// transaction is executed; the returned [`ProgramState`] is then handed over to the
+/// server, which resumes the execution on behalf of the client.
+#[derive(Debug)]
+pub struct DebugServer<'vm, M, S, Tx, Ecal> {
+    vm: &'vm mut Interpreter<M, S, Tx, Ecal>,
+    state: ProgramState,
+}
+
+impl<'vm, M, S, Tx, Ecal> DebugServer<'vm, M, S, Tx, Ecal>
+where
+    M: Memory,
+    S: InterpreterStorage,
+    Tx: ExecutableTransaction,
+    Ecal: EcalHandler,
+{
+    /// Create a server for `vm`, interrupted with `state`.
+    pub fn new(vm: &'vm mut Interpreter<M, S, Tx, Ecal>, state: ProgramState) -> Self {
+        Self { vm, state }
+    }
+
+    /// Current state of the program.
+    pub const fn state(&self) -> &ProgramState {
+        &self.state
+    }
+
+    /// Event describing the current state of the program.
+    pub fn status(&self) -> Event {
+        match self.state {
+            ProgramState::RunProgram(_) | ProgramState::VerifyPredicate(_) => {
+                // Single steps are reported by the VM as breakpoints at the next
+                // instruction, so a step only counts as a breakpoint hit if the
+                // client set a breakpoint at that instruction.
+                let reason = match self.state.debug_ref() {
+                    Some(DebugEval::Breakpoint(breakpoint))
+                        if self.vm.has_breakpoint(breakpoint) =>
+                    {
+                        StopReason::Breakpoint
+                    }
+                    _ if self.vm.single_stepping() => StopReason::Step,
+                    _ => StopReason::Breakpoint,
+                };
+                Event::Stopped {
+                    reason,
+                    frame: self.frames().swap_remove(0),
+                }
+            }
+            state => Event::Terminated { state },
+        }
+    }
+
+    /// Execute a request. Returns the response, followed by an event if the request
+    /// changed the execution state.
+    pub fn handle(&mut self, request: Request) -> Vec<Message> {
+        let seq = request.seq;
+        let resumed = matches!(request.command, Command::Continue | Command::Step);
+        let result = match request.command {
+            Command::SetBreakpoints { breakpoints } => {
+                self.set_breakpoints(&breakpoints).map(|_| None)
+            }
+            Command::ClearBreakpoints => {
+                self.vm.clear_breakpoints();
+                Ok(None)
+            }
+            Command::Continue => self.resume(false),
+            Command::Step => self.resume(true),
+            Command::Registers => Ok(Some(self.registers())),
+            Command::ReadMemory { address, length } => {
+                self.read_memory(address, length).map(Some)
+            }
+            Command::StackTrace => Ok(Some(ResponseBody::StackTrace {
+                frames: self.frames(),
+            })),
+            Command::Disconnect => Ok(None),
+        };
+
+        let mut messages = Vec::with_capacity(2);
+        match result {
+            Ok(body) => {
+                messages.push(Message::Response(Response::success(seq, body)));
+                if resumed {
+                    messages.push(Message::Event(self.status()));
+                }
+            }
+            Err(error) => messages.push(Message::Response(Response::error(seq, error))),
+        }
+        messages
+    }
+
+    /// Serve requests read from `reader`, writing the responses and events to
+    /// `writer`, until the client disconnects or closes the stream.
+    ///
+    /// Malformed requests, including messages with an invalid header, are answered
+    /// with an error response and don't end the session.
+    pub fn serve<R, W>(&mut self, mut reader: R, mut writer: W) -> io::Result<()>
+    where
+        R: BufRead,
+        W: Write,
+    {
+        write_message(&mut writer, &Message::Event(self.status()))?;
+
+        loop {
+            let payload = match read_message(&mut reader) {
+                Ok(Some(payload)) => payload,
+                Ok(None) => break,
+                Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+                    let response = Response::error(0, error);
+                    write_message(&mut writer, &Message::Response(response))?;
+                    continue
+                }
+                Err(error) => return Err(error),
+            };
+            let request: Request = match serde_json::from_slice(&payload) {
+                Ok(request) => request,
+                Err(error) => {
+                    let response = Response::error(request_seq(&payload), error);
+                    write_message(&mut writer, &Message::Response(response))?;
+                    continue
+                }
+            };
+            let disconnect = request.command == Command::Disconnect;
+
+            for message in self.handle(request) {
+                write_message(&mut writer, &message)?;
+            }
+
+            if disconnect {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Serve requests over the standard input and output of the process.
+    pub fn serve_stdio(&mut self) -> io::Result<()> {
+        self.serve(io::stdin().lock(), io::stdout().lock())
+    }
+
+    /// Accept a single client connection on `addr` and serve its requests.
+    pub fn serve_tcp<A: ToSocketAddrs>(&mut self, addr: A) -> io::Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        let (stream, _) = listener.accept()?;
+        self.serve(BufReader::new(stream.try_clone()?), stream)
+    }
+
+    fn set_breakpoints(
+        &mut self,
+        breakpoints: &[BreakpointLocation],
+    ) -> Result<(), String> {
+        let breakpoints = breakpoints
+            .iter()
+            .map(|bp| {
+                bp.pc
+                    .checked_mul(Instruction::SIZE as Word)
+                    .filter(|pc| *pc <= VM_MAX_RAM)
+                    .ok_or_else(|| format!("breakpoint out of range: {}", bp.pc))?;
+                Ok(Breakpoint::new(bp.contract.unwrap_or_default(), bp.pc))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        self.vm.overwrite_breakpoints(&breakpoints);
+        Ok(())
+    }
+
+    fn resume(&mut self, single_stepping: bool) -> Result<Option<ResponseBody>, String> {
+        match self.state {
+            ProgramState::RunProgram(_) => {}
+            ProgramState::VerifyPredicate(_) => {
+                return Err("resuming predicates is not supported".into())
+            }
+            _ => return Err("the program has terminated".into()),
+        }
+
+        self.vm.set_single_stepping(single_stepping);
+        self.state = self.vm.resume().map_err(|e| format!("{e:?}"))?;
+        Ok(None)
+    }
+
+    fn registers(&self) -> ResponseBody {
+        let registers = self
+            .vm
+            .registers()
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let name = u8::try_from(i)
+                    .map(|i| RegId::new(i).to_string())
+                    .unwrap_or_default();
+                Register {
+                    name,
+                    value: *value,
+                }
+            })
+            .collect();
+
+        ResponseBody::Registers { registers }
+    }
+
+    fn read_memory(&self, address: Word, length: Word) -> Result<ResponseBody, String> {
+        if length > MAX_READ_MEMORY_LENGTH {
+            return Err(format!(
+                "memory read too long: {length} bytes, at most {MAX_READ_MEMORY_LENGTH}"
+            ))
+        }
+
+        let bytes = self
+            .vm
+            .memory()
+            .read(address, length)
+            .map_err(|reason| format!("{reason:?}"))?;
+
+        let data = bytes.iter().fold(String::new(), |mut data, byte| {
+            let _ = write!(data, "{byte:02x}");
+            data
+        });
+
+        Ok(ResponseBody::Memory { address, data })
+    }
+
+    fn frames(&self) -> Vec<StackFrame> {
+        let debug_info = self.vm.debug_info();
+
+        self.vm
+            .call_stack_locations()
+            .into_iter()
+            .map(|location| StackFrame {
+                contract: location.context,
+                pc: location.offset,
+                source: debug_info.and_then(|info| info.resolve(&location)).cloned(),
+            })
+            .collect()
+    }
+}
+
+/// The `seq` of a request that can't be parsed, or `0` if it has none.
+fn request_seq(payload: &[u8]) -> u64 {
+    serde_json::from_slice::<serde_json::Value>(payload)
+        .ok()
+        .and_then(|value| value.get("seq")?.as_u64())
+        .unwrap_or_default()
+}
+
+/// Read a single `Content-Length` framed message. Returns `None` once the stream is
+/// closed.
+///
+/// A message with an invalid header or longer than [`MAX_MESSAGE_LENGTH`] fails with
+/// [`io::ErrorKind::InvalidData`]. The payload of a message too long is skipped, so
+/// the next message can still be read.
+pub fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut length = None;
+
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                // The error is returned after the end of the header, so the next
+                // message can still be read.
+                length = Some(value.trim().parse::<usize>().map_err(|e| {
+                    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+                }));
+            }
+        }
+    }
+
+    let length = length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+    })??;
+
+    if length > MAX_MESSAGE_LENGTH {
+        let length = length as u64;
+        if io::copy(&mut reader.by_ref().take(length), &mut io::sink())? < length {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Content-Length {length} exceeds {MAX_MESSAGE_LENGTH} bytes"),
+        ));
+    }
+
+    let mut payload = vec![0; length];
+    reader.read_exact(&mut payload)?;
+    Ok(Some(payload))
+}
+
+/// Write a message as JSON, framed with a `Content-Length` header.
+pub fn write_message<W: Write, T: Serialize>(
+    writer: &mut W,
+    message: &T,
+) -> io::Result<()> {
+    let payload = serde_json::to_vec(message)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    write!(writer, "Content-Length: {}\r\n\r\n", payload.len())?;
+    writer.write_all(&payload)?;
+    writer.flush()
+}
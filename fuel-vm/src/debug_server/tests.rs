@@ -0,0 +1,416 @@
+#![allow(non_snake_case)]
+
+use fuel_asm::op;
+use fuel_tx::{
+    ConsensusParameters,
+    Finalizable,
+    Script,
+    TransactionBuilder,
+};
+
+use super::*;
+use crate::{
+    checked_transaction::{
+        IntoChecked,
+        Ready,
+    },
+    consts::VM_REGISTER_COUNT,
+    debug_info::{
+        DebugInfo,
+        SourceLocation,
+    },
+    interpreter::{
+        MemoryInstance,
+        NotSupportedEcal,
+    },
+    storage::MemoryStorage,
+};
+
+type Vm = Interpreter<MemoryInstance, MemoryStorage, Script, NotSupportedEcal>;
+
+fn script_tx() -> Ready<Script> {
+    let script = [
+        op::movi(0x10, 1),
+        op::movi(0x11, 2),
+        op::add(0x12, 0x10, 0x11),
+        op::ret(0x12),
+    ]
+    .into_iter()
+    .collect();
+
+    let consensus_params = ConsensusParameters::standard();
+    TransactionBuilder::script(script, vec![])
+        .script_gas_limit(1_000_000)
+        .add_random_fee_input()
+        .finalize()
+        .into_checked(Default::default(), &consensus_params)
+        .expect("failed to generate checked tx")
+        .into_ready(
+            0,
+            consensus_params.gas_costs(),
+            consensus_params.fee_params(),
+        )
+        .expect("failed to finalize tx")
+}
+
+fn start(vm: &mut Vm) -> ProgramState {
+    vm.set_breakpoint(Breakpoint::script(1));
+    vm.transact(script_tx())
+        .map(ProgramState::from)
+        .expect("failed to execute script")
+}
+
+fn request(seq: u64, command: Command) -> Request {
+    Request { seq, command }
+}
+
+fn response(messages: &[Message]) -> &Response {
+    match messages.first() {
+        Some(Message::Response(response)) => response,
+        other => panic!("expected a response, got {other:?}"),
+    }
+}
+
+#[test]
+fn request__parses_dap_style_json() {
+    let parsed: Request =
+        serde_json::from_str(r#"{"seq":1,"type":"request","command":"continue"}"#)
+            .unwrap();
+    assert_eq!(parsed, request(1, Command::Continue));
+
+    let parsed: Request = serde_json::from_str(
+        r#"{"seq":2,"command":"readMemory","arguments":{"address":16,"length":8}}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        parsed,
+        request(
+            2,
+            Command::ReadMemory {
+                address: 16,
+                length: 8
+            }
+        )
+    );
+
+    let parsed: Request = serde_json::from_str(
+        r#"{"seq":3,"command":"setBreakpoints","arguments":{"breakpoints":[{"pc":2}]}}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        parsed,
+        request(
+            3,
+            Command::SetBreakpoints {
+                breakpoints: vec![BreakpointLocation {
+                    contract: None,
+                    pc: 2
+                }]
+            }
+        )
+    );
+}
+
+#[test]
+fn handle__stops_at_breakpoints_and_reads_state() {
+    let mut vm = Vm::with_memory_storage();
+    let state = start(&mut vm);
+
+    let mut debug_info = DebugInfo::new();
+    debug_info.set_script(
+        [(8, SourceLocation::new("main.sw", 7))]
+            .into_iter()
+            .collect(),
+    );
+    vm.set_debug_info(debug_info);
+
+    let mut server = DebugServer::new(&mut vm, state);
+    assert_eq!(
+        server.status(),
+        Event::Stopped {
+            reason: StopReason::Breakpoint,
+            frame: StackFrame {
+                contract: None,
+                pc: 4,
+                source: None,
+            },
+        }
+    );
+
+    let messages = server.handle(request(
+        1,
+        Command::SetBreakpoints {
+            breakpoints: vec![BreakpointLocation {
+                contract: None,
+                pc: 3,
+            }],
+        },
+    ));
+    assert!(response(&messages).success);
+
+    let messages = server.handle(request(2, Command::Continue));
+    assert_eq!(
+        messages,
+        vec![
+            Message::Response(Response::success(2, None)),
+            Message::Event(Event::Stopped {
+                reason: StopReason::Breakpoint,
+                frame: StackFrame {
+                    contract: None,
+                    pc: 12,
+                    source: Some(SourceLocation::new("main.sw", 7)),
+                },
+            }),
+        ]
+    );
+
+    let messages = server.handle(request(3, Command::Registers));
+    let Some(ResponseBody::Registers { registers }) = &response(&messages).body else {
+        panic!("expected registers");
+    };
+    assert_eq!(registers.len(), VM_REGISTER_COUNT);
+    assert_eq!(registers[0x12].name, "r18");
+    assert_eq!(registers[0x12].value, 3);
+    assert_eq!(registers[RegId::ONE.to_u8() as usize].name, "$one");
+
+    let messages = server.handle(request(4, Command::StackTrace));
+    let Some(ResponseBody::StackTrace { frames }) = &response(&messages).body else {
+        panic!("expected a stack trace");
+    };
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].pc, 12);
+
+    let is = server.vm.registers()[RegId::IS];
+    let messages = server.handle(request(
+        5,
+        Command::ReadMemory {
+            address: is,
+            length: 4,
+        },
+    ));
+    let expected: [u8; 4] = op::movi(0x10, 1).to_bytes();
+    let expected = expected.iter().fold(String::new(), |mut hex, b| {
+        let _ = write!(hex, "{b:02x}");
+        hex
+    });
+    assert_eq!(
+        response(&messages).body,
+        Some(ResponseBody::Memory {
+            address: is,
+            data: expected
+        })
+    );
+
+    let messages = server.handle(request(6, Command::Continue));
+    assert_eq!(
+        messages.get(1),
+        Some(&Message::Event(Event::Terminated {
+            state: ProgramState::Return(3)
+        }))
+    );
+
+    let messages = server.handle(request(7, Command::Step));
+    assert!(!response(&messages).success);
+}
+
+#[test]
+fn handle__step_stops_at_next_instruction() {
+    let mut vm = Vm::with_memory_storage();
+    let state = start(&mut vm);
+    let mut server = DebugServer::new(&mut vm, state);
+
+    let messages = server.handle(request(1, Command::Step));
+    assert_eq!(
+        messages.get(1),
+        Some(&Message::Event(Event::Stopped {
+            reason: StopReason::Step,
+            frame: StackFrame {
+                contract: None,
+                pc: 8,
+                source: None,
+            },
+        }))
+    );
+}
+
+#[test]
+fn handle__step_onto_a_breakpoint_is_reported_as_a_breakpoint() {
+    let mut vm = Vm::with_memory_storage();
+    let state = start(&mut vm);
+    vm.set_breakpoint(Breakpoint::script(2));
+    let mut server = DebugServer::new(&mut vm, state);
+
+    let messages = server.handle(request(1, Command::Step));
+    assert!(matches!(
+        messages.get(1),
+        Some(Message::Event(Event::Stopped {
+            reason: StopReason::Breakpoint,
+            frame: StackFrame { pc: 8, .. },
+        }))
+    ));
+
+    let messages = server.handle(request(2, Command::Step));
+    assert!(matches!(
+        messages.get(1),
+        Some(Message::Event(Event::Stopped {
+            reason: StopReason::Step,
+            frame: StackFrame { pc: 12, .. },
+        }))
+    ));
+}
+
+#[test]
+fn handle__rejects_out_of_range_requests() {
+    let mut vm = Vm::with_memory_storage();
+    let state = start(&mut vm);
+    let mut server = DebugServer::new(&mut vm, state);
+
+    let messages = server.handle(request(
+        1,
+        Command::ReadMemory {
+            address: VM_MAX_RAM,
+            length: 1,
+        },
+    ));
+    assert!(!response(&messages).success);
+
+    let messages = server.handle(request(
+        2,
+        Command::ReadMemory {
+            address: 0,
+            length: MAX_READ_MEMORY_LENGTH + 1,
+        },
+    ));
+    assert!(!response(&messages).success);
+
+    let messages = server.handle(request(
+        3,
+        Command::SetBreakpoints {
+            breakpoints: vec![BreakpointLocation {
+                contract: None,
+                pc: Word::MAX,
+            }],
+        },
+    ));
+    assert!(!response(&messages).success);
+}
+
+#[test]
+fn serve__exchanges_framed_messages() {
+    let mut vm = Vm::with_memory_storage();
+    let state = start(&mut vm);
+    let mut server = DebugServer::new(&mut vm, state);
+
+    let mut input = Vec::new();
+    for command in [Command::Continue, Command::Disconnect] {
+        write_message(&mut input, &request(1, command)).unwrap();
+    }
+
+    let mut output = Vec::new();
+    server.serve(input.as_slice(), &mut output).unwrap();
+
+    let mut reader = output.as_slice();
+    let mut messages = Vec::new();
+    while let Some(payload) = read_message(&mut reader).unwrap() {
+        messages.push(serde_json::from_slice::<Message>(&payload).unwrap());
+    }
+
+    assert_eq!(messages.len(), 4);
+    assert!(matches!(
+        messages[0],
+        Message::Event(Event::Stopped {
+            reason: StopReason::Breakpoint,
+            ..
+        })
+    ));
+    assert!(matches!(
+        messages[2],
+        Message::Event(Event::Terminated {
+            state: ProgramState::Return(3)
+        })
+    ));
+    assert_eq!(messages[3], Message::Response(Response::success(1, None)));
+}
+
+#[test]
+fn serve__answers_malformed_requests_with_an_error_and_keeps_serving() {
+    let mut vm = Vm::with_memory_storage();
+    let state = start(&mut vm);
+    let mut server = DebugServer::new(&mut vm, state);
+
+    let mut input = Vec::new();
+    for payload in [r#"{"seq":1,"command":"noSuchCommand"}"#, r#"not json"#] {
+        write!(input, "Content-Length: {}\r\n\r\n{payload}", payload.len()).unwrap();
+    }
+    write_message(&mut input, &request(3, Command::Disconnect)).unwrap();
+
+    let mut output = Vec::new();
+    server.serve(input.as_slice(), &mut output).unwrap();
+
+    let mut reader = output.as_slice();
+    let mut messages = Vec::new();
+    while let Some(payload) = read_message(&mut reader).unwrap() {
+        messages.push(serde_json::from_slice::<Message>(&payload).unwrap());
+    }
+
+    assert_eq!(messages.len(), 4);
+    for (message, seq) in messages[1..3].iter().zip([1, 0]) {
+        assert!(matches!(
+            message,
+            Message::Response(Response {
+                request_seq,
+                success: false,
+                message: Some(_),
+                ..
+            }) if *request_seq == seq
+        ));
+    }
+    assert_eq!(messages[3], Message::Response(Response::success(3, None)));
+}
+
+#[test]
+fn serve__answers_invalid_headers_with_an_error_and_keeps_serving() {
+    let mut vm = Vm::with_memory_storage();
+    let state = start(&mut vm);
+    let mut server = DebugServer::new(&mut vm, state);
+
+    let mut input = Vec::new();
+    write!(input, "Content-Length: many\r\n\r\n").unwrap();
+    let too_long = MAX_MESSAGE_LENGTH + 1;
+    write!(input, "Content-Length: {too_long}\r\n\r\n").unwrap();
+    input.resize(input.len() + too_long, b' ');
+    write_message(&mut input, &request(3, Command::Disconnect)).unwrap();
+
+    let mut output = Vec::new();
+    server.serve(input.as_slice(), &mut output).unwrap();
+
+    let mut reader = output.as_slice();
+    let mut messages = Vec::new();
+    while let Some(payload) = read_message(&mut reader).unwrap() {
+        messages.push(serde_json::from_slice::<Message>(&payload).unwrap());
+    }
+
+    assert_eq!(messages.len(), 4);
+    for message in &messages[1..3] {
+        assert!(matches!(
+            message,
+            Message::Response(Response {
+                request_seq: 0,
+                success: false,
+                message: Some(_),
+                ..
+            })
+        ));
+    }
+    assert_eq!(messages[3], Message::Response(Response::success(3, None)));
+}
+
+#[test]
+fn read_message__rejects_payloads_longer_than_the_maximum() {
+    let mut input = Vec::new();
+    write!(input, "Content-Length: {}\r\n\r\n", usize::MAX).unwrap();
+
+    let error = read_message(&mut input.as_slice()).unwrap_err();
+
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
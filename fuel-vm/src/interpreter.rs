@@ -28,6 +28,7 @@ use fuel_tx::{
     FeeParameters,
     GasCosts,
     Output,
+    PanicContext,
     PrepareSign,
     Receipt,
     Script,
@@ -61,20 +62,28 @@ mod internal;
 mod log;
 mod memory;
 mod metadata;
+mod panic_context;
 mod post_execution;
 mod receipts;
 
 mod debug;
 mod ecal;
 
-use crate::profiler::Profiler;
+use crate::{
+    profiler::Profiler,
+    trace::Tracer,
+};
 
 #[cfg(feature = "profile-gas")]
 use crate::profiler::InstructionLocation;
 
 pub use balances::RuntimeBalances;
 pub use ecal::{
+    EcalArg,
+    EcalArgs,
+    EcalContext,
     EcalHandler,
+    EcalRegistry,
     PredicateErrorEcal,
 };
 pub use memory::{
@@ -125,15 +134,28 @@ pub struct Interpreter<M, S, Tx = (), Ecal = NotSupportedEcal> {
     context: Context,
     balances: RuntimeBalances,
     profiler: Profiler,
+    tracer: Tracer,
     interpreter_params: InterpreterParams,
     /// `PanicContext` after the latest execution. It is consumed by
     /// `append_panic_receipt` and is `PanicContext::None` after consumption.
     panic_context: PanicContext,
     ecal_state: Ecal,
+    /// Inverse diffs of the executed instructions, captured in record mode.
+    #[cfg(feature = "record-mode")]
+    history: diff::History<M, S, Tx, Ecal>,
 }
 
 /// Interpreter parameters
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    fuel_types::canonical::Deserialize,
+    fuel_types::canonical::Serialize,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InterpreterParams {
     /// Gas Price
     pub gas_price: Word,
@@ -191,18 +213,6 @@ impl InterpreterParams {
     }
 }
 
-/// Sometimes it is possible to add some additional context information
-/// regarding panic reasons to simplify debugging.
-// TODO: Move this enum into `fuel-tx` and use it inside of the `Receipt::Panic` as meta
-//  information. Maybe better to have `Vec<PanicContext>` to provide more information.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) enum PanicContext {
-    /// No additional information.
-    None,
-    /// `ContractId` retrieved during instruction execution.
-    ContractId(ContractId),
-}
-
 impl<M: Memory, S, Tx, Ecal> Interpreter<M, S, Tx, Ecal> {
     /// Returns the current state of the VM memory
     pub fn memory(&self) -> &MemoryInstance {
@@ -247,6 +257,11 @@ impl<M, S, Tx, Ecal> Interpreter<M, S, Tx, Ecal> {
         &self.initial_balances
     }
 
+    /// The parameters of the interpreter.
+    pub fn interpreter_params(&self) -> &InterpreterParams {
+        &self.interpreter_params
+    }
+
     /// Get max_inputs value
     pub fn max_inputs(&self) -> u16 {
         self.interpreter_params.max_inputs
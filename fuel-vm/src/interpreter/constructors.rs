@@ -27,6 +27,11 @@ use crate::profiler::ProfileReceiver;
 
 use crate::profiler::Profiler;
 
+#[cfg(feature = "trace")]
+use crate::trace::TraceReceiver;
+
+use crate::trace::Tracer;
+
 #[cfg(feature = "test-helpers")]
 use crate::{
     interpreter::EcalHandler,
@@ -79,9 +84,12 @@ where
             context: Context::default(),
             balances: RuntimeBalances::default(),
             profiler: Profiler::default(),
+            tracer: Tracer::default(),
             interpreter_params,
             panic_context: PanicContext::None,
             ecal_state,
+            #[cfg(feature = "record-mode")]
+            history: Default::default(),
         }
     }
 }
@@ -96,6 +104,16 @@ impl<M, S, Tx, Ecal> Interpreter<M, S, Tx, Ecal> {
         self.profiler.set_receiver(alloc::boxed::Box::new(receiver));
         self
     }
+
+    /// Sets a tracer for the VM
+    #[cfg(feature = "trace")]
+    pub fn with_tracer<T>(&mut self, receiver: T) -> &mut Self
+    where
+        T: TraceReceiver + Send + Sync + 'static,
+    {
+        self.tracer.set_receiver(alloc::boxed::Box::new(receiver));
+        self
+    }
 }
 
 #[cfg(any(test, feature = "test-helpers"))]
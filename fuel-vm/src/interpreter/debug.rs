@@ -1,6 +1,16 @@
 use super::Interpreter;
-use crate::prelude::*;
-use fuel_asm::RegId;
+use crate::{
+    call::CallFrame,
+    constraints::InstructionLocation,
+    consts::VM_REGISTER_COUNT,
+    debug_info::SourceLocation,
+    prelude::*,
+};
+use alloc::vec::Vec;
+use fuel_asm::{
+    RegId,
+    Word,
+};
 
 impl<M, S, Tx, Ecal> Interpreter<M, S, Tx, Ecal>
 where
@@ -39,6 +49,42 @@ where
         self.debugger.remove_breakpoint(breakpoint)
     }
 
+    /// Returns `true` if a breakpoint is set at the location of `breakpoint`.
+    pub fn has_breakpoint(&self, breakpoint: &Breakpoint) -> bool {
+        self.debugger.has_breakpoint(breakpoint)
+    }
+
+    /// Load the source-level debug information of the executed programs.
+    pub fn set_debug_info(&mut self, debug_info: DebugInfo) {
+        self.debugger.set_debug_info(debug_info)
+    }
+
+    /// Source-level debug information of the executed programs, if loaded.
+    pub fn debug_info(&self) -> Option<&DebugInfo> {
+        self.debugger.debug_info()
+    }
+
+    /// Location of the instruction pointed to by `$pc`.
+    pub fn current_location(&self) -> InstructionLocation {
+        instruction_location(&self.registers, &self.frames)
+    }
+
+    /// Locations of the instructions on the call stack, starting from the one pointed
+    /// to by `$pc` and followed by the `CALL` instructions of its callers.
+    pub fn call_stack_locations(&self) -> Vec<InstructionLocation> {
+        crate::backtrace::call_stack_locations(
+            &self.contract_id().unwrap_or_default(),
+            &self.registers,
+            &self.frames,
+        )
+    }
+
+    /// Source location of the instruction pointed to by `$pc`, if debug information
+    /// was loaded with [`Self::set_debug_info`].
+    pub fn current_source_location(&self) -> Option<&SourceLocation> {
+        self.debug_info()?.resolve(&self.current_location())
+    }
+
     pub(crate) fn eval_debugger_state(&mut self) -> DebugEval {
         let debugger = &mut self.debugger;
 
@@ -57,6 +103,16 @@ where
     }
 }
 
+/// Location of the instruction pointed to by `$pc`, in the contract of the innermost
+/// call frame or in the script if there is none.
+pub(crate) fn instruction_location(
+    registers: &[Word; VM_REGISTER_COUNT],
+    frames: &[CallFrame],
+) -> InstructionLocation {
+    let pc = registers[RegId::PC].saturating_sub(registers[RegId::IS]);
+    InstructionLocation::new(frames.last().map(|frame| *frame.to()), pc)
+}
+
 #[cfg(test)]
 mod tests {
     use alloc::{
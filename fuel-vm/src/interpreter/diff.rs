@@ -1,11 +1,15 @@
 //! # VM State Differences
 //! This module provides the ability to generate diffs between two VMs internal states.
 //! The diff can then be used to invert a VM to the original state.
-//! This module is experimental work in progress and currently only used in testing
-//! although it could potentially stabilize to be used in production.
+//!
+//! With the `record-mode` feature, the `History` of a VM running in record mode
+//! stores the inverse diff of every executed instruction, which allows to rewind the
+//! execution with `Interpreter::step_back` and `Interpreter::run_back_to`.
 
 use alloc::{
+    borrow::Cow,
     sync::Arc,
+    vec,
     vec::Vec,
 };
 use core::{
@@ -33,6 +37,7 @@ use fuel_types::AssetId;
 
 use crate::{
     call::CallFrame,
+    consts::MEM_SIZE,
     context::Context,
     storage::{
         ContractsAssets,
@@ -47,10 +52,17 @@ use super::{
     ExecutableTransaction,
     Interpreter,
     Memory,
+    MemoryInstance,
     PanicContext,
 };
 use storage::*;
 
+#[cfg(feature = "record-mode")]
+pub use history::History;
+pub use storage::Record;
+
+#[cfg(feature = "record-mode")]
+mod history;
 mod storage;
 
 #[cfg(test)]
@@ -69,12 +81,22 @@ pub struct Diff<T: VmStateCapture + Clone> {
     changes: Vec<Change<T>>,
 }
 
+impl<T: VmStateCapture + Clone> Default for Diff<T> {
+    fn default() -> Self {
+        Self {
+            changes: Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 enum Change<T: VmStateCapture + Clone> {
     /// Holds a snapshot of register state.
     Register(T::State<VecState<Word>>),
     /// Holds a snapshot of memory state.
     Memory(T::State<MemoryRegion>),
+    /// Holds a snapshot of the memory layout.
+    MemoryLayout(T::State<MemoryLayout>),
     /// Holds a snapshot of storage state.
     Storage(T::State<StorageState>),
     /// Holds a snapshot of the call stack.
@@ -192,6 +214,181 @@ impl Debug for MemoryRegion {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The sizes of the stack and the heap.
+struct MemoryLayout {
+    /// Length of the stack buffer.
+    stack: usize,
+    /// Start of the accessible heap.
+    hp: usize,
+}
+
+impl MemoryLayout {
+    #[allow(clippy::arithmetic_side_effects)] // Safety: the heap is at most MEM_SIZE
+    fn of(memory: &MemoryInstance) -> Self {
+        let (stack, heap) = memory.sections();
+        Self {
+            stack: stack.len(),
+            hp: MEM_SIZE - heap.len(),
+        }
+    }
+
+    /// The smallest layout that contains both layouts.
+    fn union(self, other: Self) -> Self {
+        Self {
+            stack: self.stack.max(other.stack),
+            hp: self.hp.min(other.hp),
+        }
+    }
+}
+
+/// Captures the changes of the accessible memory, followed by the change of the
+/// layout, if any. The layout comes last so that inverting the regions first can
+/// write to memory that the original layout made accessible.
+#[allow(clippy::arithmetic_side_effects)] // Safety: the heap is at most MEM_SIZE
+fn capture_memory_state(a: &MemoryInstance, b: &MemoryInstance) -> Vec<Change<Deltas>> {
+    let mut changes = Vec::new();
+    let (a_stack, a_heap) = a.sections();
+    let (b_stack, b_heap) = b.sections();
+
+    let stack_len = a_stack.len().max(b_stack.len());
+    capture_memory_regions(
+        0,
+        &pad_end(a_stack, stack_len),
+        &pad_end(b_stack, stack_len),
+        &mut changes,
+    );
+
+    let heap_len = a_heap.len().max(b_heap.len());
+    capture_memory_regions(
+        MEM_SIZE - heap_len,
+        &pad_start(a_heap, heap_len),
+        &pad_start(b_heap, heap_len),
+        &mut changes,
+    );
+
+    let from = MemoryLayout::of(a);
+    let to = MemoryLayout::of(b);
+    if from != to {
+        changes.push(Change::MemoryLayout(Delta { from, to }));
+    }
+
+    changes
+}
+
+/// Captures the changes of `memory` since `snapshot`, and updates the snapshot to match
+/// it.
+#[cfg(any(feature = "trace", feature = "record-mode"))]
+fn update_memory_snapshot(
+    snapshot: &mut MemoryInstance,
+    memory: &MemoryInstance,
+) -> Vec<Change<Deltas>> {
+    let changes = capture_memory_state(snapshot, memory);
+    if changes.is_empty() {
+        return changes
+    }
+
+    let layout = MemoryLayout::of(memory);
+    let union = MemoryLayout::of(snapshot).union(layout);
+    snapshot.set_layout(union.stack, union.hp);
+
+    for change in &changes {
+        if let Change::Memory(Delta { to, .. }) = change {
+            snapshot
+                .write_noownerchecks(to.start, to.bytes.len())
+                .expect("Memory must exist here")
+                .copy_from_slice(&to.bytes);
+        }
+    }
+
+    snapshot.set_layout(layout.stack, layout.hp);
+    changes
+}
+
+/// Returns the start and the new content of the memory regions changed since
+/// `snapshot`, and updates the snapshot to match `memory`.
+#[cfg(feature = "trace")]
+pub(crate) fn memory_writes(
+    snapshot: &mut MemoryInstance,
+    memory: &MemoryInstance,
+) -> Vec<(usize, Vec<u8>)> {
+    update_memory_snapshot(snapshot, memory)
+        .into_iter()
+        .filter_map(|change| match change {
+            Change::Memory(Delta { to, .. }) => Some((to.start, to.bytes)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn pad_end(bytes: &[u8], len: usize) -> Cow<'_, [u8]> {
+    if bytes.len() == len {
+        Cow::Borrowed(bytes)
+    } else {
+        let mut padded = bytes.to_vec();
+        padded.resize(len, 0);
+        Cow::Owned(padded)
+    }
+}
+
+#[allow(clippy::arithmetic_side_effects)] // Safety: len is at least bytes.len()
+fn pad_start(bytes: &[u8], len: usize) -> Cow<'_, [u8]> {
+    if bytes.len() == len {
+        Cow::Borrowed(bytes)
+    } else {
+        let mut padded = vec![0; len - bytes.len()];
+        padded.extend_from_slice(bytes);
+        Cow::Owned(padded)
+    }
+}
+
+/// Captures the differing regions of two equally sized buffers located at `offset`.
+#[allow(clippy::arithmetic_side_effects)] // Safety: indices are bounded by the length
+fn capture_memory_regions(
+    offset: usize,
+    a: &[u8],
+    b: &[u8],
+    changes: &mut Vec<Change<Deltas>>,
+) {
+    // Compared in chunks first, as most instructions don't touch the memory at all.
+    const CHUNK: usize = 64;
+
+    debug_assert_eq!(a.len(), b.len());
+    let len = a.len().min(b.len());
+    let mut i = 0;
+
+    while i < len {
+        let chunk_end = len.min(i + CHUNK);
+        let Some(first) = a[i..chunk_end]
+            .iter()
+            .zip(&b[i..chunk_end])
+            .position(|(a, b)| a != b)
+        else {
+            i = chunk_end;
+            continue
+        };
+
+        let start = i + first;
+        let end = a[start..]
+            .iter()
+            .zip(&b[start..])
+            .position(|(a, b)| a == b)
+            .map_or(len, |n| start + n);
+
+        changes.push(Change::Memory(Delta {
+            from: MemoryRegion {
+                start: offset + start,
+                bytes: a[start..end].to_vec(),
+            },
+            to: MemoryRegion {
+                start: offset + start,
+                bytes: b[start..end].to_vec(),
+            },
+        }));
+        i = end;
+    }
+}
+
 fn capture_buffer_state<'iter, I, T>(
     a: I,
     b: I,
@@ -352,28 +549,8 @@ where
         );
         diff.changes.extend(balances);
 
-        let other_memory = other.memory().clone().into_linear_memory();
-        let this_memory = self.memory().clone().into_linear_memory();
-
-        let mut memory = this_memory.iter().enumerate().zip(other_memory.iter());
-
-        while let Some(((start, s_from), s_to)) = memory
-            .by_ref()
-            .find(|((_, a), b)| a != b)
-            .map(|((n, a), b)| ((n, *a), *b))
-        {
-            let (mut from, mut to): (Vec<_>, Vec<_>) = memory
-                .by_ref()
-                .take_while(|((_, a), b)| a != b)
-                .map(|((_, a), b)| (*a, *b))
-                .unzip();
-            from.splice(..0, core::iter::once(s_from)).next();
-            to.splice(..0, core::iter::once(s_to)).next();
-            diff.changes.push(Change::Memory(Delta {
-                from: MemoryRegion { start, bytes: from },
-                to: MemoryRegion { start, bytes: to },
-            }));
-        }
+        diff.changes
+            .extend(capture_memory_state(self.memory(), other.memory()));
 
         if self.context != other.context {
             diff.changes.push(Change::Context(Delta {
@@ -421,6 +598,9 @@ where
                 .write_noownerchecks(*start, bytes.len())
                 .expect("Memory must exist here")
                 .copy_from_slice(&bytes[..]),
+            Change::MemoryLayout(Previous(MemoryLayout { stack, hp })) => {
+                self.memory_mut().set_layout(*stack, *hp)
+            }
             Change::Context(Previous(value)) => self.context = value.clone(),
             Change::PanicContext(Previous(value)) => self.panic_context = value.clone(),
             Change::Txn(Previous(tx)) => {
@@ -511,6 +691,7 @@ impl From<Diff<Deltas>> for Diff<InitialVmState> {
                 .map(|c| match c {
                     Change::Register(v) => Change::Register(v.into()),
                     Change::Memory(v) => Change::Memory(v.into()),
+                    Change::MemoryLayout(v) => Change::MemoryLayout(v.into()),
                     Change::Storage(v) => Change::Storage(v.into()),
                     Change::Frame(v) => Change::Frame(v.into()),
                     Change::Receipt(v) => Change::Receipt(v.into()),
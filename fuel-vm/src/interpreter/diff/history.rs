@@ -0,0 +1,382 @@
+//! Record mode and reverse execution.
+//!
+//! While recording, the VM captures the inverse [`Diff`] of every executed instruction,
+//! which can later be applied to rewind the execution instruction by instruction.
+//! Storage changes are captured through the [`Record`] storage wrapper, so record mode
+//! is only available for VMs created with [`Interpreter::add_recording`].
+
+use alloc::{
+    sync::Arc,
+    vec::Vec,
+};
+use core::fmt;
+
+use fuel_asm::Word;
+use fuel_tx::Receipt;
+use fuel_types::AssetId;
+use hashbrown::HashMap;
+
+use crate::{
+    call::CallFrame,
+    consts::VM_REGISTER_COUNT,
+    context::Context,
+    state::{
+        Breakpoint,
+        DebugEval,
+        ProgramState,
+    },
+    storage::InterpreterStorage,
+};
+
+use super::{
+    capture_buffer_state,
+    capture_map_state,
+    capture_vec_state,
+    storage::storage_deltas_diff,
+    update_memory_snapshot,
+    AnyDebug,
+    Balance,
+    Change,
+    Delta,
+    Deltas,
+    Diff,
+    ExecutableTransaction,
+    InitialVmState,
+    Interpreter,
+    Memory,
+    MemoryInstance,
+    PanicContext,
+    Record,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The execution history of a VM running in record mode.
+pub struct History<M, S, Tx, Ecal> {
+    /// Inverse diffs of the recorded instructions, oldest first.
+    steps: Vec<Diff<InitialVmState>>,
+    /// State of the VM after the last recorded instruction.
+    snapshot: Option<Snapshot<Tx>>,
+    /// Number of storage changes recorded by the storage up to the snapshot.
+    storage_changes: usize,
+    /// Hooks of the record mode, installed while recording.
+    recorder: Option<Recorder<Interpreter<M, S, Tx, Ecal>>>,
+}
+
+/// Hooks called around every executed instruction while recording. They are
+/// installed by the implementation for the [`Record`] storage, which can observe the
+/// storage changes, and called from the generic execution loop.
+struct Recorder<Vm> {
+    /// Captures the state before the instruction, if it isn't captured yet.
+    before: fn(&mut Vm),
+    /// Captures the changes made by the instruction.
+    after: fn(&mut Vm),
+}
+
+impl<Vm> Clone for Recorder<Vm> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Vm> Copy for Recorder<Vm> {}
+
+/// The VM state which is compared after every instruction to find its changes.
+#[derive(Debug, Clone)]
+struct Snapshot<Tx> {
+    registers: [Word; VM_REGISTER_COUNT],
+    memory: MemoryInstance,
+    frames: Vec<CallFrame>,
+    receipts: Vec<Receipt>,
+    balances: HashMap<AssetId, Balance>,
+    context: Context,
+    panic_context: PanicContext,
+    tx: Tx,
+}
+
+impl<M, S, Tx, Ecal> Default for History<M, S, Tx, Ecal> {
+    fn default() -> Self {
+        Self {
+            steps: Vec::new(),
+            snapshot: None,
+            storage_changes: 0,
+            recorder: None,
+        }
+    }
+}
+
+impl<M, S, Tx, Ecal> Clone for History<M, S, Tx, Ecal>
+where
+    Tx: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            steps: self.steps.clone(),
+            snapshot: self.snapshot.clone(),
+            storage_changes: self.storage_changes,
+            recorder: self.recorder,
+        }
+    }
+}
+
+impl<M, S, Tx, Ecal> fmt::Debug for History<M, S, Tx, Ecal> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("History")
+            .field("recording", &self.recorder.is_some())
+            .field("steps", &self.steps.len())
+            .finish()
+    }
+}
+
+impl<M, S, Tx, Ecal> History<M, S, Tx, Ecal> {
+    /// Returns `true` if the VM is running in record mode.
+    pub const fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Number of recorded instructions that can be stepped back.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns `true` if there are no recorded instructions.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl<M, S, Tx, Ecal> Interpreter<M, S, Tx, Ecal> {
+    /// Execution history recorded in record mode.
+    pub const fn history(&self) -> &History<M, S, Tx, Ecal> {
+        &self.history
+    }
+
+    /// Run the record mode hook that precedes an instruction, returning the hook to
+    /// call once the instruction is executed.
+    pub(crate) fn history_before_instruction(&mut self) -> Option<fn(&mut Self)> {
+        let recorder = self.history.recorder?;
+        (recorder.before)(self);
+        Some(recorder.after)
+    }
+}
+
+impl<M, S, Tx, Ecal> Interpreter<M, Record<S>, Tx, Ecal>
+where
+    M: Memory,
+    S: InterpreterStorage,
+    Tx: ExecutableTransaction + PartialEq + fmt::Debug + 'static,
+{
+    /// Start or stop recording the inverse diff of every executed instruction.
+    ///
+    /// Recording has a considerable overhead, as the state of the VM is compared
+    /// after every instruction. Stopping the recording discards the history.
+    pub fn set_recording(&mut self, recording: bool) {
+        self.history = History::default();
+        if recording {
+            self.history.recorder = Some(Recorder {
+                before: Self::history_snapshot,
+                after: Self::history_record,
+            });
+        }
+    }
+
+    /// Rewind the execution by up to `n` recorded instructions.
+    ///
+    /// Returns the number of instructions that were undone, which is less than `n`
+    /// only if the beginning of the history was reached. The execution can be
+    /// continued afterwards with [`Interpreter::resume`].
+    pub fn step_back(&mut self, n: usize) -> usize {
+        self.history_merge_pending();
+
+        let mut undone = 0;
+        while undone < n {
+            let Some(step) = self.history.steps.pop() else {
+                break
+            };
+            self.reset_vm_state(&step);
+            undone = undone.saturating_add(1);
+        }
+
+        if undone > 0 {
+            self.history_rewound();
+        }
+        undone
+    }
+
+    /// Rewind the execution until the instruction of `breakpoint` is the next one to
+    /// be executed, undoing at least one instruction.
+    ///
+    /// Returns the number of instructions that were undone, or `None` if the
+    /// breakpoint wasn't reached before the beginning of the history, in which case
+    /// the whole history is undone.
+    pub fn run_back_to(&mut self, breakpoint: &Breakpoint) -> Option<usize> {
+        self.history_merge_pending();
+
+        let target = breakpoint.location();
+        let mut undone = 0usize;
+        let mut reached = false;
+        while let Some(step) = self.history.steps.pop() {
+            self.reset_vm_state(&step);
+            undone = undone.saturating_add(1);
+            if self.current_location() == target {
+                reached = true;
+                break
+            }
+        }
+
+        if undone > 0 {
+            self.history_rewound();
+        }
+        reached.then_some(undone)
+    }
+
+    fn history_snapshot(&mut self) {
+        if self.history.snapshot.is_none() {
+            self.history.snapshot = Some(Snapshot::of(self));
+            self.history.storage_changes = self.storage.1.len();
+        }
+    }
+
+    fn history_record(&mut self) {
+        if let Some(step) = self.history_capture() {
+            self.history.steps.push(step);
+        }
+    }
+
+    /// Merge the changes made since the last recorded instruction, e.g. by the
+    /// finalization of the transaction, into the last step so that they are undone
+    /// along with it.
+    fn history_merge_pending(&mut self) {
+        let Some(pending) = self.history_capture() else {
+            return
+        };
+
+        match self.history.steps.last_mut() {
+            Some(last) => {
+                let mut step = pending;
+                step.changes.extend(core::mem::take(last).changes);
+                *last = step;
+            }
+            None => self.reset_vm_state(&pending),
+        }
+    }
+
+    /// Capture the changes since the snapshot and move the snapshot to the current
+    /// state. Returns the inverse of the changes, if any.
+    fn history_capture(&mut self) -> Option<Diff<InitialVmState>> {
+        let mut snapshot = self.history.snapshot.take()?;
+
+        let mut diff = snapshot.update(self);
+        let storage_changes = self.storage.1.get(self.history.storage_changes..);
+        diff.changes
+            .extend(storage_deltas_diff(storage_changes.unwrap_or_default()).changes);
+
+        self.history.snapshot = Some(snapshot);
+        self.history.storage_changes = self.storage.1.len();
+
+        (!diff.changes.is_empty()).then(|| diff.into())
+    }
+
+    /// Invalidate the snapshot after the state was rewound and let the debugger resume
+    /// the execution from the current instruction.
+    fn history_rewound(&mut self) {
+        self.history.snapshot = None;
+        self.history.storage_changes = self.storage.1.len();
+
+        let location = self.current_location();
+        let breakpoint =
+            Breakpoint::raw(location.context.unwrap_or_default(), location.offset);
+        self.debugger_set_last_state(ProgramState::RunProgram(DebugEval::Breakpoint(
+            breakpoint,
+        )));
+    }
+}
+
+impl<Tx> Snapshot<Tx>
+where
+    Tx: Clone + PartialEq + fmt::Debug + 'static,
+{
+    fn of<M, S, Ecal>(vm: &Interpreter<M, S, Tx, Ecal>) -> Self
+    where
+        M: Memory,
+    {
+        Self {
+            registers: vm.registers,
+            memory: vm.memory().clone(),
+            frames: vm.frames.clone(),
+            receipts: vm.receipts.as_ref().clone(),
+            balances: vm.balances.as_ref().clone(),
+            context: vm.context.clone(),
+            panic_context: vm.panic_context.clone(),
+            tx: vm.tx.clone(),
+        }
+    }
+
+    /// Capture the changes of the VM since the snapshot, and update the snapshot to
+    /// match the VM.
+    fn update<M, S, Ecal>(&mut self, vm: &Interpreter<M, S, Tx, Ecal>) -> Diff<Deltas>
+    where
+        M: Memory,
+    {
+        let mut diff = Diff::default();
+
+        diff.changes.extend(capture_buffer_state(
+            self.registers.iter(),
+            vm.registers.iter(),
+            Change::Register,
+        ));
+        self.registers = vm.registers;
+
+        if self.frames != vm.frames {
+            diff.changes.extend(capture_vec_state(
+                self.frames.iter(),
+                vm.frames.iter(),
+                Change::Frame,
+            ));
+            self.frames.clone_from(&vm.frames);
+        }
+
+        if &self.receipts != vm.receipts.as_ref() {
+            diff.changes.extend(capture_vec_state(
+                self.receipts.iter(),
+                vm.receipts.as_ref().iter(),
+                Change::Receipt,
+            ));
+            self.receipts.clone_from(vm.receipts.as_ref());
+        }
+
+        if &self.balances != vm.balances.as_ref() {
+            diff.changes.extend(capture_map_state(
+                &self.balances,
+                vm.balances.as_ref(),
+                Change::Balance,
+            ));
+            self.balances.clone_from(vm.balances.as_ref());
+        }
+
+        diff.changes
+            .extend(update_memory_snapshot(&mut self.memory, vm.memory()));
+
+        if self.context != vm.context {
+            let to = vm.context.clone();
+            let from = core::mem::replace(&mut self.context, to.clone());
+            diff.changes.push(Change::Context(Delta { from, to }));
+        }
+
+        if self.panic_context != vm.panic_context {
+            let to = vm.panic_context.clone();
+            let from = core::mem::replace(&mut self.panic_context, to.clone());
+            diff.changes.push(Change::PanicContext(Delta { from, to }));
+        }
+
+        if self.tx != vm.tx {
+            let from: Arc<dyn AnyDebug> = Arc::new(self.tx.clone());
+            let to: Arc<dyn AnyDebug> = Arc::new(vm.tx.clone());
+            self.tx.clone_from(&vm.tx);
+            diff.changes.push(Change::Txn(Delta { from, to }));
+        }
+
+        diff
+    }
+}
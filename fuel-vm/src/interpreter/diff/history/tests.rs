@@ -0,0 +1,125 @@
+use alloc::{
+    vec,
+    vec::Vec,
+};
+
+use fuel_asm::{
+    op,
+    Instruction,
+    RegId,
+};
+use fuel_tx::{
+    ConsensusParameters,
+    Finalizable,
+    Script,
+    TransactionBuilder,
+};
+
+use crate::{
+    checked_transaction::{
+        IntoChecked,
+        Ready,
+    },
+    interpreter::{
+        diff::Record,
+        Interpreter,
+        MemoryInstance,
+    },
+    state::{
+        Breakpoint,
+        ProgramState,
+    },
+    storage::MemoryStorage,
+};
+
+fn recording_vm() -> Interpreter<MemoryInstance, Record<MemoryStorage>, Script> {
+    let mut vm = Interpreter::<_, _, Script>::with_memory_storage().add_recording();
+    vm.set_recording(true);
+    vm
+}
+
+fn script_tx(script: Vec<Instruction>) -> Ready<Script> {
+    let consensus_params = ConsensusParameters::standard();
+    TransactionBuilder::script(script.into_iter().collect(), vec![])
+        .script_gas_limit(1_000_000)
+        .add_random_fee_input()
+        .finalize()
+        .into_checked(Default::default(), &consensus_params)
+        .expect("failed to generate checked tx")
+        .into_ready(
+            0,
+            consensus_params.gas_costs(),
+            consensus_params.fee_params(),
+        )
+        .expect("failed to finalize tx")
+}
+
+fn rewind_script() -> Vec<Instruction> {
+    vec![
+        op::movi(0x10, 8),
+        op::cfe(0x10),
+        op::sw(RegId::SSP, 0x10, 0),
+        op::aloc(0x10),
+        op::sw(RegId::HP, 0x10, 0),
+        op::log(0x10, 0, 0, 0),
+        op::ret(RegId::ONE),
+    ]
+}
+
+#[test]
+fn step_back_restores_state_before_each_instruction() {
+    let mut vm = recording_vm();
+    vm.set_single_stepping(true);
+
+    let mut state = vm
+        .transact(script_tx(rewind_script()))
+        .map(ProgramState::from)
+        .unwrap();
+    let mut states = Vec::new();
+    while state.is_debug() {
+        states.push((
+            vm.registers().to_vec(),
+            vm.memory().clone(),
+            vm.receipts().to_vec(),
+        ));
+        state = vm.resume().unwrap();
+    }
+    assert_eq!(state, ProgramState::Return(1));
+    assert_eq!(vm.history().len(), states.len());
+
+    for (registers, memory, receipts) in states.iter().rev() {
+        assert_eq!(vm.step_back(1), 1);
+        assert_eq!(vm.registers(), registers.as_slice());
+        assert_eq!(vm.memory(), memory);
+        assert_eq!(vm.receipts(), receipts.as_slice());
+    }
+    assert_eq!(vm.step_back(1), 0);
+    assert!(vm.history().is_empty());
+}
+
+#[test]
+fn run_back_to_breakpoint_and_resume() {
+    let mut vm = recording_vm();
+    let state = vm
+        .transact(script_tx(rewind_script()))
+        .map(ProgramState::from)
+        .unwrap();
+    assert_eq!(state, ProgramState::Return(1));
+    let receipts = vm.receipts().to_vec();
+
+    assert_eq!(vm.run_back_to(&Breakpoint::script(5)), Some(2));
+    assert_eq!(vm.current_location().offset, 20);
+    assert!(vm.receipts().is_empty());
+
+    // The breakpoint isn't reached again before the beginning of the history.
+    assert_eq!(vm.run_back_to(&Breakpoint::script(5)), None);
+    assert!(vm.history().is_empty());
+    assert_eq!(vm.registers()[0x10], 0);
+
+    // The execution can be continued and is recorded again.
+    assert_eq!(vm.resume().unwrap(), ProgramState::Return(1));
+    assert_eq!(&vm.receipts()[..2], &receipts[..2]);
+    assert_eq!(vm.history().len(), rewind_script().len());
+    assert_eq!(vm.step_back(2), 2);
+    assert_eq!(vm.current_location().offset, 20);
+}
@@ -1,3 +1,4 @@
+use alloc::borrow::Cow;
 use core::fmt::Debug;
 use hashbrown::HashMap;
 
@@ -12,8 +13,10 @@ use fuel_types::{
     Bytes32,
     ContractId,
 };
+use primitive_types::U256;
 
 use crate::storage::{
+    ContractStateUsage,
     ContractsAssetKey,
     ContractsAssetsStorage,
     ContractsStateData,
@@ -72,8 +75,25 @@ pub(super) trait StorageType: Mappable {
 
     /// Records a remove state change.
     fn record_remove(key: &Self::Key, value: Self::OwnedValue) -> StorageDelta;
+
+    /// Records a state change made through [`StorageWrite`] with the raw bytes of the
+    /// values. Returns `None` if the type isn't written as raw bytes.
+    fn record_write(
+        _key: &Self::Key,
+        _value: &[u8],
+        _existing: Option<Vec<u8>>,
+    ) -> Option<StorageDelta> {
+        None
+    }
+
+    /// Records a removal made through [`StorageWrite`] with the raw bytes of the
+    /// value. Returns `None` if the type isn't written as raw bytes.
+    fn record_take(_key: &Self::Key, _value: Vec<u8>) -> Option<StorageDelta> {
+        None
+    }
 }
 
+/// Storage wrapper which records the changes made to the underlying storage.
 #[derive(Debug)]
 pub struct Record<S>(pub(super) S, pub(super) Vec<StorageDelta>)
 where
@@ -101,59 +121,67 @@ where
             balances: self.balances,
             panic_context: self.panic_context,
             profiler: self.profiler,
+            tracer: self.tracer,
             interpreter_params: self.interpreter_params,
             ecal_state: self.ecal_state,
+            #[cfg(feature = "record-mode")]
+            history: Default::default(),
         }
     }
 
     /// Get the diff of changes to this VMs storage.
     pub fn storage_diff(&self) -> Diff<Deltas> {
-        let mut diff = Diff {
-            changes: Vec::new(),
-        };
-        let mut contracts_state = Delta {
-            from: HashMap::new(),
-            to: HashMap::new(),
-        };
-        let mut contracts_assets = Delta {
-            from: HashMap::new(),
-            to: HashMap::new(),
-        };
-        let mut contracts_raw_code = Delta {
-            from: HashMap::new(),
-            to: HashMap::new(),
-        };
-        let mut uploaded_bytecode = Delta {
-            from: HashMap::new(),
-            to: HashMap::new(),
-        };
-
-        for delta in self.storage.1.iter() {
-            match delta {
-                StorageDelta::State(delta) => {
-                    mappable_delta_to_hashmap(&mut contracts_state, delta)
-                }
-                StorageDelta::Assets(delta) => {
-                    mappable_delta_to_hashmap(&mut contracts_assets, delta)
-                }
-                StorageDelta::RawCode(delta) => {
-                    mappable_delta_to_hashmap(&mut contracts_raw_code, delta)
-                }
-                StorageDelta::UploadedBytecode(delta) => {
-                    mappable_delta_to_hashmap(&mut uploaded_bytecode, delta)
-                }
+        storage_deltas_diff(&self.storage.1)
+    }
+}
+
+/// Get the diff of a sequence of recorded storage changes.
+pub(super) fn storage_deltas_diff(deltas: &[StorageDelta]) -> Diff<Deltas> {
+    let mut diff = Diff {
+        changes: Vec::new(),
+    };
+    let mut contracts_state = Delta {
+        from: HashMap::new(),
+        to: HashMap::new(),
+    };
+    let mut contracts_assets = Delta {
+        from: HashMap::new(),
+        to: HashMap::new(),
+    };
+    let mut contracts_raw_code = Delta {
+        from: HashMap::new(),
+        to: HashMap::new(),
+    };
+    let mut uploaded_bytecode = Delta {
+        from: HashMap::new(),
+        to: HashMap::new(),
+    };
+
+    for delta in deltas.iter() {
+        match delta {
+            StorageDelta::State(delta) => {
+                mappable_delta_to_hashmap(&mut contracts_state, delta)
+            }
+            StorageDelta::Assets(delta) => {
+                mappable_delta_to_hashmap(&mut contracts_assets, delta)
+            }
+            StorageDelta::RawCode(delta) => {
+                mappable_delta_to_hashmap(&mut contracts_raw_code, delta)
+            }
+            StorageDelta::UploadedBytecode(delta) => {
+                mappable_delta_to_hashmap(&mut uploaded_bytecode, delta)
             }
         }
-        storage_state_to_changes(&mut diff, contracts_state, StorageState::State);
-        storage_state_to_changes(&mut diff, contracts_assets, StorageState::Assets);
-        storage_state_to_changes(&mut diff, contracts_raw_code, StorageState::RawCode);
-        storage_state_to_changes(
-            &mut diff,
-            uploaded_bytecode,
-            StorageState::UploadedBytecode,
-        );
-        diff
     }
+    storage_state_to_changes(&mut diff, contracts_state, StorageState::State);
+    storage_state_to_changes(&mut diff, contracts_assets, StorageState::Assets);
+    storage_state_to_changes(&mut diff, contracts_raw_code, StorageState::RawCode);
+    storage_state_to_changes(
+        &mut diff,
+        uploaded_bytecode,
+        StorageState::UploadedBytecode,
+    );
+    diff
 }
 
 impl<M, S, Tx, Ecal> Interpreter<M, S, Tx, Ecal>
@@ -180,8 +208,11 @@ where
             balances: self.balances,
             panic_context: self.panic_context,
             profiler: self.profiler,
+            tracer: self.tracer,
             interpreter_params: self.interpreter_params,
             ecal_state: self.ecal_state,
+            #[cfg(feature = "record-mode")]
+            history: Default::default(),
         }
     }
 
@@ -190,109 +221,111 @@ where
     where
         Tx: Clone + 'static,
     {
+        // Memory regions of the original state may be outside of the current layout,
+        // so the layout is extended before they are restored. The original layout is
+        // restored afterwards, as it is the last memory change of the diff.
+        for change in &diff.changes {
+            if let Change::MemoryLayout(Previous(layout)) = change {
+                let layout = layout.union(MemoryLayout::of(self.memory()));
+                self.memory_mut().set_layout(layout.stack, layout.hp);
+            }
+        }
+
         for change in &diff.changes {
             self.inverse_inner(change);
             if let Change::Storage(Previous(from)) = change {
                 match from {
                     StorageState::State(MappableState { key, value }) => {
-                        if let Some(value) = value {
-                            StorageMutate::<ContractsState>::insert(
-                                &mut self.storage,
-                                key,
-                                value.as_ref(),
-                            )
-                            .unwrap();
-                        }
+                        invert_storage::<ContractsState, _>(
+                            &mut self.storage,
+                            key,
+                            value.as_ref().map(AsRef::as_ref),
+                        )
                     }
                     StorageState::Assets(MappableState { key, value }) => {
-                        if let Some(value) = value {
-                            StorageMutate::<ContractsAssets>::insert(
-                                &mut self.storage,
-                                key,
-                                value,
-                            )
-                            .unwrap();
-                        }
+                        invert_storage::<ContractsAssets, _>(
+                            &mut self.storage,
+                            key,
+                            value.as_ref(),
+                        )
                     }
                     StorageState::RawCode(MappableState { key, value }) => {
-                        if let Some(value) = value {
-                            StorageMutate::<ContractsRawCode>::insert(
-                                &mut self.storage,
-                                key,
-                                value.as_ref(),
-                            )
-                            .unwrap();
-                        }
+                        invert_storage::<ContractsRawCode, _>(
+                            &mut self.storage,
+                            key,
+                            value.as_ref().map(AsRef::as_ref),
+                        )
                     }
                     StorageState::UploadedBytecode(MappableState { key, value }) => {
-                        if let Some(value) = value {
-                            StorageMutate::<UploadedBytecodes>::insert(
-                                &mut self.storage,
-                                key,
-                                value,
-                            )
-                            .unwrap();
-                        }
+                        invert_storage::<UploadedBytecodes, _>(
+                            &mut self.storage,
+                            key,
+                            value.as_ref(),
+                        )
                     }
                 }
             }
         }
+
+        #[cfg(feature = "profile-gas")]
+        self.profiler_sync_call_stack();
+    }
+}
+
+/// Restore the value of a key, removing it if it didn't exist.
+fn invert_storage<Type, S>(storage: &mut S, key: &Type::Key, value: Option<&Type::Value>)
+where
+    Type: Mappable,
+    S: StorageMutate<Type>,
+    S::Error: Debug,
+{
+    match value {
+        Some(value) => {
+            storage.insert(key, value).unwrap();
+        }
+        None => {
+            storage.remove(key).unwrap();
+        }
     }
 }
 
 fn mappable_delta_to_hashmap<'value, K, V>(
-    state: &mut Delta<HashMap<K, &'value V>>,
+    state: &mut Delta<HashMap<K, Option<&'value V>>>,
     delta: &'value MappableDelta<K, V>,
 ) where
     K: Copy + PartialEq + Eq + core::hash::Hash + 'static,
     V: Clone + 'static,
 {
     match delta {
-        MappableDelta::Insert(key, value, Some(existing)) => {
-            state.from.entry(*key).or_insert(existing);
-            state.to.insert(*key, value);
-        }
-        MappableDelta::Insert(key, value, None) => {
-            state.to.insert(*key, value);
+        MappableDelta::Insert(key, value, existing) => {
+            state.from.entry(*key).or_insert(existing.as_ref());
+            state.to.insert(*key, Some(value));
         }
         MappableDelta::Remove(key, existing) => {
-            state.from.entry(*key).or_insert(existing);
-            state.to.remove(key);
+            state.from.entry(*key).or_insert(Some(existing));
+            state.to.insert(*key, None);
         }
     }
 }
 
 fn storage_state_to_changes<K, V>(
     diff: &mut Diff<Deltas>,
-    state: Delta<HashMap<K, &V>>,
+    state: Delta<HashMap<K, Option<&V>>>,
     f: fn(MappableState<K, V>) -> StorageState,
 ) where
     K: Copy + PartialEq + Eq + Hash + 'static,
     V: Clone + 'static,
 {
-    let Delta { mut from, to } = state;
+    let Delta { from, to } = state;
     let iter = to.into_iter().map(|(k, v)| {
         Change::Storage(Delta {
             from: f(MappableState {
                 key: k,
-                value: from.remove(&k).cloned(),
-            }),
-            to: f(MappableState {
-                key: k,
-                value: Some(v.clone()),
-            }),
-        })
-    });
-    diff.changes.extend(iter);
-    let iter = from.into_iter().map(|(k, v)| {
-        Change::Storage(Delta {
-            from: f(MappableState {
-                key: k,
-                value: Some(v.clone()),
+                value: from.get(&k).copied().flatten().cloned(),
             }),
             to: f(MappableState {
                 key: k,
-                value: None,
+                value: v.cloned(),
             }),
         })
     });
@@ -392,7 +425,7 @@ where
     S: InterpreterStorage,
 {
     fn write(&mut self, key: &Type::Key, buf: &[u8]) -> Result<usize, Self::Error> {
-        <S as StorageWrite<Type>>::write(&mut self.0, key, buf)
+        <Self as StorageWrite<Type>>::replace(self, key, buf).map(|(written, _)| written)
     }
 
     fn replace(
@@ -400,11 +433,23 @@ where
         key: &Type::Key,
         buf: &[u8],
     ) -> Result<(usize, Option<Vec<u8>>), Self::Error> {
-        <S as StorageWrite<Type>>::replace(&mut self.0, key, buf)
+        let (written, existing) =
+            <S as StorageWrite<Type>>::replace(&mut self.0, key, buf)?;
+        self.1.extend(<Type as StorageType>::record_write(
+            key,
+            buf,
+            existing.clone(),
+        ));
+        Ok((written, existing))
     }
 
     fn take(&mut self, key: &Type::Key) -> Result<Option<Vec<u8>>, Self::Error> {
-        <S as StorageWrite<Type>>::take(&mut self.0, key)
+        let existing = <S as StorageWrite<Type>>::take(&mut self.0, key)?;
+        if let Some(existing) = &existing {
+            self.1
+                .extend(<Type as StorageType>::record_take(key, existing.clone()));
+        }
+        Ok(existing)
     }
 }
 
@@ -460,6 +505,13 @@ where
         self.0.set_state_transition_bytecode(version, hash)
     }
 
+    fn contract_state_usage(
+        &self,
+        id: &ContractId,
+    ) -> Result<Option<ContractStateUsage>, Self::DataError> {
+        self.0.contract_state_usage(id)
+    }
+
     fn contract_state_range(
         &self,
         id: &ContractId,
@@ -479,8 +531,32 @@ where
     where
         I: Iterator<Item = &'a [u8]>,
     {
-        self.0
-            .contract_state_insert_range(contract, start_key, values)
+        let values: Vec<_> = values.collect();
+        let existing = self
+            .0
+            .contract_state_range(contract, start_key, values.len())?
+            .into_iter()
+            .map(|value| value.map(Cow::into_owned))
+            .collect::<Vec<_>>();
+
+        let unset = self.0.contract_state_insert_range(
+            contract,
+            start_key,
+            values.iter().copied(),
+        )?;
+
+        let deltas = state_keys(start_key).zip(values).zip(existing).map(
+            |((key, value), existing)| {
+                StorageDelta::State(MappableDelta::Insert(
+                    (contract, &key).into(),
+                    value.into(),
+                    existing,
+                ))
+            },
+        );
+        self.1.extend(deltas);
+
+        Ok(unset)
     }
 
     fn contract_state_remove_range(
@@ -489,11 +565,43 @@ where
         start_key: &Bytes32,
         range: usize,
     ) -> Result<Option<()>, S::DataError> {
-        self.0
-            .contract_state_remove_range(contract, start_key, range)
+        let existing = self
+            .0
+            .contract_state_range(contract, start_key, range)?
+            .into_iter()
+            .map(|value| value.map(Cow::into_owned))
+            .collect::<Vec<_>>();
+
+        let result = self
+            .0
+            .contract_state_remove_range(contract, start_key, range)?;
+
+        let deltas = state_keys(start_key)
+            .zip(existing)
+            .filter_map(|(key, existing)| {
+                Some(StorageDelta::State(MappableDelta::Remove(
+                    (contract, &key).into(),
+                    existing?,
+                )))
+            });
+        self.1.extend(deltas);
+
+        Ok(result)
     }
 }
 
+/// Consecutive contract state keys, starting from `start_key`.
+fn state_keys(start_key: &Bytes32) -> impl Iterator<Item = Bytes32> {
+    core::iter::successors(Some(U256::from_big_endian(start_key.as_ref())), |key| {
+        key.checked_add(U256::one())
+    })
+    .map(|key| {
+        let mut bytes = [0; 32];
+        key.to_big_endian(&mut bytes);
+        bytes.into()
+    })
+}
+
 impl StorageType for ContractsState {
     fn record_insert(
         key: &Self::Key,
@@ -506,6 +614,18 @@ impl StorageType for ContractsState {
     fn record_remove(key: &Self::Key, value: ContractsStateData) -> StorageDelta {
         StorageDelta::State(MappableDelta::Remove(*key, value))
     }
+
+    fn record_write(
+        key: &Self::Key,
+        value: &[u8],
+        existing: Option<Vec<u8>>,
+    ) -> Option<StorageDelta> {
+        Some(Self::record_insert(key, value, existing.map(Into::into)))
+    }
+
+    fn record_take(key: &Self::Key, value: Vec<u8>) -> Option<StorageDelta> {
+        Some(Self::record_remove(key, value.into()))
+    }
 }
 
 impl StorageType for ContractsAssets {
@@ -534,6 +654,18 @@ impl StorageType for ContractsRawCode {
     fn record_remove(key: &ContractId, value: Contract) -> StorageDelta {
         StorageDelta::RawCode(MappableDelta::Remove(*key, value))
     }
+
+    fn record_write(
+        key: &ContractId,
+        value: &[u8],
+        existing: Option<Vec<u8>>,
+    ) -> Option<StorageDelta> {
+        Some(Self::record_insert(key, value, existing.map(Into::into)))
+    }
+
+    fn record_take(key: &ContractId, value: Vec<u8>) -> Option<StorageDelta> {
+        Some(Self::record_remove(key, value.into()))
+    }
 }
 
 impl StorageType for UploadedBytecodes {
@@ -558,6 +690,7 @@ impl<S> Record<S>
 where
     S: InterpreterStorage,
 {
+    /// Wrap the storage to record its changes.
     pub fn new(s: S) -> Self {
         Self(s, Vec::new())
     }
@@ -11,13 +11,17 @@ use fuel_tx::{
 };
 use fuel_types::{
     AssetId,
+    Bytes32,
     ContractId,
 };
 use test_case::test_case;
 
 use crate::{
     consts::*,
-    storage::MemoryStorage,
+    storage::{
+        InterpreterStorage,
+        MemoryStorage,
+    },
 };
 
 use super::*;
@@ -240,3 +244,50 @@ fn reset_vm_txns() {
     b.reset_vm_state(&diff);
     assert_eq!(a, b);
 }
+
+#[test]
+fn record_and_invert_storage_writes() {
+    let mut vm = Interpreter::<_, _, Script>::with_memory_storage().add_recording();
+    let contract = ContractId::from([3; 32]);
+    let existing = Bytes32::from([1; 32]);
+    let range_start = Bytes32::from([5; 32]);
+    vm.storage
+        .0
+        .contract_state_insert(&contract, &existing, &[9; 32])
+        .unwrap();
+    let state = |storage: &MemoryStorage| {
+        let mut state = storage
+            .contract_state_range(&contract, &range_start, 2)
+            .unwrap()
+            .into_iter()
+            .map(|value| value.map(|value| value.into_owned().0))
+            .collect::<Vec<_>>();
+        state.push(
+            InterpreterStorage::contract_state(storage, &contract, &existing)
+                .unwrap()
+                .map(|value| value.into_owned().0),
+        );
+        state
+    };
+    let original = state(&vm.storage.0);
+    assert_eq!(original, vec![None, None, Some(vec![9; 32])]);
+
+    vm.storage
+        .contract_state_insert(&contract, &existing, &[1; 32])
+        .unwrap();
+    vm.storage
+        .contract_state_insert_range(
+            &contract,
+            &range_start,
+            [[2u8; 32].as_slice(), [3u8; 32].as_slice()].into_iter(),
+        )
+        .unwrap();
+    vm.storage
+        .contract_state_remove_range(&contract, &range_start, 1)
+        .unwrap();
+    assert_ne!(state(&vm.storage.0), original);
+
+    let diff: Diff<InitialVmState> = vm.storage_diff().into();
+    vm.reset_vm_state(&diff);
+    assert_eq!(state(&vm.storage.0), original);
+}
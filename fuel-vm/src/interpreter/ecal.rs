@@ -4,13 +4,17 @@ use fuel_asm::{
     PanicReason,
     RegId,
 };
+use fuel_types::Word;
 
 use crate::{
     constraints::reg_key::{
         split_registers,
         SystemRegisters,
     },
-    error::SimpleResult,
+    error::{
+        PanicOrBug,
+        SimpleResult,
+    },
     interpreter::NotSupportedEcal,
 };
 
@@ -18,6 +22,16 @@ use super::{
     internal::inc_pc,
     Interpreter,
     Memory,
+    PanicContext,
+};
+
+mod registry;
+
+pub use registry::{
+    EcalArg,
+    EcalArgs,
+    EcalContext,
+    EcalRegistry,
 };
 
 /// ECAL opcode handler
@@ -84,7 +98,16 @@ where
         c: RegId,
         d: RegId,
     ) -> SimpleResult<()> {
-        Ecal::ecal(self, a, b, c, d)?;
+        let result = Ecal::ecal(self, a, b, c, d);
+        // The code only belongs to the panic it was created for; a handler that
+        // builds the error but recovers from it must not leak the code onto a
+        // later, unrelated panic.
+        if !matches!(result, Err(PanicOrBug::Panic(PanicReason::EcalError)))
+            && matches!(self.panic_context, PanicContext::Ecal { .. })
+        {
+            self.panic_context = PanicContext::None;
+        }
+        result?;
         let (SystemRegisters { pc, .. }, _) = split_registers(&mut self.registers);
         if Ecal::INC_PC {
             Ok(inc_pc(pc)?)
@@ -94,6 +117,15 @@ where
     }
 }
 
+impl<M, S, Tx, Ecal> Interpreter<M, S, Tx, Ecal> {
+    /// Returns the `EcalError` panic for an ECAL handler to fail with, attaching
+    /// `code` to the panic receipt as [`PanicContext::Ecal`].
+    pub fn ecal_error(&mut self, code: Word) -> PanicOrBug {
+        self.panic_context = PanicContext::Ecal { code };
+        PanicReason::EcalError.into()
+    }
+}
+
 impl<M, S, Tx, Ecal> Interpreter<M, S, Tx, Ecal>
 where
     Ecal: EcalHandler,
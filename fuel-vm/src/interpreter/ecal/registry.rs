@@ -0,0 +1,425 @@
+//! A registry of host functions, dispatched by the ECAL instruction on the value of
+//! its first register.
+
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::String,
+    sync::Arc,
+    vec::Vec,
+};
+use core::fmt::{
+    self,
+    Write as _,
+};
+
+use fuel_asm::{
+    PanicReason,
+    RegId,
+};
+use fuel_tx::{
+    DependentCost,
+    GasCosts,
+};
+use fuel_types::{
+    Address,
+    AssetId,
+    Bytes32,
+    ContractId,
+    Word,
+};
+
+use super::EcalHandler;
+use crate::{
+    consts::VM_REGISTER_COUNT,
+    error::{
+        PanicOrBug,
+        SimpleResult,
+    },
+    interpreter::{
+        Interpreter,
+        Memory,
+        MemoryInstance,
+    },
+};
+
+type HostFn = dyn Fn(&mut EcalContext<'_>) -> SimpleResult<()> + Send + Sync;
+
+/// A registered host function.
+#[derive(Clone)]
+struct HostFunction {
+    gas: Word,
+    call: Arc<HostFn>,
+}
+
+/// ECAL handler dispatching to the host function registered under the value of the
+/// `$rA` register of the instruction. The `$rB`, `$rC` and `$rD` registers are decoded
+/// into the arguments of the function, see [`EcalArg`].
+///
+/// An ECAL with an unregistered function id panics with `EcalError`. The ids of the
+/// standard debug functions fit in the immediate of `MOVI`.
+#[derive(Clone, Default)]
+pub struct EcalRegistry {
+    functions: BTreeMap<Word, HostFunction>,
+}
+
+impl fmt::Debug for EcalRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EcalRegistry")
+            .field("functions", &self.functions.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl EcalRegistry {
+    /// Id of the debug function panicking with `EcalError` if its argument is zero.
+    pub const ASSERT: Word = 0xdeb4;
+    /// Id of the debug function printing a hex dump of a memory range, given as a
+    /// pointer and a length.
+    pub const DUMP_MEMORY: Word = 0xdeb3;
+    /// Id of the debug function printing the name and the value of its argument
+    /// register.
+    pub const PRINT_REGISTER: Word = 0xdeb2;
+    /// Id of the debug function printing a string, given as a pointer and a length.
+    pub const PRINT_STRING: Word = 0xdeb1;
+
+    /// Create a registry without any functions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `function` under `id`, replacing the function previously registered
+    /// under it. `gas` is charged before the function is called, which may charge
+    /// more with [`EcalContext::gas_charge`].
+    pub fn with_function<A, F>(mut self, id: Word, gas: Word, function: F) -> Self
+    where
+        A: EcalArgs,
+        F: Fn(&mut EcalContext<'_>, A) -> SimpleResult<()> + Send + Sync + 'static,
+    {
+        let call = move |ctx: &mut EcalContext<'_>| {
+            let args = A::decode(ctx)?;
+            function(ctx, args)
+        };
+        self.functions.insert(
+            id,
+            HostFunction {
+                gas,
+                call: Arc::new(call),
+            },
+        );
+        self
+    }
+
+    /// Register the standard debug functions, which send their output to `output`:
+    /// [`Self::PRINT_STRING`], [`Self::PRINT_REGISTER`], [`Self::DUMP_MEMORY`] and
+    /// [`Self::ASSERT`]. Each of them costs one unit of gas, and the functions
+    /// printing memory additionally charge the `LOGD` cost of the printed bytes.
+    pub fn with_debug_functions<F>(self, output: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        let output = Arc::new(output);
+        let print = output.clone();
+        let print_register = output.clone();
+        let dump = output.clone();
+
+        self.with_function(
+            Self::PRINT_STRING,
+            1,
+            move |ctx, (ptr, len): (Word, Word)| {
+                let cost = ctx.gas_costs().logd();
+                ctx.dependent_gas_charge(cost, len)?;
+                let string = String::from_utf8(ctx.memory().read(ptr, len)?.to_vec())
+                    .map_err(|_| PanicReason::EcalError)?;
+                print(&string);
+                Ok(())
+            },
+        )
+        .with_function(Self::PRINT_REGISTER, 1, move |ctx, (reg,): (RegId,)| {
+            print_register(&format!("{reg} = {}", ctx.register(reg)));
+            Ok(())
+        })
+        .with_function(
+            Self::DUMP_MEMORY,
+            1,
+            move |ctx, (ptr, len): (Word, Word)| {
+                let cost = ctx.gas_costs().logd();
+                ctx.dependent_gas_charge(cost, len)?;
+                let bytes = ctx.memory().read(ptr, len)?;
+                dump(&hex_dump(ptr, bytes));
+                Ok(())
+            },
+        )
+        .with_function(Self::ASSERT, 1, move |ctx, (value,): (Word,)| {
+            if value == 0 {
+                output(&format!(
+                    "assertion failed at pc {}",
+                    ctx.register(RegId::PC)
+                ));
+                return Err(PanicReason::EcalError.into())
+            }
+            Ok(())
+        })
+    }
+
+    /// Returns `true` if a function is registered under `id`.
+    pub fn contains(&self, id: Word) -> bool {
+        self.functions.contains_key(&id)
+    }
+}
+
+/// Hex dump of `bytes` located at `address`, with 16 bytes per line.
+fn hex_dump(address: Word, bytes: &[u8]) -> String {
+    let mut dump = String::new();
+    for (line, chunk) in (address..).step_by(16).zip(bytes.chunks(16)) {
+        let _ = write!(dump, "{line:#010x}:");
+        for byte in chunk {
+            let _ = write!(dump, " {byte:02x}");
+        }
+        dump.push('\n');
+    }
+    dump
+}
+
+impl EcalHandler for EcalRegistry {
+    fn ecal<M, S, Tx>(
+        vm: &mut Interpreter<M, S, Tx, Self>,
+        a: RegId,
+        b: RegId,
+        c: RegId,
+        d: RegId,
+    ) -> SimpleResult<()>
+    where
+        M: Memory,
+    {
+        let id = vm.registers()[a];
+        let function = vm
+            .ecal_state()
+            .functions
+            .get(&id)
+            .cloned()
+            .ok_or(PanicReason::EcalError)?;
+        vm.gas_charge(function.gas)?;
+
+        let mut ctx = EcalContext {
+            vm,
+            args: [b, c, d],
+            next_arg: 0,
+        };
+        (function.call)(&mut ctx)
+    }
+}
+
+/// The parts of the VM accessible to host functions.
+trait EcalVm {
+    fn registers(&self) -> &[Word; VM_REGISTER_COUNT];
+
+    fn registers_mut(&mut self) -> &mut [Word; VM_REGISTER_COUNT];
+
+    fn memory(&self) -> &MemoryInstance;
+
+    fn gas_costs(&self) -> &GasCosts;
+
+    fn gas_charge(&mut self, gas: Word) -> SimpleResult<()>;
+
+    fn dependent_gas_charge(
+        &mut self,
+        cost: DependentCost,
+        arg: Word,
+    ) -> SimpleResult<()>;
+
+    fn memory_write(&mut self, addr: Word, len: Word) -> SimpleResult<&mut [u8]>;
+
+    fn ecal_error(&mut self, code: Word) -> PanicOrBug;
+}
+
+impl<M, S, Tx> EcalVm for Interpreter<M, S, Tx, EcalRegistry>
+where
+    M: Memory,
+{
+    fn registers(&self) -> &[Word; VM_REGISTER_COUNT] {
+        &self.registers
+    }
+
+    fn registers_mut(&mut self) -> &mut [Word; VM_REGISTER_COUNT] {
+        &mut self.registers
+    }
+
+    fn memory(&self) -> &MemoryInstance {
+        self.memory.as_ref()
+    }
+
+    fn gas_costs(&self) -> &GasCosts {
+        Interpreter::gas_costs(self)
+    }
+
+    fn gas_charge(&mut self, gas: Word) -> SimpleResult<()> {
+        Interpreter::gas_charge(self, gas)
+    }
+
+    fn dependent_gas_charge(
+        &mut self,
+        cost: DependentCost,
+        arg: Word,
+    ) -> SimpleResult<()> {
+        Interpreter::dependent_gas_charge(self, cost, arg)
+    }
+
+    fn memory_write(&mut self, addr: Word, len: Word) -> SimpleResult<&mut [u8]> {
+        Interpreter::memory_write(self, addr, len)
+    }
+
+    fn ecal_error(&mut self, code: Word) -> PanicOrBug {
+        Interpreter::ecal_error(self, code)
+    }
+}
+
+/// Access to the VM from a host function of an [`EcalRegistry`].
+pub struct EcalContext<'vm> {
+    vm: &'vm mut dyn EcalVm,
+    args: [RegId; 3],
+    next_arg: usize,
+}
+
+impl EcalContext<'_> {
+    /// The value of the register.
+    pub fn register(&self, reg: RegId) -> Word {
+        self.vm.registers()[reg]
+    }
+
+    /// Set the value of a writable register, panicking with
+    /// `ReservedRegisterNotWritable` for the reserved ones.
+    pub fn set_register(&mut self, reg: RegId, value: Word) -> SimpleResult<()> {
+        if reg < RegId::WRITABLE {
+            return Err(PanicReason::ReservedRegisterNotWritable.into())
+        }
+        self.vm.registers_mut()[reg] = value;
+        Ok(())
+    }
+
+    /// The `$rB`, `$rC` and `$rD` registers of the ECAL instruction.
+    pub fn args(&self) -> [RegId; 3] {
+        self.args
+    }
+
+    /// The memory of the VM.
+    pub fn memory(&self) -> &MemoryInstance {
+        self.vm.memory()
+    }
+
+    /// Mutable access to the memory range, panicking with `MemoryOwnership` if it is
+    /// not owned by the current context.
+    pub fn memory_write(&mut self, addr: Word, len: Word) -> SimpleResult<&mut [u8]> {
+        self.vm.memory_write(addr, len)
+    }
+
+    /// The gas costs of the VM, to charge host functions like the instructions.
+    pub fn gas_costs(&self) -> &GasCosts {
+        self.vm.gas_costs()
+    }
+
+    /// Charge `gas`, panicking with `OutOfGas` if there is not enough left.
+    pub fn gas_charge(&mut self, gas: Word) -> SimpleResult<()> {
+        self.vm.gas_charge(gas)
+    }
+
+    /// Charge the gas of `cost` for `arg` units, panicking with `OutOfGas` if there
+    /// is not enough left.
+    pub fn dependent_gas_charge(
+        &mut self,
+        cost: DependentCost,
+        arg: Word,
+    ) -> SimpleResult<()> {
+        self.vm.dependent_gas_charge(cost, arg)
+    }
+
+    /// The `EcalError` panic for the host function to fail with, attaching `code`
+    /// to the panic receipt.
+    pub fn error(&mut self, code: Word) -> PanicOrBug {
+        self.vm.ecal_error(code)
+    }
+
+    /// The next argument register, panicking with `EcalError` if all of them were
+    /// decoded already.
+    fn next_arg(&mut self) -> Result<RegId, PanicReason> {
+        let reg = *self.args.get(self.next_arg).ok_or(PanicReason::EcalError)?;
+        self.next_arg = self.next_arg.saturating_add(1);
+        Ok(reg)
+    }
+}
+
+/// An argument of a host function, decoded from one or more of the argument
+/// registers of the ECAL instruction.
+pub trait EcalArg: Sized {
+    /// Decode the argument from the next argument registers.
+    fn decode(ctx: &mut EcalContext<'_>) -> Result<Self, PanicReason>;
+}
+
+/// The value of the register.
+impl EcalArg for Word {
+    fn decode(ctx: &mut EcalContext<'_>) -> Result<Self, PanicReason> {
+        let reg = ctx.next_arg()?;
+        Ok(ctx.register(reg))
+    }
+}
+
+/// The register itself.
+impl EcalArg for RegId {
+    fn decode(ctx: &mut EcalContext<'_>) -> Result<Self, PanicReason> {
+        ctx.next_arg()
+    }
+}
+
+macro_rules! impl_ecal_arg_at_pointer {
+    ($($t:ty),*) => {$(
+        /// Read from the memory at the pointer held by the register.
+        impl EcalArg for $t {
+            fn decode(ctx: &mut EcalContext<'_>) -> Result<Self, PanicReason> {
+                let ptr = Word::decode(ctx)?;
+                Ok(Self::new(ctx.memory().read_bytes(ptr)?))
+            }
+        }
+    )*};
+}
+
+impl_ecal_arg_at_pointer!(Address, AssetId, Bytes32, ContractId);
+
+/// Read from the memory at the pointer and the length held by two registers.
+impl EcalArg for Vec<u8> {
+    fn decode(ctx: &mut EcalContext<'_>) -> Result<Self, PanicReason> {
+        let ptr = Word::decode(ctx)?;
+        let len = Word::decode(ctx)?;
+        Ok(ctx.memory().read(ptr, len)?.to_vec())
+    }
+}
+
+/// Read from the memory at the pointer and the length held by two registers,
+/// panicking with `EcalError` if the bytes are not valid UTF-8.
+impl EcalArg for String {
+    fn decode(ctx: &mut EcalContext<'_>) -> Result<Self, PanicReason> {
+        String::from_utf8(Vec::decode(ctx)?).map_err(|_| PanicReason::EcalError)
+    }
+}
+
+/// The arguments of a host function, a tuple of up to three [`EcalArg`]s decoded in
+/// order.
+pub trait EcalArgs: Sized {
+    /// Decode the arguments from the argument registers.
+    fn decode(ctx: &mut EcalContext<'_>) -> Result<Self, PanicReason>;
+}
+
+macro_rules! impl_ecal_args {
+    ($($t:ident),*) => {
+        impl<$($t: EcalArg),*> EcalArgs for ($($t,)*) {
+            #[allow(unused_variables)]
+            fn decode(ctx: &mut EcalContext<'_>) -> Result<Self, PanicReason> {
+                Ok(($($t::decode(ctx)?,)*))
+            }
+        }
+    };
+}
+
+impl_ecal_args!();
+impl_ecal_args!(A);
+impl_ecal_args!(A, B);
+impl_ecal_args!(A, B, C);
@@ -22,6 +22,7 @@ use crate::{
 
 use fuel_asm::{
     wideint,
+    GMArgs,
     Instruction,
     PanicInstruction,
     PanicReason,
@@ -80,8 +81,29 @@ where
             }
         }
 
-        self.instruction_inner(raw.into())
-            .map_err(|e| InterpreterError::from_runtime(e, raw.into()))
+        #[cfg(feature = "profile-coverage")]
+        {
+            let (registers, frames) = (&self.registers, &self.frames);
+            self.profiler.visit_instruction(
+                || crate::interpreter::debug::instruction_location(registers, frames),
+                raw.into(),
+            );
+        }
+
+        #[cfg(feature = "record-mode")]
+        let record = self.history_before_instruction();
+        self.tracer
+            .before_instruction(&self.registers, self.memory.as_ref(), raw.into());
+        let result = self
+            .instruction_inner(raw.into())
+            .map_err(|e| InterpreterError::from_runtime(e, raw.into()));
+        self.tracer
+            .after_instruction(&self.registers, self.memory.as_ref());
+        #[cfg(feature = "record-mode")]
+        if let Some(record) = record {
+            record(self);
+        }
+        result
     }
 
     fn instruction_inner(
@@ -579,6 +601,8 @@ where
                 let a = ret.unpack();
                 let ra = r!(a);
                 self.ret(ra)?;
+                #[cfg(feature = "profile-gas")]
+                self.profiler_sync_call_stack();
                 return Ok(ExecuteState::Return(ra))
             }
 
@@ -586,7 +610,10 @@ where
                 let (a, b) = retd.unpack();
                 let len = r!(b);
                 self.dependent_gas_charge(self.gas_costs().retd(), len)?;
-                return Ok(self.ret_data(r!(a), len).map(ExecuteState::ReturnData)?)
+                let digest = self.ret_data(r!(a), len)?;
+                #[cfg(feature = "profile-gas")]
+                self.profiler_sync_call_stack();
+                return Ok(ExecuteState::ReturnData(digest))
             }
 
             Instruction::RVRT(rvrt) => {
@@ -747,6 +774,8 @@ where
 
                 // Enter call context
                 self.prepare_call(a, b, c, d)?;
+                #[cfg(feature = "profile-gas")]
+                self.profiler_sync_call_stack();
             }
 
             Instruction::CB(cb) => {
@@ -872,6 +901,13 @@ where
             Instruction::GM(gm) => {
                 self.gas_charge(self.gas_costs().gm())?;
                 let (a, imm) = gm.unpack();
+                // The state usage of the contract is read from the storage.
+                if matches!(
+                    GMArgs::try_from(imm.to_u32()),
+                    Ok(GMArgs::StateSlotCount | GMArgs::StateByteSize)
+                ) {
+                    self.gas_charge(self.gas_costs().srw())?;
+                }
                 self.metadata(a.into(), imm.into())?;
             }
 
@@ -938,6 +938,15 @@ where
             self.profiler.on_transaction(r);
         }
 
+        #[cfg(feature = "trace")]
+        {
+            let r = match &state_result {
+                Ok(state) => Ok(state),
+                Err(err) => Err(err.erase_generics()),
+            };
+            self.tracer.on_transaction(r);
+        }
+
         let state = state_result?;
         Ok(StateTransitionRef::new(
             state,
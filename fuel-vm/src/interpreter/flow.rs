@@ -140,13 +140,14 @@ where
         let mut receipt =
             Receipt::panic(self.internal_contract().unwrap_or_default(), result, pc, is);
 
-        match self.panic_context {
-            PanicContext::None => {}
-            PanicContext::ContractId(contract_id) => {
-                receipt = receipt.with_panic_contract_id(Some(contract_id));
-            }
-        };
-        self.panic_context = PanicContext::None;
+        if self.panic_context.is_none() {
+            self.panic_context = self.derive_panic_context(&result);
+        }
+        if let PanicContext::ContractId(contract_id) = self.panic_context {
+            receipt = receipt.with_panic_contract_id(Some(contract_id));
+        }
+        let panic_context = core::mem::take(&mut self.panic_context);
+        receipt = receipt.with_panic_context(panic_context);
 
         self.receipts
             .push(receipt)
@@ -39,7 +39,9 @@ impl<M, S, Tx, Ecal> Interpreter<M, S, Tx, Ecal> {
         self.registers[RegId::CGAS] = gas;
     }
 
-    pub(crate) fn dependent_gas_charge(
+    /// Do a gas charge of `gas_cost` for `arg` units, panicing when running out of
+    /// gas.
+    pub fn dependent_gas_charge(
         &mut self,
         gas_cost: DependentCost,
         arg: Word,
@@ -57,6 +59,21 @@ impl<M, S, Tx, Ecal> Interpreter<M, S, Tx, Ecal> {
         dependent_gas_charge(cgas, ggas, profiler, gas_cost, arg)
     }
 
+    /// Update the call stack tracked by the profiler after call frames were pushed or
+    /// popped.
+    #[cfg(feature = "profile-gas")]
+    pub(crate) fn profiler_sync_call_stack(&mut self) {
+        if self.profiler.call_depth() != self.frames.len() {
+            let callers = crate::backtrace::call_stack_locations(
+                &ContractId::zeroed(),
+                &self.registers,
+                &self.frames,
+            );
+            self.profiler
+                .set_call_stack(callers.into_iter().skip(1).rev().collect());
+        }
+    }
+
     /// Do a gas charge with the given amount, panicing when running out of gas.
     pub fn gas_charge(&mut self, gas: Word) -> SimpleResult<()> {
         let current_contract = self.contract_id();
@@ -45,6 +45,8 @@ where
         self.initial_balances = initial_balances.clone();
 
         self.frames.clear();
+        #[cfg(feature = "profile-gas")]
+        self.profiler_sync_call_stack();
         self.receipts.clear();
         self.memory_mut().reset();
 
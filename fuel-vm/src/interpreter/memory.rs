@@ -131,6 +131,33 @@ impl MemoryInstance {
         MEM_SIZE.saturating_sub(self.heap.len())
     }
 
+    /// The stack buffer and the accessible part of the heap, which starts at `hp`.
+    #[allow(clippy::arithmetic_side_effects)] // Safety: hp is kept valid everywhere
+    pub(crate) fn sections(&self) -> (&[u8], &[u8]) {
+        (&self.stack, &self.heap[self.hp - self.heap_offset()..])
+    }
+
+    /// Resize the stack to `stack_len` bytes and move the start of the heap to `hp`,
+    /// zeroing any newly accessible bytes.
+    #[allow(clippy::arithmetic_side_effects)] // Safety: hp is clamped to MEM_SIZE
+    pub(crate) fn set_layout(&mut self, stack_len: usize, hp: usize) {
+        let hp = hp.min(MEM_SIZE);
+        let new_len = MEM_SIZE - hp;
+        let old_len = self.heap.len();
+        if old_len < new_len {
+            let prefix_zeroes = new_len - old_len;
+            self.heap.resize(new_len, 0);
+            self.heap.copy_within(..old_len, prefix_zeroes);
+            self.heap[..prefix_zeroes].fill(0);
+        } else if hp < self.hp {
+            let start = hp - self.heap_offset();
+            let end = self.hp - self.heap_offset();
+            self.heap[start..end].fill(0);
+        }
+        self.hp = hp;
+        self.stack.resize(stack_len.min(hp), 0);
+    }
+
     /// Returns a linear memory representation where stack is at the beginning and heap is
     /// at the end.
     pub fn into_linear_memory(self) -> Vec<u8> {
@@ -619,6 +646,13 @@ where
         Ok(())
     }
 
+    /// Returns mutable access to the memory range, panicking with `MemoryOwnership`
+    /// if it is not owned by the current context.
+    pub fn memory_write(&mut self, addr: Word, len: Word) -> SimpleResult<&mut [u8]> {
+        let owner = self.ownership_registers();
+        Ok(self.memory.as_mut().write(owner, addr, len)?)
+    }
+
     pub(crate) fn malloc(&mut self, a: Word) -> SimpleResult<()> {
         let (SystemRegisters { hp, sp, pc, .. }, _) =
             split_registers(&mut self.registers);
@@ -10,7 +10,15 @@ use crate::{
     consts::*,
     context::Context,
     convert,
-    error::SimpleResult,
+    error::{
+        IoResult,
+        RuntimeError,
+        SimpleResult,
+    },
+    storage::{
+        ContractStateUsage,
+        InterpreterStorage,
+    },
 };
 
 use fuel_asm::{
@@ -55,9 +63,24 @@ where
         &mut self,
         ra: RegisterId,
         imm: Immediate18,
-    ) -> SimpleResult<()> {
+    ) -> IoResult<(), S::DataError>
+    where
+        S: InterpreterStorage,
+    {
         let tx_offset = self.tx_offset() as Word;
         let chain_id = self.chain_id();
+        let state_usage = match GMArgs::try_from(imm) {
+            Ok(GMArgs::StateSlotCount | GMArgs::StateByteSize) => {
+                let contract_id = self.internal_contract()?;
+                let usage = self
+                    .storage
+                    .contract_state_usage(&contract_id)
+                    .map_err(RuntimeError::Storage)?
+                    .ok_or(PanicReason::InvalidMetadataIdentifier)?;
+                Some(usage)
+            }
+            _ => None,
+        };
         let (SystemRegisters { pc, .. }, mut w) = split_registers(&mut self.registers);
         let result = &mut w[WriteRegKey::try_from(ra)?];
         metadata(
@@ -68,7 +91,9 @@ where
             imm,
             chain_id,
             tx_offset,
-        )
+            state_usage,
+        )?;
+        Ok(())
     }
 
     pub(crate) fn get_transaction_field(
@@ -97,6 +122,9 @@ where
     }
 }
 
+/// The `state_usage` of the current contract is only fetched for the selectors
+/// reading it.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn metadata(
     context: &Context,
     frames: &[CallFrame],
@@ -105,6 +133,7 @@ pub(crate) fn metadata(
     imm: Immediate18,
     chain_id: ChainId,
     tx_offset: Word,
+    state_usage: Option<ContractStateUsage>,
 ) -> SimpleResult<()> {
     let parent = context
         .is_internal()
@@ -128,6 +157,16 @@ pub(crate) fn metadata(
             Some(p) => (p == 0) as Word,
             None => return Err(PanicReason::ExpectedInternalContext.into()),
         },
+        GMArgs::StateSlotCount => {
+            state_usage
+                .ok_or(PanicReason::ExpectedInternalContext)?
+                .slots
+        }
+        GMArgs::StateByteSize => {
+            state_usage
+                .ok_or(PanicReason::ExpectedInternalContext)?
+                .bytes
+        }
     };
 
     inc_pc(pc)?;
@@ -0,0 +1,287 @@
+//! Derivation of the [`PanicContext`] of a panic from the instruction that caused it.
+
+use super::{
+    memory::OwnershipRegisters,
+    Interpreter,
+    Memory,
+    PanicContext,
+};
+use crate::consts::WORD_SIZE;
+
+use alloc::{
+    vec,
+    vec::Vec,
+};
+use fuel_asm::{
+    Instruction,
+    PanicInstruction,
+    PanicReason,
+    RegId,
+};
+use fuel_tx::ContractIdExt;
+use fuel_types::{
+    AssetId,
+    Bytes32,
+    Word,
+};
+
+/// A memory range accessed by an instruction.
+struct Access {
+    start: Word,
+    len: Word,
+    write: bool,
+}
+
+impl Access {
+    const fn read(start: Word, len: Word) -> Self {
+        Self {
+            start,
+            len,
+            write: false,
+        }
+    }
+
+    const fn write(start: Word, len: Word) -> Self {
+        Self {
+            start,
+            len,
+            write: true,
+        }
+    }
+}
+
+impl<M, S, Tx, Ecal> Interpreter<M, S, Tx, Ecal>
+where
+    M: Memory,
+{
+    /// Derives the context of a panic from the failed instruction and the state of
+    /// the registers and memory at the moment of the panic.
+    ///
+    /// Only the panics of the instructions listed in [`Self::memory_accesses`],
+    /// [`Self::balance_panic_context`] and [`Self::arithmetic_panic_context`] get a
+    /// context. Others, e.g. the memory panics of `LDC`, of the wide integer
+    /// instructions or of ECAL handlers, get `PanicContext::None` unless the context
+    /// was already set when the panic was raised.
+    pub(crate) fn derive_panic_context(&self, result: &PanicInstruction) -> PanicContext {
+        let Ok(instruction) = Instruction::try_from(*result.instruction()) else {
+            return PanicContext::None
+        };
+
+        match result.reason() {
+            PanicReason::MemoryOverflow
+            | PanicReason::MemoryOwnership
+            | PanicReason::MemoryWriteOverlap => self.memory_panic_context(instruction),
+            PanicReason::NotEnoughBalance => self.balance_panic_context(instruction),
+            PanicReason::ArithmeticOverflow => self.arithmetic_panic_context(instruction),
+            _ => PanicContext::None,
+        }
+    }
+
+    /// The first memory range of the instruction that is out of bounds or not owned,
+    /// or the first written range if all of them are accessible.
+    fn memory_panic_context(&self, instruction: Instruction) -> PanicContext {
+        let accesses = self.memory_accesses(instruction);
+        let ownership = OwnershipRegisters::new(self);
+        let inaccessible = |access: &&Access| {
+            self.memory().verify(access.start, access.len).is_err()
+                || access.write
+                    && !ownership.has_ownership_range(
+                        &(access.start..access.start.saturating_add(access.len)),
+                    )
+        };
+
+        accesses
+            .iter()
+            .find(inaccessible)
+            .or_else(|| accesses.iter().find(|access| access.write))
+            .map_or(PanicContext::None, |access| PanicContext::MemoryRange {
+                start: access.start,
+                len: access.len,
+            })
+    }
+
+    /// The memory ranges accessed by the instruction.
+    fn memory_accesses(&self, instruction: Instruction) -> Vec<Access> {
+        let r = |reg: RegId| self.registers[reg];
+        let word = WORD_SIZE as Word;
+
+        match instruction {
+            Instruction::LB(lb) => {
+                let (_, b, imm) = lb.unpack();
+                vec![Access::read(r(b).saturating_add(imm.into()), 1)]
+            }
+            Instruction::LW(lw) => {
+                let (_, b, imm) = lw.unpack();
+                let offset = Word::from(imm).saturating_mul(word);
+                vec![Access::read(r(b).saturating_add(offset), word)]
+            }
+            Instruction::SB(sb) => {
+                let (a, _, imm) = sb.unpack();
+                vec![Access::write(r(a).saturating_add(imm.into()), 1)]
+            }
+            Instruction::SW(sw) => {
+                let (a, _, imm) = sw.unpack();
+                let offset = Word::from(imm).saturating_mul(word);
+                vec![Access::write(r(a).saturating_add(offset), word)]
+            }
+            Instruction::MCL(mcl) => {
+                let (a, b) = mcl.unpack();
+                vec![Access::write(r(a), r(b))]
+            }
+            Instruction::MCLI(mcli) => {
+                let (a, imm) = mcli.unpack();
+                vec![Access::write(r(a), imm.into())]
+            }
+            Instruction::CCP(ccp) => {
+                let (a, b, _, d) = ccp.unpack();
+                vec![Access::write(r(a), r(d)), Access::read(r(b), 32)]
+            }
+            Instruction::MCP(mcp) => {
+                let (a, b, c) = mcp.unpack();
+                vec![Access::write(r(a), r(c)), Access::read(r(b), r(c))]
+            }
+            Instruction::MCPI(mcpi) => {
+                let (a, b, imm) = mcpi.unpack();
+                vec![
+                    Access::write(r(a), imm.into()),
+                    Access::read(r(b), imm.into()),
+                ]
+            }
+            Instruction::MEQ(meq) => {
+                let (_, b, c, d) = meq.unpack();
+                vec![Access::read(r(b), r(d)), Access::read(r(c), r(d))]
+            }
+            Instruction::LOGD(logd) => {
+                let (_, _, c, d) = logd.unpack();
+                vec![Access::read(r(c), r(d))]
+            }
+            Instruction::RETD(retd) => {
+                let (a, b) = retd.unpack();
+                vec![Access::read(r(a), r(b))]
+            }
+            Instruction::S256(s256) => {
+                let (a, b, c) = s256.unpack();
+                vec![Access::write(r(a), 32), Access::read(r(b), r(c))]
+            }
+            Instruction::K256(k256) => {
+                let (a, b, c) = k256.unpack();
+                vec![Access::write(r(a), 32), Access::read(r(b), r(c))]
+            }
+            Instruction::ECK1(eck1) => {
+                let (a, b, c) = eck1.unpack();
+                vec![
+                    Access::write(r(a), 64),
+                    Access::read(r(b), 64),
+                    Access::read(r(c), 32),
+                ]
+            }
+            Instruction::SRWQ(srwq) => {
+                let (a, _, c, d) = srwq.unpack();
+                vec![
+                    Access::write(r(a), r(d).saturating_mul(32)),
+                    Access::read(r(c), 32),
+                ]
+            }
+            Instruction::SWWQ(swwq) => {
+                let (a, _, c, d) = swwq.unpack();
+                vec![
+                    Access::read(r(a), 32),
+                    Access::read(r(c), r(d).saturating_mul(32)),
+                ]
+            }
+            Instruction::SCWQ(scwq) => {
+                let (a, _, _) = scwq.unpack();
+                vec![Access::read(r(a), 32)]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// The asset and the amount of the failed transfer.
+    fn balance_panic_context(&self, instruction: Instruction) -> PanicContext {
+        let r = |reg: RegId| self.registers[reg];
+        let asset_at = |ptr: Word| {
+            self.memory()
+                .read_bytes(ptr)
+                .map(AssetId::new)
+                .unwrap_or_default()
+        };
+
+        let (asset_id, amount) = match instruction {
+            Instruction::TR(tr) => {
+                let (_, b, c) = tr.unpack();
+                (asset_at(r(c)), r(b))
+            }
+            Instruction::TRO(tro) => {
+                let (_, _, c, d) = tro.unpack();
+                (asset_at(r(d)), r(c))
+            }
+            Instruction::CALL(call) => {
+                let (_, b, c, _) = call.unpack();
+                (asset_at(r(c)), r(b))
+            }
+            Instruction::SMO(smo) => {
+                let (_, _, _, d) = smo.unpack();
+                (self.interpreter_params.base_asset_id, r(d))
+            }
+            Instruction::BURN(burn) => {
+                let (a, b) = burn.unpack();
+                let Ok(contract_id) = self.internal_contract() else {
+                    return PanicContext::None
+                };
+                let sub_id = self
+                    .memory()
+                    .read_bytes(r(b))
+                    .map(Bytes32::new)
+                    .unwrap_or_default();
+                (contract_id.asset_id(&sub_id), r(a))
+            }
+            _ => return PanicContext::None,
+        };
+
+        PanicContext::Balance { asset_id, amount }
+    }
+
+    /// The operands of the overflowed arithmetic operation.
+    fn arithmetic_panic_context(&self, instruction: Instruction) -> PanicContext {
+        let r = |reg: RegId| self.registers[reg];
+
+        let (lhs, rhs) = match instruction {
+            Instruction::ADD(op) => {
+                let (_, b, c) = op.unpack();
+                (r(b), r(c))
+            }
+            Instruction::SUB(op) => {
+                let (_, b, c) = op.unpack();
+                (r(b), r(c))
+            }
+            Instruction::MUL(op) => {
+                let (_, b, c) = op.unpack();
+                (r(b), r(c))
+            }
+            Instruction::EXP(op) => {
+                let (_, b, c) = op.unpack();
+                (r(b), r(c))
+            }
+            Instruction::ADDI(op) => {
+                let (_, b, imm) = op.unpack();
+                (r(b), imm.into())
+            }
+            Instruction::SUBI(op) => {
+                let (_, b, imm) = op.unpack();
+                (r(b), imm.into())
+            }
+            Instruction::MULI(op) => {
+                let (_, b, imm) = op.unpack();
+                (r(b), imm.into())
+            }
+            Instruction::EXPI(op) => {
+                let (_, b, imm) = op.unpack();
+                (r(b), imm.into())
+            }
+            _ => return PanicContext::None,
+        };
+
+        PanicContext::Arithmetic { lhs, rhs }
+    }
+}
@@ -23,6 +23,8 @@ extern crate libm as _; // Not needed with stdlib
 use criterion as _;
 
 pub mod backtrace;
+#[cfg(feature = "std")]
+pub mod block_executor;
 pub mod call;
 pub mod checked_transaction;
 pub mod constraints;
@@ -30,11 +32,16 @@ pub mod consts;
 pub mod context;
 mod convert;
 pub mod crypto;
+pub mod debug_info;
+#[cfg(feature = "debug-server")]
+pub mod debug_server;
 pub mod error;
 pub mod interpreter;
 pub mod memory_client;
 pub mod pool;
 pub mod predicate;
+#[cfg(feature = "replay")]
+pub mod replay;
 pub mod state;
 pub mod storage;
 pub mod transactor;
@@ -64,6 +71,43 @@ pub mod profiler {
     }
 }
 
+#[cfg(feature = "trace")]
+pub mod trace;
+
+#[cfg(not(feature = "trace"))]
+/// Placeholder
+pub mod trace {
+    use crate::{
+        consts::VM_REGISTER_COUNT,
+        interpreter::MemoryInstance,
+    };
+    use fuel_asm::RawInstruction;
+    use fuel_types::Word;
+
+    /// Placeholder tracer.
+    #[derive(Default, Debug, Clone)]
+    pub struct Tracer;
+
+    impl Tracer {
+        /// Called by the VM before an instruction.
+        pub(crate) fn before_instruction(
+            &mut self,
+            _registers: &[Word; VM_REGISTER_COUNT],
+            _memory: &MemoryInstance,
+            _raw: RawInstruction,
+        ) {
+        }
+
+        /// Called by the VM after an instruction.
+        pub(crate) fn after_instruction(
+            &mut self,
+            _registers: &[Word; VM_REGISTER_COUNT],
+            _memory: &MemoryInstance,
+        ) {
+        }
+    }
+}
+
 // Fully re-export fuel dependencies
 #[doc(no_inline)]
 pub use fuel_asm;
@@ -133,6 +177,11 @@ pub mod prelude {
             CallFrame,
         },
         context::Context,
+        debug_info::{
+            DebugInfo,
+            ProgramDebugInfo,
+            SourceLocation,
+        },
         error::{
             Bug,
             BugVariant,
@@ -158,6 +207,7 @@ pub mod prelude {
         storage::{
             InterpreterStorage,
             MemoryStorage,
+            OverlayStorage,
             PredicateStorage,
         },
         transactor::Transactor,
@@ -184,9 +234,17 @@ pub mod prelude {
     ))]
     pub use crate::util::gas_profiling::GasProfiler;
 
+    #[cfg(feature = "file-storage")]
+    pub use crate::storage::FileStorage;
+
+    #[cfg(feature = "profile-coverage")]
+    pub use crate::profiler::CoverageReport;
+    #[cfg(all(feature = "profile-gas", feature = "std"))]
+    pub use crate::profiler::FlamegraphReceiver;
     pub use crate::profiler::Profiler;
     #[cfg(feature = "profile-any")]
     pub use crate::profiler::{
+        BranchCoverage,
         CoverageProfilingData,
         GasProfilingData,
         InstructionLocation,
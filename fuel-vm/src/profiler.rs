@@ -2,11 +2,8 @@
 
 use alloc::{
     boxed::Box,
-    format,
-    string::{
-        String,
-        ToString,
-    },
+    collections::BTreeMap,
+    string::String,
     vec::Vec,
 };
 use core::fmt;
@@ -14,12 +11,34 @@ use hashbrown::HashMap;
 
 use dyn_clone::DynClone;
 
+#[cfg(feature = "serde")]
 use fuel_types::ContractId;
 
-use crate::prelude::*;
+use crate::{
+    debug_info::SourceLocation,
+    prelude::*,
+};
 
 pub use crate::constraints::InstructionLocation;
 
+#[cfg(feature = "profile-gas")]
+mod flamegraph;
+
+#[cfg(all(feature = "profile-gas", feature = "std"))]
+pub use flamegraph::FlamegraphReceiver;
+
+#[cfg(feature = "profile-coverage")]
+mod coverage;
+
+#[cfg(feature = "profile-coverage")]
+pub use coverage::{
+    CoverageReport,
+    CoverageSummary,
+    CoverageTotals,
+    ProgramCoverageSummary,
+    ProgramId,
+};
+
 #[cfg(feature = "serde")]
 impl serde::Serialize for InstructionLocation {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -77,43 +96,6 @@ impl<'de> serde::Deserialize<'de> for InstructionLocation {
     }
 }
 
-impl InstructionLocation {
-    /// New location from context and offset
-    pub const fn new(context: Option<ContractId>, offset: u64) -> Self {
-        Self { context, offset }
-    }
-
-    /// Context, i.e. current contract
-    pub const fn context(&self) -> Option<ContractId> {
-        self.context
-    }
-
-    /// Offset from the IS register
-    pub const fn offset(&self) -> u64 {
-        self.offset
-    }
-}
-
-impl fmt::Display for InstructionLocation {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "Location({}, offset={})",
-            self.context
-                .map(|contract_id| format!(
-                    "contract_id={}",
-                    contract_id.iter().fold(String::new(), |mut output, b| {
-                        use core::fmt::Write;
-                        let _ = write!(output, "{b:02x?}");
-                        output
-                    })
-                ),)
-                .unwrap_or_else(|| "script".to_string()),
-            self.offset
-        )
-    }
-}
-
 type PerLocation<T> = HashMap<InstructionLocation, T>;
 
 /// Iterates through location (key, value) pairs
@@ -183,6 +165,11 @@ pub struct Profiler {
     receiver: Option<Box<dyn ProfileReceiver + Send + Sync>>,
     /// Collected profiling data
     data: ProfilingData,
+    /// Locations of the `CALL` instructions of the current call frames, outermost first
+    call_stack: Vec<InstructionLocation>,
+    /// Location of the last executed conditional jump, until the next instruction
+    /// shows whether it was taken
+    pending_branch: Option<InstructionLocation>,
 }
 
 impl Profiler {
@@ -191,6 +178,7 @@ impl Profiler {
         &mut self,
         state_result: Result<&ProgramState, InterpreterError<String>>,
     ) {
+        self.pending_branch = None;
         if let Some(r) = &mut self.receiver {
             r.on_transaction(state_result, &self.data);
         }
@@ -218,9 +206,59 @@ impl Profiler {
         self.data_mut().coverage_mut().set(location);
     }
 
+    /// Called by the VM before executing `instruction`. Records whether the previous
+    /// instruction, if it was a conditional jump, was taken. The `location` of the
+    /// instruction is only computed when it is needed for that.
+    #[cfg(feature = "profile-coverage")]
+    pub fn visit_instruction<F>(
+        &mut self,
+        location: F,
+        instruction: fuel_asm::RawInstruction,
+    ) where
+        F: FnOnce() -> InstructionLocation,
+    {
+        let [opcode, ..] = instruction.to_be_bytes();
+        let is_conditional_jump =
+            Opcode::try_from(opcode).is_ok_and(coverage::is_conditional_jump);
+        if self.pending_branch.is_none() && !is_conditional_jump {
+            return
+        }
+
+        let location = location();
+        if let Some(branch) = self.pending_branch.take() {
+            let fallthrough = branch.offset.checked_add(Instruction::SIZE as Word);
+            let taken = location.context != branch.context
+                || Some(location.offset) != fallthrough;
+            self.data.coverage.add_branch(branch, taken);
+        }
+
+        if is_conditional_jump {
+            self.pending_branch = Some(location);
+        }
+    }
+
     /// Add gas to the current coverage location.
     pub fn add_gas(&mut self, location: InstructionLocation, gas_use: u64) {
-        self.data_mut().gas_mut().add(location, gas_use);
+        self.data.gas.add(location, gas_use);
+
+        // Outside of any call the stack is the location alone, which
+        // `GasProfilingData::stacks` already derives from the gas per location.
+        if !self.call_stack.is_empty() {
+            self.call_stack.push(location);
+            self.data.gas.add_stack(&self.call_stack, gas_use);
+            self.call_stack.pop();
+        }
+    }
+
+    /// Number of call frames of the tracked call stack.
+    pub fn call_depth(&self) -> usize {
+        self.call_stack.len()
+    }
+
+    /// Set the locations of the `CALL` instructions of the current call frames,
+    /// outermost first. Gas added afterwards is also attributed to this call stack.
+    pub fn set_call_stack(&mut self, call_stack: Vec<InstructionLocation>) {
+        self.call_stack = call_stack;
     }
 }
 
@@ -280,6 +318,19 @@ impl ProfilingData {
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CoverageProfilingData {
     executed: PerLocation<()>,
+    /// Outcomes of the executed conditional jumps
+    #[cfg_attr(feature = "serde", serde(default))]
+    branches: PerLocation<BranchCoverage>,
+}
+
+/// How many times a conditional jump was taken or not.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BranchCoverage {
+    /// Number of times the jump was taken
+    pub taken: u64,
+    /// Number of times the execution fell through to the next instruction
+    pub not_taken: u64,
 }
 
 impl<'a> CoverageProfilingData {
@@ -297,6 +348,27 @@ impl<'a> CoverageProfilingData {
     pub fn iter(&'a self) -> PerLocationKeys<'a, ()> {
         PerLocationKeys(self.executed.keys())
     }
+
+    /// Record an outcome of the conditional jump at location
+    pub fn add_branch(&mut self, location: InstructionLocation, taken: bool) {
+        let branch = self.branches.entry(location).or_default();
+        // Saturating is ok for profiling.
+        if taken {
+            branch.taken = branch.taken.saturating_add(1);
+        } else {
+            branch.not_taken = branch.not_taken.saturating_add(1);
+        }
+    }
+
+    /// Get the outcomes of the conditional jump at location
+    pub fn branch(&self, location: &InstructionLocation) -> BranchCoverage {
+        self.branches.get(location).copied().unwrap_or_default()
+    }
+
+    /// Iterate through the locations of conditional jumps and their outcomes
+    pub fn branches(&'a self) -> PerLocationIter<'a, BranchCoverage> {
+        PerLocationIter(self.branches.iter())
+    }
 }
 
 impl fmt::Display for CoverageProfilingData {
@@ -312,6 +384,40 @@ impl fmt::Display for CoverageProfilingData {
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GasProfilingData {
     gas_use: PerLocation<u64>,
+    /// Used gas per call stack, see [`GasProfilingData::add_stack`]
+    #[cfg_attr(feature = "serde", serde(default, with = "serde_stacks"))]
+    gas_use_per_stack: HashMap<Vec<InstructionLocation>, u64>,
+}
+
+/// Serializes the call stacks as a sequence of pairs, as they can't be map keys
+#[cfg(feature = "serde")]
+mod serde_stacks {
+    use super::*;
+    use serde::{
+        Deserialize,
+        Deserializer,
+        Serializer,
+    };
+
+    pub fn serialize<S>(
+        stacks: &HashMap<Vec<InstructionLocation>, u64>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(stacks)
+    }
+
+    pub fn deserialize<'de, D>(
+        deserializer: D,
+    ) -> Result<HashMap<Vec<InstructionLocation>, u64>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let stacks = Vec::<(Vec<InstructionLocation>, u64)>::deserialize(deserializer)?;
+        Ok(stacks.into_iter().collect())
+    }
 }
 
 impl<'a> GasProfilingData {
@@ -343,6 +449,50 @@ impl<'a> GasProfilingData {
     pub fn values(&'a self) -> PerLocationValues<'a, u64> {
         PerLocationValues(self.gas_use.values())
     }
+
+    /// Increase gas used by the instruction at the end of `stack`, which is preceded by
+    /// the locations of the `CALL` instructions leading to it, outermost first. The gas
+    /// used outside of any call is only recorded with [`GasProfilingData::add`].
+    pub fn add_stack(&mut self, stack: &[InstructionLocation], amount: u64) {
+        // Saturating is ok for profiling, as above.
+        match self.gas_use_per_stack.get_mut(stack) {
+            Some(gas_use) => *gas_use = gas_use.saturating_add(amount),
+            None => {
+                self.gas_use_per_stack.insert(stack.to_vec(), amount);
+            }
+        }
+    }
+
+    /// Iterate through call stacks and gas values. The gas used by the script or
+    /// predicate itself, outside of any call, is yielded as stacks of a single
+    /// location taken from the gas per location.
+    pub fn stacks(&'a self) -> impl Iterator<Item = (&'a [InstructionLocation], u64)> {
+        let outside_calls = self
+            .gas_use
+            .iter()
+            .filter(|(location, _)| location.context.is_none())
+            .map(|(location, gas)| (core::slice::from_ref(location), *gas));
+        self.gas_use_per_stack
+            .iter()
+            .map(|(stack, gas)| (stack.as_slice(), *gas))
+            .chain(outside_calls)
+    }
+}
+
+impl GasProfilingData {
+    /// Aggregate the used gas per source location. Gas used by instructions without
+    /// a known source location is accumulated under `None`.
+    pub fn per_source_location<'a>(
+        &self,
+        debug_info: &'a DebugInfo,
+    ) -> BTreeMap<Option<&'a SourceLocation>, u64> {
+        let mut result = BTreeMap::new();
+        for (location, gas) in self.iter() {
+            let total = result.entry(debug_info.resolve(location)).or_insert(0u64);
+            *total = total.saturating_add(*gas);
+        }
+        result
+    }
 }
 
 impl fmt::Display for GasProfilingData {
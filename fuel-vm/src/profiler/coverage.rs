@@ -0,0 +1,297 @@
+//! Coverage reports of the executed programs, accumulated across transactions.
+
+use alloc::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    string::String,
+    vec::Vec,
+};
+use core::fmt::{
+    self,
+    Write as _,
+};
+
+use fuel_asm::{
+    Instruction,
+    Opcode,
+};
+use fuel_crypto::Hasher;
+use fuel_types::{
+    Bytes32,
+    ContractId,
+    Word,
+};
+
+use super::{
+    BranchCoverage,
+    CoverageProfilingData,
+};
+
+/// Returns `true` if the instructions with the opcode may either jump or fall through
+/// to the next instruction.
+pub(super) fn is_conditional_jump(opcode: Opcode) -> bool {
+    matches!(
+        opcode,
+        Opcode::JNE
+            | Opcode::JNEI
+            | Opcode::JNZI
+            | Opcode::JNZF
+            | Opcode::JNZB
+            | Opcode::JNEF
+            | Opcode::JNEB
+    )
+}
+
+/// Identifies a program in a coverage report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ProgramId {
+    /// A script, identified by the hash of its bytecode.
+    Script(Bytes32),
+    /// A contract.
+    Contract(ContractId),
+}
+
+impl fmt::Display for ProgramId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Script(hash) => write!(f, "script:{hash}"),
+            Self::Contract(id) => write!(f, "contract:{id}"),
+        }
+    }
+}
+
+/// Coverage of a single program.
+#[derive(Debug, Clone)]
+struct ProgramCoverage {
+    /// Offsets of the words of the bytecode that decode to instructions.
+    instructions: BTreeSet<Word>,
+    /// Offsets of the conditional jumps.
+    jumps: BTreeSet<Word>,
+    /// Offsets of the executed instructions.
+    executed: BTreeSet<Word>,
+    /// Outcomes of the executed conditional jumps.
+    branches: BTreeMap<Word, BranchCoverage>,
+}
+
+impl ProgramCoverage {
+    fn new(bytecode: &[u8]) -> Self {
+        let mut instructions = BTreeSet::new();
+        let mut jumps = BTreeSet::new();
+        for (offset, instruction) in (0..)
+            .step_by(Instruction::SIZE)
+            .zip(fuel_asm::from_bytes(bytecode.iter().copied()))
+        {
+            if let Ok(instruction) = instruction {
+                instructions.insert(offset);
+                if is_conditional_jump(instruction.opcode()) {
+                    jumps.insert(offset);
+                }
+            }
+        }
+
+        Self {
+            instructions,
+            jumps,
+            executed: BTreeSet::new(),
+            branches: BTreeMap::new(),
+        }
+    }
+
+    fn totals(&self) -> CoverageTotals {
+        let branches_hit = self
+            .jumps
+            .iter()
+            .filter_map(|offset| self.branches.get(offset))
+            .map(|branch| {
+                usize::from(branch.taken > 0)
+                    .saturating_add(usize::from(branch.not_taken > 0))
+            })
+            .fold(0usize, usize::saturating_add);
+
+        CoverageTotals {
+            instructions: self.instructions.len(),
+            instructions_hit: self.instructions.intersection(&self.executed).count(),
+            branches: self.jumps.len().saturating_mul(2),
+            branches_hit,
+        }
+    }
+}
+
+/// Instruction and branch coverage of a set of programs, accumulated from the
+/// [`CoverageProfilingData`] of any number of transactions.
+///
+/// Every word of the bytecode that decodes to an instruction is counted as an
+/// instruction, and every conditional jump as two branches, taken and not taken.
+/// A jump whose target is the next instruction is always counted as not taken.
+#[derive(Debug, Clone, Default)]
+pub struct CoverageReport {
+    programs: BTreeMap<ProgramId, ProgramCoverage>,
+}
+
+impl CoverageReport {
+    /// Create an empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Include the contract in the report. Only the coverage of contracts added to
+    /// the report is recorded.
+    pub fn add_contract(&mut self, id: ContractId, bytecode: &[u8]) {
+        self.programs
+            .entry(ProgramId::Contract(id))
+            .or_insert_with(|| ProgramCoverage::new(bytecode));
+    }
+
+    /// Merge the coverage data of a transaction into the report. `script` is the
+    /// bytecode of the executed script, if any.
+    pub fn add_run(&mut self, script: Option<&[u8]>, data: &CoverageProfilingData) {
+        let script = script.map(|bytecode| {
+            let id = ProgramId::Script(Hasher::hash(bytecode));
+            self.programs
+                .entry(id)
+                .or_insert_with(|| ProgramCoverage::new(bytecode));
+            id
+        });
+        let program = |context: Option<ContractId>| match context {
+            Some(contract) => Some(ProgramId::Contract(contract)),
+            None => script,
+        };
+
+        for location in data.iter() {
+            if let Some(coverage) = program(location.context)
+                .and_then(|program| self.programs.get_mut(&program))
+            {
+                coverage.executed.insert(location.offset);
+            }
+        }
+
+        for (location, branch) in data.branches() {
+            if let Some(coverage) = program(location.context)
+                .and_then(|program| self.programs.get_mut(&program))
+            {
+                let total = coverage.branches.entry(location.offset).or_default();
+                total.taken = total.taken.saturating_add(branch.taken);
+                total.not_taken = total.not_taken.saturating_add(branch.not_taken);
+            }
+        }
+    }
+
+    /// Summary of the coverage per program and in total.
+    pub fn summary(&self) -> CoverageSummary {
+        let programs = self
+            .programs
+            .iter()
+            .map(|(program, coverage)| ProgramCoverageSummary {
+                program: *program,
+                totals: coverage.totals(),
+            })
+            .collect::<Vec<_>>();
+
+        let total =
+            programs
+                .iter()
+                .fold(CoverageTotals::default(), |total, p| CoverageTotals {
+                    instructions: total
+                        .instructions
+                        .saturating_add(p.totals.instructions),
+                    instructions_hit: total
+                        .instructions_hit
+                        .saturating_add(p.totals.instructions_hit),
+                    branches: total.branches.saturating_add(p.totals.branches),
+                    branches_hit: total
+                        .branches_hit
+                        .saturating_add(p.totals.branches_hit),
+                });
+
+        CoverageSummary { programs, total }
+    }
+
+    /// Render the report in the lcov tracefile format, with a record per program.
+    ///
+    /// The instructions are reported as lines, numbered from 1 in the order of the
+    /// bytecode, so the instruction at offset `o` is on line `o / 4 + 1`.
+    pub fn lcov(&self) -> String {
+        let mut output = String::new();
+        for (program, coverage) in &self.programs {
+            let _ = write_lcov_record(&mut output, program, coverage);
+        }
+        output
+    }
+}
+
+/// Write the lcov record of a program.
+fn write_lcov_record(
+    output: &mut String,
+    program: &ProgramId,
+    coverage: &ProgramCoverage,
+) -> fmt::Result {
+    #[allow(clippy::arithmetic_side_effects)] // Safety: the instruction size is not zero
+    let line = |offset: &Word| (offset / Instruction::SIZE as Word).saturating_add(1);
+    let totals = coverage.totals();
+
+    writeln!(output, "TN:")?;
+    writeln!(output, "SF:{program}")?;
+    for offset in &coverage.jumps {
+        let (taken, not_taken) = match coverage.branches.get(offset) {
+            Some(branch) => (Some(branch.taken), Some(branch.not_taken)),
+            None if coverage.executed.contains(offset) => (Some(0), Some(0)),
+            None => (None, None),
+        };
+        for (branch, count) in [taken, not_taken].into_iter().enumerate() {
+            match count {
+                Some(count) => {
+                    writeln!(output, "BRDA:{},0,{branch},{count}", line(offset))?
+                }
+                None => writeln!(output, "BRDA:{},0,{branch},-", line(offset))?,
+            }
+        }
+    }
+    writeln!(output, "BRF:{}", totals.branches)?;
+    writeln!(output, "BRH:{}", totals.branches_hit)?;
+    for offset in &coverage.instructions {
+        let hits = u8::from(coverage.executed.contains(offset));
+        writeln!(output, "DA:{},{hits}", line(offset))?;
+    }
+    writeln!(output, "LF:{}", totals.instructions)?;
+    writeln!(output, "LH:{}", totals.instructions_hit)?;
+    writeln!(output, "end_of_record")
+}
+
+/// Summary of a [`CoverageReport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoverageSummary {
+    /// Coverage of every program of the report.
+    pub programs: Vec<ProgramCoverageSummary>,
+    /// Coverage of all the programs together.
+    pub total: CoverageTotals,
+}
+
+/// Coverage of a single program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProgramCoverageSummary {
+    /// The program.
+    pub program: ProgramId,
+    /// Its coverage.
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub totals: CoverageTotals,
+}
+
+/// Numbers of covered instructions and branches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoverageTotals {
+    /// Number of instructions.
+    pub instructions: usize,
+    /// Number of executed instructions.
+    pub instructions_hit: usize,
+    /// Number of branches, two per conditional jump.
+    pub branches: usize,
+    /// Number of branches that were taken at least once.
+    pub branches_hit: usize,
+}
@@ -0,0 +1,271 @@
+//! Gas flamegraphs, rendered from the gas used per call stack.
+
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{
+        String,
+        ToString,
+    },
+    vec,
+    vec::Vec,
+};
+use core::fmt::Write as _;
+
+use super::{
+    GasProfilingData,
+    InstructionLocation,
+};
+use crate::debug_info::DebugInfo;
+
+#[cfg(feature = "std")]
+use super::{
+    ProfileReceiver,
+    ProfilingData,
+};
+#[cfg(feature = "std")]
+use crate::{
+    error::InterpreterError,
+    state::ProgramState,
+};
+#[cfg(feature = "std")]
+use std::{
+    io,
+    sync::{
+        Arc,
+        Mutex,
+        PoisonError,
+    },
+};
+
+impl GasProfilingData {
+    /// Render the gas used per call stack in the folded stacks format consumed by
+    /// `flamegraph.pl` and `inferno`, one `frame;frame;frame gas` line per stack.
+    ///
+    /// Frames are named after their source location if `debug_info` resolves them,
+    /// and `script:offset` or `contract:offset` otherwise.
+    pub fn folded_stacks(&self, debug_info: Option<&DebugInfo>) -> String {
+        let mut folded = BTreeMap::<String, u64>::new();
+        for (stack, gas) in self.stacks() {
+            let frames = stack
+                .iter()
+                .map(|location| frame_name(location, debug_info))
+                .collect::<Vec<_>>()
+                .join(";");
+            let total = folded.entry(frames).or_insert(0);
+            *total = total.saturating_add(gas);
+        }
+
+        folded
+            .into_iter()
+            .fold(String::new(), |mut output, (frames, gas)| {
+                let _ = writeln!(output, "{frames} {gas}");
+                output
+            })
+    }
+
+    /// Encode the gas used per call stack as an uncompressed pprof protobuf
+    /// profile, with a single `gas` sample type.
+    pub fn pprof(&self, debug_info: Option<&DebugInfo>) -> Vec<u8> {
+        let mut strings = StringTable::default();
+        let gas = strings.index("gas");
+
+        // Every distinct instruction location gets a location and a function.
+        let mut locations = BTreeMap::<InstructionLocation, u64>::new();
+        let mut stacks = self.stacks().collect::<Vec<_>>();
+        stacks.sort();
+        for (stack, _) in &stacks {
+            for location in stack.iter() {
+                let next_id = u64::try_from(locations.len())
+                    .unwrap_or(u64::MAX)
+                    .saturating_add(1);
+                locations.entry(*location).or_insert(next_id);
+            }
+        }
+
+        let mut profile = Proto::default();
+        profile.message(1, |value_type| {
+            value_type.uint64(1, gas);
+            value_type.uint64(2, gas);
+        });
+
+        for (stack, used) in &stacks {
+            profile.message(2, |sample| {
+                // Leaf first, as expected by pprof.
+                sample.packed(1, stack.iter().rev().map(|l| locations[l]));
+                sample.packed(2, core::iter::once(*used));
+            });
+        }
+
+        for (location, id) in &locations {
+            let source = debug_info.and_then(|info| info.resolve(location));
+            let line = source.map_or(0, |source| u64::from(source.line));
+            profile.message(4, |message| {
+                message.uint64(1, *id);
+                message.uint64(3, location.offset);
+                message.message(4, |message| {
+                    message.uint64(1, *id);
+                    message.uint64(2, line);
+                });
+            });
+
+            let name = strings.index(&frame_name(location, debug_info));
+            let file = strings.index(source.map_or("", |source| source.file.as_str()));
+            profile.message(5, |function| {
+                function.uint64(1, *id);
+                function.uint64(2, name);
+                function.uint64(3, name);
+                function.uint64(4, file);
+                function.uint64(5, line);
+            });
+        }
+
+        for string in &strings.strings {
+            profile.bytes(6, string.as_bytes());
+        }
+        profile.message(11, |value_type| {
+            value_type.uint64(1, gas);
+            value_type.uint64(2, gas);
+        });
+
+        profile.0
+    }
+}
+
+/// Name of the frame of an instruction in a flamegraph.
+fn frame_name(location: &InstructionLocation, debug_info: Option<&DebugInfo>) -> String {
+    let name = match debug_info.and_then(|info| info.resolve(location)) {
+        Some(source) => source.to_string(),
+        None => match location.context {
+            Some(contract) => format!("{contract}:{}", location.offset),
+            None => format!("script:{}", location.offset),
+        },
+    };
+    // Semicolons separate the frames of folded stacks.
+    name.replace(';', ",")
+}
+
+/// The string table of a pprof profile, which starts with an empty string.
+struct StringTable {
+    strings: Vec<String>,
+    indices: BTreeMap<String, u64>,
+}
+
+impl Default for StringTable {
+    fn default() -> Self {
+        Self {
+            strings: vec![String::new()],
+            indices: [(String::new(), 0)].into_iter().collect(),
+        }
+    }
+}
+
+impl StringTable {
+    fn index(&mut self, string: &str) -> u64 {
+        if let Some(index) = self.indices.get(string) {
+            return *index
+        }
+        let index = u64::try_from(self.strings.len()).unwrap_or(u64::MAX);
+        self.strings.push(string.to_string());
+        self.indices.insert(string.to_string(), index);
+        index
+    }
+}
+
+/// Minimal protobuf encoder for the fields used by pprof profiles.
+#[derive(Default)]
+struct Proto(Vec<u8>);
+
+impl Proto {
+    #[allow(clippy::arithmetic_side_effects)] // Safety: shifts are within the word
+    #[allow(clippy::cast_possible_truncation)] // Safety: masked to 7 bits
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push((value & 0x7f) as u8 | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    #[allow(clippy::arithmetic_side_effects)] // Safety: field numbers are small
+    fn key(&mut self, field: u64, wire_type: u64) {
+        self.varint(field << 3 | wire_type);
+    }
+
+    fn uint64(&mut self, field: u64, value: u64) {
+        self.key(field, 0);
+        self.varint(value);
+    }
+
+    fn bytes(&mut self, field: u64, bytes: &[u8]) {
+        self.key(field, 2);
+        self.varint(bytes.len() as u64);
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn packed(&mut self, field: u64, values: impl Iterator<Item = u64>) {
+        let mut packed = Self::default();
+        values.for_each(|value| packed.varint(value));
+        self.bytes(field, &packed.0);
+    }
+
+    fn message(&mut self, field: u64, f: impl FnOnce(&mut Self)) {
+        let mut message = Self::default();
+        f(&mut message);
+        self.bytes(field, &message.0);
+    }
+}
+
+/// Collects the gas profiling data of the executed transactions, to be rendered as a
+/// flamegraph with [`FlamegraphReceiver::write_folded`] or
+/// [`FlamegraphReceiver::write_pprof`]. Clones share the collected data.
+#[cfg(feature = "std")]
+#[derive(Clone, Default)]
+pub struct FlamegraphReceiver {
+    data: Arc<Mutex<GasProfilingData>>,
+    debug_info: Option<Arc<DebugInfo>>,
+}
+
+#[cfg(feature = "std")]
+impl FlamegraphReceiver {
+    /// Create a receiver that names frames after their instruction locations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Name the frames after the source locations resolved by `debug_info`.
+    pub fn with_debug_info(mut self, debug_info: DebugInfo) -> Self {
+        self.debug_info = Some(Arc::new(debug_info));
+        self
+    }
+
+    /// Gas profiling data collected by the profiler, as of the last transaction.
+    pub fn data(&self) -> GasProfilingData {
+        self.data
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Write the gas used per call stack in the folded stacks format.
+    pub fn write_folded<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        let folded = self.data().folded_stacks(self.debug_info.as_deref());
+        writer.write_all(folded.as_bytes())
+    }
+
+    /// Write the gas used per call stack as a pprof protobuf profile.
+    pub fn write_pprof<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.data().pprof(self.debug_info.as_deref()))
+    }
+}
+
+#[cfg(feature = "std")]
+impl ProfileReceiver for FlamegraphReceiver {
+    fn on_transaction(
+        &mut self,
+        _state: Result<&ProgramState, InterpreterError<String>>,
+        data: &ProfilingData,
+    ) {
+        *self.data.lock().unwrap_or_else(PoisonError::into_inner) = data.gas().clone();
+    }
+}
@@ -0,0 +1,486 @@
+//! Deterministic replay of transactions from self-contained replay files.
+//!
+//! A [`ReplayFile`] is recorded by [`Transactor::record_replay`] and holds everything
+//! needed to execute the transaction again: the transaction, the interpreter
+//! parameters, the block and the storage values read by the execution. Together with
+//! the receipts and the storage values after the execution, it serves as a regression
+//! snapshot: [`ReplayFile::replay`] executes the transaction on a [`MemoryStorage`]
+//! and reports every [`Divergence`] from the recorded execution.
+
+use crate::{
+    checked_transaction::{
+        CheckError,
+        Checked,
+    },
+    interpreter::{
+        EcalHandler,
+        InterpreterParams,
+        Memory,
+        MemoryInstance,
+    },
+    storage::{
+        ContractStateUsage,
+        ContractsAssetsStorage,
+        InterpreterStorage,
+        MemoryStorage,
+        WitnessEntry,
+        WitnessRecorder,
+    },
+    transactor::Transactor,
+};
+
+use fuel_tx::{
+    Receipt,
+    Script,
+    Transaction,
+};
+use fuel_types::{
+    canonical::Deserialize,
+    AssetId,
+    BlockHeight,
+    Bytes32,
+    ContractId,
+    Word,
+};
+
+use alloc::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    format,
+    string::String,
+    vec::Vec,
+};
+use std::{
+    fs::File,
+    io::{
+        self,
+        BufReader,
+        BufWriter,
+        Write,
+    },
+    path::Path,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A recorded execution of a script, sufficient to execute it again.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ReplayFile {
+    /// The canonical encoding of the transaction before the execution.
+    pub transaction: Vec<u8>,
+    /// The parameters of the interpreter.
+    pub interpreter_params: InterpreterParams,
+    /// The height of the block of the execution.
+    pub block_height: BlockHeight,
+    /// The timestamp of the block.
+    pub timestamp: Word,
+    /// The coinbase contract of the block.
+    pub coinbase: ContractId,
+    /// The version of the consensus parameters.
+    pub consensus_parameters_version: u32,
+    /// The version of the state transition function.
+    pub state_transition_version: u32,
+    /// The storage values read by the execution, before the execution.
+    pub pre_state: Vec<WitnessEntry>,
+    /// The contract codes, state slots, state usages and balances of the
+    /// `pre_state`, after the execution.
+    pub post_state: Vec<WitnessEntry>,
+    /// The receipts of the execution.
+    pub receipts: Vec<Receipt>,
+    /// The gas used by the script.
+    pub gas_used: Word,
+    /// The debug representation of the error of the execution, if it failed.
+    pub error: Option<String>,
+}
+
+/// A difference between the recorded execution and its replay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Divergence {
+    /// The receipt at `index` differs. A missing receipt is `None`.
+    Receipt {
+        /// The index of the receipt.
+        index: usize,
+        /// The recorded receipt.
+        expected: Option<Receipt>,
+        /// The receipt of the replay.
+        actual: Option<Receipt>,
+    },
+    /// The script used a different amount of gas.
+    GasUsed {
+        /// The recorded gas.
+        expected: Word,
+        /// The gas used by the replay.
+        actual: Word,
+    },
+    /// A storage entry has a different value after the execution.
+    State {
+        /// The recorded value.
+        expected: WitnessEntry,
+        /// The value after the replay.
+        actual: WitnessEntry,
+    },
+    /// The replay read a storage value the recorded execution didn't read.
+    Access(WitnessEntry),
+    /// The execution failed differently.
+    Error {
+        /// The recorded error.
+        expected: Option<String>,
+        /// The error of the replay.
+        actual: Option<String>,
+    },
+}
+
+/// The outcome of [`ReplayFile::replay`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// The recording of the replay.
+    pub actual: ReplayFile,
+    /// The differences from the recorded execution.
+    pub divergences: Vec<Divergence>,
+}
+
+impl ReplayReport {
+    /// Whether the replay reproduced the recorded execution.
+    pub fn is_faithful(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+/// The replay file can't be executed.
+#[derive(Debug, PartialEq, derive_more::Display)]
+pub enum ReplayError {
+    /// The transaction isn't a canonically encoded transaction.
+    #[display(fmt = "The transaction can't be decoded: {_0:?}")]
+    InvalidTransaction(fuel_types::canonical::Error),
+    /// The transaction isn't a script.
+    #[display(fmt = "The transaction is not a script")]
+    NotAScript,
+    /// The transaction can't be checked with the interpreter parameters.
+    #[display(fmt = "The transaction can't be checked: {_0:?}")]
+    Check(CheckError),
+}
+
+impl From<CheckError> for ReplayError {
+    fn from(error: CheckError) -> Self {
+        Self::Check(error)
+    }
+}
+
+impl ReplayFile {
+    /// Record the last execution of the `transactor`, whose script was encoded as
+    /// `transaction` before the execution.
+    pub(crate) fn record<M, S, Ecal>(
+        transactor: &mut Transactor<M, WitnessRecorder<S>, Script, Ecal>,
+        transaction: Vec<u8>,
+    ) -> Result<Self, S::DataError>
+    where
+        M: Memory,
+        S: InterpreterStorage,
+        Ecal: EcalHandler,
+    {
+        let witness = transactor.take_witness()?;
+        let storage = AsRef::<WitnessRecorder<S>>::as_ref(transactor).inner();
+        let timestamp = storage.timestamp(witness.block_height)?;
+        let post_state = post_state(storage, &witness.entries)?;
+
+        let receipts = transactor.receipts().unwrap_or_default().to_vec();
+        let gas_used = receipts
+            .iter()
+            .find_map(|receipt| match receipt {
+                Receipt::ScriptResult { gas_used, .. } => Some(*gas_used),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            transaction,
+            interpreter_params: witness.interpreter_params,
+            block_height: witness.block_height,
+            timestamp,
+            coinbase: witness.coinbase,
+            consensus_parameters_version: witness.consensus_parameters_version,
+            state_transition_version: witness.state_transition_version,
+            pre_state: witness.entries,
+            post_state,
+            receipts,
+            gas_used,
+            error: transactor.error().map(|error| format!("{error:?}")),
+        })
+    }
+
+    /// Read a replay file from JSON.
+    pub fn read<R: io::Read>(reader: R) -> io::Result<Self> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Write the replay file as JSON.
+    pub fn write<W: Write>(&self, writer: W) -> io::Result<()> {
+        Ok(serde_json::to_writer(writer, self)?)
+    }
+
+    /// Load the replay file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Save the replay file to `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    /// The storage before the execution, with the block of the execution and the
+    /// values of the `pre_state`.
+    pub fn storage(&self) -> MemoryStorage {
+        let mut storage = MemoryStorage::new_with_versions(
+            self.block_height,
+            self.coinbase,
+            self.consensus_parameters_version,
+            self.state_transition_version,
+        );
+        storage.set_timestamp(self.block_height, self.timestamp);
+        for entry in &self.pre_state {
+            match entry {
+                WitnessEntry::Contract { contract_id, code } => {
+                    storage
+                        .storage_contract_insert(contract_id, &code.as_slice().into())
+                        .expect("Infallible");
+                }
+                WitnessEntry::State {
+                    contract_id,
+                    key,
+                    value,
+                } => {
+                    storage
+                        .contract_state_insert(contract_id, key, value)
+                        .expect("Infallible");
+                }
+                WitnessEntry::Balance {
+                    contract_id,
+                    asset_id,
+                    amount,
+                } => {
+                    storage
+                        .contract_asset_id_balance_insert(contract_id, asset_id, *amount)
+                        .expect("Infallible");
+                }
+                WitnessEntry::Timestamp { height, timestamp } => {
+                    storage.set_timestamp(*height, *timestamp);
+                }
+                WitnessEntry::BlockHash { height, hash } => {
+                    storage.set_block_hash(*height, *hash);
+                }
+                WitnessEntry::MissingContract { .. }
+                | WitnessEntry::UnsetState { .. }
+                | WitnessEntry::StateUsage { .. }
+                | WitnessEntry::UnsetBalance { .. } => {}
+            }
+        }
+        // The state usages replace the usages of the slots inserted above.
+        for entry in &self.pre_state {
+            if let WitnessEntry::StateUsage {
+                contract_id,
+                slots,
+                bytes,
+            } = entry
+            {
+                storage.set_contract_state_usage(
+                    contract_id,
+                    ContractStateUsage {
+                        slots: *slots,
+                        bytes: *bytes,
+                    },
+                );
+            }
+        }
+        storage
+    }
+
+    /// Execute the transaction on the [`Self::storage`] and compare the execution
+    /// with the recorded one.
+    pub fn replay(&self) -> Result<ReplayReport, ReplayError> {
+        let Transaction::Script(tx) = Transaction::from_bytes(&self.transaction)
+            .map_err(ReplayError::InvalidTransaction)?
+        else {
+            return Err(ReplayError::NotAScript)
+        };
+        let tx = Checked::replayed(tx, self.block_height, &self.interpreter_params)?;
+
+        let mut transactor = Transactor::<_, _, Script>::new(
+            MemoryInstance::new(),
+            WitnessRecorder::new(self.storage()),
+            self.interpreter_params.clone(),
+        );
+        let actual = transactor.record_replay(tx).expect("Infallible");
+        let divergences = self.divergences(&actual);
+
+        Ok(ReplayReport {
+            actual,
+            divergences,
+        })
+    }
+
+    fn divergences(&self, actual: &Self) -> Vec<Divergence> {
+        let mut divergences = Vec::new();
+
+        let receipts = self.receipts.len().max(actual.receipts.len());
+        for index in 0..receipts {
+            let expected = self.receipts.get(index);
+            let actual = actual.receipts.get(index);
+            if expected != actual {
+                divergences.push(Divergence::Receipt {
+                    index,
+                    expected: expected.cloned(),
+                    actual: actual.cloned(),
+                });
+            }
+        }
+
+        if self.gas_used != actual.gas_used {
+            divergences.push(Divergence::GasUsed {
+                expected: self.gas_used,
+                actual: actual.gas_used,
+            });
+        }
+
+        if self.error != actual.error {
+            divergences.push(Divergence::Error {
+                expected: self.error.clone(),
+                actual: actual.error.clone(),
+            });
+        }
+
+        let post_state: BTreeMap<_, _> = actual
+            .post_state
+            .iter()
+            .map(|entry| (EntryKey::of(entry), entry))
+            .collect();
+        for expected in &self.post_state {
+            match post_state.get(&EntryKey::of(expected)) {
+                Some(actual) if *actual != expected => {
+                    divergences.push(Divergence::State {
+                        expected: expected.clone(),
+                        actual: (*actual).clone(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let pre_state: BTreeSet<_> = self.pre_state.iter().map(EntryKey::of).collect();
+        divergences.extend(
+            actual
+                .pre_state
+                .iter()
+                .filter(|entry| !pre_state.contains(&EntryKey::of(entry)))
+                .cloned()
+                .map(Divergence::Access),
+        );
+
+        divergences
+    }
+}
+
+/// The storage key of a witness entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum EntryKey {
+    Contract(ContractId),
+    State(ContractId, Bytes32),
+    StateUsage(ContractId),
+    Balance(ContractId, AssetId),
+    Timestamp(BlockHeight),
+    BlockHash(BlockHeight),
+}
+
+impl EntryKey {
+    fn of(entry: &WitnessEntry) -> Self {
+        match entry {
+            WitnessEntry::Contract { contract_id, .. }
+            | WitnessEntry::MissingContract { contract_id } => {
+                Self::Contract(*contract_id)
+            }
+            WitnessEntry::State {
+                contract_id, key, ..
+            }
+            | WitnessEntry::UnsetState { contract_id, key } => {
+                Self::State(*contract_id, *key)
+            }
+            WitnessEntry::StateUsage { contract_id, .. } => {
+                Self::StateUsage(*contract_id)
+            }
+            WitnessEntry::Balance {
+                contract_id,
+                asset_id,
+                ..
+            }
+            | WitnessEntry::UnsetBalance {
+                contract_id,
+                asset_id,
+            } => Self::Balance(*contract_id, *asset_id),
+            WitnessEntry::Timestamp { height, .. } => Self::Timestamp(*height),
+            WitnessEntry::BlockHash { height, .. } => Self::BlockHash(*height),
+        }
+    }
+}
+
+/// The current values of the contract codes, state slots, state usages and balances
+/// of `entries`.
+fn post_state<S: InterpreterStorage>(
+    storage: &S,
+    entries: &[WitnessEntry],
+) -> Result<Vec<WitnessEntry>, S::DataError> {
+    let mut post_state = Vec::new();
+    for entry in entries {
+        let entry = match EntryKey::of(entry) {
+            EntryKey::Contract(contract_id) => {
+                match storage.storage_contract(&contract_id)? {
+                    Some(code) => WitnessEntry::Contract {
+                        contract_id,
+                        code: code.into_owned().into(),
+                    },
+                    None => WitnessEntry::MissingContract { contract_id },
+                }
+            }
+            EntryKey::State(contract_id, key) => {
+                match storage.contract_state(&contract_id, &key)? {
+                    Some(value) => WitnessEntry::State {
+                        contract_id,
+                        key,
+                        value: value.into_owned().into(),
+                    },
+                    None => WitnessEntry::UnsetState { contract_id, key },
+                }
+            }
+            EntryKey::StateUsage(contract_id) => {
+                match storage.contract_state_usage(&contract_id)? {
+                    Some(usage) => WitnessEntry::StateUsage {
+                        contract_id,
+                        slots: usage.slots,
+                        bytes: usage.bytes,
+                    },
+                    None => continue,
+                }
+            }
+            EntryKey::Balance(contract_id, asset_id) => {
+                match storage.contract_asset_id_balance(&contract_id, &asset_id)? {
+                    Some(amount) => WitnessEntry::Balance {
+                        contract_id,
+                        asset_id,
+                        amount,
+                    },
+                    None => WitnessEntry::UnsetBalance {
+                        contract_id,
+                        asset_id,
+                    },
+                }
+            }
+            EntryKey::Timestamp(_) | EntryKey::BlockHash(_) => continue,
+        };
+        post_state.push(entry);
+    }
+    Ok(post_state)
+}
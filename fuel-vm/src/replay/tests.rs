@@ -0,0 +1,284 @@
+#![allow(non_snake_case)]
+
+use super::*;
+use crate::{
+    storage::ExecutionWitness,
+    tests::test_helpers::{
+        self,
+        contract_id,
+        counter,
+        counter_value,
+    },
+};
+
+use fuel_asm::{
+    op,
+    GMArgs,
+    RegId,
+};
+use fuel_tx::{
+    ConsensusParameters,
+    Contract,
+};
+use fuel_types::canonical::Serialize;
+
+const COUNTER: Word = 41;
+const TIMESTAMP: Word = 1234;
+
+/// A contract setting the slot `[1, 0, ..]` and logging the state usage before and
+/// after.
+fn state_usage() -> Contract {
+    let program: Vec<u8> = [
+        op::gm_args(0x10, GMArgs::StateSlotCount),
+        op::gm_args(0x11, GMArgs::StateByteSize),
+        op::log(0x10, 0x11, RegId::ZERO, RegId::ZERO),
+        op::move_(0x12, RegId::SSP),
+        op::cfei(32),
+        op::sb(0x12, RegId::ONE, 0),
+        op::sww(0x12, 0x13, RegId::ONE),
+        op::gm_args(0x10, GMArgs::StateSlotCount),
+        op::gm_args(0x11, GMArgs::StateByteSize),
+        op::log(0x10, 0x11, RegId::ZERO, RegId::ZERO),
+        op::ret(RegId::ONE),
+    ]
+    .into_iter()
+    .collect();
+    program.into()
+}
+
+fn storage() -> MemoryStorage {
+    let mut storage = MemoryStorage::default();
+    storage
+        .storage_contract_insert(&contract_id(), &counter())
+        .unwrap();
+    storage
+        .contract_state_insert(
+            &contract_id(),
+            &Bytes32::zeroed(),
+            counter_value(COUNTER).as_ref(),
+        )
+        .unwrap();
+    storage.set_timestamp(storage.block_height().unwrap(), TIMESTAMP);
+    storage
+}
+
+/// A script calling the counter and logging the timestamp of the block.
+fn call_counter() -> Checked<Script> {
+    test_helpers::call_counter([
+        op::bhei(0x12),
+        op::time(0x13, 0x12),
+        op::log(0x13, RegId::ZERO, RegId::ZERO, RegId::ZERO),
+        op::ret(RegId::ONE),
+    ])
+}
+
+fn record() -> ReplayFile {
+    let mut transactor = Transactor::<_, _, Script>::new(
+        MemoryInstance::new(),
+        WitnessRecorder::new(storage()),
+        InterpreterParams::new(0, &ConsensusParameters::standard()),
+    );
+    transactor
+        .record_replay(call_counter())
+        .expect("failed to record")
+}
+
+#[test]
+fn record_replay__records_the_execution() {
+    let tx = call_counter();
+    let file = record();
+
+    assert_eq!(
+        file.transaction,
+        Transaction::from(tx.transaction().clone()).to_bytes()
+    );
+    assert_eq!(file.timestamp, TIMESTAMP);
+    assert_eq!(file.error, None);
+    assert!(matches!(
+        file.receipts[1],
+        Receipt::Log { ra, .. } if ra == COUNTER + 1
+    ));
+    assert!(matches!(
+        file.receipts[3],
+        Receipt::Log { ra, .. } if ra == TIMESTAMP
+    ));
+    assert!(matches!(
+        file.receipts.last(),
+        Some(Receipt::ScriptResult { gas_used, .. }) if *gas_used == file.gas_used
+    ));
+    assert_eq!(
+        file.post_state,
+        vec![
+            WitnessEntry::Contract {
+                contract_id: contract_id(),
+                code: counter().into(),
+            },
+            WitnessEntry::State {
+                contract_id: contract_id(),
+                key: Bytes32::zeroed(),
+                value: counter_value(COUNTER + 1).into(),
+            },
+            // The call writes the balance of the transferred asset, even without any
+            // transfer.
+            WitnessEntry::Balance {
+                contract_id: contract_id(),
+                asset_id: AssetId::zeroed(),
+                amount: 0,
+            },
+        ]
+    );
+}
+
+#[test]
+fn replay__reproduces_the_recorded_execution() {
+    let file = record();
+
+    let mut json = Vec::new();
+    file.write(&mut json).unwrap();
+    let file = ReplayFile::read(json.as_slice()).unwrap();
+    let report = file.replay().expect("failed to replay");
+
+    assert!(report.is_faithful(), "{:?}", report.divergences);
+    assert_eq!(report.actual, file);
+}
+
+#[test]
+fn replay__reports_the_divergences() {
+    let mut file = record();
+    file.pre_state[1] = WitnessEntry::State {
+        contract_id: contract_id(),
+        key: Bytes32::zeroed(),
+        value: counter_value(COUNTER + 10).into(),
+    };
+    file.gas_used = file.gas_used.saturating_add(1);
+    let missing = Receipt::log(contract_id(), 0, 0, 0, 0, 0, 0);
+    file.receipts.push(missing.clone());
+
+    let report = file.replay().expect("failed to replay");
+
+    assert_eq!(
+        report.divergences,
+        vec![
+            Divergence::Receipt {
+                index: 1,
+                expected: Some(file.receipts[1].clone()),
+                actual: Some(report.actual.receipts[1].clone()),
+            },
+            Divergence::Receipt {
+                index: file.receipts.len() - 1,
+                expected: Some(missing),
+                actual: None,
+            },
+            Divergence::GasUsed {
+                expected: file.gas_used,
+                actual: report.actual.gas_used,
+            },
+            Divergence::State {
+                expected: file.post_state[1].clone(),
+                actual: WitnessEntry::State {
+                    contract_id: contract_id(),
+                    key: Bytes32::zeroed(),
+                    value: counter_value(COUNTER + 11).into(),
+                },
+            },
+        ]
+    );
+}
+
+#[test]
+fn replay__reports_reads_outside_of_the_pre_state() {
+    let mut file = record();
+    let timestamp = file.pre_state.pop();
+    assert_eq!(
+        timestamp,
+        Some(WitnessEntry::Timestamp {
+            height: file.block_height,
+            timestamp: TIMESTAMP,
+        })
+    );
+
+    let report = file.replay().expect("failed to replay");
+
+    assert_eq!(
+        report.divergences,
+        vec![Divergence::Access(timestamp.unwrap())]
+    );
+}
+
+#[test]
+fn replay__fails_for_invalid_transactions() {
+    let mut file = record();
+    file.transaction.truncate(8);
+
+    assert!(matches!(
+        file.replay(),
+        Err(ReplayError::InvalidTransaction(_))
+    ));
+}
+
+#[test]
+fn save__writes_a_file_read_by_load() {
+    let file = record();
+    let path =
+        std::env::temp_dir().join(format!("fuel-vm-replay-{}.json", std::process::id()));
+
+    file.save(&path).unwrap();
+    let loaded = ReplayFile::load(&path);
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(loaded.unwrap(), file);
+}
+
+#[test]
+fn replay__reproduces_the_state_usage() {
+    let mut storage = storage();
+    storage
+        .storage_contract_insert(&contract_id(), &state_usage())
+        .unwrap();
+    let mut transactor = Transactor::<_, _, Script>::new(
+        MemoryInstance::new(),
+        WitnessRecorder::new(storage),
+        InterpreterParams::new(0, &ConsensusParameters::standard()),
+    );
+    let file = transactor
+        .record_replay(call_counter())
+        .expect("failed to record");
+
+    assert!(matches!(
+        file.receipts[1],
+        Receipt::Log { ra: 1, rb: 32, .. }
+    ));
+    assert!(matches!(
+        file.receipts[2],
+        Receipt::Log { ra: 2, rb: 64, .. }
+    ));
+    assert!(file.pre_state.contains(&WitnessEntry::StateUsage {
+        contract_id: contract_id(),
+        slots: 1,
+        bytes: 32,
+    }));
+    assert!(file.post_state.contains(&WitnessEntry::StateUsage {
+        contract_id: contract_id(),
+        slots: 2,
+        bytes: 64,
+    }));
+
+    let report = file.replay().expect("failed to replay");
+    assert!(report.is_faithful(), "{:?}", report.divergences);
+
+    let witness = ExecutionWitness {
+        block_height: file.block_height,
+        coinbase: file.coinbase,
+        consensus_parameters_version: file.consensus_parameters_version,
+        state_transition_version: file.state_transition_version,
+        interpreter_params: file.interpreter_params.clone(),
+        entries: file.pre_state.clone(),
+    };
+    let mut transactor =
+        Transactor::<_, _, Script>::from_witness(MemoryInstance::new(), witness);
+    transactor.transact(call_counter());
+    assert_eq!(
+        transactor.receipts().expect("failed to execute"),
+        file.receipts.as_slice()
+    );
+}
@@ -4,7 +4,10 @@ use fuel_types::{
     Word,
 };
 
-use crate::consts::VM_MAX_RAM;
+use crate::{
+    constraints::InstructionLocation,
+    consts::VM_MAX_RAM,
+};
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -56,6 +59,12 @@ impl Breakpoint {
     pub const fn pc(&self) -> Word {
         self.pc
     }
+
+    /// Location of the instruction that triggers the breakpoint.
+    pub fn location(&self) -> InstructionLocation {
+        let context = (self.contract != ContractId::zeroed()).then_some(self.contract);
+        InstructionLocation::new(context, self.pc)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -1,8 +1,12 @@
-use crate::state::{
-    Breakpoint,
-    DebugEval,
-    ProgramState,
+use crate::{
+    debug_info::DebugInfo,
+    state::{
+        Breakpoint,
+        DebugEval,
+        ProgramState,
+    },
 };
+use alloc::sync::Arc;
 
 use fuel_types::{
     ContractId,
@@ -23,6 +27,8 @@ pub struct Debugger {
     single_stepping: bool,
     breakpoints: HashMap<ContractId, HashSet<Word>>,
     last_state: Option<ProgramState>,
+    /// Source-level debug information of the executed programs.
+    debug_info: Option<Arc<DebugInfo>>,
 }
 
 impl Debugger {
@@ -74,6 +80,13 @@ impl Debugger {
             .map(|set| set.remove(&breakpoint.pc()));
     }
 
+    /// Returns `true` if a breakpoint is set at the location of `breakpoint`.
+    pub fn has_breakpoint(&self, breakpoint: &Breakpoint) -> bool {
+        self.breakpoints
+            .get(breakpoint.contract())
+            .is_some_and(|set| set.contains(&breakpoint.pc()))
+    }
+
     /// Evaluate the current state of the interpreter whether or not a
     /// breakpoint was reached.
     pub fn eval_state(&mut self, contract: Option<&ContractId>, pc: Word) -> DebugEval {
@@ -106,6 +119,24 @@ impl Debugger {
         self.last_state.replace(state);
     }
 
+    /// Set the source-level debug information of the executed programs.
+    ///
+    /// Doesn't activate the debugger, as the information is only used to describe
+    /// locations and has no effect on the execution.
+    pub fn set_debug_info(&mut self, debug_info: DebugInfo) {
+        self.debug_info = Some(Arc::new(debug_info));
+    }
+
+    /// Source-level debug information of the executed programs, if loaded.
+    pub fn debug_info(&self) -> Option<&DebugInfo> {
+        self.debug_info.as_deref()
+    }
+
+    /// Shared handle to the source-level debug information, if loaded.
+    pub(crate) fn debug_info_shared(&self) -> Option<Arc<DebugInfo>> {
+        self.debug_info.clone()
+    }
+
     /// Retried the last state of execution; return `None` if the VM was never
     /// executed.
     pub const fn last_state(&self) -> &Option<ProgramState> {
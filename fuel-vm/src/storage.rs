@@ -7,27 +7,67 @@ use fuel_types::{
     ContractId,
 };
 
+mod access;
 mod contracts_assets;
 mod contracts_state;
+#[cfg(feature = "file-storage")]
+mod file;
 mod interpreter;
 mod memory;
+mod merkle;
+mod overlay;
 pub(crate) mod predicate;
-
+mod witness;
+
+pub use access::{
+    AccessList,
+    AccessRecorder,
+    AccessTable,
+    ReadSet,
+    WriteAccess,
+    WriteSet,
+};
 pub use contracts_assets::{
     ContractsAssetKey,
     ContractsAssets,
 };
 pub use contracts_state::{
+    ContractStateUsage,
     ContractsState,
     ContractsStateData,
     ContractsStateKey,
 };
+#[cfg(feature = "file-storage")]
+pub use file::FileStorage;
 pub use interpreter::{
     ContractsAssetsStorage,
+    ContractsStorageIter,
     InterpreterStorage,
+    StoragePage,
 };
 pub use memory::MemoryStorage;
+pub use merkle::{
+    MerkleStorage,
+    MerkleTable,
+};
+pub use overlay::{
+    Changes,
+    Changeset,
+    ChangesetTable,
+    OverlayStorage,
+    Savepoint,
+};
 pub use predicate::PredicateStorage;
+pub use witness::{
+    ContractRoots,
+    ExecutionWitness,
+    WitnessEntry,
+    WitnessError,
+    WitnessRecordTable,
+    WitnessRecorder,
+    WitnessStorage,
+    WitnessTable,
+};
 
 #[cfg(feature = "alloc")]
 use alloc::vec::Vec;
@@ -0,0 +1,560 @@
+//! Recording of the storage entries accessed by transactions.
+
+use super::{
+    interpreter::ContractsAssetsStorage,
+    memory::state_keys,
+    ContractStateUsage,
+    ContractsAssetKey,
+    ContractsAssets,
+    ContractsRawCode,
+    ContractsState,
+    ContractsStateData,
+    ContractsStateKey,
+    ContractsStorageIter,
+    InterpreterStorage,
+    StoragePage,
+    UploadedBytecodes,
+};
+
+use fuel_storage::{
+    Mappable,
+    StorageInspect,
+    StorageMutate,
+    StorageRead,
+    StorageSize,
+    StorageWrite,
+};
+use fuel_tx::{
+    ConsensusParameters,
+    Contract,
+};
+use fuel_types::{
+    AssetId,
+    BlockHeight,
+    Bytes32,
+    ContractId,
+    Word,
+};
+
+use alloc::{
+    borrow::{
+        Cow,
+        ToOwned,
+    },
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    vec::Vec,
+};
+use core::cell::RefCell;
+
+/// The value of a storage entry before and after it was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteAccess<V> {
+    /// The value before the first write, `None` if the entry was unset.
+    pub old: Option<V>,
+    /// The value after the last write, `None` if the entry was removed.
+    pub new: Option<V>,
+}
+
+/// The storage entries whose values were observed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReadSet {
+    /// The read contract codes.
+    pub contracts: BTreeSet<ContractId>,
+    /// The read contract balances.
+    pub balances: BTreeSet<ContractsAssetKey>,
+    /// The read contract state slots.
+    pub contract_state: BTreeSet<ContractsStateKey>,
+    /// The contracts whose state usage was read.
+    pub state_usage: BTreeSet<ContractId>,
+    /// The contracts whose state slots were iterated, observing which slots are set.
+    pub state_pages: BTreeSet<ContractId>,
+    /// The contracts whose balances were iterated, observing which balances are set.
+    pub balance_pages: BTreeSet<ContractId>,
+}
+
+/// The storage entries that were written.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WriteSet {
+    /// The written contract codes.
+    pub contracts: BTreeMap<ContractId, WriteAccess<Contract>>,
+    /// The written contract balances.
+    pub balances: BTreeMap<ContractsAssetKey, WriteAccess<Word>>,
+    /// The written contract state slots.
+    pub contract_state: BTreeMap<ContractsStateKey, WriteAccess<ContractsStateData>>,
+}
+
+/// The storage entries accessed through an [`AccessRecorder`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AccessList {
+    /// The entries whose values were observed.
+    pub reads: ReadSet,
+    /// The entries that were written.
+    pub writes: WriteSet,
+}
+
+impl AccessList {
+    /// Returns `true` if no entries were accessed.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Returns `true` if one of the access lists writes an entry accessed by the other.
+    ///
+    /// Transactions with conflicting access lists may observe different values when
+    /// they are executed in a different order.
+    pub fn conflicts_with(&self, other: &Self) -> bool {
+        self.writes.conflicts_with(other) || other.writes.conflicts_with(self)
+    }
+}
+
+impl WriteSet {
+    /// Returns `true` if an entry accessed by the `access_list` was written.
+    pub fn conflicts_with(&self, access_list: &AccessList) -> bool {
+        let (reads, writes) = (&access_list.reads, &access_list.writes);
+        self.contracts.keys().any(|key| {
+            reads.contracts.contains(key) || writes.contracts.contains_key(key)
+        }) || self.balances.keys().any(|key| {
+            reads.balances.contains(key)
+                || reads.balance_pages.contains(key.contract_id())
+                || writes.balances.contains_key(key)
+        }) || self.contract_state.keys().any(|key| {
+            reads.contract_state.contains(key)
+                || reads.state_usage.contains(key.contract_id())
+                || reads.state_pages.contains(key.contract_id())
+                || writes.contract_state.contains_key(key)
+        })
+    }
+
+    /// Add the writes made after these writes, keeping the oldest value of the
+    /// entries written by both.
+    pub fn extend(&mut self, later: Self) {
+        extend_writes(&mut self.contracts, later.contracts);
+        extend_writes(&mut self.balances, later.balances);
+        extend_writes(&mut self.contract_state, later.contract_state);
+    }
+}
+
+fn extend_writes<K: Ord, V>(
+    writes: &mut BTreeMap<K, WriteAccess<V>>,
+    later: BTreeMap<K, WriteAccess<V>>,
+) {
+    for (key, access) in later {
+        record_write(writes, key, access.old, access.new)
+    }
+}
+
+/// A storage table whose accesses are recorded by the [`AccessRecorder`].
+pub trait AccessTable: Mappable {
+    /// Record a read of the `key`.
+    fn record_read(list: &mut AccessList, key: &Self::Key);
+
+    /// Record a write of the `key`, changing the value from `old` to `new`.
+    fn record_write(
+        list: &mut AccessList,
+        key: &Self::Key,
+        old: Option<Self::OwnedValue>,
+        new: Option<Self::OwnedValue>,
+    );
+}
+
+fn record_write<K: Ord, V>(
+    writes: &mut BTreeMap<K, WriteAccess<V>>,
+    key: K,
+    old: Option<V>,
+    new: Option<V>,
+) {
+    writes
+        .entry(key)
+        .or_insert(WriteAccess { old, new: None })
+        .new = new;
+}
+
+impl AccessTable for ContractsRawCode {
+    fn record_read(list: &mut AccessList, key: &ContractId) {
+        list.reads.contracts.insert(*key);
+    }
+
+    fn record_write(
+        list: &mut AccessList,
+        key: &ContractId,
+        old: Option<Contract>,
+        new: Option<Contract>,
+    ) {
+        record_write(&mut list.writes.contracts, *key, old, new)
+    }
+}
+
+impl AccessTable for ContractsAssets {
+    fn record_read(list: &mut AccessList, key: &ContractsAssetKey) {
+        list.reads.balances.insert(*key);
+    }
+
+    fn record_write(
+        list: &mut AccessList,
+        key: &ContractsAssetKey,
+        old: Option<Word>,
+        new: Option<Word>,
+    ) {
+        record_write(&mut list.writes.balances, *key, old, new)
+    }
+}
+
+impl AccessTable for ContractsState {
+    fn record_read(list: &mut AccessList, key: &ContractsStateKey) {
+        list.reads.contract_state.insert(*key);
+    }
+
+    fn record_write(
+        list: &mut AccessList,
+        key: &ContractsStateKey,
+        old: Option<ContractsStateData>,
+        new: Option<ContractsStateData>,
+    ) {
+        record_write(&mut list.writes.contract_state, *key, old, new)
+    }
+}
+
+/// The uploaded bytecodes are not part of the access list.
+impl AccessTable for UploadedBytecodes {
+    fn record_read(_: &mut AccessList, _: &Bytes32) {}
+
+    fn record_write(
+        _: &mut AccessList,
+        _: &Bytes32,
+        _: Option<Self::OwnedValue>,
+        _: Option<Self::OwnedValue>,
+    ) {
+    }
+}
+
+/// Storage wrapper recording the contract codes, balances and state slots accessed
+/// through it into an [`AccessList`].
+///
+/// Every operation observing a value adds the key to the read set, including the
+/// writes returning the previous value. The write set keeps the value before the
+/// first write and after the last write of every written key. The recorded accesses
+/// accumulate until [`AccessRecorder::take_access_list`] is called.
+#[derive(Debug)]
+pub struct AccessRecorder<S> {
+    storage: S,
+    access_list: RefCell<AccessList>,
+}
+
+impl<S> AccessRecorder<S> {
+    /// Record the accesses to the `storage`.
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            access_list: Default::default(),
+        }
+    }
+
+    /// The underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Stop recording and return the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+
+    /// The accesses recorded so far.
+    pub fn access_list(&self) -> AccessList {
+        self.access_list.borrow().clone()
+    }
+
+    /// Return the accesses recorded so far and start a new recording.
+    pub fn take_access_list(&mut self) -> AccessList {
+        core::mem::take(self.access_list.get_mut())
+    }
+
+    fn record_read<Type: AccessTable>(&self, key: &Type::Key) {
+        Type::record_read(&mut self.access_list.borrow_mut(), key)
+    }
+
+    fn record_write<Type: AccessTable>(
+        &mut self,
+        key: &Type::Key,
+        old: Option<Type::OwnedValue>,
+        new: Option<Type::OwnedValue>,
+    ) {
+        let list = self.access_list.get_mut();
+        Type::record_read(list, key);
+        Type::record_write(list, key, old, new);
+    }
+}
+
+impl<Type, S> StorageInspect<Type> for AccessRecorder<S>
+where
+    Type: AccessTable,
+    S: StorageInspect<Type>,
+{
+    type Error = S::Error;
+
+    fn get(
+        &self,
+        key: &Type::Key,
+    ) -> Result<Option<Cow<'_, Type::OwnedValue>>, S::Error> {
+        self.record_read::<Type>(key);
+        self.storage.get(key)
+    }
+
+    fn contains_key(&self, key: &Type::Key) -> Result<bool, S::Error> {
+        self.record_read::<Type>(key);
+        self.storage.contains_key(key)
+    }
+}
+
+impl<Type, S> StorageMutate<Type> for AccessRecorder<S>
+where
+    Type: AccessTable,
+    S: StorageMutate<Type>,
+{
+    fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::Value,
+    ) -> Result<Option<Type::OwnedValue>, S::Error> {
+        let existing = self.storage.insert(key, value)?;
+        self.record_write::<Type>(key, existing.clone(), Some(value.to_owned().into()));
+        Ok(existing)
+    }
+
+    fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::OwnedValue>, S::Error> {
+        let existing = self.storage.remove(key)?;
+        self.record_write::<Type>(key, existing.clone(), None);
+        Ok(existing)
+    }
+}
+
+impl<Type, S> StorageSize<Type> for AccessRecorder<S>
+where
+    Type: AccessTable,
+    S: StorageSize<Type>,
+{
+    fn size_of_value(&self, key: &Type::Key) -> Result<Option<usize>, S::Error> {
+        self.record_read::<Type>(key);
+        self.storage.size_of_value(key)
+    }
+}
+
+impl<Type, S> StorageRead<Type> for AccessRecorder<S>
+where
+    Type: AccessTable,
+    S: StorageRead<Type>,
+{
+    fn read(&self, key: &Type::Key, buf: &mut [u8]) -> Result<Option<usize>, S::Error> {
+        self.record_read::<Type>(key);
+        self.storage.read(key, buf)
+    }
+
+    fn read_alloc(&self, key: &Type::Key) -> Result<Option<Vec<u8>>, S::Error> {
+        self.record_read::<Type>(key);
+        self.storage.read_alloc(key)
+    }
+}
+
+impl<Type, S> StorageWrite<Type> for AccessRecorder<S>
+where
+    Type: AccessTable,
+    Type::OwnedValue: From<Vec<u8>>,
+    S: StorageWrite<Type>,
+{
+    fn write(&mut self, key: &Type::Key, buf: &[u8]) -> Result<usize, S::Error> {
+        self.replace(key, buf).map(|(written, _)| written)
+    }
+
+    fn replace(
+        &mut self,
+        key: &Type::Key,
+        buf: &[u8],
+    ) -> Result<(usize, Option<Vec<u8>>), S::Error> {
+        let (written, existing) = self.storage.replace(key, buf)?;
+        self.record_write::<Type>(
+            key,
+            existing.clone().map(Into::into),
+            Some(buf.to_vec().into()),
+        );
+        Ok((written, existing))
+    }
+
+    fn take(&mut self, key: &Type::Key) -> Result<Option<Vec<u8>>, S::Error> {
+        let existing = self.storage.take(key)?;
+        self.record_write::<Type>(key, existing.clone().map(Into::into), None);
+        Ok(existing)
+    }
+}
+
+impl<S: InterpreterStorage> ContractsAssetsStorage for AccessRecorder<S> {}
+
+impl<S> InterpreterStorage for AccessRecorder<S>
+where
+    S: InterpreterStorage,
+{
+    type DataError = S::DataError;
+
+    fn block_height(&self) -> Result<BlockHeight, Self::DataError> {
+        self.storage.block_height()
+    }
+
+    fn consensus_parameters_version(&self) -> Result<u32, Self::DataError> {
+        self.storage.consensus_parameters_version()
+    }
+
+    fn state_transition_version(&self) -> Result<u32, Self::DataError> {
+        self.storage.state_transition_version()
+    }
+
+    fn timestamp(&self, height: BlockHeight) -> Result<Word, Self::DataError> {
+        self.storage.timestamp(height)
+    }
+
+    fn block_hash(&self, block_height: BlockHeight) -> Result<Bytes32, Self::DataError> {
+        self.storage.block_hash(block_height)
+    }
+
+    fn coinbase(&self) -> Result<ContractId, Self::DataError> {
+        self.storage.coinbase()
+    }
+
+    fn set_consensus_parameters(
+        &mut self,
+        version: u32,
+        consensus_parameters: &ConsensusParameters,
+    ) -> Result<Option<ConsensusParameters>, Self::DataError> {
+        self.storage
+            .set_consensus_parameters(version, consensus_parameters)
+    }
+
+    fn set_state_transition_bytecode(
+        &mut self,
+        version: u32,
+        hash: &Bytes32,
+    ) -> Result<Option<Bytes32>, Self::DataError> {
+        self.storage.set_state_transition_bytecode(version, hash)
+    }
+
+    fn contract_state_usage(
+        &self,
+        id: &ContractId,
+    ) -> Result<Option<ContractStateUsage>, Self::DataError> {
+        self.access_list.borrow_mut().reads.state_usage.insert(*id);
+        self.storage.contract_state_usage(id)
+    }
+
+    fn contract_state_range(
+        &self,
+        id: &ContractId,
+        start_key: &Bytes32,
+        range: usize,
+    ) -> Result<Vec<Option<Cow<'_, ContractsStateData>>>, Self::DataError> {
+        let values = self.storage.contract_state_range(id, start_key, range)?;
+        let mut list = self.access_list.borrow_mut();
+        state_keys(id, start_key)
+            .take(values.len())
+            .for_each(|key| ContractsState::record_read(&mut list, &key));
+        Ok(values)
+    }
+
+    fn contract_state_insert_range<'a, I>(
+        &mut self,
+        contract: &ContractId,
+        start_key: &Bytes32,
+        values: I,
+    ) -> Result<usize, Self::DataError>
+    where
+        I: Iterator<Item = &'a [u8]>,
+    {
+        let values: Vec<_> = values.collect();
+        let existing = owned_range(self.storage.contract_state_range(
+            contract,
+            start_key,
+            values.len(),
+        )?);
+
+        let unset = self.storage.contract_state_insert_range(
+            contract,
+            start_key,
+            values.iter().copied(),
+        )?;
+
+        state_keys(contract, start_key)
+            .zip(values)
+            .zip(existing)
+            .for_each(|((key, value), existing)| {
+                self.record_write::<ContractsState>(&key, existing, Some(value.into()))
+            });
+
+        Ok(unset)
+    }
+
+    fn contract_state_remove_range(
+        &mut self,
+        contract: &ContractId,
+        start_key: &Bytes32,
+        range: usize,
+    ) -> Result<Option<()>, Self::DataError> {
+        let existing = owned_range(
+            self.storage
+                .contract_state_range(contract, start_key, range)?,
+        );
+
+        let result = self
+            .storage
+            .contract_state_remove_range(contract, start_key, range)?;
+
+        state_keys(contract, start_key)
+            .zip(existing)
+            .for_each(|(key, existing)| {
+                self.record_write::<ContractsState>(&key, existing, None)
+            });
+
+        Ok(result)
+    }
+}
+
+fn owned_range(
+    range: Vec<Option<Cow<'_, ContractsStateData>>>,
+) -> Vec<Option<ContractsStateData>> {
+    range
+        .into_iter()
+        .map(|value| value.map(Cow::into_owned))
+        .collect()
+}
+
+impl<S> ContractsStorageIter for AccessRecorder<S>
+where
+    S: ContractsStorageIter,
+{
+    fn contract_state_page(
+        &self,
+        id: &ContractId,
+        start_key: &Bytes32,
+        limit: usize,
+    ) -> Result<StoragePage<Bytes32, ContractsStateData>, Self::DataError> {
+        self.access_list.borrow_mut().reads.state_pages.insert(*id);
+        self.storage.contract_state_page(id, start_key, limit)
+    }
+
+    fn contract_balances_page(
+        &self,
+        id: &ContractId,
+        start_asset_id: &AssetId,
+        limit: usize,
+    ) -> Result<StoragePage<AssetId, Word>, Self::DataError> {
+        self.access_list
+            .borrow_mut()
+            .reads
+            .balance_pages
+            .insert(*id);
+        self.storage
+            .contract_balances_page(id, start_asset_id, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests;
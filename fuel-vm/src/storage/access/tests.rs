@@ -0,0 +1,287 @@
+#![allow(non_snake_case)]
+
+use super::*;
+use crate::storage::MemoryStorage;
+
+use fuel_storage::{
+    StorageAsMut,
+    StorageAsRef,
+};
+use fuel_types::AssetId;
+
+fn asset_key(asset: u8) -> ContractsAssetKey {
+    (&ContractId::default(), &AssetId::from([asset; 32])).into()
+}
+
+fn state_key(key: u8) -> ContractsStateKey {
+    (&ContractId::default(), &Bytes32::from([key; 32])).into()
+}
+
+fn recorder() -> AccessRecorder<MemoryStorage> {
+    let mut storage = MemoryStorage::default();
+    storage
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1), &10)
+        .unwrap();
+    storage
+        .storage_as_mut::<ContractsState>()
+        .insert(&state_key(1), &[1; 32])
+        .unwrap();
+    AccessRecorder::new(storage)
+}
+
+#[test]
+fn reads__are_recorded_in_the_read_set() {
+    let recorder = recorder();
+
+    recorder
+        .storage::<ContractsAssets>()
+        .get(&asset_key(1))
+        .unwrap();
+    recorder
+        .storage::<ContractsAssets>()
+        .contains_key(&asset_key(2))
+        .unwrap();
+    recorder
+        .storage::<ContractsRawCode>()
+        .contains_key(&ContractId::from([3; 32]))
+        .unwrap();
+    recorder
+        .read_contract(&ContractId::from([4; 32]), &mut [0; 8])
+        .unwrap();
+    StorageSize::<ContractsState>::size_of_value(&recorder, &state_key(5)).unwrap();
+
+    let list = recorder.access_list();
+    assert_eq!(
+        list.reads,
+        ReadSet {
+            contracts: [ContractId::from([3; 32]), ContractId::from([4; 32])].into(),
+            balances: [asset_key(1), asset_key(2)].into(),
+            contract_state: [state_key(5)].into(),
+            state_usage: Default::default(),
+            state_pages: Default::default(),
+            balance_pages: Default::default(),
+        }
+    );
+    assert_eq!(list.writes, WriteSet::default());
+}
+
+#[test]
+fn writes__keep_the_first_old_and_the_last_new_value() {
+    let mut recorder = recorder();
+
+    recorder
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1), &20)
+        .unwrap();
+    recorder
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1), &30)
+        .unwrap();
+    recorder
+        .contract_state_insert(&ContractId::default(), &[2; 32].into(), &[2; 32])
+        .unwrap();
+    recorder
+        .contract_state_remove(&ContractId::default(), &[1; 32].into())
+        .unwrap();
+
+    let list = recorder.access_list();
+    assert_eq!(
+        list.writes.balances,
+        [(
+            asset_key(1),
+            WriteAccess {
+                old: Some(10),
+                new: Some(30)
+            }
+        )]
+        .into()
+    );
+    assert_eq!(
+        list.writes.contract_state,
+        [
+            (
+                state_key(1),
+                WriteAccess {
+                    old: Some(vec![1; 32].into()),
+                    new: None
+                }
+            ),
+            (
+                state_key(2),
+                WriteAccess {
+                    old: None,
+                    new: Some(vec![2; 32].into())
+                }
+            )
+        ]
+        .into()
+    );
+    assert_eq!(list.reads.balances, [asset_key(1)].into());
+    assert_eq!(
+        list.reads.contract_state,
+        [state_key(1), state_key(2)].into()
+    );
+}
+
+#[test]
+fn state_range_operations__record_every_key_of_the_range() {
+    let mut recorder = recorder();
+    let start = Bytes32::from([1; 32]);
+    let keys: Vec<ContractsStateKey> =
+        state_keys(&ContractId::default(), &start).take(3).collect();
+
+    recorder
+        .contract_state_range(&ContractId::default(), &start, 3)
+        .unwrap();
+    assert_eq!(
+        recorder.take_access_list().reads.contract_state,
+        keys.iter().copied().collect()
+    );
+
+    let values = [[5; 32], [6; 32]];
+    recorder
+        .contract_state_insert_range(
+            &ContractId::default(),
+            &start,
+            values.iter().map(|v| v.as_slice()),
+        )
+        .unwrap();
+    recorder
+        .contract_state_remove_range(&ContractId::default(), &start, 1)
+        .unwrap();
+
+    let writes = recorder.take_access_list().writes.contract_state;
+    assert_eq!(
+        writes,
+        [
+            (
+                keys[0],
+                WriteAccess {
+                    old: Some(vec![1; 32].into()),
+                    new: None
+                }
+            ),
+            (
+                keys[1],
+                WriteAccess {
+                    old: None,
+                    new: Some(vec![6; 32].into())
+                }
+            )
+        ]
+        .into()
+    );
+}
+
+#[test]
+fn take_access_list__starts_a_new_recording() {
+    let mut recorder = recorder();
+    recorder
+        .storage::<ContractsAssets>()
+        .get(&asset_key(1))
+        .unwrap();
+
+    assert!(!recorder.take_access_list().is_empty());
+    assert!(recorder.access_list().is_empty());
+}
+
+#[test]
+fn conflicts_with__detects_write_after_read_and_write_after_write() {
+    let read = |key| {
+        let mut list = AccessList::default();
+        list.reads.contract_state.insert(state_key(key));
+        list
+    };
+    let write = |key| {
+        let mut list = AccessList::default();
+        list.writes.contract_state.insert(
+            state_key(key),
+            WriteAccess {
+                old: None,
+                new: None,
+            },
+        );
+        list
+    };
+
+    assert!(!read(1).conflicts_with(&read(1)));
+    assert!(!read(1).conflicts_with(&write(2)));
+    assert!(read(1).conflicts_with(&write(1)));
+    assert!(write(1).conflicts_with(&read(1)));
+    assert!(write(1).conflicts_with(&write(1)));
+}
+
+#[test]
+fn contract_state_usage__conflicts_with_writes_of_any_slot_of_the_contract() {
+    let mut recorder = recorder();
+    let usage = recorder
+        .contract_state_usage(&ContractId::default())
+        .unwrap()
+        .unwrap();
+    let usage_read = recorder.take_access_list();
+    recorder
+        .storage_as_mut::<ContractsState>()
+        .insert(&state_key(2), &[2; 16])
+        .unwrap();
+    let write = recorder.take_access_list();
+
+    assert_eq!(
+        usage,
+        ContractStateUsage {
+            slots: 1,
+            bytes: 32
+        }
+    );
+    assert_eq!(
+        usage_read.reads.state_usage,
+        [ContractId::default()].into_iter().collect()
+    );
+    assert!(usage_read.conflicts_with(&write));
+    assert!(write.conflicts_with(&usage_read));
+
+    let mut other = AccessList::default();
+    other.reads.state_usage.insert(ContractId::from([1; 32]));
+    assert!(!other.conflicts_with(&write));
+}
+
+#[test]
+fn pages__conflict_with_writes_of_any_entry_of_the_contract() {
+    let mut recorder = recorder();
+
+    let page = recorder
+        .contract_state_page(&ContractId::default(), &Bytes32::zeroed(), 10)
+        .unwrap();
+    assert_eq!(page.entries.len(), 1);
+    recorder
+        .contract_balances_page(&ContractId::default(), &AssetId::zeroed(), 10)
+        .unwrap();
+    let pages_read = recorder.take_access_list();
+    assert_eq!(
+        pages_read.reads.state_pages,
+        [ContractId::default()].into_iter().collect()
+    );
+    assert_eq!(
+        pages_read.reads.balance_pages,
+        [ContractId::default()].into_iter().collect()
+    );
+
+    recorder
+        .storage_as_mut::<ContractsState>()
+        .insert(&state_key(2), &[2; 32])
+        .unwrap();
+    let state_write = recorder.take_access_list();
+    recorder
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(2), &20)
+        .unwrap();
+    let balance_write = recorder.take_access_list();
+
+    assert!(pages_read.conflicts_with(&state_write));
+    assert!(pages_read.conflicts_with(&balance_write));
+    let mut other = AccessList::default();
+    other.reads.state_pages.insert(ContractId::from([1; 32]));
+    other.reads.balance_pages.insert(ContractId::from([1; 32]));
+    assert!(!other.conflicts_with(&state_write));
+    assert!(!other.conflicts_with(&balance_write));
+}
@@ -4,6 +4,7 @@ use fuel_types::{
     fmt_truncated_hex,
     Bytes32,
     ContractId,
+    Word,
 };
 
 use alloc::{
@@ -109,3 +110,31 @@ impl IntoIterator for ContractsStateData {
         self.0.into_iter()
     }
 }
+
+/// The set state slots of a contract and their total size.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContractStateUsage {
+    /// The number of set state slots.
+    pub slots: Word,
+    /// The total size of the values of the slots in bytes.
+    pub bytes: Word,
+}
+
+impl ContractStateUsage {
+    /// Account for a slot whose value of `old` bytes is replaced by a value of `new`
+    /// bytes. `None` is an unset slot.
+    pub fn update(&mut self, old: Option<usize>, new: Option<usize>) {
+        let [old_len, new_len] = [old, new].map(|len| len.unwrap_or_default() as Word);
+        self.slots = self
+            .slots
+            .saturating_sub(old.is_some() as Word)
+            .saturating_add(new.is_some() as Word);
+        self.bytes = self.bytes.saturating_sub(old_len).saturating_add(new_len);
+    }
+
+    /// Returns `true` if no slot is set.
+    pub fn is_empty(&self) -> bool {
+        self.slots == 0
+    }
+}
@@ -0,0 +1,672 @@
+//! File-backed storage, persisted to an append-only log.
+
+use super::{
+    interpreter::ContractsAssetsStorage,
+    memory::MemoryStorageInner,
+    ContractStateUsage,
+    ContractsAssetKey,
+    ContractsAssets,
+    ContractsRawCode,
+    ContractsState,
+    ContractsStateData,
+    ContractsStateKey,
+    ContractsStorageIter,
+    InterpreterStorage,
+    MemoryStorage,
+    StoragePage,
+    UploadedBytecode,
+    UploadedBytecodes,
+};
+
+use fuel_storage::{
+    Mappable,
+    StorageInspect,
+    StorageMutate,
+    StorageRead,
+    StorageSize,
+    StorageWrite,
+};
+use fuel_tx::{
+    ConsensusParameters,
+    Contract,
+};
+use fuel_types::{
+    AssetId,
+    BlockHeight,
+    Bytes32,
+    ContractId,
+    Word,
+};
+
+use alloc::{
+    borrow::Cow,
+    collections::BTreeMap,
+    vec::Vec,
+};
+use core::convert::Infallible;
+use std::{
+    fs::{
+        self,
+        File,
+        OpenOptions,
+    },
+    io::{
+        self,
+        BufRead,
+        BufReader,
+        Seek,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+/// A change of a single storage entry. `None` removes the entry.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Change {
+    Contract(ContractId, Option<Contract>),
+    Balance(ContractsAssetKey, Option<Word>),
+    State(ContractsStateKey, Option<ContractsStateData>),
+    ConsensusParameters(u32, Option<ConsensusParameters>),
+    UploadedBytecode(Bytes32, Option<UploadedBytecode>),
+    StateTransitionBytecodeVersion(u32, Option<Bytes32>),
+}
+
+/// The changes made by a single `persist`, stored as one line of the log.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct Batch {
+    changes: Vec<Change>,
+}
+
+impl Batch {
+    /// The changes turning the state `from` into the state `to`.
+    fn diff(from: &MemoryStorageInner, to: &MemoryStorageInner) -> Self {
+        let mut changes = Vec::new();
+        diff_tables(
+            &from.contracts,
+            &to.contracts,
+            Change::Contract,
+            &mut changes,
+        );
+        diff_tables(&from.balances, &to.balances, Change::Balance, &mut changes);
+        diff_tables(
+            &from.contract_state,
+            &to.contract_state,
+            Change::State,
+            &mut changes,
+        );
+        diff_tables(
+            &from.consensus_parameters_versions,
+            &to.consensus_parameters_versions,
+            Change::ConsensusParameters,
+            &mut changes,
+        );
+        diff_tables(
+            &from.state_transition_bytecodes,
+            &to.state_transition_bytecodes,
+            Change::UploadedBytecode,
+            &mut changes,
+        );
+        diff_tables(
+            &from.state_transition_bytecodes_versions,
+            &to.state_transition_bytecodes_versions,
+            Change::StateTransitionBytecodeVersion,
+            &mut changes,
+        );
+        Self { changes }
+    }
+
+    fn apply(self, state: &mut MemoryStorageInner) {
+        for change in self.changes {
+            match change {
+                Change::Contract(key, value) => apply(&mut state.contracts, key, value),
+                Change::Balance(key, value) => apply(&mut state.balances, key, value),
+                Change::State(key, Some(value)) => {
+                    state.insert_state(key, value);
+                }
+                Change::State(key, None) => {
+                    state.remove_state(&key);
+                }
+                Change::ConsensusParameters(key, value) => {
+                    apply(&mut state.consensus_parameters_versions, key, value)
+                }
+                Change::UploadedBytecode(key, value) => {
+                    apply(&mut state.state_transition_bytecodes, key, value)
+                }
+                Change::StateTransitionBytecodeVersion(key, value) => {
+                    apply(&mut state.state_transition_bytecodes_versions, key, value)
+                }
+            }
+        }
+    }
+
+    fn write_line<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        serde_json::to_writer(&mut *writer, self)?;
+        writer.write_all(b"\n")
+    }
+}
+
+fn diff_tables<K, V, F>(
+    from: &BTreeMap<K, V>,
+    to: &BTreeMap<K, V>,
+    change: F,
+    changes: &mut Vec<Change>,
+) where
+    K: Ord + Clone,
+    V: PartialEq + Clone,
+    F: Fn(K, Option<V>) -> Change,
+{
+    let removed = from
+        .keys()
+        .filter(|key| !to.contains_key(key))
+        .map(|key| change(key.clone(), None));
+    let updated = to
+        .iter()
+        .filter(|(key, value)| from.get(key) != Some(value))
+        .map(|(key, value)| change(key.clone(), Some(value.clone())));
+    changes.extend(removed.chain(updated));
+}
+
+fn apply<K: Ord, V>(table: &mut BTreeMap<K, V>, key: K, value: Option<V>) {
+    match value {
+        Some(value) => table.insert(key, value),
+        None => table.remove(&key),
+    };
+}
+
+/// File-backed storage implementation for the interpreter.
+///
+/// It tracks the same 3 states as [`MemoryStorage`] and keeps all of them in memory.
+/// [`FileStorage::persist`] appends the changes of the transacted state to a log
+/// file, one batch per line, and [`FileStorage::open`] replays the log to restore
+/// the persisted state. A batch that was only partially written, for example due
+/// to a crash, is discarded on open.
+///
+/// The block height, the coinbase and the versions are not persisted.
+#[derive(Debug)]
+pub struct FileStorage {
+    path: PathBuf,
+    log: File,
+    storage: MemoryStorage,
+}
+
+impl FileStorage {
+    /// Open the storage persisted to the log at `path`, creating an empty log if it
+    /// doesn't exist.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        block_height: BlockHeight,
+        coinbase: ContractId,
+    ) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut log = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)?;
+
+        let mut state = MemoryStorageInner::default();
+        let valid_len = replay(&mut log, &mut state)?;
+        if valid_len != log.metadata()?.len() {
+            log.set_len(valid_len)?;
+            log.sync_all()?;
+        }
+
+        let mut storage = MemoryStorage::new(block_height, coinbase);
+        storage.set_persisted(state);
+
+        Ok(Self { path, log, storage })
+    }
+
+    /// Path of the log.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The in-memory view of the storage.
+    pub fn memory_storage(&self) -> &MemoryStorage {
+        &self.storage
+    }
+
+    /// Set the transacted state to the memory state.
+    pub fn commit(&mut self) {
+        self.storage.commit()
+    }
+
+    /// Revert the memory state to the transacted state.
+    pub fn revert(&mut self) {
+        self.storage.revert()
+    }
+
+    /// Revert the memory and transacted changes to the persisted state.
+    pub fn rollback(&mut self) {
+        self.storage.rollback()
+    }
+
+    /// Persist the changes from transacted to memory+persisted state, appending them
+    /// to the log. The state and the log are not changed if writing the log fails.
+    pub fn persist(&mut self) -> io::Result<()> {
+        let batch = Batch::diff(self.storage.persisted(), self.storage.transacted());
+        if !batch.changes.is_empty() {
+            let mut line = Vec::new();
+            batch.write_line(&mut line)?;
+            let len = self.log.metadata()?.len();
+            if let Err(error) =
+                self.log.write_all(&line).and_then(|_| self.log.sync_data())
+            {
+                // Drop the partially written batch, otherwise the next one would be
+                // appended after it and `open` would reject the log as corrupted.
+                let _ = self.log.set_len(len);
+                return Err(error)
+            }
+        }
+        self.storage.persist();
+        Ok(())
+    }
+
+    /// Rewrite the log as a single batch of the persisted state, dropping the
+    /// history of overwritten and removed entries.
+    pub fn compact(&mut self) -> io::Result<()> {
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".compact");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        let batch = Batch::diff(&MemoryStorageInner::default(), self.storage.persisted());
+        let mut tmp = File::create(&tmp_path)?;
+        if !batch.changes.is_empty() {
+            batch.write_line(&mut tmp)?;
+        }
+        tmp.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+        sync_parent_dir(&self.path)?;
+
+        self.log = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&self.path)?;
+        Ok(())
+    }
+}
+
+/// Sync the directory containing `path`, making a rename to `path` durable.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()
+}
+
+/// Directories can't be synced on this platform.
+#[cfg(not(unix))]
+fn sync_parent_dir(_: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Apply the batches of the log to `state`, returning the length of the log up to
+/// the end of the last complete batch.
+fn replay(log: &mut File, state: &mut MemoryStorageInner) -> io::Result<u64> {
+    log.rewind()?;
+    let mut reader = BufReader::new(log);
+    let mut valid_len = 0u64;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            return Ok(valid_len)
+        }
+
+        let batch = line
+            .strip_suffix(b"\n")
+            .and_then(|line| serde_json::from_slice::<Batch>(line).ok());
+        match batch {
+            Some(batch) => batch.apply(state),
+            // Only the last batch may be incomplete.
+            None if reader.fill_buf()?.is_empty() => return Ok(valid_len),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "corrupted storage log",
+                ))
+            }
+        }
+        valid_len = valid_len.saturating_add(read as u64);
+    }
+}
+
+impl StorageInspect<ContractsRawCode> for FileStorage {
+    type Error = Infallible;
+
+    fn get(&self, key: &ContractId) -> Result<Option<Cow<'_, Contract>>, Infallible> {
+        StorageInspect::<ContractsRawCode>::get(&self.storage, key)
+    }
+
+    fn contains_key(&self, key: &ContractId) -> Result<bool, Infallible> {
+        StorageInspect::<ContractsRawCode>::contains_key(&self.storage, key)
+    }
+}
+
+impl StorageMutate<ContractsRawCode> for FileStorage {
+    fn insert(
+        &mut self,
+        key: &ContractId,
+        value: &[u8],
+    ) -> Result<Option<Contract>, Infallible> {
+        StorageMutate::<ContractsRawCode>::insert(&mut self.storage, key, value)
+    }
+
+    fn remove(&mut self, key: &ContractId) -> Result<Option<Contract>, Infallible> {
+        StorageMutate::<ContractsRawCode>::remove(&mut self.storage, key)
+    }
+}
+
+impl StorageWrite<ContractsRawCode> for FileStorage {
+    fn write(&mut self, key: &ContractId, buf: &[u8]) -> Result<usize, Infallible> {
+        StorageWrite::<ContractsRawCode>::write(&mut self.storage, key, buf)
+    }
+
+    fn replace(
+        &mut self,
+        key: &ContractId,
+        buf: &[u8],
+    ) -> Result<(usize, Option<Vec<u8>>), Self::Error> {
+        StorageWrite::<ContractsRawCode>::replace(&mut self.storage, key, buf)
+    }
+
+    fn take(&mut self, key: &ContractId) -> Result<Option<Vec<u8>>, Self::Error> {
+        StorageWrite::<ContractsRawCode>::take(&mut self.storage, key)
+    }
+}
+
+impl StorageSize<ContractsRawCode> for FileStorage {
+    fn size_of_value(&self, key: &ContractId) -> Result<Option<usize>, Infallible> {
+        StorageSize::<ContractsRawCode>::size_of_value(&self.storage, key)
+    }
+}
+
+impl StorageRead<ContractsRawCode> for FileStorage {
+    fn read(
+        &self,
+        key: &ContractId,
+        buf: &mut [u8],
+    ) -> Result<Option<usize>, Self::Error> {
+        StorageRead::<ContractsRawCode>::read(&self.storage, key, buf)
+    }
+
+    fn read_alloc(&self, key: &ContractId) -> Result<Option<Vec<u8>>, Self::Error> {
+        StorageRead::<ContractsRawCode>::read_alloc(&self.storage, key)
+    }
+}
+
+impl StorageInspect<UploadedBytecodes> for FileStorage {
+    type Error = Infallible;
+
+    fn get(
+        &self,
+        key: &<UploadedBytecodes as Mappable>::Key,
+    ) -> Result<Option<Cow<'_, UploadedBytecode>>, Infallible> {
+        StorageInspect::<UploadedBytecodes>::get(&self.storage, key)
+    }
+
+    fn contains_key(
+        &self,
+        key: &<UploadedBytecodes as Mappable>::Key,
+    ) -> Result<bool, Infallible> {
+        StorageInspect::<UploadedBytecodes>::contains_key(&self.storage, key)
+    }
+}
+
+impl StorageMutate<UploadedBytecodes> for FileStorage {
+    fn insert(
+        &mut self,
+        key: &<UploadedBytecodes as Mappable>::Key,
+        value: &<UploadedBytecodes as Mappable>::Value,
+    ) -> Result<Option<UploadedBytecode>, Infallible> {
+        StorageMutate::<UploadedBytecodes>::insert(&mut self.storage, key, value)
+    }
+
+    fn remove(
+        &mut self,
+        key: &<UploadedBytecodes as Mappable>::Key,
+    ) -> Result<Option<UploadedBytecode>, Infallible> {
+        StorageMutate::<UploadedBytecodes>::remove(&mut self.storage, key)
+    }
+}
+
+impl StorageInspect<ContractsAssets> for FileStorage {
+    type Error = Infallible;
+
+    fn get(
+        &self,
+        key: &<ContractsAssets as Mappable>::Key,
+    ) -> Result<Option<Cow<'_, Word>>, Infallible> {
+        StorageInspect::<ContractsAssets>::get(&self.storage, key)
+    }
+
+    fn contains_key(
+        &self,
+        key: &<ContractsAssets as Mappable>::Key,
+    ) -> Result<bool, Infallible> {
+        StorageInspect::<ContractsAssets>::contains_key(&self.storage, key)
+    }
+}
+
+impl StorageMutate<ContractsAssets> for FileStorage {
+    fn insert(
+        &mut self,
+        key: &<ContractsAssets as Mappable>::Key,
+        value: &Word,
+    ) -> Result<Option<Word>, Infallible> {
+        StorageMutate::<ContractsAssets>::insert(&mut self.storage, key, value)
+    }
+
+    fn remove(
+        &mut self,
+        key: &<ContractsAssets as Mappable>::Key,
+    ) -> Result<Option<Word>, Infallible> {
+        StorageMutate::<ContractsAssets>::remove(&mut self.storage, key)
+    }
+}
+
+impl StorageInspect<ContractsState> for FileStorage {
+    type Error = Infallible;
+
+    fn get(
+        &self,
+        key: &<ContractsState as Mappable>::Key,
+    ) -> Result<Option<Cow<'_, <ContractsState as Mappable>::OwnedValue>>, Infallible>
+    {
+        StorageInspect::<ContractsState>::get(&self.storage, key)
+    }
+
+    fn contains_key(
+        &self,
+        key: &<ContractsState as Mappable>::Key,
+    ) -> Result<bool, Infallible> {
+        StorageInspect::<ContractsState>::contains_key(&self.storage, key)
+    }
+}
+
+impl StorageMutate<ContractsState> for FileStorage {
+    fn insert(
+        &mut self,
+        key: &<ContractsState as Mappable>::Key,
+        value: &<ContractsState as Mappable>::Value,
+    ) -> Result<Option<<ContractsState as Mappable>::OwnedValue>, Infallible> {
+        StorageMutate::<ContractsState>::insert(&mut self.storage, key, value)
+    }
+
+    fn remove(
+        &mut self,
+        key: &<ContractsState as Mappable>::Key,
+    ) -> Result<Option<ContractsStateData>, Infallible> {
+        StorageMutate::<ContractsState>::remove(&mut self.storage, key)
+    }
+}
+
+impl StorageWrite<ContractsState> for FileStorage {
+    fn write(
+        &mut self,
+        key: &<ContractsState as Mappable>::Key,
+        buf: &[u8],
+    ) -> Result<usize, Infallible> {
+        StorageWrite::<ContractsState>::write(&mut self.storage, key, buf)
+    }
+
+    fn replace(
+        &mut self,
+        key: &<ContractsState as Mappable>::Key,
+        buf: &[u8],
+    ) -> Result<(usize, Option<Vec<u8>>), Self::Error> {
+        StorageWrite::<ContractsState>::replace(&mut self.storage, key, buf)
+    }
+
+    fn take(
+        &mut self,
+        key: &<ContractsState as Mappable>::Key,
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        StorageWrite::<ContractsState>::take(&mut self.storage, key)
+    }
+}
+
+impl StorageSize<ContractsState> for FileStorage {
+    fn size_of_value(
+        &self,
+        key: &<ContractsState as Mappable>::Key,
+    ) -> Result<Option<usize>, Infallible> {
+        StorageSize::<ContractsState>::size_of_value(&self.storage, key)
+    }
+}
+
+impl StorageRead<ContractsState> for FileStorage {
+    fn read(
+        &self,
+        key: &<ContractsState as Mappable>::Key,
+        buf: &mut [u8],
+    ) -> Result<Option<usize>, Self::Error> {
+        StorageRead::<ContractsState>::read(&self.storage, key, buf)
+    }
+
+    fn read_alloc(
+        &self,
+        key: &<ContractsState as Mappable>::Key,
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        StorageRead::<ContractsState>::read_alloc(&self.storage, key)
+    }
+}
+
+impl ContractsAssetsStorage for FileStorage {}
+
+impl InterpreterStorage for FileStorage {
+    type DataError = Infallible;
+
+    fn block_height(&self) -> Result<BlockHeight, Infallible> {
+        self.storage.block_height()
+    }
+
+    fn consensus_parameters_version(&self) -> Result<u32, Self::DataError> {
+        self.storage.consensus_parameters_version()
+    }
+
+    fn state_transition_version(&self) -> Result<u32, Self::DataError> {
+        self.storage.state_transition_version()
+    }
+
+    fn timestamp(&self, height: BlockHeight) -> Result<Word, Self::DataError> {
+        self.storage.timestamp(height)
+    }
+
+    fn block_hash(&self, block_height: BlockHeight) -> Result<Bytes32, Infallible> {
+        self.storage.block_hash(block_height)
+    }
+
+    fn coinbase(&self) -> Result<ContractId, Infallible> {
+        self.storage.coinbase()
+    }
+
+    fn set_consensus_parameters(
+        &mut self,
+        version: u32,
+        consensus_parameters: &ConsensusParameters,
+    ) -> Result<Option<ConsensusParameters>, Self::DataError> {
+        self.storage
+            .set_consensus_parameters(version, consensus_parameters)
+    }
+
+    fn set_state_transition_bytecode(
+        &mut self,
+        version: u32,
+        bytecode: &Bytes32,
+    ) -> Result<Option<Bytes32>, Self::DataError> {
+        self.storage
+            .set_state_transition_bytecode(version, bytecode)
+    }
+
+    fn contract_state_usage(
+        &self,
+        id: &ContractId,
+    ) -> Result<Option<ContractStateUsage>, Self::DataError> {
+        self.storage.contract_state_usage(id)
+    }
+
+    fn contract_state_range(
+        &self,
+        id: &ContractId,
+        start_key: &Bytes32,
+        range: usize,
+    ) -> Result<Vec<Option<Cow<'_, ContractsStateData>>>, Self::DataError> {
+        self.storage.contract_state_range(id, start_key, range)
+    }
+
+    fn contract_state_insert_range<'a, I>(
+        &mut self,
+        contract: &ContractId,
+        start_key: &Bytes32,
+        values: I,
+    ) -> Result<usize, Self::DataError>
+    where
+        I: Iterator<Item = &'a [u8]>,
+    {
+        self.storage
+            .contract_state_insert_range(contract, start_key, values)
+    }
+
+    fn contract_state_remove_range(
+        &mut self,
+        contract: &ContractId,
+        start_key: &Bytes32,
+        range: usize,
+    ) -> Result<Option<()>, Self::DataError> {
+        self.storage
+            .contract_state_remove_range(contract, start_key, range)
+    }
+}
+
+impl ContractsStorageIter for FileStorage {
+    fn contract_state_page(
+        &self,
+        id: &ContractId,
+        start_key: &Bytes32,
+        limit: usize,
+    ) -> Result<StoragePage<Bytes32, ContractsStateData>, Infallible> {
+        self.storage.contract_state_page(id, start_key, limit)
+    }
+
+    fn contract_balances_page(
+        &self,
+        id: &ContractId,
+        start_asset_id: &AssetId,
+        limit: usize,
+    ) -> Result<StoragePage<AssetId, Word>, Infallible> {
+        self.storage
+            .contract_balances_page(id, start_asset_id, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests;
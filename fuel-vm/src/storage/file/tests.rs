@@ -0,0 +1,371 @@
+#![allow(non_snake_case)]
+
+use super::*;
+use crate::{
+    checked_transaction::IntoChecked,
+    interpreter::InterpreterParams,
+    prelude::{
+        MemoryInstance,
+        Transactor,
+    },
+};
+
+use alloc::vec;
+use fuel_storage::{
+    StorageAsMut,
+    StorageAsRef,
+};
+use fuel_tx::{
+    ConsensusParameters,
+    Finalizable,
+    Output,
+    Script,
+    TransactionBuilder,
+};
+use fuel_types::Salt;
+use std::sync::atomic::{
+    AtomicUsize,
+    Ordering,
+};
+use test_case::test_case;
+
+/// A log path in the temporary directory, removed on drop.
+struct TempLog(PathBuf);
+
+impl TempLog {
+    fn new() -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "fuel-vm-file-storage-{}-{}.log",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = std::env::temp_dir().join(name);
+        let _ = fs::remove_file(&path);
+        Self(path)
+    }
+
+    fn open(&self) -> FileStorage {
+        let default = MemoryStorage::default();
+        FileStorage::open(
+            &self.0,
+            default.block_height().unwrap(),
+            default.coinbase().unwrap(),
+        )
+        .expect("failed to open the storage")
+    }
+}
+
+impl Drop for TempLog {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn state_key(contract: u8, key: u8) -> ContractsStateKey {
+    (&ContractId::from([contract; 32]), &Bytes32::from([key; 32])).into()
+}
+
+fn asset_key(contract: u8, asset: u8) -> ContractsAssetKey {
+    (&ContractId::from([contract; 32]), &[asset; 32].into()).into()
+}
+
+fn fill(storage: &mut FileStorage) {
+    storage
+        .storage_as_mut::<ContractsRawCode>()
+        .insert(&ContractId::from([1; 32]), &[1, 2, 3])
+        .unwrap();
+    storage
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1, 2), &100)
+        .unwrap();
+    storage
+        .storage_as_mut::<ContractsState>()
+        .insert(&state_key(1, 3), &[4; 32])
+        .unwrap();
+    storage
+        .storage_as_mut::<UploadedBytecodes>()
+        .insert(
+            &Bytes32::from([5; 32]),
+            &UploadedBytecode::Completed(vec![6, 7]),
+        )
+        .unwrap();
+    storage
+        .set_consensus_parameters(1, &ConsensusParameters::standard())
+        .unwrap();
+    storage
+        .set_state_transition_bytecode(1, &Bytes32::from([5; 32]))
+        .unwrap();
+}
+
+#[test]
+fn open__restores_the_persisted_state() {
+    let log = TempLog::new();
+    let mut storage = log.open();
+    fill(&mut storage);
+    storage.commit();
+    storage.persist().unwrap();
+    let expected = storage.memory_storage().persisted().clone();
+    drop(storage);
+
+    let storage = log.open();
+    assert_eq!(storage.memory_storage().persisted(), &expected);
+    assert_eq!(
+        storage
+            .storage::<ContractsRawCode>()
+            .get(&ContractId::from([1; 32]))
+            .unwrap()
+            .unwrap()
+            .as_ref()
+            .as_ref(),
+        &[1, 2, 3]
+    );
+    assert_eq!(
+        storage.contract_asset_id_balance(&ContractId::from([1; 32]), &[2; 32].into()),
+        Ok(Some(100))
+    );
+}
+
+#[test]
+fn persist__only_writes_the_transacted_state() {
+    let log = TempLog::new();
+    let mut storage = log.open();
+    fill(&mut storage);
+    storage.commit();
+    storage
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1, 2), &5)
+        .unwrap();
+    storage.persist().unwrap();
+    drop(storage);
+
+    let mut storage = log.open();
+    assert_eq!(
+        storage
+            .storage::<ContractsAssets>()
+            .get(&asset_key(1, 2))
+            .unwrap()
+            .map(Cow::into_owned),
+        Some(100)
+    );
+
+    storage
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1, 2), &5)
+        .unwrap();
+    storage
+        .storage_as_mut::<ContractsState>()
+        .remove(&state_key(1, 3))
+        .unwrap();
+    storage.revert();
+    assert_eq!(
+        storage
+            .storage::<ContractsAssets>()
+            .get(&asset_key(1, 2))
+            .unwrap()
+            .map(Cow::into_owned),
+        Some(100)
+    );
+}
+
+#[test]
+fn persist__removals_survive_reopening() {
+    let log = TempLog::new();
+    let mut storage = log.open();
+    fill(&mut storage);
+    storage.commit();
+    storage.persist().unwrap();
+
+    storage
+        .storage_as_mut::<ContractsState>()
+        .remove(&state_key(1, 3))
+        .unwrap();
+    storage.commit();
+    storage.persist().unwrap();
+    drop(storage);
+
+    let storage = log.open();
+    assert!(!storage
+        .storage::<ContractsState>()
+        .contains_key(&state_key(1, 3))
+        .unwrap());
+}
+
+#[test]
+fn rollback__drops_the_changes_since_the_last_persist() {
+    let log = TempLog::new();
+    let mut storage = log.open();
+    fill(&mut storage);
+    storage.commit();
+    storage.rollback();
+
+    assert!(!storage
+        .storage::<ContractsRawCode>()
+        .contains_key(&ContractId::from([1; 32]))
+        .unwrap());
+    storage.persist().unwrap();
+    assert_eq!(fs::metadata(storage.path()).unwrap().len(), 0);
+}
+
+#[test]
+fn open__discards_an_incomplete_last_batch() {
+    let log = TempLog::new();
+    let mut storage = log.open();
+    fill(&mut storage);
+    storage.commit();
+    storage.persist().unwrap();
+    let len = fs::metadata(&log.0).unwrap().len();
+    drop(storage);
+
+    let mut file = OpenOptions::new().append(true).open(&log.0).unwrap();
+    file.write_all(br#"{"changes":[{"balance""#).unwrap();
+    drop(file);
+
+    let mut storage = log.open();
+    assert_eq!(fs::metadata(&log.0).unwrap().len(), len);
+    storage
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1, 2), &7)
+        .unwrap();
+    storage.commit();
+    storage.persist().unwrap();
+    drop(storage);
+
+    let storage = log.open();
+    assert_eq!(
+        storage
+            .storage::<ContractsAssets>()
+            .get(&asset_key(1, 2))
+            .unwrap()
+            .map(Cow::into_owned),
+        Some(7)
+    );
+}
+
+#[test]
+fn open__fails_on_a_corrupted_batch() {
+    let log = TempLog::new();
+    fs::write(&log.0, b"not a batch\n{\"changes\":[]}\n").unwrap();
+
+    let default = MemoryStorage::default();
+    let error = FileStorage::open(
+        &log.0,
+        default.block_height().unwrap(),
+        default.coinbase().unwrap(),
+    )
+    .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn compact__keeps_the_persisted_state() {
+    let log = TempLog::new();
+    let mut storage = log.open();
+    for balance in 0..10 {
+        storage
+            .storage_as_mut::<ContractsAssets>()
+            .insert(&asset_key(1, 2), &balance)
+            .unwrap();
+        storage.commit();
+        storage.persist().unwrap();
+    }
+    fill(&mut storage);
+    storage.commit();
+    storage.persist().unwrap();
+    let expected = storage.memory_storage().persisted().clone();
+    let len = fs::metadata(&log.0).unwrap().len();
+
+    storage.compact().unwrap();
+    assert!(fs::metadata(&log.0).unwrap().len() < len);
+    storage
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(9, 9), &9)
+        .unwrap();
+    storage.commit();
+    storage.persist().unwrap();
+    drop(storage);
+
+    let storage = log.open();
+    let mut expected = expected;
+    expected.balances.insert(asset_key(9, 9), 9);
+    assert_eq!(storage.memory_storage().persisted(), &expected);
+}
+
+#[test]
+fn transactor__deployed_contract_is_persisted() {
+    let log = TempLog::new();
+    let consensus_params = ConsensusParameters::standard();
+    let interpreter_params = InterpreterParams::new(0, &consensus_params);
+
+    let program: Vec<u8> = [fuel_asm::op::ret(fuel_asm::RegId::ONE)]
+        .into_iter()
+        .collect();
+    let contract = Contract::from(program.clone());
+    let salt = Salt::zeroed();
+    let state_root = Contract::default_state_root();
+    let contract_id = contract.id(&salt, &contract.root(), &state_root);
+    let tx = TransactionBuilder::create(program.clone().into(), salt, vec![])
+        .add_random_fee_input()
+        .add_output(Output::contract_created(contract_id, state_root))
+        .finalize()
+        .into_checked(Default::default(), &consensus_params)
+        .expect("failed to check tx");
+
+    let mut vm = Transactor::<_, _, Script>::new(
+        MemoryInstance::new(),
+        log.open(),
+        interpreter_params,
+    );
+    vm.deploy(tx).expect("failed to deploy");
+    let storage: &mut FileStorage = vm.as_mut();
+    storage.commit();
+    storage.persist().unwrap();
+    drop(vm);
+
+    let storage = log.open();
+    let code = storage
+        .storage::<ContractsRawCode>()
+        .get(&contract_id)
+        .unwrap()
+        .expect("the contract is not persisted");
+    assert_eq!(code.as_ref().as_ref(), program.as_slice());
+}
+
+const fn key(k: u8) -> [u8; 32] {
+    [
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, k,
+    ]
+}
+
+#[test_case(&[&[0u8; 32]], &[0u8; 32], 1 => vec![Some(Default::default())])]
+#[test_case(&[&[0u8; 32]], &[0u8; 32], 0 => Vec::<Option<ContractsStateData>>::with_capacity(0))]
+#[test_case(&[], &[0u8; 32], 1 => vec![None])]
+#[test_case(&[], &[1u8; 32], 1 => vec![None])]
+#[test_case(&[&[0u8; 32]], &key(1), 2 => vec![None, None])]
+#[test_case(&[&key(1), &key(3)], &[0u8; 32], 4 => vec![None, Some(Default::default()), None, Some(Default::default())])]
+#[test_case(&[&[0u8; 32], &key(1)], &[0u8; 32], 1 => vec![Some(Default::default())])]
+fn contract_state_range__matches_memory_storage(
+    store: &[&[u8; 32]],
+    start: &[u8; 32],
+    range: usize,
+) -> Vec<Option<ContractsStateData>> {
+    let log = TempLog::new();
+    let mut storage = log.open();
+    for k in store {
+        storage
+            .storage_as_mut::<ContractsState>()
+            .insert(&(&ContractId::default(), &(**k).into()).into(), &[0; 32])
+            .unwrap();
+    }
+    storage.commit();
+    storage.persist().unwrap();
+    drop(storage);
+
+    log.open()
+        .contract_state_range(&ContractId::default(), &(*start).into(), range)
+        .unwrap()
+        .into_iter()
+        .map(|v| v.map(|v| v.into_owned()))
+        .collect()
+}
@@ -27,6 +27,7 @@ use crate::{
         RuntimeError,
     },
     storage::{
+        ContractStateUsage,
         ContractsAssets,
         ContractsRawCode,
         ContractsState,
@@ -218,6 +219,17 @@ pub trait InterpreterStorage:
         Ok(result)
     }
 
+    /// Fetch the number of set state slots of a contract and their total size.
+    ///
+    /// Returns `None` if the storage doesn't track the usage, in which case the `GM`
+    /// selectors reading it panic as unknown selectors.
+    fn contract_state_usage(
+        &self,
+        _id: &ContractId,
+    ) -> Result<Option<ContractStateUsage>, Self::DataError> {
+        Ok(None)
+    }
+
     /// Fetch a range of values from a key-value mapping in a contract storage.
     /// Returns the full range requested using optional values in case
     /// a requested slot is unset.  
@@ -283,6 +295,85 @@ pub trait ContractsAssetsStorage: StorageMutate<ContractsAssets> {
 
 impl<S> ContractsAssetsStorage for &mut S where S: ContractsAssetsStorage {}
 
+/// A page of storage entries in key order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoragePage<K, V> {
+    /// The entries of the page.
+    pub entries: Vec<(K, V)>,
+    /// The key of the first entry after the page, if any.
+    pub next_key: Option<K>,
+}
+
+impl<K, V> StoragePage<K, V> {
+    /// Take the first `limit` entries of `entries` as a page.
+    pub fn paginate<I>(mut entries: I, limit: usize) -> Self
+    where
+        I: Iterator<Item = (K, V)>,
+    {
+        let page = entries.by_ref().take(limit).collect();
+        Self {
+            entries: page,
+            next_key: entries.next().map(|(key, _)| key),
+        }
+    }
+}
+
+/// Iteration over the state slots and balances of a contract in key order.
+///
+/// The entries are returned in pages of at most `limit` entries, starting from a key.
+/// The next page starts from the [`StoragePage::next_key`] of the previous one, and
+/// the first one from the zeroed key.
+pub trait ContractsStorageIter: InterpreterStorage {
+    /// Fetch the state slots of the contract `id` with keys from `start_key`.
+    fn contract_state_page(
+        &self,
+        id: &ContractId,
+        start_key: &Bytes32,
+        limit: usize,
+    ) -> Result<StoragePage<Bytes32, ContractsStateData>, Self::DataError>;
+
+    /// Fetch the balances of the contract `id` with asset IDs from `start_asset_id`.
+    fn contract_balances_page(
+        &self,
+        id: &ContractId,
+        start_asset_id: &AssetId,
+        limit: usize,
+    ) -> Result<StoragePage<AssetId, Word>, Self::DataError>;
+}
+
+impl<S> ContractsStorageIter for &mut S
+where
+    S: ContractsStorageIter,
+{
+    fn contract_state_page(
+        &self,
+        id: &ContractId,
+        start_key: &Bytes32,
+        limit: usize,
+    ) -> Result<StoragePage<Bytes32, ContractsStateData>, Self::DataError> {
+        <S as ContractsStorageIter>::contract_state_page(
+            self.deref(),
+            id,
+            start_key,
+            limit,
+        )
+    }
+
+    fn contract_balances_page(
+        &self,
+        id: &ContractId,
+        start_asset_id: &AssetId,
+        limit: usize,
+    ) -> Result<StoragePage<AssetId, Word>, Self::DataError> {
+        <S as ContractsStorageIter>::contract_balances_page(
+            self.deref(),
+            id,
+            start_asset_id,
+            limit,
+        )
+    }
+}
+
 impl<S> InterpreterStorage for &mut S
 where
     S: InterpreterStorage,
@@ -352,6 +443,13 @@ where
         <S as InterpreterStorage>::read_contract(self.deref(), id, writer)
     }
 
+    fn contract_state_usage(
+        &self,
+        id: &ContractId,
+    ) -> Result<Option<ContractStateUsage>, Self::DataError> {
+        <S as InterpreterStorage>::contract_state_usage(self.deref(), id)
+    }
+
     fn contract_state_range(
         &self,
         id: &ContractId,
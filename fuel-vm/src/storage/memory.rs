@@ -1,11 +1,14 @@
 use crate::storage::{
+    ContractStateUsage,
     ContractsAssetKey,
     ContractsAssets,
     ContractsRawCode,
     ContractsState,
     ContractsStateData,
     ContractsStateKey,
+    ContractsStorageIter,
     InterpreterStorage,
+    StoragePage,
     UploadedBytecode,
     UploadedBytecodes,
 };
@@ -25,6 +28,7 @@ use fuel_tx::{
     Contract,
 };
 use fuel_types::{
+    AssetId,
     BlockHeight,
     Bytes32,
     ContractId,
@@ -42,16 +46,55 @@ use core::convert::Infallible;
 use super::interpreter::ContractsAssetsStorage;
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
-struct MemoryStorageInner {
-    contracts: BTreeMap<ContractId, Contract>,
-    balances: BTreeMap<ContractsAssetKey, Word>,
-    contract_state: BTreeMap<ContractsStateKey, ContractsStateData>,
+pub(super) struct MemoryStorageInner {
+    pub(super) contracts: BTreeMap<ContractId, Contract>,
+    pub(super) balances: BTreeMap<ContractsAssetKey, Word>,
+    pub(super) contract_state: BTreeMap<ContractsStateKey, ContractsStateData>,
+    /// The usage of the state slots per contract, kept up to date with `contract_state`.
+    pub(super) state_usage: BTreeMap<ContractId, ContractStateUsage>,
     /// Mapping from consensus parameters version to consensus parameters.
-    consensus_parameters_versions: BTreeMap<u32, ConsensusParameters>,
+    pub(super) consensus_parameters_versions: BTreeMap<u32, ConsensusParameters>,
     /// Mapping from state transition bytecode root to bytecode.
-    state_transition_bytecodes: BTreeMap<Bytes32, UploadedBytecode>,
+    pub(super) state_transition_bytecodes: BTreeMap<Bytes32, UploadedBytecode>,
     /// Mapping from state transition bytecode version to hash.
-    state_transition_bytecodes_versions: BTreeMap<u32, Bytes32>,
+    pub(super) state_transition_bytecodes_versions: BTreeMap<u32, Bytes32>,
+}
+
+impl MemoryStorageInner {
+    /// Set the state slot `key` to `value`, returning the previous value.
+    pub(super) fn insert_state(
+        &mut self,
+        key: ContractsStateKey,
+        value: ContractsStateData,
+    ) -> Option<ContractsStateData> {
+        let len = value.as_ref().len();
+        let previous = self.contract_state.insert(key, value);
+        self.update_usage(&key, previous.as_ref(), Some(len));
+        previous
+    }
+
+    /// Unset the state slot `key`, returning the previous value.
+    pub(super) fn remove_state(
+        &mut self,
+        key: &ContractsStateKey,
+    ) -> Option<ContractsStateData> {
+        let previous = self.contract_state.remove(key);
+        self.update_usage(key, previous.as_ref(), None);
+        previous
+    }
+
+    fn update_usage(
+        &mut self,
+        key: &ContractsStateKey,
+        previous: Option<&ContractsStateData>,
+        len: Option<usize>,
+    ) {
+        let usage = self.state_usage.entry(*key.contract_id()).or_default();
+        usage.update(previous.map(|value| value.as_ref().len()), len);
+        if usage.is_empty() {
+            self.state_usage.remove(key.contract_id());
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -67,6 +110,10 @@ pub struct MemoryStorage {
     coinbase: ContractId,
     consensus_parameters_version: u32,
     state_transition_version: u32,
+    /// Timestamps overriding the default timestamps of the blocks.
+    timestamps: BTreeMap<BlockHeight, Word>,
+    /// Hashes overriding the default hashes of the blocks.
+    block_hashes: BTreeMap<BlockHeight, Bytes32>,
     memory: MemoryStorageInner,
     transacted: MemoryStorageInner,
     persisted: MemoryStorageInner,
@@ -90,6 +137,8 @@ impl MemoryStorage {
             coinbase,
             consensus_parameters_version,
             state_transition_version,
+            timestamps: Default::default(),
+            block_hashes: Default::default(),
             memory: Default::default(),
             transacted: Default::default(),
             persisted: Default::default(),
@@ -115,6 +164,26 @@ impl MemoryStorage {
             .unwrap_or(Cow::Owned(ContractsStateData::default()))
     }
 
+    /// Replace all the states with `state`.
+    #[cfg(feature = "file-storage")]
+    pub(super) fn set_persisted(&mut self, state: MemoryStorageInner) {
+        self.memory = state.clone();
+        self.transacted = state.clone();
+        self.persisted = state;
+    }
+
+    /// The persisted state.
+    #[cfg(feature = "file-storage")]
+    pub(super) fn persisted(&self) -> &MemoryStorageInner {
+        &self.persisted
+    }
+
+    /// The transacted state, which becomes the persisted state on `persist`.
+    #[cfg(feature = "file-storage")]
+    pub(super) fn transacted(&self) -> &MemoryStorageInner {
+        &self.transacted
+    }
+
     /// Set the transacted state to the memory state.
     pub fn commit(&mut self) {
         self.transacted = self.memory.clone();
@@ -137,6 +206,31 @@ impl MemoryStorage {
         self.persisted = self.transacted.clone();
     }
 
+    /// Set the timestamp of the block at `height`, instead of one derived from the
+    /// height.
+    pub fn set_timestamp(&mut self, height: BlockHeight, timestamp: Word) {
+        self.timestamps.insert(height, timestamp);
+    }
+
+    /// Set the hash of the block at `height`, instead of the hash of the height.
+    pub fn set_block_hash(&mut self, height: BlockHeight, hash: Bytes32) {
+        self.block_hashes.insert(height, hash);
+    }
+
+    /// Set the state usage of the `contract`, instead of the usage of its slots in
+    /// the storage. It is updated by the following writes to its slots.
+    pub fn set_contract_state_usage(
+        &mut self,
+        contract: &ContractId,
+        usage: ContractStateUsage,
+    ) {
+        if usage.is_empty() {
+            self.memory.state_usage.remove(contract);
+        } else {
+            self.memory.state_usage.insert(*contract, usage);
+        }
+    }
+
     #[cfg(feature = "test-helpers")]
     /// Set the block height of the chain
     pub fn set_block_height(&mut self, block_height: BlockHeight) {
@@ -371,14 +465,14 @@ impl StorageMutate<ContractsState> for MemoryStorage {
         key: &<ContractsState as Mappable>::Key,
         value: &<ContractsState as Mappable>::Value,
     ) -> Result<Option<<ContractsState as Mappable>::OwnedValue>, Infallible> {
-        Ok(self.memory.contract_state.insert(*key, value.into()))
+        Ok(self.memory.insert_state(*key, value.into()))
     }
 
     fn remove(
         &mut self,
         key: &<ContractsState as Mappable>::Key,
     ) -> Result<Option<ContractsStateData>, Infallible> {
-        Ok(self.memory.contract_state.remove(key))
+        Ok(self.memory.remove_state(key))
     }
 }
 
@@ -390,8 +484,7 @@ impl StorageWrite<ContractsState> for MemoryStorage {
     ) -> Result<usize, Infallible> {
         let size = buf.len();
         self.memory
-            .contract_state
-            .insert(*key, ContractsStateData::from(buf));
+            .insert_state(*key, ContractsStateData::from(buf));
         Ok(size)
     }
 
@@ -406,8 +499,7 @@ impl StorageWrite<ContractsState> for MemoryStorage {
         let size = buf.len();
         let prev = self
             .memory
-            .contract_state
-            .insert(*key, ContractsStateData::from(buf))
+            .insert_state(*key, ContractsStateData::from(buf))
             .map(Into::into);
         Ok((size, prev))
     }
@@ -416,7 +508,7 @@ impl StorageWrite<ContractsState> for MemoryStorage {
         &mut self,
         key: &<ContractsState as Mappable>::Key,
     ) -> Result<Option<Vec<u8>>, Self::Error> {
-        let prev = self.memory.contract_state.remove(key).map(Into::into);
+        let prev = self.memory.remove_state(key).map(Into::into);
         Ok(prev)
     }
 }
@@ -481,10 +573,16 @@ impl InterpreterStorage for MemoryStorage {
         const GENESIS: Tai64 = Tai64::UNIX_EPOCH;
         const INTERVAL: Word = 10;
 
+        if let Some(timestamp) = self.timestamps.get(&height) {
+            return Ok(*timestamp)
+        }
         Ok((GENESIS + (*height as Word * INTERVAL)).0)
     }
 
     fn block_hash(&self, block_height: BlockHeight) -> Result<Bytes32, Infallible> {
+        if let Some(hash) = self.block_hashes.get(&block_height) {
+            return Ok(*hash)
+        }
         Ok(Hasher::hash(block_height.to_be_bytes()))
     }
 
@@ -514,6 +612,15 @@ impl InterpreterStorage for MemoryStorage {
             .insert(version, *bytecode))
     }
 
+    fn contract_state_usage(
+        &self,
+        id: &ContractId,
+    ) -> Result<Option<ContractStateUsage>, Infallible> {
+        Ok(Some(
+            self.memory.state_usage.get(id).copied().unwrap_or_default(),
+        ))
+    }
+
     fn contract_state_range(
         &self,
         id: &ContractId,
@@ -603,17 +710,76 @@ impl InterpreterStorage for MemoryStorage {
             })
             .take(range)
             .collect();
-        self.memory.contract_state.retain(|key, _| {
+        let usage = self.memory.state_usage.entry(*contract).or_default();
+        self.memory.contract_state.retain(|key, value| {
             let c = key.contract_id();
             let k = key.state_key();
             let r = values.remove(&**k);
             all_set_key &= c == contract && r;
+            if c == contract && r {
+                usage.update(Some(value.as_ref().len()), None);
+            }
             c != contract || !r
         });
+        if usage.is_empty() {
+            self.memory.state_usage.remove(contract);
+        }
         Ok((all_set_key && values.is_empty()).then_some(()))
     }
 }
 
+impl ContractsStorageIter for MemoryStorage {
+    fn contract_state_page(
+        &self,
+        id: &ContractId,
+        start_key: &Bytes32,
+        limit: usize,
+    ) -> Result<StoragePage<Bytes32, ContractsStateData>, Infallible> {
+        let start: ContractsStateKey = (id, start_key).into();
+        let end: ContractsStateKey = (id, &Bytes32::new([u8::MAX; 32])).into();
+        let entries = self
+            .memory
+            .contract_state
+            .range(start..=end)
+            .map(|(key, value)| (*key.state_key(), value.clone()));
+
+        Ok(StoragePage::paginate(entries, limit))
+    }
+
+    fn contract_balances_page(
+        &self,
+        id: &ContractId,
+        start_asset_id: &AssetId,
+        limit: usize,
+    ) -> Result<StoragePage<AssetId, Word>, Infallible> {
+        let start: ContractsAssetKey = (id, start_asset_id).into();
+        let end: ContractsAssetKey = (id, &AssetId::new([u8::MAX; 32])).into();
+        let entries = self
+            .memory
+            .balances
+            .range(start..=end)
+            .map(|(key, balance)| (*key.asset_id(), *balance));
+
+        Ok(StoragePage::paginate(entries, limit))
+    }
+}
+
+/// Consecutive state keys of the `contract`, starting from `start_key`.
+pub(super) fn state_keys<'a>(
+    contract: &'a ContractId,
+    start_key: &Bytes32,
+) -> impl Iterator<Item = ContractsStateKey> + 'a {
+    core::iter::successors(Some(**start_key), |n| {
+        let mut n = *n;
+        if add_one(&mut n) {
+            None
+        } else {
+            Some(n)
+        }
+    })
+    .map(move |key| (contract, &Bytes32::from(key)).into())
+}
+
 fn add_one(a: &mut [u8; 32]) -> bool {
     let right = u128::from_be_bytes(a[16..].try_into().unwrap());
     let (right, of) = right.overflowing_add(1);
@@ -665,4 +831,138 @@ mod tests {
             .map(|v| v.map(|v| v.into_owned()))
             .collect()
     }
+
+    #[test_case(&[], &[0u8; 32], 2 => (vec![], None))]
+    #[test_case(&[key(1), key(2), key(3)], &[0u8; 32], 2 => (vec![key(1), key(2)], Some(key(3))))]
+    #[test_case(&[key(1), key(2), key(3)], &key(2), 2 => (vec![key(2), key(3)], None))]
+    #[test_case(&[key(1), key(2), key(3)], &key(3), 0 => (vec![], Some(key(3))))]
+    #[test_case(&[key(1), [u8::MAX; 32]], &key(2), 2 => (vec![[u8::MAX; 32]], None))]
+    fn test_contract_state_page(
+        store: &[[u8; 32]],
+        start: &[u8; 32],
+        limit: usize,
+    ) -> (Vec<[u8; 32]>, Option<[u8; 32]>) {
+        let contract = ContractId::from([1; 32]);
+        let mut mem = MemoryStorage::default();
+        for k in store {
+            mem.contract_state_insert(&contract, &(*k).into(), k)
+                .unwrap();
+        }
+        // Slots of the neighbouring contracts are never part of the page.
+        mem.contract_state_insert(&[0; 32].into(), &[u8::MAX; 32].into(), &[0; 32])
+            .unwrap();
+        mem.contract_state_insert(&[2; 32].into(), &[0; 32].into(), &[0; 32])
+            .unwrap();
+
+        let page = mem
+            .contract_state_page(&contract, &(*start).into(), limit)
+            .unwrap();
+        assert!(page
+            .entries
+            .iter()
+            .all(|(key, value)| key.as_ref() == value.as_ref()));
+        (
+            page.entries.into_iter().map(|(key, _)| *key).collect(),
+            page.next_key.map(|key| *key),
+        )
+    }
+
+    #[test]
+    fn test_contract_state_usage() {
+        let contract = ContractId::from([1; 32]);
+        let usage =
+            |mem: &MemoryStorage| mem.contract_state_usage(&contract).unwrap().unwrap();
+        let mut mem = MemoryStorage::default();
+
+        mem.contract_state_insert(&contract, &key(1).into(), &[1; 10])
+            .unwrap();
+        mem.contract_state_insert_range(
+            &contract,
+            &key(2).into(),
+            [[2; 32].as_ref(), [3; 32].as_ref()].into_iter(),
+        )
+        .unwrap();
+        mem.contract_state_insert(&[2; 32].into(), &key(1).into(), &[1; 32])
+            .unwrap();
+        assert_eq!(
+            usage(&mem),
+            ContractStateUsage {
+                slots: 3,
+                bytes: 74
+            }
+        );
+
+        mem.contract_state_insert(&contract, &key(1).into(), &[1; 20])
+            .unwrap();
+        assert_eq!(
+            usage(&mem),
+            ContractStateUsage {
+                slots: 3,
+                bytes: 84
+            }
+        );
+
+        mem.commit();
+        mem.contract_state_remove_range(&contract, &key(2).into(), 2)
+            .unwrap();
+        assert_eq!(
+            usage(&mem),
+            ContractStateUsage {
+                slots: 1,
+                bytes: 20
+            }
+        );
+
+        mem.revert();
+        assert_eq!(
+            usage(&mem),
+            ContractStateUsage {
+                slots: 3,
+                bytes: 84
+            }
+        );
+
+        mem.contract_state_remove(&contract, &key(1).into())
+            .unwrap();
+        mem.contract_state_remove_range(&contract, &key(2).into(), 2)
+            .unwrap();
+        assert_eq!(usage(&mem), ContractStateUsage::default());
+        assert_eq!(mem.memory.state_usage.len(), 1);
+    }
+
+    #[test]
+    fn test_contract_balances_page() {
+        let contract = ContractId::from([1; 32]);
+        let mut mem = MemoryStorage::default();
+        for asset in [3, 1, 2] {
+            mem.contract_asset_id_balance_insert(
+                &contract,
+                &AssetId::from(key(asset)),
+                asset.into(),
+            )
+            .unwrap();
+        }
+        mem.contract_asset_id_balance_insert(&ContractId::zeroed(), &key(4).into(), 4)
+            .unwrap();
+
+        let mut balances = vec![];
+        let mut start = AssetId::zeroed();
+        loop {
+            let page = mem.contract_balances_page(&contract, &start, 2).unwrap();
+            balances.extend(page.entries);
+            match page.next_key {
+                Some(next_key) => start = next_key,
+                None => break,
+            }
+        }
+
+        assert_eq!(
+            balances,
+            vec![
+                (AssetId::from(key(1)), 1),
+                (AssetId::from(key(2)), 2),
+                (AssetId::from(key(3)), 3),
+            ]
+        );
+    }
 }
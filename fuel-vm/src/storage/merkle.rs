@@ -0,0 +1,417 @@
+//! Sparse Merkle commitments to the state and balances of contracts.
+
+use super::{
+    interpreter::ContractsAssetsStorage,
+    memory::state_keys,
+    ContractStateUsage,
+    ContractsAssetKey,
+    ContractsAssets,
+    ContractsRawCode,
+    ContractsState,
+    ContractsStateData,
+    ContractsStateKey,
+    InterpreterStorage,
+    UploadedBytecodes,
+};
+
+use fuel_merkle::sparse::{
+    in_memory::MerkleTree,
+    proof::Proof,
+    MerkleTreeKey,
+};
+use fuel_storage::{
+    Mappable,
+    MerkleRoot,
+    MerkleRootStorage,
+    StorageInspect,
+    StorageMutate,
+    StorageRead,
+    StorageSize,
+    StorageWrite,
+};
+use fuel_tx::ConsensusParameters;
+use fuel_types::{
+    AssetId,
+    BlockHeight,
+    Bytes32,
+    ContractId,
+    Word,
+};
+
+use alloc::{
+    borrow::{
+        Cow,
+        ToOwned,
+    },
+    collections::BTreeMap,
+    vec::Vec,
+};
+
+/// A storage table whose entries are committed to by the [`MerkleStorage`].
+pub trait MerkleTable: Mappable {
+    /// Update the commitment to the `key` after its value was set to `value`, or
+    /// removed if `None`.
+    fn update<S>(
+        storage: &mut MerkleStorage<S>,
+        key: &Self::Key,
+        value: Option<&Self::OwnedValue>,
+    );
+}
+
+impl MerkleTable for ContractsState {
+    fn update<S>(
+        storage: &mut MerkleStorage<S>,
+        key: &ContractsStateKey,
+        value: Option<&ContractsStateData>,
+    ) {
+        let tree = storage.state.entry(*key.contract_id()).or_default();
+        update_leaf(tree, key.state_key(), value.map(AsRef::as_ref));
+    }
+}
+
+impl MerkleTable for ContractsAssets {
+    fn update<S>(
+        storage: &mut MerkleStorage<S>,
+        key: &ContractsAssetKey,
+        value: Option<&Word>,
+    ) {
+        let tree = storage.balances.entry(*key.contract_id()).or_default();
+        let value = value.map(|amount| amount.to_be_bytes());
+        update_leaf(tree, key.asset_id(), value.as_ref().map(AsRef::as_ref));
+    }
+}
+
+/// The contract codes are not committed to.
+impl MerkleTable for ContractsRawCode {
+    fn update<S>(_: &mut MerkleStorage<S>, _: &ContractId, _: Option<&Self::OwnedValue>) {
+    }
+}
+
+/// The uploaded bytecodes are not committed to.
+impl MerkleTable for UploadedBytecodes {
+    fn update<S>(_: &mut MerkleStorage<S>, _: &Bytes32, _: Option<&Self::OwnedValue>) {}
+}
+
+fn update_leaf(tree: &mut MerkleTree, key: impl AsRef<[u8]>, value: Option<&[u8]>) {
+    let key = MerkleTreeKey::new(key);
+    match value {
+        Some(value) => tree.update(key, value),
+        None => tree.delete(key),
+    }
+}
+
+/// Storage wrapper maintaining a sparse Merkle tree over the state slots and another
+/// over the balances of every contract, as they are written through it.
+///
+/// The leaves of the state tree are keyed by [`MerkleTreeKey::new`] of the slot key
+/// and hold the slot value, like [`Contract::initial_state_root`]. The leaves of the
+/// balance tree are keyed by [`MerkleTreeKey::new`] of the asset id and hold the
+/// big-endian amount. A slot set to an empty value is not part of the tree.
+///
+/// The trees start empty: entries already present in the underlying storage must be
+/// added with [`MerkleStorage::with_state`] and [`MerkleStorage::with_balances`].
+///
+/// [`Contract::initial_state_root`]: fuel_tx::Contract::initial_state_root
+#[derive(Debug)]
+pub struct MerkleStorage<S> {
+    storage: S,
+    state: BTreeMap<ContractId, MerkleTree>,
+    balances: BTreeMap<ContractId, MerkleTree>,
+}
+
+impl<S> MerkleStorage<S> {
+    /// Maintain the roots of the contracts of the `storage`.
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            state: Default::default(),
+            balances: Default::default(),
+        }
+    }
+
+    /// Add the state slots already present in the underlying storage to the trees.
+    pub fn with_state<I>(mut self, slots: I) -> Self
+    where
+        I: IntoIterator<Item = (ContractsStateKey, ContractsStateData)>,
+    {
+        for (key, value) in slots {
+            ContractsState::update(&mut self, &key, Some(&value));
+        }
+        self
+    }
+
+    /// Add the balances already present in the underlying storage to the trees.
+    pub fn with_balances<I>(mut self, balances: I) -> Self
+    where
+        I: IntoIterator<Item = (ContractsAssetKey, Word)>,
+    {
+        for (key, amount) in balances {
+            ContractsAssets::update(&mut self, &key, Some(&amount));
+        }
+        self
+    }
+
+    /// The underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Stop maintaining the roots and return the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+
+    /// The root of the state slots of the `contract`.
+    pub fn state_root(&self, contract: &ContractId) -> Bytes32 {
+        root(self.state.get(contract))
+    }
+
+    /// The root of the balances of the `contract`.
+    pub fn balance_root(&self, contract: &ContractId) -> Bytes32 {
+        root(self.balances.get(contract))
+    }
+
+    /// An inclusion proof of the state slot `key` of the `contract` against its
+    /// [`MerkleStorage::state_root`], or an exclusion proof if the slot is unset.
+    pub fn state_proof(&self, contract: &ContractId, key: &Bytes32) -> Proof {
+        proof(self.state.get(contract), key)
+    }
+
+    /// An inclusion proof of the balance of the `asset_id` of the `contract` against
+    /// its [`MerkleStorage::balance_root`], or an exclusion proof if it is unset.
+    pub fn balance_proof(&self, contract: &ContractId, asset_id: &AssetId) -> Proof {
+        proof(self.balances.get(contract), asset_id)
+    }
+}
+
+fn root(tree: Option<&MerkleTree>) -> Bytes32 {
+    tree.map(MerkleTree::root).unwrap_or_default().into()
+}
+
+fn proof(tree: Option<&MerkleTree>, key: impl AsRef<[u8]>) -> Proof {
+    let key = MerkleTreeKey::new(key);
+    match tree {
+        Some(tree) => tree.generate_proof(&key),
+        None => MerkleTree::new().generate_proof(&key),
+    }
+    .expect("The in-memory tree is infallible")
+}
+
+impl<S> MerkleRootStorage<ContractId, ContractsState> for MerkleStorage<S>
+where
+    S: StorageInspect<ContractsState>,
+{
+    fn root(&self, contract: &ContractId) -> Result<MerkleRoot, S::Error> {
+        Ok(*self.state_root(contract))
+    }
+}
+
+impl<S> MerkleRootStorage<ContractId, ContractsAssets> for MerkleStorage<S>
+where
+    S: StorageInspect<ContractsAssets>,
+{
+    fn root(&self, contract: &ContractId) -> Result<MerkleRoot, S::Error> {
+        Ok(*self.balance_root(contract))
+    }
+}
+
+impl<Type, S> StorageInspect<Type> for MerkleStorage<S>
+where
+    Type: MerkleTable,
+    S: StorageInspect<Type>,
+{
+    type Error = S::Error;
+
+    fn get(
+        &self,
+        key: &Type::Key,
+    ) -> Result<Option<Cow<'_, Type::OwnedValue>>, S::Error> {
+        self.storage.get(key)
+    }
+
+    fn contains_key(&self, key: &Type::Key) -> Result<bool, S::Error> {
+        self.storage.contains_key(key)
+    }
+}
+
+impl<Type, S> StorageMutate<Type> for MerkleStorage<S>
+where
+    Type: MerkleTable,
+    S: StorageMutate<Type>,
+{
+    fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::Value,
+    ) -> Result<Option<Type::OwnedValue>, S::Error> {
+        let existing = self.storage.insert(key, value)?;
+        Type::update(self, key, Some(&value.to_owned().into()));
+        Ok(existing)
+    }
+
+    fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::OwnedValue>, S::Error> {
+        let existing = self.storage.remove(key)?;
+        Type::update(self, key, None);
+        Ok(existing)
+    }
+}
+
+impl<Type, S> StorageSize<Type> for MerkleStorage<S>
+where
+    Type: MerkleTable,
+    S: StorageSize<Type>,
+{
+    fn size_of_value(&self, key: &Type::Key) -> Result<Option<usize>, S::Error> {
+        self.storage.size_of_value(key)
+    }
+}
+
+impl<Type, S> StorageRead<Type> for MerkleStorage<S>
+where
+    Type: MerkleTable,
+    S: StorageRead<Type>,
+{
+    fn read(&self, key: &Type::Key, buf: &mut [u8]) -> Result<Option<usize>, S::Error> {
+        self.storage.read(key, buf)
+    }
+
+    fn read_alloc(&self, key: &Type::Key) -> Result<Option<Vec<u8>>, S::Error> {
+        self.storage.read_alloc(key)
+    }
+}
+
+impl<Type, S> StorageWrite<Type> for MerkleStorage<S>
+where
+    Type: MerkleTable,
+    Type::OwnedValue: From<Vec<u8>>,
+    S: StorageWrite<Type>,
+{
+    fn write(&mut self, key: &Type::Key, buf: &[u8]) -> Result<usize, S::Error> {
+        self.replace(key, buf).map(|(written, _)| written)
+    }
+
+    fn replace(
+        &mut self,
+        key: &Type::Key,
+        buf: &[u8],
+    ) -> Result<(usize, Option<Vec<u8>>), S::Error> {
+        let (written, existing) = self.storage.replace(key, buf)?;
+        Type::update(self, key, Some(&buf.to_vec().into()));
+        Ok((written, existing))
+    }
+
+    fn take(&mut self, key: &Type::Key) -> Result<Option<Vec<u8>>, S::Error> {
+        let existing = self.storage.take(key)?;
+        Type::update(self, key, None);
+        Ok(existing)
+    }
+}
+
+impl<S: InterpreterStorage> ContractsAssetsStorage for MerkleStorage<S> {}
+
+impl<S> InterpreterStorage for MerkleStorage<S>
+where
+    S: InterpreterStorage,
+{
+    type DataError = S::DataError;
+
+    fn block_height(&self) -> Result<BlockHeight, Self::DataError> {
+        self.storage.block_height()
+    }
+
+    fn consensus_parameters_version(&self) -> Result<u32, Self::DataError> {
+        self.storage.consensus_parameters_version()
+    }
+
+    fn state_transition_version(&self) -> Result<u32, Self::DataError> {
+        self.storage.state_transition_version()
+    }
+
+    fn timestamp(&self, height: BlockHeight) -> Result<Word, Self::DataError> {
+        self.storage.timestamp(height)
+    }
+
+    fn block_hash(&self, block_height: BlockHeight) -> Result<Bytes32, Self::DataError> {
+        self.storage.block_hash(block_height)
+    }
+
+    fn coinbase(&self) -> Result<ContractId, Self::DataError> {
+        self.storage.coinbase()
+    }
+
+    fn set_consensus_parameters(
+        &mut self,
+        version: u32,
+        consensus_parameters: &ConsensusParameters,
+    ) -> Result<Option<ConsensusParameters>, Self::DataError> {
+        self.storage
+            .set_consensus_parameters(version, consensus_parameters)
+    }
+
+    fn set_state_transition_bytecode(
+        &mut self,
+        version: u32,
+        hash: &Bytes32,
+    ) -> Result<Option<Bytes32>, Self::DataError> {
+        self.storage.set_state_transition_bytecode(version, hash)
+    }
+
+    fn contract_state_usage(
+        &self,
+        id: &ContractId,
+    ) -> Result<Option<ContractStateUsage>, Self::DataError> {
+        self.storage.contract_state_usage(id)
+    }
+
+    fn contract_state_range(
+        &self,
+        id: &ContractId,
+        start_key: &Bytes32,
+        range: usize,
+    ) -> Result<Vec<Option<Cow<'_, ContractsStateData>>>, Self::DataError> {
+        self.storage.contract_state_range(id, start_key, range)
+    }
+
+    fn contract_state_insert_range<'a, I>(
+        &mut self,
+        contract: &ContractId,
+        start_key: &Bytes32,
+        values: I,
+    ) -> Result<usize, Self::DataError>
+    where
+        I: Iterator<Item = &'a [u8]>,
+    {
+        let values: Vec<_> = values.collect();
+        let unset = self.storage.contract_state_insert_range(
+            contract,
+            start_key,
+            values.iter().copied(),
+        )?;
+
+        for (key, value) in state_keys(contract, start_key).zip(values) {
+            ContractsState::update(self, &key, Some(&value.into()));
+        }
+
+        Ok(unset)
+    }
+
+    fn contract_state_remove_range(
+        &mut self,
+        contract: &ContractId,
+        start_key: &Bytes32,
+        range: usize,
+    ) -> Result<Option<()>, Self::DataError> {
+        let result = self
+            .storage
+            .contract_state_remove_range(contract, start_key, range)?;
+
+        for key in state_keys(contract, start_key).take(range) {
+            ContractsState::update(self, &key, None);
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests;
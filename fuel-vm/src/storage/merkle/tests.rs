@@ -0,0 +1,292 @@
+#![allow(non_snake_case)]
+
+use super::*;
+use crate::{
+    checked_transaction::IntoChecked,
+    interpreter::{
+        InterpreterParams,
+        MemoryInstance,
+    },
+    storage::MemoryStorage,
+    transactor::Transactor,
+};
+
+use fuel_asm::{
+    op,
+    RegId,
+};
+use fuel_merkle::sparse::in_memory;
+use fuel_storage::{
+    StorageAsMut,
+    StorageAsRef,
+};
+use fuel_tx::{
+    Contract,
+    Finalizable,
+    Output,
+    Script,
+    StorageSlot,
+    TransactionBuilder,
+};
+use fuel_types::Salt;
+
+fn contract() -> ContractId {
+    ContractId::from([7; 32])
+}
+
+fn state_key(key: u8) -> ContractsStateKey {
+    (&contract(), &Bytes32::from([key; 32])).into()
+}
+
+fn asset_key(asset: u8) -> ContractsAssetKey {
+    (&contract(), &AssetId::from([asset; 32])).into()
+}
+
+fn state_root_of(slots: &[([u8; 32], [u8; 32])]) -> Bytes32 {
+    let slots: Vec<_> = slots
+        .iter()
+        .map(|(key, value)| StorageSlot::new((*key).into(), (*value).into()))
+        .collect();
+    Contract::initial_state_root(slots.iter())
+}
+
+#[test]
+fn roots__are_empty_without_entries() {
+    let storage = MerkleStorage::new(MemoryStorage::default());
+
+    assert_eq!(
+        storage.state_root(&contract()),
+        Contract::default_state_root()
+    );
+    assert_eq!(storage.balance_root(&contract()), Bytes32::zeroed());
+}
+
+#[test]
+fn state_root__follows_inserts_and_removals() {
+    let mut after_two = [2; 32];
+    after_two[31] = 3;
+    let mut storage = MerkleStorage::new(MemoryStorage::default());
+
+    storage
+        .storage_as_mut::<ContractsState>()
+        .insert(&state_key(1), &[1; 32])
+        .unwrap();
+    storage
+        .contract_state_insert_range(
+            &contract(),
+            &[2; 32].into(),
+            [[2; 32].as_ref(), [3; 32].as_ref()].into_iter(),
+        )
+        .unwrap();
+    StorageWrite::<ContractsState>::write(&mut storage, &state_key(4), &[4; 32]).unwrap();
+    assert_eq!(
+        storage.state_root(&contract()),
+        state_root_of(&[
+            ([1; 32], [1; 32]),
+            ([2; 32], [2; 32]),
+            (after_two, [3; 32]),
+            ([4; 32], [4; 32]),
+        ])
+    );
+
+    storage
+        .storage_as_mut::<ContractsState>()
+        .insert(&state_key(1), &[5; 32])
+        .unwrap();
+    storage
+        .contract_state_remove_range(&contract(), &[2; 32].into(), 2)
+        .unwrap();
+    StorageWrite::<ContractsState>::take(&mut storage, &state_key(4)).unwrap();
+    assert_eq!(
+        storage.state_root(&contract()),
+        state_root_of(&[([1; 32], [5; 32])])
+    );
+    assert_eq!(
+        storage
+            .storage::<ContractsState>()
+            .root(&contract())
+            .unwrap(),
+        *state_root_of(&[([1; 32], [5; 32])])
+    );
+
+    storage
+        .storage_as_mut::<ContractsState>()
+        .remove(&state_key(1))
+        .unwrap();
+    assert_eq!(
+        storage.state_root(&contract()),
+        Contract::default_state_root()
+    );
+}
+
+#[test]
+fn state_root__follows_the_slots_of_other_contracts_separately() {
+    let other = ContractId::from([8; 32]);
+    let mut storage = MerkleStorage::new(MemoryStorage::default());
+
+    storage
+        .contract_state_insert(&contract(), &[1; 32].into(), &[1; 32])
+        .unwrap();
+    storage
+        .contract_state_insert(&other, &[2; 32].into(), &[2; 32])
+        .unwrap();
+
+    assert_eq!(
+        storage.state_root(&contract()),
+        state_root_of(&[([1; 32], [1; 32])])
+    );
+    assert_eq!(
+        storage.state_root(&other),
+        state_root_of(&[([2; 32], [2; 32])])
+    );
+}
+
+#[test]
+fn balance_root__commits_to_the_big_endian_amounts() {
+    let mut storage = MerkleStorage::new(MemoryStorage::default());
+
+    storage
+        .contract_asset_id_balance_insert(&contract(), &AssetId::from([1; 32]), 10)
+        .unwrap();
+    storage
+        .contract_asset_id_balance_insert(&contract(), &AssetId::from([2; 32]), 20)
+        .unwrap();
+    storage
+        .storage_as_mut::<ContractsAssets>()
+        .remove(&asset_key(2))
+        .unwrap();
+
+    let expected = in_memory::MerkleTree::root_from_set(
+        [(MerkleTreeKey::new([1; 32]), 10u64.to_be_bytes())].into_iter(),
+    );
+    assert_eq!(storage.balance_root(&contract()), expected.into());
+    assert_eq!(
+        storage
+            .storage::<ContractsAssets>()
+            .root(&contract())
+            .unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn state_proof__proves_set_and_unset_slots() {
+    let mut storage = MerkleStorage::new(MemoryStorage::default());
+    storage
+        .contract_state_insert(&contract(), &[1; 32].into(), &[1; 32])
+        .unwrap();
+    storage
+        .contract_state_insert(&contract(), &[2; 32].into(), &[2; 32])
+        .unwrap();
+    let root = storage.state_root(&contract());
+
+    let Proof::Inclusion(proof) = storage.state_proof(&contract(), &[1; 32].into())
+    else {
+        panic!("expected an inclusion proof")
+    };
+    assert!(proof.verify(&root, &MerkleTreeKey::new([1; 32]), &[1; 32]));
+    assert!(!proof.verify(&root, &MerkleTreeKey::new([1; 32]), &[2; 32]));
+
+    let Proof::Exclusion(proof) = storage.state_proof(&contract(), &[3; 32].into())
+    else {
+        panic!("expected an exclusion proof")
+    };
+    assert!(proof.verify(&root, &MerkleTreeKey::new([3; 32])));
+
+    let other = ContractId::from([8; 32]);
+    let Proof::Exclusion(proof) = storage.state_proof(&other, &[1; 32].into()) else {
+        panic!("expected an exclusion proof")
+    };
+    assert!(proof.verify(&storage.state_root(&other), &MerkleTreeKey::new([1; 32])));
+}
+
+#[test]
+fn balance_proof__proves_set_and_unset_balances() {
+    let mut storage = MerkleStorage::new(MemoryStorage::default());
+    storage
+        .contract_asset_id_balance_insert(&contract(), &AssetId::from([1; 32]), 10)
+        .unwrap();
+    let root = storage.balance_root(&contract());
+
+    let Proof::Inclusion(proof) =
+        storage.balance_proof(&contract(), &AssetId::from([1; 32]))
+    else {
+        panic!("expected an inclusion proof")
+    };
+    assert!(proof.verify(&root, &MerkleTreeKey::new([1; 32]), &10u64.to_be_bytes()));
+
+    let Proof::Exclusion(proof) =
+        storage.balance_proof(&contract(), &AssetId::from([2; 32]))
+    else {
+        panic!("expected an exclusion proof")
+    };
+    assert!(proof.verify(&root, &MerkleTreeKey::new([2; 32])));
+}
+
+#[test]
+fn with_state__adds_the_existing_entries() {
+    let mut memory = MemoryStorage::default();
+    memory
+        .storage_as_mut::<ContractsState>()
+        .insert(&state_key(1), &[1; 32])
+        .unwrap();
+    memory
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1), &10)
+        .unwrap();
+
+    let slots: Vec<_> = memory
+        .all_contract_state()
+        .map(|(key, value)| (*key, value.clone()))
+        .collect();
+    let mut storage = MerkleStorage::new(memory)
+        .with_state(slots)
+        .with_balances([(asset_key(1), 10)]);
+    storage
+        .contract_state_insert(&contract(), &[2; 32].into(), &[2; 32])
+        .unwrap();
+
+    assert_eq!(
+        storage.state_root(&contract()),
+        state_root_of(&[([1; 32], [1; 32]), ([2; 32], [2; 32])])
+    );
+    assert_eq!(
+        storage.balance_root(&contract()),
+        in_memory::MerkleTree::root_from_set(
+            [(MerkleTreeKey::new([1; 32]), 10u64.to_be_bytes())].into_iter()
+        )
+        .into()
+    );
+}
+
+#[test]
+fn deploy__state_root_matches_the_initial_state_root() {
+    let program: Vec<u8> = [op::ret(RegId::ONE)].into_iter().collect();
+    let contract = Contract::from(program.clone());
+    let slots = vec![
+        StorageSlot::new([1; 32].into(), [1; 32].into()),
+        StorageSlot::new([2; 32].into(), [2; 32].into()),
+    ];
+    let salt = Salt::zeroed();
+    let state_root = Contract::initial_state_root(slots.iter());
+    let contract_id = contract.id(&salt, &contract.root(), &state_root);
+    let consensus_params = ConsensusParameters::standard();
+    let tx = TransactionBuilder::create(program.into(), salt, slots)
+        .add_random_fee_input()
+        .add_output(Output::contract_created(contract_id, state_root))
+        .finalize()
+        .into_checked(Default::default(), &consensus_params)
+        .expect("failed to check tx");
+
+    let mut transactor = Transactor::<_, _, Script>::new(
+        MemoryInstance::new(),
+        MerkleStorage::new(MemoryStorage::default()),
+        InterpreterParams::new(0, &consensus_params),
+    );
+    transactor.deploy(tx).expect("failed to deploy");
+
+    assert_eq!(
+        AsRef::<MerkleStorage<_>>::as_ref(&transactor).state_root(&contract_id),
+        state_root
+    );
+}
@@ -0,0 +1,729 @@
+//! Storage buffering the changes on top of another storage.
+
+use super::{
+    interpreter::ContractsAssetsStorage,
+    memory::state_keys,
+    ContractStateUsage,
+    ContractsAssetKey,
+    ContractsAssets,
+    ContractsRawCode,
+    ContractsState,
+    ContractsStateData,
+    ContractsStateKey,
+    ContractsStorageIter,
+    InterpreterStorage,
+    StoragePage,
+    UploadedBytecodes,
+};
+
+use fuel_storage::{
+    Mappable,
+    StorageInspect,
+    StorageMutate,
+    StorageRead,
+    StorageSize,
+    StorageWrite,
+};
+use fuel_tx::ConsensusParameters;
+use fuel_types::{
+    AssetId,
+    BlockHeight,
+    Bytes32,
+    ContractId,
+    Word,
+};
+
+use alloc::{
+    borrow::{
+        Cow,
+        ToOwned,
+    },
+    collections::{
+        BTreeMap,
+        VecDeque,
+    },
+    vec,
+    vec::Vec,
+};
+
+/// The changes of a single table: `None` marks a removed entry.
+pub type Changes<Type> =
+    BTreeMap<<Type as Mappable>::OwnedKey, Option<<Type as Mappable>::OwnedValue>>;
+
+/// The changes made to the storage tables by an [`OverlayStorage`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Changeset {
+    contracts: Changes<ContractsRawCode>,
+    balances: Changes<ContractsAssets>,
+    contract_state: Changes<ContractsState>,
+    uploaded_bytecodes: Changes<UploadedBytecodes>,
+    consensus_parameters: BTreeMap<u32, ConsensusParameters>,
+    state_transition_bytecodes: BTreeMap<u32, Bytes32>,
+}
+
+/// A storage table whose changes are tracked by the [`Changeset`].
+pub trait ChangesetTable: Mappable {
+    /// The changes of the table.
+    fn changes(changeset: &Changeset) -> &Changes<Self>;
+
+    /// The mutable changes of the table.
+    fn changes_mut(changeset: &mut Changeset) -> &mut Changes<Self>;
+}
+
+impl ChangesetTable for ContractsRawCode {
+    fn changes(changeset: &Changeset) -> &Changes<Self> {
+        &changeset.contracts
+    }
+
+    fn changes_mut(changeset: &mut Changeset) -> &mut Changes<Self> {
+        &mut changeset.contracts
+    }
+}
+
+impl ChangesetTable for ContractsAssets {
+    fn changes(changeset: &Changeset) -> &Changes<Self> {
+        &changeset.balances
+    }
+
+    fn changes_mut(changeset: &mut Changeset) -> &mut Changes<Self> {
+        &mut changeset.balances
+    }
+}
+
+impl ChangesetTable for ContractsState {
+    fn changes(changeset: &Changeset) -> &Changes<Self> {
+        &changeset.contract_state
+    }
+
+    fn changes_mut(changeset: &mut Changeset) -> &mut Changes<Self> {
+        &mut changeset.contract_state
+    }
+}
+
+impl ChangesetTable for UploadedBytecodes {
+    fn changes(changeset: &Changeset) -> &Changes<Self> {
+        &changeset.uploaded_bytecodes
+    }
+
+    fn changes_mut(changeset: &mut Changeset) -> &mut Changes<Self> {
+        &mut changeset.uploaded_bytecodes
+    }
+}
+
+impl Changeset {
+    /// The changes of the `Type` table.
+    pub fn changes<Type: ChangesetTable>(&self) -> &Changes<Type> {
+        Type::changes(self)
+    }
+
+    /// The consensus parameters set per version.
+    pub fn consensus_parameters(&self) -> &BTreeMap<u32, ConsensusParameters> {
+        &self.consensus_parameters
+    }
+
+    /// The state transition bytecode roots set per version.
+    pub fn state_transition_bytecodes(&self) -> &BTreeMap<u32, Bytes32> {
+        &self.state_transition_bytecodes
+    }
+
+    /// Returns `true` if there are no changes.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Apply the changes of `other` on top of these changes.
+    pub fn extend(&mut self, other: Self) {
+        self.contracts.extend(other.contracts);
+        self.balances.extend(other.balances);
+        self.contract_state.extend(other.contract_state);
+        self.uploaded_bytecodes.extend(other.uploaded_bytecodes);
+        self.consensus_parameters.extend(other.consensus_parameters);
+        self.state_transition_bytecodes
+            .extend(other.state_transition_bytecodes);
+    }
+
+    /// Write the changes into the `storage`.
+    pub fn apply<S>(self, storage: &mut S) -> Result<(), S::DataError>
+    where
+        S: InterpreterStorage,
+    {
+        apply_changes::<ContractsRawCode, _, _>(self.contracts, storage, |v| v.as_ref())?;
+        apply_changes::<ContractsAssets, _, _>(self.balances, storage, |v| v)?;
+        apply_changes::<ContractsState, _, _>(self.contract_state, storage, |v| {
+            v.as_ref()
+        })?;
+        apply_changes::<UploadedBytecodes, _, _>(
+            self.uploaded_bytecodes,
+            storage,
+            |v| v,
+        )?;
+        for (version, consensus_parameters) in self.consensus_parameters {
+            storage.set_consensus_parameters(version, &consensus_parameters)?;
+        }
+        for (version, hash) in self.state_transition_bytecodes {
+            storage.set_state_transition_bytecode(version, &hash)?;
+        }
+        Ok(())
+    }
+}
+
+fn apply_changes<Type, K, S>(
+    changes: Changes<Type>,
+    storage: &mut S,
+    value: impl Fn(&Type::OwnedValue) -> &Type::Value,
+) -> Result<(), S::Error>
+where
+    Type: Mappable<Key = K, OwnedKey = K>,
+    S: StorageMutate<Type>,
+{
+    for (key, change) in changes {
+        match change {
+            Some(v) => storage.insert(&key, value(&v))?,
+            None => storage.remove(&key)?,
+        };
+    }
+    Ok(())
+}
+
+/// A point of the [`OverlayStorage`] history that the changes can be rolled back to.
+///
+/// A savepoint is identified by its depth and by the generation in which it was
+/// created, so that a savepoint that was rolled back or released doesn't match a
+/// savepoint created later at the same depth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Savepoint {
+    depth: usize,
+    generation: u64,
+}
+
+/// Storage implementation for the interpreter buffering all the changes on top of
+/// another storage.
+///
+/// The `base` storage is never modified until [`OverlayStorage::flush`]. The changes
+/// are grouped by nested savepoints: [`OverlayStorage::rollback_to`] discards the
+/// changes made after a savepoint and [`OverlayStorage::release`] keeps them. Rolling
+/// back or releasing a savepoint does the same with all the savepoints created after
+/// it.
+///
+/// Only the consensus parameters and state transition bytecodes set through the
+/// overlay are returned as previous values by
+/// [`InterpreterStorage::set_consensus_parameters`] and
+/// [`InterpreterStorage::set_state_transition_bytecode`], because the storage has no
+/// way to read them from the base.
+#[derive(Debug, Clone)]
+pub struct OverlayStorage<S> {
+    base: S,
+    /// The changes made before the first savepoint, followed by the changes made
+    /// after each open savepoint.
+    layers: Vec<Changeset>,
+    /// The generations of the open savepoints, in the order of their layers.
+    savepoints: Vec<u64>,
+    /// The generation of the next savepoint.
+    generation: u64,
+}
+
+impl<S> OverlayStorage<S> {
+    /// Buffer the changes on top of the `base` storage.
+    pub fn new(base: S) -> Self {
+        Self {
+            base,
+            layers: vec![Changeset::default()],
+            savepoints: Vec::new(),
+            generation: 0,
+        }
+    }
+
+    /// The underlying storage.
+    pub fn base(&self) -> &S {
+        &self.base
+    }
+
+    /// Discard the buffered changes and return the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.base
+    }
+
+    /// Create a savepoint at the current state.
+    pub fn savepoint(&mut self) -> Savepoint {
+        let savepoint = Savepoint {
+            depth: self.layers.len(),
+            generation: self.generation,
+        };
+        self.generation = self.generation.wrapping_add(1);
+        self.layers.push(Changeset::default());
+        self.savepoints.push(savepoint.generation);
+        savepoint
+    }
+
+    /// Returns `true` if the `savepoint` wasn't rolled back or released yet.
+    pub fn is_open(&self, savepoint: Savepoint) -> bool {
+        savepoint
+            .depth
+            .checked_sub(1)
+            .and_then(|index| self.savepoints.get(index))
+            == Some(&savepoint.generation)
+    }
+
+    /// Discard the changes made after the `savepoint`.
+    ///
+    /// Does nothing if the savepoint was already rolled back or released.
+    pub fn rollback_to(&mut self, savepoint: Savepoint) {
+        if self.is_open(savepoint) {
+            self.truncate(savepoint.depth);
+        }
+    }
+
+    /// Keep the changes made after the `savepoint` and remove the savepoint.
+    ///
+    /// Does nothing if the savepoint was already rolled back or released.
+    pub fn release(&mut self, savepoint: Savepoint) {
+        if !self.is_open(savepoint) {
+            return
+        }
+        let released = self.layers.split_off(savepoint.depth);
+        self.truncate(savepoint.depth);
+        let layer = self
+            .layers
+            .last_mut()
+            .expect("The first layer is never removed");
+        released
+            .into_iter()
+            .for_each(|changes| layer.extend(changes));
+    }
+
+    /// Discard all the buffered changes and savepoints.
+    pub fn rollback(&mut self) {
+        self.layers = vec![Changeset::default()];
+        self.savepoints.clear();
+    }
+
+    /// Remove the layers and the savepoints from `depth`.
+    fn truncate(&mut self, depth: usize) {
+        self.layers.truncate(depth);
+        self.savepoints.truncate(depth.saturating_sub(1));
+    }
+
+    /// The buffered changes, including the changes made after the open savepoints.
+    pub fn changeset(&self) -> Changeset {
+        let mut changeset = Changeset::default();
+        self.layers
+            .iter()
+            .for_each(|changes| changeset.extend(changes.clone()));
+        changeset
+    }
+
+    /// Remove and return the buffered changes, releasing all the savepoints.
+    pub fn take_changeset(&mut self) -> Changeset {
+        let mut changeset = Changeset::default();
+        self.savepoints.clear();
+        core::mem::replace(&mut self.layers, vec![Changeset::default()])
+            .into_iter()
+            .for_each(|changes| changeset.extend(changes));
+        changeset
+    }
+
+    /// The buffered value of `key`, if it was changed: `Some(None)` if it was removed.
+    fn buffered<Type>(&self, key: &Type::Key) -> Option<Option<&Type::OwnedValue>>
+    where
+        Type: ChangesetTable,
+        Type::OwnedKey: Ord + 'static,
+    {
+        let key: Type::OwnedKey = key.to_owned().into();
+        self.layers
+            .iter()
+            .rev()
+            .find_map(|layer| Type::changes(layer).get(&key))
+            .map(Option::as_ref)
+    }
+
+    fn buffer<Type>(&mut self, key: &Type::Key, value: Option<Type::OwnedValue>)
+    where
+        Type: ChangesetTable,
+        Type::OwnedKey: Ord + 'static,
+    {
+        let layer = self
+            .layers
+            .last_mut()
+            .expect("The first layer is never removed");
+        Type::changes_mut(layer).insert(key.to_owned().into(), value);
+    }
+}
+
+impl<S> OverlayStorage<S>
+where
+    S: InterpreterStorage,
+{
+    /// Write the buffered changes into the underlying storage, releasing all the
+    /// savepoints.
+    ///
+    /// If writing fails, the changes written so far are not reverted in the underlying
+    /// storage, and the buffered changes are lost.
+    pub fn flush(&mut self) -> Result<(), S::DataError> {
+        let changeset = self.take_changeset();
+        changeset.apply(&mut self.base)
+    }
+}
+
+impl<Type, S> StorageInspect<Type> for OverlayStorage<S>
+where
+    Type: ChangesetTable,
+    Type::OwnedKey: Ord + 'static,
+    S: StorageInspect<Type>,
+{
+    type Error = S::Error;
+
+    fn get(
+        &self,
+        key: &Type::Key,
+    ) -> Result<Option<Cow<'_, Type::OwnedValue>>, S::Error> {
+        match self.buffered::<Type>(key) {
+            Some(value) => Ok(value.map(Cow::Borrowed)),
+            None => self.base.get(key),
+        }
+    }
+
+    fn contains_key(&self, key: &Type::Key) -> Result<bool, S::Error> {
+        match self.buffered::<Type>(key) {
+            Some(value) => Ok(value.is_some()),
+            None => self.base.contains_key(key),
+        }
+    }
+}
+
+impl<Type, S> StorageMutate<Type> for OverlayStorage<S>
+where
+    Type: ChangesetTable,
+    Type::OwnedKey: Ord + 'static,
+    S: StorageInspect<Type>,
+{
+    fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::Value,
+    ) -> Result<Option<Type::OwnedValue>, S::Error> {
+        let existing = self.get(key)?.map(Cow::into_owned);
+        self.buffer::<Type>(key, Some(value.to_owned().into()));
+        Ok(existing)
+    }
+
+    fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::OwnedValue>, S::Error> {
+        let existing = self.get(key)?.map(Cow::into_owned);
+        if existing.is_some() {
+            self.buffer::<Type>(key, None);
+        }
+        Ok(existing)
+    }
+}
+
+impl<Type, S> StorageSize<Type> for OverlayStorage<S>
+where
+    Type: ChangesetTable,
+    Type::OwnedKey: Ord + 'static,
+    Type::OwnedValue: AsRef<[u8]>,
+    S: StorageSize<Type>,
+{
+    fn size_of_value(&self, key: &Type::Key) -> Result<Option<usize>, S::Error> {
+        match self.buffered::<Type>(key) {
+            Some(value) => Ok(value.map(|value| value.as_ref().len())),
+            None => self.base.size_of_value(key),
+        }
+    }
+}
+
+impl<Type, S> StorageRead<Type> for OverlayStorage<S>
+where
+    Type: ChangesetTable,
+    Type::OwnedKey: Ord + 'static,
+    Type::OwnedValue: AsRef<[u8]>,
+    S: StorageRead<Type>,
+{
+    fn read(&self, key: &Type::Key, buf: &mut [u8]) -> Result<Option<usize>, S::Error> {
+        match self.buffered::<Type>(key) {
+            Some(value) => Ok(value.map(|value| {
+                let value = value.as_ref();
+                let len = buf.len().min(value.len());
+                buf[..len].copy_from_slice(&value[..len]);
+                len
+            })),
+            None => self.base.read(key, buf),
+        }
+    }
+
+    fn read_alloc(&self, key: &Type::Key) -> Result<Option<Vec<u8>>, S::Error> {
+        match self.buffered::<Type>(key) {
+            Some(value) => Ok(value.map(|value| value.as_ref().to_vec())),
+            None => self.base.read_alloc(key),
+        }
+    }
+}
+
+impl<Type, S> StorageWrite<Type> for OverlayStorage<S>
+where
+    Type: ChangesetTable,
+    Type::OwnedKey: Ord + 'static,
+    Type::OwnedValue: AsRef<[u8]> + From<Vec<u8>>,
+    S: StorageInspect<Type>,
+{
+    fn write(&mut self, key: &Type::Key, buf: &[u8]) -> Result<usize, S::Error> {
+        self.buffer::<Type>(key, Some(buf.to_vec().into()));
+        Ok(buf.len())
+    }
+
+    fn replace(
+        &mut self,
+        key: &Type::Key,
+        buf: &[u8],
+    ) -> Result<(usize, Option<Vec<u8>>), S::Error> {
+        let existing = self.get(key)?.map(|value| value.as_ref().as_ref().to_vec());
+        let written = self.write(key, buf)?;
+        Ok((written, existing))
+    }
+
+    fn take(&mut self, key: &Type::Key) -> Result<Option<Vec<u8>>, S::Error> {
+        let existing = self.remove(key)?;
+        Ok(existing.map(|value| value.as_ref().to_vec()))
+    }
+}
+
+impl<S: InterpreterStorage> ContractsAssetsStorage for OverlayStorage<S> {}
+
+impl<S> InterpreterStorage for OverlayStorage<S>
+where
+    S: InterpreterStorage,
+{
+    type DataError = S::DataError;
+
+    fn block_height(&self) -> Result<BlockHeight, Self::DataError> {
+        self.base.block_height()
+    }
+
+    fn consensus_parameters_version(&self) -> Result<u32, Self::DataError> {
+        self.base.consensus_parameters_version()
+    }
+
+    fn state_transition_version(&self) -> Result<u32, Self::DataError> {
+        self.base.state_transition_version()
+    }
+
+    fn timestamp(&self, height: BlockHeight) -> Result<Word, Self::DataError> {
+        self.base.timestamp(height)
+    }
+
+    fn block_hash(&self, block_height: BlockHeight) -> Result<Bytes32, Self::DataError> {
+        self.base.block_hash(block_height)
+    }
+
+    fn coinbase(&self) -> Result<ContractId, Self::DataError> {
+        self.base.coinbase()
+    }
+
+    fn set_consensus_parameters(
+        &mut self,
+        version: u32,
+        consensus_parameters: &ConsensusParameters,
+    ) -> Result<Option<ConsensusParameters>, Self::DataError> {
+        let existing = self
+            .layers
+            .iter()
+            .rev()
+            .find_map(|layer| layer.consensus_parameters.get(&version))
+            .cloned();
+        let layer = self
+            .layers
+            .last_mut()
+            .expect("The first layer is never removed");
+        layer
+            .consensus_parameters
+            .insert(version, consensus_parameters.clone());
+        Ok(existing)
+    }
+
+    fn set_state_transition_bytecode(
+        &mut self,
+        version: u32,
+        hash: &Bytes32,
+    ) -> Result<Option<Bytes32>, Self::DataError> {
+        let existing = self
+            .layers
+            .iter()
+            .rev()
+            .find_map(|layer| layer.state_transition_bytecodes.get(&version))
+            .copied();
+        let layer = self
+            .layers
+            .last_mut()
+            .expect("The first layer is never removed");
+        layer.state_transition_bytecodes.insert(version, *hash);
+        Ok(existing)
+    }
+
+    fn contract_state_usage(
+        &self,
+        id: &ContractId,
+    ) -> Result<Option<ContractStateUsage>, Self::DataError> {
+        let start: ContractsStateKey = (id, &Bytes32::zeroed()).into();
+        let end: ContractsStateKey = (id, &Bytes32::new([u8::MAX; 32])).into();
+        let mut buffered = BTreeMap::new();
+        for layer in self.layers.iter().rev() {
+            for (key, value) in layer.contract_state.range(start..=end) {
+                buffered.entry(key).or_insert(value);
+            }
+        }
+
+        let Some(mut usage) = self.base.contract_state_usage(id)? else {
+            return Ok(None)
+        };
+        for (key, value) in buffered {
+            let previous = StorageSize::<ContractsState>::size_of_value(&self.base, key)?;
+            usage.update(previous, value.as_ref().map(|value| value.as_ref().len()));
+        }
+        Ok(Some(usage))
+    }
+
+    fn contract_state_range(
+        &self,
+        id: &ContractId,
+        start_key: &Bytes32,
+        range: usize,
+    ) -> Result<Vec<Option<Cow<'_, ContractsStateData>>>, Self::DataError> {
+        state_keys(id, start_key)
+            .take(range)
+            .map(|key| StorageInspect::<ContractsState>::get(self, &key))
+            .collect()
+    }
+
+    fn contract_state_insert_range<'a, I>(
+        &mut self,
+        contract: &ContractId,
+        start_key: &Bytes32,
+        values: I,
+    ) -> Result<usize, Self::DataError>
+    where
+        I: Iterator<Item = &'a [u8]>,
+    {
+        let mut unset_count: usize = 0;
+        for (key, value) in state_keys(contract, start_key).zip(values) {
+            if !StorageInspect::<ContractsState>::contains_key(self, &key)? {
+                unset_count = unset_count.saturating_add(1);
+            }
+            StorageWrite::<ContractsState>::write(self, &key, value)?;
+        }
+        Ok(unset_count)
+    }
+
+    fn contract_state_remove_range(
+        &mut self,
+        contract: &ContractId,
+        start_key: &Bytes32,
+        range: usize,
+    ) -> Result<Option<()>, Self::DataError> {
+        let mut all_set_key = true;
+        for key in state_keys(contract, start_key).take(range) {
+            all_set_key &= StorageMutate::<ContractsState>::remove(self, &key)?.is_some();
+        }
+        Ok(all_set_key.then_some(()))
+    }
+}
+
+impl<S> ContractsStorageIter for OverlayStorage<S>
+where
+    S: ContractsStorageIter,
+{
+    fn contract_state_page(
+        &self,
+        id: &ContractId,
+        start_key: &Bytes32,
+        limit: usize,
+    ) -> Result<StoragePage<Bytes32, ContractsStateData>, Self::DataError> {
+        let start: ContractsStateKey = (id, start_key).into();
+        let end: ContractsStateKey = (id, &Bytes32::new([u8::MAX; 32])).into();
+        let mut buffered = BTreeMap::new();
+        for layer in self.layers.iter() {
+            for (key, value) in layer.contract_state.range(start..=end) {
+                buffered.insert(*key.state_key(), value.clone());
+            }
+        }
+
+        merge_page(*start_key, limit, buffered, |key, limit| {
+            self.base.contract_state_page(id, key, limit)
+        })
+    }
+
+    fn contract_balances_page(
+        &self,
+        id: &ContractId,
+        start_asset_id: &AssetId,
+        limit: usize,
+    ) -> Result<StoragePage<AssetId, Word>, Self::DataError> {
+        let start: ContractsAssetKey = (id, start_asset_id).into();
+        let end: ContractsAssetKey = (id, &AssetId::new([u8::MAX; 32])).into();
+        let mut buffered = BTreeMap::new();
+        for layer in self.layers.iter() {
+            for (key, value) in layer.balances.range(start..=end) {
+                buffered.insert(*key.asset_id(), *value);
+            }
+        }
+
+        merge_page(*start_asset_id, limit, buffered, |key, limit| {
+            self.base.contract_balances_page(id, key, limit)
+        })
+    }
+}
+
+/// Returns the page of at most `limit` entries from `start` of the base storage
+/// entries, fetched page by page with `base_page`, overridden by the `buffered`
+/// changes with keys from `start`.
+fn merge_page<K, V, E>(
+    start: K,
+    limit: usize,
+    buffered: BTreeMap<K, Option<V>>,
+    mut base_page: impl FnMut(&K, usize) -> Result<StoragePage<K, V>, E>,
+) -> Result<StoragePage<K, V>, E>
+where
+    K: Ord,
+{
+    // One more entry than the page is collected to find the next key.
+    let size = limit.saturating_add(1);
+    let mut buffered = buffered.into_iter().peekable();
+    let mut base = VecDeque::new();
+    let mut base_next = Some(start);
+    let mut entries = Vec::with_capacity(size);
+
+    while entries.len() < size {
+        if base.is_empty() {
+            if let Some(next) = base_next.take() {
+                let page = base_page(&next, size)?;
+                base.extend(page.entries);
+                base_next = page.next_key;
+                continue
+            }
+        }
+
+        let from_base = match (base.front(), buffered.peek()) {
+            (None, None) => break,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (Some((base_key, _)), Some((key, _))) => base_key < key,
+        };
+        if from_base {
+            entries.extend(base.pop_front());
+        } else if let Some((key, value)) = buffered.next() {
+            if base.front().is_some_and(|(base_key, _)| *base_key == key) {
+                base.pop_front();
+            }
+            if let Some(value) = value {
+                entries.push((key, value));
+            }
+        }
+    }
+
+    let next_key = if entries.len() > limit {
+        entries.pop().map(|(key, _)| key)
+    } else {
+        None
+    };
+    Ok(StoragePage { entries, next_key })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,582 @@
+#![allow(non_snake_case)]
+
+use super::*;
+use crate::{
+    checked_transaction::IntoChecked,
+    interpreter::InterpreterParams,
+    prelude::{
+        MemoryInstance,
+        Transactor,
+    },
+    storage::{
+        ContractsStateKey,
+        MemoryStorage,
+    },
+};
+
+use fuel_storage::{
+    StorageAsMut,
+    StorageAsRef,
+};
+use fuel_tx::{
+    Contract,
+    Finalizable,
+    Output,
+    Script,
+    TransactionBuilder,
+};
+use fuel_types::{
+    AssetId,
+    Salt,
+};
+use test_case::test_case;
+
+fn asset_key(contract: u8, asset: u8) -> super::super::ContractsAssetKey {
+    (
+        &ContractId::from([contract; 32]),
+        &AssetId::from([asset; 32]),
+    )
+        .into()
+}
+
+fn state_key(key: u8) -> ContractsStateKey {
+    (&ContractId::default(), &Bytes32::from([key; 32])).into()
+}
+
+fn balance<S: InterpreterStorage>(storage: &S, contract: u8, asset: u8) -> Option<Word> {
+    storage
+        .contract_asset_id_balance(
+            &ContractId::from([contract; 32]),
+            &AssetId::from([asset; 32]),
+        )
+        .unwrap()
+}
+
+fn base() -> MemoryStorage {
+    let mut base = MemoryStorage::default();
+    base.storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1, 1), &10)
+        .unwrap();
+    base.storage_as_mut::<ContractsState>()
+        .insert(&state_key(1), &[1; 32])
+        .unwrap();
+    base
+}
+
+#[test]
+fn get__reads_through_to_the_base_storage() {
+    let mut overlay = OverlayStorage::new(base());
+    assert_eq!(balance(&overlay, 1, 1), Some(10));
+
+    overlay
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1, 1), &20)
+        .unwrap();
+    overlay
+        .storage_as_mut::<ContractsState>()
+        .remove(&state_key(1))
+        .unwrap();
+
+    assert_eq!(balance(&overlay, 1, 1), Some(20));
+    assert!(!overlay
+        .storage::<ContractsState>()
+        .contains_key(&state_key(1))
+        .unwrap());
+    assert_eq!(balance(overlay.base(), 1, 1), Some(10));
+    assert!(overlay
+        .base()
+        .storage::<ContractsState>()
+        .contains_key(&state_key(1))
+        .unwrap());
+}
+
+#[test]
+fn insert__returns_the_previous_value_of_the_base_or_the_overlay() {
+    let mut overlay = OverlayStorage::new(base());
+
+    let previous = overlay
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1, 1), &20)
+        .unwrap();
+    assert_eq!(previous, Some(10));
+    let previous = overlay
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1, 1), &30)
+        .unwrap();
+    assert_eq!(previous, Some(20));
+    let previous = overlay
+        .storage_as_mut::<ContractsAssets>()
+        .remove(&asset_key(1, 1))
+        .unwrap();
+    assert_eq!(previous, Some(30));
+    let previous = overlay
+        .storage_as_mut::<ContractsAssets>()
+        .remove(&asset_key(1, 1))
+        .unwrap();
+    assert_eq!(previous, None);
+}
+
+#[test]
+fn rollback_to__discards_the_changes_after_the_savepoint() {
+    let mut overlay = OverlayStorage::new(base());
+    overlay
+        .contract_asset_id_balance_insert(&[1; 32].into(), &[1; 32].into(), 20)
+        .unwrap();
+
+    let outer = overlay.savepoint();
+    overlay
+        .contract_asset_id_balance_insert(&[1; 32].into(), &[1; 32].into(), 30)
+        .unwrap();
+    let inner = overlay.savepoint();
+    overlay
+        .contract_asset_id_balance_insert(&[1; 32].into(), &[1; 32].into(), 40)
+        .unwrap();
+
+    overlay.rollback_to(inner);
+    assert_eq!(balance(&overlay, 1, 1), Some(30));
+    overlay.rollback_to(outer);
+    assert_eq!(balance(&overlay, 1, 1), Some(20));
+}
+
+#[test]
+fn rollback_to__discards_the_savepoints_created_after_it() {
+    let mut overlay = OverlayStorage::new(base());
+
+    let outer = overlay.savepoint();
+    overlay
+        .contract_asset_id_balance_insert(&[1; 32].into(), &[1; 32].into(), 30)
+        .unwrap();
+    let inner = overlay.savepoint();
+    overlay
+        .contract_asset_id_balance_insert(&[1; 32].into(), &[1; 32].into(), 40)
+        .unwrap();
+
+    overlay.rollback_to(outer);
+    assert_eq!(balance(&overlay, 1, 1), Some(10));
+    overlay.release(inner);
+    assert_eq!(balance(&overlay, 1, 1), Some(10));
+    assert!(overlay.changeset().is_empty());
+}
+
+#[test]
+fn rollback_to__ignores_stale_savepoints() {
+    let mut overlay = OverlayStorage::new(base());
+
+    let stale = overlay.savepoint();
+    overlay.rollback_to(stale);
+    assert!(!overlay.is_open(stale));
+
+    // The new savepoint takes the place of the stale one.
+    let savepoint = overlay.savepoint();
+    overlay
+        .contract_asset_id_balance_insert(&[1; 32].into(), &[1; 32].into(), 30)
+        .unwrap();
+    let _inner = overlay.savepoint();
+    overlay
+        .contract_asset_id_balance_insert(&[1; 32].into(), &[1; 32].into(), 40)
+        .unwrap();
+
+    overlay.rollback_to(stale);
+    overlay.release(stale);
+    assert_eq!(balance(&overlay, 1, 1), Some(40));
+    assert!(overlay.is_open(savepoint));
+
+    overlay.release(savepoint);
+    assert!(!overlay.is_open(savepoint));
+    let stale = savepoint;
+    let savepoint = overlay.savepoint();
+    overlay
+        .contract_asset_id_balance_insert(&[1; 32].into(), &[1; 32].into(), 50)
+        .unwrap();
+    overlay.rollback_to(stale);
+    assert_eq!(balance(&overlay, 1, 1), Some(50));
+    overlay.rollback_to(savepoint);
+    assert_eq!(balance(&overlay, 1, 1), Some(40));
+}
+
+#[test]
+fn release__keeps_the_changes_after_the_savepoint() {
+    let mut overlay = OverlayStorage::new(base());
+
+    let outer = overlay.savepoint();
+    overlay
+        .contract_asset_id_balance_insert(&[1; 32].into(), &[1; 32].into(), 30)
+        .unwrap();
+    let inner = overlay.savepoint();
+    overlay
+        .contract_asset_id_balance_insert(&[1; 32].into(), &[2; 32].into(), 40)
+        .unwrap();
+
+    overlay.release(inner);
+    assert_eq!(balance(&overlay, 1, 1), Some(30));
+    assert_eq!(balance(&overlay, 1, 2), Some(40));
+
+    overlay.rollback_to(outer);
+    assert_eq!(balance(&overlay, 1, 1), Some(10));
+    assert_eq!(balance(&overlay, 1, 2), None);
+}
+
+#[test]
+fn changeset__contains_the_typed_changes_of_each_table() {
+    let mut overlay = OverlayStorage::new(base());
+    overlay
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1, 1), &20)
+        .unwrap();
+    let savepoint = overlay.savepoint();
+    overlay
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1, 1), &30)
+        .unwrap();
+    overlay
+        .storage_as_mut::<ContractsState>()
+        .remove(&state_key(1))
+        .unwrap();
+    overlay
+        .storage_as_mut::<ContractsRawCode>()
+        .insert(&ContractId::from([2; 32]), &[1, 2, 3])
+        .unwrap();
+    overlay
+        .set_consensus_parameters(1, &ConsensusParameters::standard())
+        .unwrap();
+
+    let changeset = overlay.changeset();
+    assert_eq!(
+        changeset.changes::<ContractsAssets>(),
+        &[(asset_key(1, 1), Some(30))].into_iter().collect()
+    );
+    assert_eq!(
+        changeset.changes::<ContractsState>(),
+        &[(state_key(1), None)].into_iter().collect()
+    );
+    assert_eq!(
+        changeset.changes::<ContractsRawCode>(),
+        &[(
+            ContractId::from([2; 32]),
+            Some(Contract::from(vec![1, 2, 3]))
+        )]
+        .into_iter()
+        .collect()
+    );
+    assert!(changeset.changes::<UploadedBytecodes>().is_empty());
+    assert_eq!(changeset.consensus_parameters().len(), 1);
+
+    overlay.rollback_to(savepoint);
+    assert_eq!(
+        overlay.changeset().changes::<ContractsAssets>(),
+        &[(asset_key(1, 1), Some(20))].into_iter().collect()
+    );
+    assert!(overlay.changeset().consensus_parameters().is_empty());
+}
+
+#[test]
+fn flush__writes_the_changes_into_the_base_storage() {
+    let mut overlay = OverlayStorage::new(base());
+    overlay
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1, 1), &20)
+        .unwrap();
+    overlay
+        .storage_as_mut::<ContractsState>()
+        .remove(&state_key(1))
+        .unwrap();
+    let savepoint = overlay.savepoint();
+    overlay
+        .storage_as_mut::<ContractsRawCode>()
+        .insert(&ContractId::from([2; 32]), &[1, 2, 3])
+        .unwrap();
+    overlay
+        .set_state_transition_bytecode(1, &Bytes32::from([3; 32]))
+        .unwrap();
+
+    overlay.flush().unwrap();
+    overlay.rollback_to(savepoint);
+    assert!(overlay.changeset().is_empty());
+
+    let base = overlay.into_inner();
+    assert_eq!(balance(&base, 1, 1), Some(20));
+    assert!(!base
+        .storage::<ContractsState>()
+        .contains_key(&state_key(1))
+        .unwrap());
+    assert!(base
+        .storage::<ContractsRawCode>()
+        .contains_key(&ContractId::from([2; 32]))
+        .unwrap());
+    let mut base = base;
+    assert_eq!(
+        base.set_state_transition_bytecode(1, &Bytes32::from([4; 32])),
+        Ok(Some(Bytes32::from([3; 32])))
+    );
+}
+
+#[test]
+fn rollback__discards_all_the_changes() {
+    let mut overlay = OverlayStorage::new(base());
+    overlay
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1, 1), &20)
+        .unwrap();
+    overlay.savepoint();
+    overlay
+        .storage_as_mut::<ContractsAssets>()
+        .insert(&asset_key(1, 2), &20)
+        .unwrap();
+
+    overlay.rollback();
+
+    assert!(overlay.changeset().is_empty());
+    assert_eq!(balance(&overlay, 1, 1), Some(10));
+    assert_eq!(balance(&overlay, 1, 2), None);
+}
+
+const fn key(k: u8) -> [u8; 32] {
+    [
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, k,
+    ]
+}
+
+#[test_case(&[key(1)], &[], &key(0), 3)]
+#[test_case(&[key(1), key(2)], &[key(2)], &key(1), 2)]
+#[test_case(&[], &[key(5)], &key(4), 3)]
+#[test_case(&[key(1), key(3)], &[key(1), key(2)], &key(0), 4)]
+fn state_range_operations__match_memory_storage(
+    base_keys: &[[u8; 32]],
+    overlay_keys: &[[u8; 32]],
+    start: &[u8; 32],
+    range: usize,
+) {
+    let contract = ContractId::default();
+    let start = Bytes32::from(*start);
+
+    let mut memory = MemoryStorage::default();
+    for k in base_keys {
+        memory
+            .contract_state_insert(&contract, &(*k).into(), &[1; 32])
+            .unwrap();
+    }
+    let mut overlay = OverlayStorage::new(memory.clone());
+    for k in overlay_keys {
+        memory
+            .contract_state_insert(&contract, &(*k).into(), &[2; 32])
+            .unwrap();
+        overlay
+            .contract_state_insert(&contract, &(*k).into(), &[2; 32])
+            .unwrap();
+    }
+
+    let owned = |range: Vec<Option<Cow<ContractsStateData>>>| {
+        range
+            .into_iter()
+            .map(|v| v.map(Cow::into_owned))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        owned(
+            overlay
+                .contract_state_range(&contract, &start, range)
+                .unwrap()
+        ),
+        owned(
+            memory
+                .contract_state_range(&contract, &start, range)
+                .unwrap()
+        )
+    );
+
+    let values = [[3; 32], [4; 32]];
+    assert_eq!(
+        overlay.contract_state_insert_range(
+            &contract,
+            &start,
+            values.iter().map(|v| v.as_slice())
+        ),
+        memory.contract_state_insert_range(
+            &contract,
+            &start,
+            values.iter().map(|v| v.as_slice())
+        )
+    );
+    assert_eq!(
+        owned(
+            overlay
+                .contract_state_range(&contract, &start, range)
+                .unwrap()
+        ),
+        owned(
+            memory
+                .contract_state_range(&contract, &start, range)
+                .unwrap()
+        )
+    );
+
+    let remove_start = Bytes32::from(key(1));
+    assert_eq!(
+        overlay.contract_state_remove_range(&contract, &remove_start, range),
+        memory.contract_state_remove_range(&contract, &remove_start, range)
+    );
+    assert_eq!(
+        owned(
+            overlay
+                .contract_state_range(&contract, &start, range)
+                .unwrap()
+        ),
+        owned(
+            memory
+                .contract_state_range(&contract, &start, range)
+                .unwrap()
+        )
+    );
+}
+
+#[test]
+fn transactor__speculative_deployment_is_discarded() {
+    let consensus_params = ConsensusParameters::standard();
+    let interpreter_params = InterpreterParams::new(0, &consensus_params);
+
+    let deploy = |program: Vec<u8>| {
+        let contract = Contract::from(program.clone());
+        let salt = Salt::zeroed();
+        let state_root = Contract::default_state_root();
+        let contract_id = contract.id(&salt, &contract.root(), &state_root);
+        let tx = TransactionBuilder::create(program.into(), salt, vec![])
+            .add_random_fee_input()
+            .add_output(Output::contract_created(contract_id, state_root))
+            .finalize()
+            .into_checked(Default::default(), &consensus_params)
+            .expect("failed to check tx");
+        (contract_id, tx)
+    };
+    let (kept, kept_tx) = deploy(vec![0x24, 0x04, 0x00, 0x00]);
+    let (discarded, discarded_tx) = deploy(vec![0x24, 0x00, 0x00, 0x00]);
+
+    let mut vm = Transactor::<_, _, Script>::new(
+        MemoryInstance::new(),
+        OverlayStorage::new(MemoryStorage::default()),
+        interpreter_params,
+    );
+    vm.deploy(kept_tx).expect("failed to deploy");
+    let savepoint = AsMut::<OverlayStorage<_>>::as_mut(&mut vm).savepoint();
+    vm.deploy(discarded_tx).expect("failed to deploy");
+    let storage: &mut OverlayStorage<MemoryStorage> = vm.as_mut();
+    assert!(storage.storage_contract_exists(&discarded).unwrap());
+
+    storage.rollback_to(savepoint);
+    storage.flush().unwrap();
+    let base = storage.base();
+    assert!(base.storage_contract_exists(&kept).unwrap());
+    assert!(!base.storage_contract_exists(&discarded).unwrap());
+}
+
+#[test]
+fn contract_state_usage__includes_the_buffered_changes() {
+    let mut overlay = OverlayStorage::new(base());
+    overlay
+        .storage_as_mut::<ContractsState>()
+        .insert(&state_key(2), &[2; 10])
+        .unwrap();
+    let savepoint = overlay.savepoint();
+    overlay
+        .storage_as_mut::<ContractsState>()
+        .remove(&state_key(1))
+        .unwrap();
+    overlay
+        .storage_as_mut::<ContractsState>()
+        .insert(&state_key(2), &[2; 20])
+        .unwrap();
+
+    assert_eq!(
+        overlay
+            .contract_state_usage(&ContractId::default())
+            .unwrap()
+            .unwrap(),
+        ContractStateUsage {
+            slots: 1,
+            bytes: 20
+        }
+    );
+
+    overlay.rollback_to(savepoint);
+    assert_eq!(
+        overlay
+            .contract_state_usage(&ContractId::default())
+            .unwrap()
+            .unwrap(),
+        ContractStateUsage {
+            slots: 2,
+            bytes: 42
+        }
+    );
+}
+
+#[test]
+fn contract_state_page__merges_the_buffered_changes_into_the_base_pages() {
+    let contract = ContractId::from([3; 32]);
+    let slot = |key: u8| Bytes32::from([key; 32]);
+    let mut base = MemoryStorage::default();
+    for key in 1..=6 {
+        base.contract_state_insert(&contract, &slot(key), &[key; 32])
+            .unwrap();
+    }
+
+    let mut overlay = OverlayStorage::new(base);
+    overlay
+        .contract_state_insert(&contract, &slot(2), &[20; 32])
+        .unwrap();
+    overlay
+        .contract_state_insert(&contract, &slot(7), &[7; 32])
+        .unwrap();
+    let _ = overlay.savepoint();
+    for key in [3, 4, 5] {
+        StorageMutate::<ContractsState>::remove(
+            &mut overlay,
+            &(&contract, &slot(key)).into(),
+        )
+        .unwrap();
+    }
+
+    let mut keys = Vec::new();
+    let mut start_key = Bytes32::zeroed();
+    loop {
+        let page = overlay
+            .contract_state_page(&contract, &start_key, 2)
+            .unwrap();
+        assert!(page.entries.len() <= 2);
+        for (key, value) in page.entries {
+            let expected = if key == slot(2) { [20; 32] } else { *key };
+            assert_eq!(value.as_ref(), expected.as_slice());
+            keys.push(key);
+        }
+        match page.next_key {
+            Some(next_key) => start_key = next_key,
+            None => break,
+        }
+    }
+    assert_eq!(keys, vec![slot(1), slot(2), slot(6), slot(7)]);
+}
+
+#[test]
+fn contract_balances_page__merges_the_buffered_changes_into_the_base_pages() {
+    let mut overlay = OverlayStorage::new(base());
+    let contract = ContractId::from([1; 32]);
+    overlay
+        .contract_asset_id_balance_insert(&contract, &AssetId::from([1; 32]), 0)
+        .unwrap();
+    StorageMutate::<ContractsAssets>::remove(&mut overlay, &asset_key(1, 1)).unwrap();
+    overlay
+        .contract_asset_id_balance_insert(&contract, &AssetId::from([2; 32]), 20)
+        .unwrap();
+
+    let page = overlay
+        .contract_balances_page(&contract, &AssetId::zeroed(), 0)
+        .unwrap();
+    assert_eq!(page.entries, vec![]);
+    assert_eq!(page.next_key, Some(AssetId::from([2; 32])));
+
+    let page = overlay
+        .contract_balances_page(&contract, &AssetId::zeroed(), 10)
+        .unwrap();
+    assert_eq!(page.entries, vec![(AssetId::from([2; 32]), 20)]);
+    assert_eq!(page.next_key, None);
+}
@@ -29,6 +29,7 @@ use fuel_types::{
 
 use super::{
     interpreter::ContractsAssetsStorage,
+    ContractStateUsage,
     ContractsRawCode,
     ContractsState,
     ContractsStateData,
@@ -242,6 +243,13 @@ impl InterpreterStorage for PredicateStorage {
         Err(StorageUnavailable)
     }
 
+    fn contract_state_usage(
+        &self,
+        _id: &ContractId,
+    ) -> Result<Option<ContractStateUsage>, StorageUnavailable> {
+        Err(StorageUnavailable)
+    }
+
     fn contract_state_range(
         &self,
         _id: &ContractId,
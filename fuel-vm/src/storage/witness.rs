@@ -0,0 +1,699 @@
+//! Stateless execution against a witness of the accessed storage entries.
+
+use super::{
+    interpreter::ContractsAssetsStorage,
+    memory::state_keys,
+    ContractStateUsage,
+    ContractsAssetKey,
+    ContractsAssets,
+    ContractsRawCode,
+    ContractsState,
+    ContractsStateData,
+    ContractsStateKey,
+    InterpreterStorage,
+    UploadedBytecodes,
+};
+use crate::prelude::{
+    InterpreterError,
+    RuntimeError,
+};
+
+use fuel_merkle::sparse::{
+    in_memory,
+    proof::Proof,
+    MerkleTreeKey,
+};
+use fuel_storage::{
+    Mappable,
+    StorageInspect,
+    StorageMutate,
+    StorageRead,
+    StorageSize,
+    StorageWrite,
+};
+use fuel_tx::{
+    ConsensusParameters,
+    Contract,
+};
+use fuel_types::{
+    AssetId,
+    BlockHeight,
+    Bytes32,
+    ContractId,
+    Word,
+};
+
+use alloc::{
+    borrow::{
+        Cow,
+        ToOwned,
+    },
+    collections::BTreeMap,
+    vec::Vec,
+};
+
+mod recorder;
+
+pub use recorder::{
+    ExecutionWitness,
+    WitnessEntry,
+    WitnessRecordTable,
+    WitnessRecorder,
+};
+
+/// The roots a contract is known to have before the execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContractRoots {
+    /// The [`Contract::root`] of the code.
+    pub code_root: Bytes32,
+    /// The sparse Merkle root of the state slots, see
+    /// [`MerkleStorage`](super::MerkleStorage).
+    pub state_root: Bytes32,
+    /// The sparse Merkle root of the balances, see
+    /// [`MerkleStorage`](super::MerkleStorage).
+    pub balance_root: Bytes32,
+}
+
+/// The failures of the [`WitnessStorage`].
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+pub enum WitnessError {
+    /// The roots of the contract were not added to the witness.
+    #[display(fmt = "The roots of the contract {_0} are unknown")]
+    UnknownContract(ContractId),
+    /// The roots of the contract were already added to the witness, and its entries
+    /// may have been proven against them.
+    #[display(fmt = "The roots of the contract {_0} are already known")]
+    ContractAlreadyKnown(ContractId),
+    /// The code doesn't match the code root of the contract.
+    #[display(fmt = "The code of the contract {_0} doesn't match its root")]
+    InvalidCode(ContractId),
+    /// The proof of a state slot doesn't match the state root of the contract.
+    #[display(fmt = "Invalid proof of the state slot {_0:?}")]
+    InvalidStateProof(ContractsStateKey),
+    /// The proof of a balance doesn't match the balance root of the contract.
+    #[display(fmt = "Invalid proof of the balance {_0:?}")]
+    InvalidBalanceProof(ContractsAssetKey),
+    /// The VM accessed the code of a contract that is not part of the witness.
+    #[display(fmt = "The code of the contract {_0} is not part of the witness")]
+    UnprovenCode(ContractId),
+    /// The VM accessed a state slot that is not part of the witness.
+    #[display(fmt = "The state slot {_0:?} is not part of the witness")]
+    UnprovenState(ContractsStateKey),
+    /// The VM accessed a balance that is not part of the witness.
+    #[display(fmt = "The balance {_0:?} is not part of the witness")]
+    UnprovenBalance(ContractsAssetKey),
+    /// The VM accessed an uploaded bytecode, which is never part of the witness.
+    #[display(fmt = "The uploaded bytecode {_0} is not part of the witness")]
+    UnprovenBytecode(Bytes32),
+    /// The VM accessed the timestamp or the hash of a block that is not part of the
+    /// witness.
+    #[display(fmt = "The block {_0} is not part of the witness")]
+    UnprovenBlock(BlockHeight),
+    /// The VM set the consensus parameters or the state transition bytecode, whose
+    /// previous values are never part of the witness.
+    #[display(fmt = "The previous version {_0} is not part of the witness")]
+    UnprovenVersion(u32),
+    /// The state slots of a contract don't rebuild its state root, so they are not
+    /// all of its slots.
+    #[display(fmt = "The state slots of the contract {_0} don't match its root")]
+    IncompleteState(ContractId),
+    /// The VM accessed the state usage of a contract that is not part of the witness.
+    #[display(fmt = "The state usage of the contract {_0} is not part of the witness")]
+    UnprovenStateUsage(ContractId),
+}
+
+impl From<WitnessError> for InterpreterError<WitnessError> {
+    fn from(val: WitnessError) -> Self {
+        let rt: RuntimeError<WitnessError> = val.into();
+        rt.into()
+    }
+}
+
+impl From<WitnessError> for RuntimeError<WitnessError> {
+    fn from(val: WitnessError) -> Self {
+        RuntimeError::Storage(val)
+    }
+}
+
+/// A storage table whose entries are part of the witness.
+pub trait WitnessTable: Mappable {
+    /// The entries of the table known to the `storage`, `None` if proven unset.
+    fn entries(
+        storage: &WitnessStorage,
+    ) -> &BTreeMap<Self::OwnedKey, Option<Self::OwnedValue>>;
+
+    /// The mutable entries of the table known to the `storage`.
+    fn entries_mut(
+        storage: &mut WitnessStorage,
+    ) -> &mut BTreeMap<Self::OwnedKey, Option<Self::OwnedValue>>;
+
+    /// The error returned when the `key` is not part of the witness.
+    fn unproven(key: &Self::Key) -> WitnessError;
+
+    /// Called after the value of the `key` was replaced by the VM, with its
+    /// `previous` value.
+    fn updated(
+        _storage: &mut WitnessStorage,
+        _key: &Self::OwnedKey,
+        _previous: Option<&Self::OwnedValue>,
+    ) {
+    }
+}
+
+impl WitnessTable for ContractsRawCode {
+    fn entries(storage: &WitnessStorage) -> &BTreeMap<ContractId, Option<Contract>> {
+        &storage.contracts
+    }
+
+    fn entries_mut(
+        storage: &mut WitnessStorage,
+    ) -> &mut BTreeMap<ContractId, Option<Contract>> {
+        &mut storage.contracts
+    }
+
+    fn unproven(key: &ContractId) -> WitnessError {
+        WitnessError::UnprovenCode(*key)
+    }
+}
+
+impl WitnessTable for ContractsState {
+    fn entries(
+        storage: &WitnessStorage,
+    ) -> &BTreeMap<ContractsStateKey, Option<ContractsStateData>> {
+        &storage.contract_state
+    }
+
+    fn entries_mut(
+        storage: &mut WitnessStorage,
+    ) -> &mut BTreeMap<ContractsStateKey, Option<ContractsStateData>> {
+        &mut storage.contract_state
+    }
+
+    fn unproven(key: &ContractsStateKey) -> WitnessError {
+        WitnessError::UnprovenState(*key)
+    }
+
+    fn updated(
+        storage: &mut WitnessStorage,
+        key: &ContractsStateKey,
+        previous: Option<&ContractsStateData>,
+    ) {
+        let value = storage.contract_state.get(key).and_then(Option::as_ref);
+        let [previous, value] =
+            [previous, value].map(|value| value.map(|value| value.as_ref().len()));
+        if let Some(usage) = storage.state_usage.get_mut(key.contract_id()) {
+            usage.update(previous, value);
+        }
+    }
+}
+
+impl WitnessTable for ContractsAssets {
+    fn entries(storage: &WitnessStorage) -> &BTreeMap<ContractsAssetKey, Option<Word>> {
+        &storage.balances
+    }
+
+    fn entries_mut(
+        storage: &mut WitnessStorage,
+    ) -> &mut BTreeMap<ContractsAssetKey, Option<Word>> {
+        &mut storage.balances
+    }
+
+    fn unproven(key: &ContractsAssetKey) -> WitnessError {
+        WitnessError::UnprovenBalance(*key)
+    }
+}
+
+/// The uploaded bytecodes are never part of the witness.
+impl WitnessTable for UploadedBytecodes {
+    fn entries(storage: &WitnessStorage) -> &BTreeMap<Bytes32, Option<Self::OwnedValue>> {
+        &storage.uploaded_bytecodes
+    }
+
+    fn entries_mut(
+        storage: &mut WitnessStorage,
+    ) -> &mut BTreeMap<Bytes32, Option<Self::OwnedValue>> {
+        &mut storage.uploaded_bytecodes
+    }
+
+    fn unproven(key: &Bytes32) -> WitnessError {
+        WitnessError::UnprovenBytecode(*key)
+    }
+}
+
+/// Storage holding only the entries of a witness, for executing a transaction
+/// without the full state.
+///
+/// The contract codes, state slots and balances are added with their proofs against
+/// the [`ContractRoots`] of their contract, which are checked before the entries are
+/// accepted. The VM can then read and write these entries freely, while accessing
+/// any other entry fails with a [`WitnessError`].
+///
+/// The state usage of a contract is proven by adding all of its state slots with
+/// [`WitnessStorage::insert_all_state`].
+///
+/// The timestamps and hashes of the blocks are trusted as given.
+#[derive(Debug, Clone)]
+pub struct WitnessStorage {
+    block_height: BlockHeight,
+    coinbase: ContractId,
+    consensus_parameters_version: u32,
+    state_transition_version: u32,
+    timestamps: BTreeMap<BlockHeight, Word>,
+    block_hashes: BTreeMap<BlockHeight, Bytes32>,
+    roots: BTreeMap<ContractId, ContractRoots>,
+    contracts: BTreeMap<ContractId, Option<Contract>>,
+    contract_state: BTreeMap<ContractsStateKey, Option<ContractsStateData>>,
+    balances: BTreeMap<ContractsAssetKey, Option<Word>>,
+    state_usage: BTreeMap<ContractId, ContractStateUsage>,
+    uploaded_bytecodes:
+        BTreeMap<Bytes32, Option<<UploadedBytecodes as Mappable>::OwnedValue>>,
+}
+
+impl WitnessStorage {
+    /// Create an empty witness for executing at the `block_height`.
+    pub fn new(block_height: BlockHeight, coinbase: ContractId) -> Self {
+        Self {
+            block_height,
+            coinbase,
+            consensus_parameters_version: 0,
+            state_transition_version: 0,
+            timestamps: Default::default(),
+            block_hashes: Default::default(),
+            roots: Default::default(),
+            contracts: Default::default(),
+            contract_state: Default::default(),
+            balances: Default::default(),
+            state_usage: Default::default(),
+            uploaded_bytecodes: Default::default(),
+        }
+    }
+
+    /// Execute with the given versions of the consensus parameters and the state
+    /// transition function.
+    pub fn with_versions(
+        mut self,
+        consensus_parameters_version: u32,
+        state_transition_version: u32,
+    ) -> Self {
+        self.consensus_parameters_version = consensus_parameters_version;
+        self.state_transition_version = state_transition_version;
+        self
+    }
+
+    /// Add the timestamp of the block at `height`.
+    pub fn insert_timestamp(&mut self, height: BlockHeight, timestamp: Word) {
+        self.timestamps.insert(height, timestamp);
+    }
+
+    /// Add the hash of the block at `height`.
+    pub fn insert_block_hash(&mut self, height: BlockHeight, hash: Bytes32) {
+        self.block_hashes.insert(height, hash);
+    }
+
+    /// Add the `roots` of the `contract`, against which its entries are proven, and
+    /// its `code` if it is accessed. The roots of a contract can only be added once.
+    pub fn insert_contract(
+        &mut self,
+        contract: ContractId,
+        roots: ContractRoots,
+        code: Option<Contract>,
+    ) -> Result<(), WitnessError> {
+        if self.roots.contains_key(&contract) {
+            return Err(WitnessError::ContractAlreadyKnown(contract))
+        }
+        if let Some(code) = code {
+            if code.root() != roots.code_root {
+                return Err(WitnessError::InvalidCode(contract))
+            }
+            self.contracts.insert(contract, Some(code));
+        }
+        self.roots.insert(contract, roots);
+        Ok(())
+    }
+
+    /// Add the `value` of the state slot `key` of the `contract`, `None` if unset,
+    /// with an inclusion or exclusion `proof` against its state root.
+    pub fn insert_state(
+        &mut self,
+        contract: &ContractId,
+        key: &Bytes32,
+        value: Option<ContractsStateData>,
+        proof: &Proof,
+    ) -> Result<(), WitnessError> {
+        let roots = self.contract_roots(contract)?;
+        let state_key = (contract, key).into();
+        if !verify(
+            proof,
+            &roots.state_root,
+            key,
+            value.as_ref().map(AsRef::as_ref),
+        ) {
+            return Err(WitnessError::InvalidStateProof(state_key))
+        }
+        self.contract_state.insert(state_key, value);
+        Ok(())
+    }
+
+    /// Add all the set state `slots` of the `contract`, which are proven complete
+    /// by rebuilding its state root from them. This proves the state usage of the
+    /// contract, which the VM then keeps up to date.
+    pub fn insert_all_state<I>(
+        &mut self,
+        contract: &ContractId,
+        slots: I,
+    ) -> Result<(), WitnessError>
+    where
+        I: IntoIterator<Item = (Bytes32, ContractsStateData)>,
+    {
+        let roots = self.contract_roots(contract)?;
+        let slots: BTreeMap<_, _> = slots.into_iter().collect();
+        // Empty values are not part of the tree, so they can't be proven.
+        if let Some(key) = slots
+            .iter()
+            .find_map(|(key, value)| value.as_ref().is_empty().then_some(key))
+        {
+            return Err(WitnessError::InvalidStateProof((contract, key).into()))
+        }
+        let root = in_memory::MerkleTree::root_from_set(
+            slots
+                .iter()
+                .map(|(key, value)| (MerkleTreeKey::new(key), value)),
+        );
+        if Bytes32::from(root) != roots.state_root {
+            return Err(WitnessError::IncompleteState(*contract))
+        }
+
+        let mut usage = ContractStateUsage::default();
+        for (key, value) in slots {
+            usage.update(None, Some(value.as_ref().len()));
+            self.contract_state
+                .insert((contract, &key).into(), Some(value));
+        }
+        self.state_usage.insert(*contract, usage);
+        Ok(())
+    }
+
+    /// Add the balance of the `asset_id` of the `contract`, `None` if unset, with an
+    /// inclusion or exclusion `proof` against its balance root.
+    pub fn insert_balance(
+        &mut self,
+        contract: &ContractId,
+        asset_id: &AssetId,
+        amount: Option<Word>,
+        proof: &Proof,
+    ) -> Result<(), WitnessError> {
+        let roots = self.contract_roots(contract)?;
+        let asset_key = (contract, asset_id).into();
+        let value = amount.map(Word::to_be_bytes);
+        if !verify(
+            proof,
+            &roots.balance_root,
+            asset_id,
+            value.as_ref().map(AsRef::as_ref),
+        ) {
+            return Err(WitnessError::InvalidBalanceProof(asset_key))
+        }
+        self.balances.insert(asset_key, amount);
+        Ok(())
+    }
+
+    /// The roots of the `contract` before the execution.
+    pub fn roots(&self, contract: &ContractId) -> Option<&ContractRoots> {
+        self.roots.get(contract)
+    }
+
+    fn contract_roots(
+        &self,
+        contract: &ContractId,
+    ) -> Result<ContractRoots, WitnessError> {
+        self.roots
+            .get(contract)
+            .copied()
+            .ok_or(WitnessError::UnknownContract(*contract))
+    }
+
+    fn known<Type>(
+        &self,
+        key: &Type::Key,
+    ) -> Result<Option<&Type::OwnedValue>, WitnessError>
+    where
+        Type: WitnessTable,
+        Type::OwnedKey: Ord + 'static,
+    {
+        let owned: Type::OwnedKey = key.to_owned().into();
+        Type::entries(self)
+            .get(&owned)
+            .map(Option::as_ref)
+            .ok_or_else(|| Type::unproven(key))
+    }
+
+    fn set<Type>(
+        &mut self,
+        key: &Type::Key,
+        value: Option<Type::OwnedValue>,
+    ) -> Result<Option<Type::OwnedValue>, WitnessError>
+    where
+        Type: WitnessTable,
+        Type::OwnedKey: Ord + 'static,
+    {
+        let owned: Type::OwnedKey = key.to_owned().into();
+        let previous = match Type::entries_mut(self).get_mut(&owned) {
+            Some(entry) => core::mem::replace(entry, value),
+            None => return Err(Type::unproven(key)),
+        };
+        Type::updated(self, &owned, previous.as_ref());
+        Ok(previous)
+    }
+}
+
+fn verify(
+    proof: &Proof,
+    root: &Bytes32,
+    key: impl AsRef<[u8]>,
+    value: Option<&[u8]>,
+) -> bool {
+    let key = MerkleTreeKey::new(key);
+    match (proof, value) {
+        (Proof::Inclusion(proof), Some(value)) if !value.is_empty() => {
+            proof.verify(root, &key, value)
+        }
+        (Proof::Exclusion(proof), None) => proof.verify(root, &key),
+        _ => false,
+    }
+}
+
+impl<Type> StorageInspect<Type> for WitnessStorage
+where
+    Type: WitnessTable,
+    Type::OwnedKey: Ord + 'static,
+{
+    type Error = WitnessError;
+
+    fn get(
+        &self,
+        key: &Type::Key,
+    ) -> Result<Option<Cow<'_, Type::OwnedValue>>, WitnessError> {
+        Ok(self.known::<Type>(key)?.map(Cow::Borrowed))
+    }
+
+    fn contains_key(&self, key: &Type::Key) -> Result<bool, WitnessError> {
+        Ok(self.known::<Type>(key)?.is_some())
+    }
+}
+
+impl<Type> StorageMutate<Type> for WitnessStorage
+where
+    Type: WitnessTable,
+    Type::OwnedKey: Ord + 'static,
+{
+    fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::Value,
+    ) -> Result<Option<Type::OwnedValue>, WitnessError> {
+        self.set::<Type>(key, Some(value.to_owned().into()))
+    }
+
+    fn remove(
+        &mut self,
+        key: &Type::Key,
+    ) -> Result<Option<Type::OwnedValue>, WitnessError> {
+        self.set::<Type>(key, None)
+    }
+}
+
+impl<Type> StorageSize<Type> for WitnessStorage
+where
+    Type: WitnessTable,
+    Type::OwnedKey: Ord + 'static,
+    Type::OwnedValue: AsRef<[u8]>,
+{
+    fn size_of_value(&self, key: &Type::Key) -> Result<Option<usize>, WitnessError> {
+        Ok(self.known::<Type>(key)?.map(|value| value.as_ref().len()))
+    }
+}
+
+impl<Type> StorageRead<Type> for WitnessStorage
+where
+    Type: WitnessTable,
+    Type::OwnedKey: Ord + 'static,
+    Type::OwnedValue: AsRef<[u8]>,
+{
+    fn read(
+        &self,
+        key: &Type::Key,
+        buf: &mut [u8],
+    ) -> Result<Option<usize>, WitnessError> {
+        Ok(self.known::<Type>(key)?.map(|value| {
+            let value = value.as_ref();
+            let len = buf.len().min(value.len());
+            buf[..len].copy_from_slice(&value[..len]);
+            len
+        }))
+    }
+
+    fn read_alloc(&self, key: &Type::Key) -> Result<Option<Vec<u8>>, WitnessError> {
+        Ok(self
+            .known::<Type>(key)?
+            .map(|value| value.as_ref().to_vec()))
+    }
+}
+
+impl<Type> StorageWrite<Type> for WitnessStorage
+where
+    Type: WitnessTable,
+    Type::OwnedKey: Ord + 'static,
+    Type::OwnedValue: AsRef<[u8]> + From<Vec<u8>>,
+{
+    fn write(&mut self, key: &Type::Key, buf: &[u8]) -> Result<usize, WitnessError> {
+        self.set::<Type>(key, Some(buf.to_vec().into()))?;
+        Ok(buf.len())
+    }
+
+    fn replace(
+        &mut self,
+        key: &Type::Key,
+        buf: &[u8],
+    ) -> Result<(usize, Option<Vec<u8>>), WitnessError> {
+        let existing = self.set::<Type>(key, Some(buf.to_vec().into()))?;
+        Ok((buf.len(), existing.map(|value| value.as_ref().to_vec())))
+    }
+
+    fn take(&mut self, key: &Type::Key) -> Result<Option<Vec<u8>>, WitnessError> {
+        let existing = self.set::<Type>(key, None)?;
+        Ok(existing.map(|value| value.as_ref().to_vec()))
+    }
+}
+
+impl ContractsAssetsStorage for WitnessStorage {}
+
+impl InterpreterStorage for WitnessStorage {
+    type DataError = WitnessError;
+
+    fn block_height(&self) -> Result<BlockHeight, WitnessError> {
+        Ok(self.block_height)
+    }
+
+    fn consensus_parameters_version(&self) -> Result<u32, WitnessError> {
+        Ok(self.consensus_parameters_version)
+    }
+
+    fn state_transition_version(&self) -> Result<u32, WitnessError> {
+        Ok(self.state_transition_version)
+    }
+
+    fn timestamp(&self, height: BlockHeight) -> Result<Word, WitnessError> {
+        self.timestamps
+            .get(&height)
+            .copied()
+            .ok_or(WitnessError::UnprovenBlock(height))
+    }
+
+    fn block_hash(&self, block_height: BlockHeight) -> Result<Bytes32, WitnessError> {
+        self.block_hashes
+            .get(&block_height)
+            .copied()
+            .ok_or(WitnessError::UnprovenBlock(block_height))
+    }
+
+    fn coinbase(&self) -> Result<ContractId, WitnessError> {
+        Ok(self.coinbase)
+    }
+
+    fn set_consensus_parameters(
+        &mut self,
+        version: u32,
+        _: &ConsensusParameters,
+    ) -> Result<Option<ConsensusParameters>, WitnessError> {
+        Err(WitnessError::UnprovenVersion(version))
+    }
+
+    fn set_state_transition_bytecode(
+        &mut self,
+        version: u32,
+        _: &Bytes32,
+    ) -> Result<Option<Bytes32>, WitnessError> {
+        Err(WitnessError::UnprovenVersion(version))
+    }
+
+    fn contract_state_usage(
+        &self,
+        id: &ContractId,
+    ) -> Result<Option<ContractStateUsage>, WitnessError> {
+        self.state_usage
+            .get(id)
+            .copied()
+            .map(Some)
+            .ok_or(WitnessError::UnprovenStateUsage(*id))
+    }
+
+    fn contract_state_range(
+        &self,
+        id: &ContractId,
+        start_key: &Bytes32,
+        range: usize,
+    ) -> Result<Vec<Option<Cow<'_, ContractsStateData>>>, WitnessError> {
+        state_keys(id, start_key)
+            .take(range)
+            .map(|key| StorageInspect::<ContractsState>::get(self, &key))
+            .collect()
+    }
+
+    fn contract_state_insert_range<'a, I>(
+        &mut self,
+        contract: &ContractId,
+        start_key: &Bytes32,
+        values: I,
+    ) -> Result<usize, WitnessError>
+    where
+        I: Iterator<Item = &'a [u8]>,
+    {
+        let mut unset_count: usize = 0;
+        for (key, value) in state_keys(contract, start_key).zip(values) {
+            if StorageWrite::<ContractsState>::replace(self, &key, value)?
+                .1
+                .is_none()
+            {
+                unset_count = unset_count.saturating_add(1);
+            }
+        }
+        Ok(unset_count)
+    }
+
+    fn contract_state_remove_range(
+        &mut self,
+        contract: &ContractId,
+        start_key: &Bytes32,
+        range: usize,
+    ) -> Result<Option<()>, WitnessError> {
+        let mut all_set_key = true;
+        for key in state_keys(contract, start_key).take(range) {
+            all_set_key &= StorageMutate::<ContractsState>::remove(self, &key)?.is_some();
+        }
+        Ok(all_set_key.then_some(()))
+    }
+}
+
+#[cfg(test)]
+mod tests;
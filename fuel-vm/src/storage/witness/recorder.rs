@@ -0,0 +1,646 @@
+//! Recording of the execution witness of transactions.
+
+use super::WitnessStorage;
+use crate::{
+    interpreter::InterpreterParams,
+    storage::{
+        interpreter::ContractsAssetsStorage,
+        memory::state_keys,
+        ContractStateUsage,
+        ContractsAssetKey,
+        ContractsAssets,
+        ContractsRawCode,
+        ContractsState,
+        ContractsStateData,
+        ContractsStateKey,
+        InterpreterStorage,
+        UploadedBytecodes,
+    },
+};
+
+use fuel_storage::{
+    Mappable,
+    StorageInspect,
+    StorageMutate,
+    StorageRead,
+    StorageSize,
+    StorageWrite,
+};
+use fuel_tx::{
+    ConsensusParameters,
+    Contract,
+};
+use fuel_types::{
+    canonical::{
+        Deserialize,
+        Serialize,
+    },
+    AssetId,
+    BlockHeight,
+    Bytes32,
+    ContractId,
+    Word,
+};
+
+use alloc::{
+    borrow::Cow,
+    collections::BTreeMap,
+    vec::Vec,
+};
+use core::cell::RefCell;
+
+/// A value read from the storage during the execution.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WitnessEntry {
+    /// The code of a contract.
+    Contract {
+        /// The id of the contract.
+        contract_id: ContractId,
+        /// The bytecode of the contract.
+        code: Vec<u8>,
+    },
+    /// A contract without code.
+    MissingContract {
+        /// The id of the contract.
+        contract_id: ContractId,
+    },
+    /// A set state slot.
+    State {
+        /// The contract owning the slot.
+        contract_id: ContractId,
+        /// The key of the slot.
+        key: Bytes32,
+        /// The value of the slot.
+        value: Vec<u8>,
+    },
+    /// An unset state slot.
+    UnsetState {
+        /// The contract owning the slot.
+        contract_id: ContractId,
+        /// The key of the slot.
+        key: Bytes32,
+    },
+    /// The state usage of a contract.
+    StateUsage {
+        /// The id of the contract.
+        contract_id: ContractId,
+        /// The number of set state slots.
+        slots: Word,
+        /// The total size of the values of the slots in bytes.
+        bytes: Word,
+    },
+    /// A set balance.
+    Balance {
+        /// The contract owning the balance.
+        contract_id: ContractId,
+        /// The asset of the balance.
+        asset_id: AssetId,
+        /// The amount of the asset.
+        amount: Word,
+    },
+    /// An unset balance.
+    UnsetBalance {
+        /// The contract owning the balance.
+        contract_id: ContractId,
+        /// The asset of the balance.
+        asset_id: AssetId,
+    },
+    /// The timestamp of a block.
+    Timestamp {
+        /// The height of the block.
+        height: BlockHeight,
+        /// The timestamp of the block.
+        timestamp: Word,
+    },
+    /// The hash of a block.
+    BlockHash {
+        /// The height of the block.
+        height: BlockHeight,
+        /// The hash of the block.
+        hash: Bytes32,
+    },
+}
+
+/// Every storage value read by an execution, sufficient to replay it with a
+/// [`WitnessStorage`].
+///
+/// The values are the ones before the execution, and the entries are sorted, so the
+/// canonical encoding of the witness of an execution is deterministic.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExecutionWitness {
+    /// The height of the block of the execution.
+    pub block_height: BlockHeight,
+    /// The coinbase contract of the block.
+    pub coinbase: ContractId,
+    /// The version of the consensus parameters.
+    pub consensus_parameters_version: u32,
+    /// The version of the state transition function.
+    pub state_transition_version: u32,
+    /// The parameters of the interpreter, including the gas costs and the limits of
+    /// the consensus parameters.
+    pub interpreter_params: InterpreterParams,
+    /// The read values.
+    pub entries: Vec<WitnessEntry>,
+}
+
+/// Trusts the values of the `witness`, which are not proven against any root. The
+/// interpreter parameters of the witness are not part of the storage, see
+/// [`Transactor::from_witness`](crate::transactor::Transactor::from_witness).
+impl From<ExecutionWitness> for WitnessStorage {
+    fn from(witness: ExecutionWitness) -> Self {
+        let mut storage = WitnessStorage::new(witness.block_height, witness.coinbase)
+            .with_versions(
+                witness.consensus_parameters_version,
+                witness.state_transition_version,
+            );
+        for entry in witness.entries {
+            match entry {
+                WitnessEntry::Contract { contract_id, code } => {
+                    storage.contracts.insert(contract_id, Some(code.into()));
+                }
+                WitnessEntry::MissingContract { contract_id } => {
+                    storage.contracts.insert(contract_id, None);
+                }
+                WitnessEntry::State {
+                    contract_id,
+                    key,
+                    value,
+                } => {
+                    storage
+                        .contract_state
+                        .insert((&contract_id, &key).into(), Some(value.into()));
+                }
+                WitnessEntry::UnsetState { contract_id, key } => {
+                    storage
+                        .contract_state
+                        .insert((&contract_id, &key).into(), None);
+                }
+                WitnessEntry::StateUsage {
+                    contract_id,
+                    slots,
+                    bytes,
+                } => {
+                    storage
+                        .state_usage
+                        .insert(contract_id, ContractStateUsage { slots, bytes });
+                }
+                WitnessEntry::Balance {
+                    contract_id,
+                    asset_id,
+                    amount,
+                } => {
+                    storage
+                        .balances
+                        .insert((&contract_id, &asset_id).into(), Some(amount));
+                }
+                WitnessEntry::UnsetBalance {
+                    contract_id,
+                    asset_id,
+                } => {
+                    storage
+                        .balances
+                        .insert((&contract_id, &asset_id).into(), None);
+                }
+                WitnessEntry::Timestamp { height, timestamp } => {
+                    storage.insert_timestamp(height, timestamp);
+                }
+                WitnessEntry::BlockHash { height, hash } => {
+                    storage.insert_block_hash(height, hash);
+                }
+            }
+        }
+        storage
+    }
+}
+
+/// The values read so far, before any write.
+#[derive(Debug, Default, Clone)]
+struct Recorded {
+    contracts: BTreeMap<ContractId, Option<Contract>>,
+    contract_state: BTreeMap<ContractsStateKey, Option<ContractsStateData>>,
+    state_usage: BTreeMap<ContractId, ContractStateUsage>,
+    balances: BTreeMap<ContractsAssetKey, Option<Word>>,
+    timestamps: BTreeMap<BlockHeight, Word>,
+    block_hashes: BTreeMap<BlockHeight, Bytes32>,
+}
+
+impl Recorded {
+    fn into_entries(self) -> Vec<WitnessEntry> {
+        let contracts =
+            self.contracts
+                .into_iter()
+                .map(|(contract_id, code)| match code {
+                    Some(code) => WitnessEntry::Contract {
+                        contract_id,
+                        code: code.into(),
+                    },
+                    None => WitnessEntry::MissingContract { contract_id },
+                });
+        let contract_state = self.contract_state.into_iter().map(|(key, value)| {
+            let contract_id = *key.contract_id();
+            let key = *key.state_key();
+            match value {
+                Some(value) => WitnessEntry::State {
+                    contract_id,
+                    key,
+                    value: value.into(),
+                },
+                None => WitnessEntry::UnsetState { contract_id, key },
+            }
+        });
+        let state_usage = self.state_usage.into_iter().map(|(contract_id, usage)| {
+            WitnessEntry::StateUsage {
+                contract_id,
+                slots: usage.slots,
+                bytes: usage.bytes,
+            }
+        });
+        let balances = self.balances.into_iter().map(|(key, amount)| {
+            let contract_id = *key.contract_id();
+            let asset_id = *key.asset_id();
+            match amount {
+                Some(amount) => WitnessEntry::Balance {
+                    contract_id,
+                    asset_id,
+                    amount,
+                },
+                None => WitnessEntry::UnsetBalance {
+                    contract_id,
+                    asset_id,
+                },
+            }
+        });
+        let timestamps = self
+            .timestamps
+            .into_iter()
+            .map(|(height, timestamp)| WitnessEntry::Timestamp { height, timestamp });
+        let block_hashes = self
+            .block_hashes
+            .into_iter()
+            .map(|(height, hash)| WitnessEntry::BlockHash { height, hash });
+
+        contracts
+            .chain(contract_state)
+            .chain(state_usage)
+            .chain(balances)
+            .chain(timestamps)
+            .chain(block_hashes)
+            .collect()
+    }
+}
+
+/// A storage table whose values are recorded by the [`WitnessRecorder`].
+pub trait WitnessRecordTable: Mappable {
+    /// Record the `value` of the `key`, unless a value was recorded already.
+    fn record<S>(
+        recorder: &WitnessRecorder<S>,
+        key: &Self::Key,
+        value: Option<&Self::OwnedValue>,
+    );
+}
+
+impl WitnessRecordTable for ContractsRawCode {
+    fn record<S>(
+        recorder: &WitnessRecorder<S>,
+        key: &ContractId,
+        value: Option<&Contract>,
+    ) {
+        recorder
+            .recorded
+            .borrow_mut()
+            .contracts
+            .entry(*key)
+            .or_insert_with(|| value.cloned());
+    }
+}
+
+impl WitnessRecordTable for ContractsState {
+    fn record<S>(
+        recorder: &WitnessRecorder<S>,
+        key: &ContractsStateKey,
+        value: Option<&ContractsStateData>,
+    ) {
+        recorder
+            .recorded
+            .borrow_mut()
+            .contract_state
+            .entry(*key)
+            .or_insert_with(|| value.cloned());
+    }
+}
+
+impl WitnessRecordTable for ContractsAssets {
+    fn record<S>(
+        recorder: &WitnessRecorder<S>,
+        key: &ContractsAssetKey,
+        value: Option<&Word>,
+    ) {
+        recorder
+            .recorded
+            .borrow_mut()
+            .balances
+            .entry(*key)
+            .or_insert_with(|| value.copied());
+    }
+}
+
+/// The uploaded bytecodes are not part of the witness.
+impl WitnessRecordTable for UploadedBytecodes {
+    fn record<S>(_: &WitnessRecorder<S>, _: &Bytes32, _: Option<&Self::OwnedValue>) {}
+}
+
+/// Storage wrapper recording the values read through it into an
+/// [`ExecutionWitness`].
+///
+/// Every contract code, state slot, state usage, balance, block timestamp and block
+/// hash observed by the VM is recorded with its value before the first write,
+/// including the values only overwritten. The recorded values accumulate until
+/// [`WitnessRecorder::take_witness`] is called.
+#[derive(Debug)]
+pub struct WitnessRecorder<S> {
+    storage: S,
+    recorded: RefCell<Recorded>,
+}
+
+impl<S> WitnessRecorder<S> {
+    /// Record the values read from the `storage`.
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            recorded: Default::default(),
+        }
+    }
+
+    /// The underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Stop recording and return the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+
+    fn record<Type: WitnessRecordTable>(
+        &self,
+        key: &Type::Key,
+        value: Option<&Type::OwnedValue>,
+    ) {
+        Type::record(self, key, value)
+    }
+}
+
+impl<S: InterpreterStorage> WitnessRecorder<S> {
+    /// The witness of the values read so far by executions with the
+    /// `interpreter_params`.
+    pub fn witness(
+        &self,
+        interpreter_params: &InterpreterParams,
+    ) -> Result<ExecutionWitness, S::DataError> {
+        self.build_witness(self.recorded.borrow().clone(), interpreter_params)
+    }
+
+    /// Return the witness of the values read so far by executions with the
+    /// `interpreter_params` and start a new recording.
+    pub fn take_witness(
+        &mut self,
+        interpreter_params: &InterpreterParams,
+    ) -> Result<ExecutionWitness, S::DataError> {
+        let recorded = core::mem::take(self.recorded.get_mut());
+        self.build_witness(recorded, interpreter_params)
+    }
+
+    fn build_witness(
+        &self,
+        recorded: Recorded,
+        interpreter_params: &InterpreterParams,
+    ) -> Result<ExecutionWitness, S::DataError> {
+        Ok(ExecutionWitness {
+            block_height: self.storage.block_height()?,
+            coinbase: self.storage.coinbase()?,
+            consensus_parameters_version: self.storage.consensus_parameters_version()?,
+            state_transition_version: self.storage.state_transition_version()?,
+            interpreter_params: interpreter_params.clone(),
+            entries: recorded.into_entries(),
+        })
+    }
+}
+
+impl<Type, S> StorageInspect<Type> for WitnessRecorder<S>
+where
+    Type: WitnessRecordTable,
+    S: StorageInspect<Type>,
+{
+    type Error = S::Error;
+
+    fn get(
+        &self,
+        key: &Type::Key,
+    ) -> Result<Option<Cow<'_, Type::OwnedValue>>, S::Error> {
+        let value = self.storage.get(key)?;
+        self.record::<Type>(key, value.as_deref());
+        Ok(value)
+    }
+
+    fn contains_key(&self, key: &Type::Key) -> Result<bool, S::Error> {
+        Ok(self.get(key)?.is_some())
+    }
+}
+
+impl<Type, S> StorageMutate<Type> for WitnessRecorder<S>
+where
+    Type: WitnessRecordTable,
+    S: StorageMutate<Type>,
+{
+    fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::Value,
+    ) -> Result<Option<Type::OwnedValue>, S::Error> {
+        let existing = self.storage.insert(key, value)?;
+        self.record::<Type>(key, existing.as_ref());
+        Ok(existing)
+    }
+
+    fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::OwnedValue>, S::Error> {
+        let existing = self.storage.remove(key)?;
+        self.record::<Type>(key, existing.as_ref());
+        Ok(existing)
+    }
+}
+
+impl<Type, S> StorageSize<Type> for WitnessRecorder<S>
+where
+    Type: WitnessRecordTable,
+    S: StorageSize<Type>,
+{
+    fn size_of_value(&self, key: &Type::Key) -> Result<Option<usize>, S::Error> {
+        self.get(key)?;
+        self.storage.size_of_value(key)
+    }
+}
+
+impl<Type, S> StorageRead<Type> for WitnessRecorder<S>
+where
+    Type: WitnessRecordTable,
+    S: StorageRead<Type>,
+{
+    fn read(&self, key: &Type::Key, buf: &mut [u8]) -> Result<Option<usize>, S::Error> {
+        self.get(key)?;
+        self.storage.read(key, buf)
+    }
+
+    fn read_alloc(&self, key: &Type::Key) -> Result<Option<Vec<u8>>, S::Error> {
+        self.get(key)?;
+        self.storage.read_alloc(key)
+    }
+}
+
+impl<Type, S> StorageWrite<Type> for WitnessRecorder<S>
+where
+    Type: WitnessRecordTable,
+    Type::OwnedValue: From<Vec<u8>>,
+    S: StorageWrite<Type>,
+{
+    fn write(&mut self, key: &Type::Key, buf: &[u8]) -> Result<usize, S::Error> {
+        self.replace(key, buf).map(|(written, _)| written)
+    }
+
+    fn replace(
+        &mut self,
+        key: &Type::Key,
+        buf: &[u8],
+    ) -> Result<(usize, Option<Vec<u8>>), S::Error> {
+        let (written, existing) = self.storage.replace(key, buf)?;
+        self.record::<Type>(key, existing.clone().map(Into::into).as_ref());
+        Ok((written, existing))
+    }
+
+    fn take(&mut self, key: &Type::Key) -> Result<Option<Vec<u8>>, S::Error> {
+        let existing = self.storage.take(key)?;
+        self.record::<Type>(key, existing.clone().map(Into::into).as_ref());
+        Ok(existing)
+    }
+}
+
+impl<S: InterpreterStorage> ContractsAssetsStorage for WitnessRecorder<S> {}
+
+impl<S> InterpreterStorage for WitnessRecorder<S>
+where
+    S: InterpreterStorage,
+{
+    type DataError = S::DataError;
+
+    fn block_height(&self) -> Result<BlockHeight, Self::DataError> {
+        self.storage.block_height()
+    }
+
+    fn consensus_parameters_version(&self) -> Result<u32, Self::DataError> {
+        self.storage.consensus_parameters_version()
+    }
+
+    fn state_transition_version(&self) -> Result<u32, Self::DataError> {
+        self.storage.state_transition_version()
+    }
+
+    fn timestamp(&self, height: BlockHeight) -> Result<Word, Self::DataError> {
+        let timestamp = self.storage.timestamp(height)?;
+        self.recorded
+            .borrow_mut()
+            .timestamps
+            .insert(height, timestamp);
+        Ok(timestamp)
+    }
+
+    fn block_hash(&self, block_height: BlockHeight) -> Result<Bytes32, Self::DataError> {
+        let hash = self.storage.block_hash(block_height)?;
+        self.recorded
+            .borrow_mut()
+            .block_hashes
+            .insert(block_height, hash);
+        Ok(hash)
+    }
+
+    fn coinbase(&self) -> Result<ContractId, Self::DataError> {
+        self.storage.coinbase()
+    }
+
+    fn set_consensus_parameters(
+        &mut self,
+        version: u32,
+        consensus_parameters: &ConsensusParameters,
+    ) -> Result<Option<ConsensusParameters>, Self::DataError> {
+        self.storage
+            .set_consensus_parameters(version, consensus_parameters)
+    }
+
+    fn set_state_transition_bytecode(
+        &mut self,
+        version: u32,
+        hash: &Bytes32,
+    ) -> Result<Option<Bytes32>, Self::DataError> {
+        self.storage.set_state_transition_bytecode(version, hash)
+    }
+
+    fn contract_state_usage(
+        &self,
+        id: &ContractId,
+    ) -> Result<Option<ContractStateUsage>, Self::DataError> {
+        let usage = self.storage.contract_state_usage(id)?;
+        let mut recorded = self.recorded.borrow_mut();
+        if let Some(usage) = usage.filter(|_| !recorded.state_usage.contains_key(id)) {
+            // The usage before the execution, without the writes to the recorded slots.
+            let start: ContractsStateKey = (id, &Bytes32::zeroed()).into();
+            let end: ContractsStateKey = (id, &Bytes32::new([u8::MAX; 32])).into();
+            let mut before = usage;
+            for (key, value) in recorded.contract_state.range(start..=end) {
+                let current =
+                    StorageSize::<ContractsState>::size_of_value(&self.storage, key)?;
+                before.update(current, value.as_ref().map(|value| value.as_ref().len()));
+            }
+            recorded.state_usage.insert(*id, before);
+        }
+        Ok(usage)
+    }
+
+    fn contract_state_range(
+        &self,
+        id: &ContractId,
+        start_key: &Bytes32,
+        range: usize,
+    ) -> Result<Vec<Option<Cow<'_, ContractsStateData>>>, Self::DataError> {
+        let values = self.storage.contract_state_range(id, start_key, range)?;
+        for (key, value) in state_keys(id, start_key).zip(&values) {
+            self.record::<ContractsState>(&key, value.as_deref());
+        }
+        Ok(values)
+    }
+
+    fn contract_state_insert_range<'a, I>(
+        &mut self,
+        contract: &ContractId,
+        start_key: &Bytes32,
+        values: I,
+    ) -> Result<usize, Self::DataError>
+    where
+        I: Iterator<Item = &'a [u8]>,
+    {
+        let values: Vec<_> = values.collect();
+        self.contract_state_range(contract, start_key, values.len())?;
+        self.storage
+            .contract_state_insert_range(contract, start_key, values.into_iter())
+    }
+
+    fn contract_state_remove_range(
+        &mut self,
+        contract: &ContractId,
+        start_key: &Bytes32,
+        range: usize,
+    ) -> Result<Option<()>, Self::DataError> {
+        self.contract_state_range(contract, start_key, range)?;
+        self.storage
+            .contract_state_remove_range(contract, start_key, range)
+    }
+}
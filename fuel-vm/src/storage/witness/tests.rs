@@ -0,0 +1,449 @@
+#![allow(non_snake_case)]
+
+use super::*;
+use crate::{
+    interpreter::{
+        InterpreterParams,
+        MemoryInstance,
+    },
+    storage::{
+        MemoryStorage,
+        MerkleStorage,
+    },
+    tests::test_helpers::{
+        call_counter,
+        contract_id,
+        counter,
+        counter_value,
+    },
+    transactor::Transactor,
+};
+
+use fuel_asm::{
+    op,
+    RegId,
+};
+use fuel_tx::{
+    GasCosts,
+    Receipt,
+    Script,
+};
+use fuel_types::canonical::{
+    Deserialize,
+    Serialize,
+};
+
+const COUNTER: Word = 41;
+
+/// The full storage with the counter contract and another unrelated slot and balance.
+fn full_storage() -> MerkleStorage<MemoryStorage> {
+    let mut storage = MerkleStorage::new(MemoryStorage::default());
+    storage
+        .storage_contract_insert(&contract_id(), &counter())
+        .unwrap();
+    storage
+        .contract_state_insert(
+            &contract_id(),
+            &Bytes32::zeroed(),
+            counter_value(COUNTER).as_ref(),
+        )
+        .unwrap();
+    storage
+        .contract_state_insert(&contract_id(), &[1; 32].into(), &[1; 32])
+        .unwrap();
+    storage
+        .contract_asset_id_balance_insert(&contract_id(), &AssetId::from([1; 32]), 10)
+        .unwrap();
+    storage
+}
+
+fn roots(storage: &MerkleStorage<MemoryStorage>) -> ContractRoots {
+    ContractRoots {
+        code_root: counter().root(),
+        state_root: storage.state_root(&contract_id()),
+        balance_root: storage.balance_root(&contract_id()),
+    }
+}
+
+/// The witness of the counter slot and the balance written by the call.
+fn witness(full: &MerkleStorage<MemoryStorage>) -> WitnessStorage {
+    let mut witness = WitnessStorage::new(Default::default(), Default::default());
+    witness
+        .insert_contract(contract_id(), roots(full), Some(counter()))
+        .unwrap();
+    witness
+        .insert_state(
+            &contract_id(),
+            &Bytes32::zeroed(),
+            Some(counter_value(COUNTER)),
+            &full.state_proof(&contract_id(), &Bytes32::zeroed()),
+        )
+        .unwrap();
+    witness
+        .insert_balance(
+            &contract_id(),
+            &AssetId::zeroed(),
+            None,
+            &full.balance_proof(&contract_id(), &AssetId::zeroed()),
+        )
+        .unwrap();
+    witness
+}
+
+fn params() -> InterpreterParams {
+    InterpreterParams::new(0, &ConsensusParameters::standard())
+}
+
+fn transact<S: InterpreterStorage>(storage: S) -> Transactor<MemoryInstance, S, Script> {
+    execute(Transactor::new(MemoryInstance::new(), storage, params()))
+}
+
+fn execute<S: InterpreterStorage>(
+    mut transactor: Transactor<MemoryInstance, S, Script>,
+) -> Transactor<MemoryInstance, S, Script> {
+    transactor.transact(call_counter([op::ret(RegId::ONE)]));
+    transactor
+}
+
+#[test]
+fn execution__matches_the_execution_on_the_full_storage() {
+    let full = full_storage();
+    let witness = witness(&full);
+
+    let expected = transact(full);
+    let transactor = transact(witness);
+
+    let expected = expected.receipts().expect("failed to execute");
+    assert_eq!(transactor.receipts().expect("failed to execute"), expected);
+    assert!(matches!(
+        expected[1],
+        Receipt::Log { ra, .. } if ra == COUNTER + 1
+    ));
+    assert_eq!(
+        AsRef::<WitnessStorage>::as_ref(&transactor)
+            .contract_state(&contract_id(), &Bytes32::zeroed())
+            .unwrap()
+            .unwrap()
+            .as_ref(),
+        &counter_value(COUNTER + 1)
+    );
+}
+
+#[test]
+fn execution__fails_when_accessing_an_unproven_key() {
+    let full = full_storage();
+    let mut witness = WitnessStorage::new(Default::default(), Default::default());
+    witness
+        .insert_contract(contract_id(), roots(&full), Some(counter()))
+        .unwrap();
+    witness
+        .insert_balance(
+            &contract_id(),
+            &AssetId::zeroed(),
+            None,
+            &full.balance_proof(&contract_id(), &AssetId::zeroed()),
+        )
+        .unwrap();
+
+    let transactor = transact(witness);
+
+    let state_key = (&contract_id(), &Bytes32::zeroed()).into();
+    assert!(matches!(
+        transactor.error(),
+        Some(InterpreterError::Storage(WitnessError::UnprovenState(key))) if *key == state_key
+    ));
+}
+
+#[test]
+fn storage__fails_for_entries_outside_of_the_witness() {
+    let witness = witness(&full_storage());
+
+    assert_eq!(
+        witness.contract_state(&contract_id(), &[1; 32].into()),
+        Err(WitnessError::UnprovenState(
+            (&contract_id(), &[1; 32].into()).into()
+        ))
+    );
+    assert_eq!(
+        witness.contract_asset_id_balance(&contract_id(), &AssetId::from([1; 32])),
+        Err(WitnessError::UnprovenBalance(
+            (&contract_id(), &AssetId::from([1; 32])).into()
+        ))
+    );
+    assert_eq!(
+        witness.storage_contract_exists(&ContractId::from([8; 32])),
+        Err(WitnessError::UnprovenCode(ContractId::from([8; 32])))
+    );
+    assert_eq!(
+        witness.block_hash(1.into()),
+        Err(WitnessError::UnprovenBlock(1.into()))
+    );
+}
+
+#[test]
+fn insert_state__rejects_invalid_proofs() {
+    let full = full_storage();
+    let mut witness = WitnessStorage::new(Default::default(), Default::default());
+    let proof = full.state_proof(&contract_id(), &Bytes32::zeroed());
+    let state_key = (&contract_id(), &Bytes32::zeroed()).into();
+
+    assert_eq!(
+        witness.insert_state(&contract_id(), &Bytes32::zeroed(), None, &proof),
+        Err(WitnessError::UnknownContract(contract_id()))
+    );
+
+    witness
+        .insert_contract(contract_id(), roots(&full), None)
+        .unwrap();
+    assert_eq!(
+        witness.insert_state(
+            &contract_id(),
+            &Bytes32::zeroed(),
+            Some(counter_value(COUNTER + 1)),
+            &proof
+        ),
+        Err(WitnessError::InvalidStateProof(state_key))
+    );
+    assert_eq!(
+        witness.insert_state(&contract_id(), &Bytes32::zeroed(), None, &proof),
+        Err(WitnessError::InvalidStateProof(state_key))
+    );
+    assert_eq!(
+        witness.insert_state(
+            &contract_id(),
+            &[2; 32].into(),
+            Some(counter_value(COUNTER)),
+            &full.state_proof(&contract_id(), &[2; 32].into())
+        ),
+        Err(WitnessError::InvalidStateProof(
+            (&contract_id(), &[2; 32].into()).into()
+        ))
+    );
+    assert_eq!(
+        witness.insert_balance(
+            &contract_id(),
+            &AssetId::from([1; 32]),
+            Some(11),
+            &full.balance_proof(&contract_id(), &AssetId::from([1; 32]))
+        ),
+        Err(WitnessError::InvalidBalanceProof(
+            (&contract_id(), &AssetId::from([1; 32])).into()
+        ))
+    );
+}
+
+#[test]
+fn insert_all_state__proves_the_state_usage() {
+    let full = full_storage();
+    let mut witness = WitnessStorage::new(Default::default(), Default::default());
+    witness
+        .insert_contract(contract_id(), roots(&full), None)
+        .unwrap();
+    let counter_slot = (Bytes32::zeroed(), counter_value(COUNTER));
+    let other_slot = (Bytes32::from([1; 32]), vec![1; 32].into());
+
+    assert_eq!(
+        witness.insert_all_state(&contract_id(), [counter_slot.clone()]),
+        Err(WitnessError::IncompleteState(contract_id()))
+    );
+    assert_eq!(
+        witness.contract_state_usage(&contract_id()),
+        Err(WitnessError::UnprovenStateUsage(contract_id()))
+    );
+
+    witness
+        .insert_all_state(&contract_id(), [counter_slot, other_slot])
+        .unwrap();
+    assert_eq!(
+        witness.contract_state_usage(&contract_id()),
+        Ok(Some(ContractStateUsage {
+            slots: 2,
+            bytes: 64
+        }))
+    );
+
+    witness
+        .contract_state_remove(&contract_id(), &[1; 32].into())
+        .unwrap();
+    assert_eq!(
+        witness.contract_state_usage(&contract_id()),
+        Ok(Some(ContractStateUsage {
+            slots: 1,
+            bytes: 32
+        }))
+    );
+}
+
+#[test]
+fn insert_contract__rejects_code_not_matching_the_root() {
+    let full = full_storage();
+    let mut witness = WitnessStorage::new(Default::default(), Default::default());
+
+    assert_eq!(
+        witness.insert_contract(contract_id(), roots(&full), Some(vec![0; 8].into())),
+        Err(WitnessError::InvalidCode(contract_id()))
+    );
+    assert_eq!(witness.roots(&contract_id()), None);
+}
+
+#[test]
+fn insert_contract__rejects_a_contract_added_before() {
+    let full = full_storage();
+    let mut witness = witness(&full);
+    let mut other_roots = roots(&full);
+    other_roots.state_root = Bytes32::zeroed();
+
+    assert_eq!(
+        witness.insert_contract(contract_id(), other_roots, Some(counter())),
+        Err(WitnessError::ContractAlreadyKnown(contract_id()))
+    );
+    assert_eq!(witness.roots(&contract_id()), Some(&roots(&full)));
+}
+
+#[test]
+fn recorded_witness__replays_the_execution() {
+    let full = full_storage().into_inner();
+
+    let mut transactor = transact(WitnessRecorder::new(full));
+    let expected = transactor.receipts().expect("failed to execute").to_vec();
+    let witness = transactor.take_witness().unwrap();
+
+    assert_eq!(
+        witness.entries,
+        vec![
+            WitnessEntry::Contract {
+                contract_id: contract_id(),
+                code: counter().into(),
+            },
+            WitnessEntry::State {
+                contract_id: contract_id(),
+                key: Bytes32::zeroed(),
+                value: counter_value(COUNTER).into(),
+            },
+            WitnessEntry::UnsetBalance {
+                contract_id: contract_id(),
+                asset_id: AssetId::zeroed(),
+            },
+        ]
+    );
+    assert!(transactor.witness().unwrap().entries.is_empty());
+
+    let bytes = witness.to_bytes();
+    let decoded = ExecutionWitness::from_bytes(&bytes).expect("invalid encoding");
+    assert_eq!(decoded, witness);
+
+    let replayed = execute(Transactor::from_witness(MemoryInstance::new(), decoded));
+    assert_eq!(replayed.receipts().expect("failed to replay"), expected);
+}
+
+#[test]
+fn recorded_witness__replays_with_the_recorded_interpreter_params() {
+    let full = full_storage().into_inner();
+    let params = InterpreterParams {
+        gas_costs: GasCosts::free(),
+        ..params()
+    };
+
+    let mut transactor = execute(Transactor::new(
+        MemoryInstance::new(),
+        WitnessRecorder::new(full),
+        params.clone(),
+    ));
+    let expected = transactor.receipts().expect("failed to execute").to_vec();
+    let witness = transactor.take_witness().unwrap();
+    assert_eq!(witness.interpreter_params, params);
+
+    let bytes = witness.to_bytes();
+    let decoded = ExecutionWitness::from_bytes(&bytes).expect("invalid encoding");
+    assert_eq!(decoded.interpreter_params, params);
+
+    let replayed = execute(Transactor::from_witness(
+        MemoryInstance::new(),
+        decoded.clone(),
+    ));
+    assert_eq!(replayed.receipts().expect("failed to replay"), expected);
+
+    // The standard gas costs charge for the same execution.
+    let standard = transact(WitnessStorage::from(decoded));
+    assert_ne!(standard.receipts().expect("failed to replay"), expected);
+}
+
+#[test]
+fn recorder__keeps_the_values_before_the_first_write() {
+    let mut recorder = WitnessRecorder::new(full_storage().into_inner());
+
+    recorder
+        .contract_state_insert(&contract_id(), &[1; 32].into(), &[2; 32])
+        .unwrap();
+    recorder
+        .contract_state_insert(&contract_id(), &[1; 32].into(), &[3; 32])
+        .unwrap();
+    recorder
+        .contract_state_insert_range(
+            &contract_id(),
+            &[5; 32].into(),
+            [[5; 32].as_ref()].into_iter(),
+        )
+        .unwrap();
+    recorder
+        .contract_asset_id_balance_insert(&contract_id(), &AssetId::from([1; 32]), 20)
+        .unwrap();
+    recorder.timestamp(3.into()).unwrap();
+    recorder.block_hash(2.into()).unwrap();
+
+    let memory = MemoryStorage::default();
+    assert_eq!(
+        recorder.witness(&params()).unwrap().entries,
+        vec![
+            WitnessEntry::State {
+                contract_id: contract_id(),
+                key: [1; 32].into(),
+                value: vec![1; 32],
+            },
+            WitnessEntry::UnsetState {
+                contract_id: contract_id(),
+                key: [5; 32].into(),
+            },
+            WitnessEntry::Balance {
+                contract_id: contract_id(),
+                asset_id: AssetId::from([1; 32]),
+                amount: 10,
+            },
+            WitnessEntry::Timestamp {
+                height: 3.into(),
+                timestamp: memory.timestamp(3.into()).unwrap(),
+            },
+            WitnessEntry::BlockHash {
+                height: 2.into(),
+                hash: memory.block_hash(2.into()).unwrap(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn recorder__records_the_state_usage_before_the_first_write() {
+    let mut recorder = WitnessRecorder::new(full_storage().into_inner());
+
+    recorder
+        .contract_state_insert(&contract_id(), &[5; 32].into(), &[5; 10])
+        .unwrap();
+    recorder
+        .contract_state_remove(&contract_id(), &[1; 32].into())
+        .unwrap();
+    assert_eq!(
+        recorder.contract_state_usage(&contract_id()),
+        Ok(Some(ContractStateUsage {
+            slots: 2,
+            bytes: 42
+        }))
+    );
+
+    assert!(recorder.witness(&params()).unwrap().entries.contains(
+        &WitnessEntry::StateUsage {
+            contract_id: contract_id(),
+            slots: 2,
+            bytes: 64,
+        }
+    ));
+}
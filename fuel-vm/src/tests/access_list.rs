@@ -0,0 +1,100 @@
+#![allow(non_snake_case)]
+
+use alloc::vec;
+
+use crate::{
+    consts::*,
+    interpreter::InterpreterParams,
+    prelude::*,
+    storage::{
+        AccessRecorder,
+        ContractsStateKey,
+        WriteAccess,
+    },
+};
+use fuel_asm::{
+    op,
+    RegId,
+};
+use fuel_tx::ConsensusParameters;
+use fuel_types::Bytes32;
+
+#[test]
+fn transactor__returns_the_read_and_write_sets_of_the_transaction() {
+    let mut test_context = TestBuilder::new(2322u64);
+
+    #[rustfmt::skip]
+    let contract = vec![
+        op::move_(0x12, RegId::SSP),
+        op::cfei(64),
+        op::addi(0x13, 0x12, 32),
+        op::movi(0x14, 1),
+        op::sb(0x13, 0x14, 31),     // The second key is 1
+        op::srw(0x10, 0x11, 0x12),  // Read the slot 0
+        op::movi(0x15, 7),
+        op::sww(0x13, 0x11, 0x15),  // Write 7 to the slot 1
+        op::ret(RegId::ONE),
+    ];
+    let contract_id = test_context
+        .setup_contract(contract, None, None)
+        .contract_id;
+
+    #[rustfmt::skip]
+    let mut script = vec![
+        op::movi(0x10, (ContractId::LEN + WORD_SIZE * 2) as Immediate18),
+        op::aloc(0x10),
+    ];
+    contract_id.iter().enumerate().for_each(|(i, b)| {
+        script.push(op::movi(0x10, *b as Immediate18));
+        script.push(op::sb(RegId::HP, 0x10, i as Immediate12));
+    });
+    script.push(op::call(RegId::HP, RegId::ZERO, RegId::ZERO, RegId::CGAS));
+    script.push(op::ret(RegId::ONE));
+
+    let tx = test_context
+        .start_script(script, vec![])
+        .script_gas_limit(1_000_000)
+        .contract_input(contract_id)
+        .fee_input()
+        .contract_output(&contract_id)
+        .build();
+
+    let mut transactor = Transactor::<_, _, Script>::new(
+        MemoryInstance::new(),
+        AccessRecorder::new(test_context.get_storage().clone()),
+        InterpreterParams::new(0, &ConsensusParameters::standard()),
+    );
+    transactor.transact(tx);
+    assert!(matches!(
+        transactor.receipts().and_then(|r| r.last()),
+        Some(Receipt::ScriptResult {
+            result: ScriptExecutionResult::Success,
+            ..
+        })
+    ));
+
+    let access_list = transactor.take_access_list();
+    let slot = |key: u8| {
+        let mut bytes = [0; 32];
+        bytes[31] = key;
+        ContractsStateKey::new(&contract_id, &Bytes32::from(bytes))
+    };
+
+    assert!(access_list.reads.contracts.contains(&contract_id));
+    assert_eq!(access_list.reads.contract_state, [slot(0), slot(1)].into());
+    assert!(access_list.writes.contracts.is_empty());
+    let mut value = vec![0; 32];
+    value[..WORD_SIZE].copy_from_slice(&7u64.to_be_bytes());
+    assert_eq!(
+        access_list.writes.contract_state,
+        [(
+            slot(1),
+            WriteAccess {
+                old: None,
+                new: Some(value.into())
+            }
+        )]
+        .into()
+    );
+    assert!(transactor.access_list().is_empty());
+}
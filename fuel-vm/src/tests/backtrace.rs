@@ -1,6 +1,12 @@
-use alloc::vec;
+#![allow(non_snake_case)]
+
+use alloc::{
+    string::ToString,
+    vec,
+};
 
 use crate::{
+    constraints::InstructionLocation,
     consts::*,
     prelude::*,
 };
@@ -91,3 +97,56 @@ fn backtrace() {
         .to();
     assert_eq!(id, &contract_call);
 }
+
+#[test]
+fn backtrace__renders_source_locations_from_debug_info() {
+    let script = vec![
+        op::movi(0x10, 1),
+        op::div(0x10, 0x10, RegId::ZERO),
+        op::ret(RegId::ONE),
+    ];
+
+    let mut debug_info = DebugInfo::new();
+    debug_info.set_script(
+        [
+            (0, SourceLocation::new("main.sw", 1).with_function("main")),
+            (4, SourceLocation::new("main.sw", 2).with_function("main")),
+        ]
+        .into_iter()
+        .collect(),
+    );
+
+    let mut vm = Interpreter::<_, _, Script>::with_memory_storage();
+    vm.set_debug_info(debug_info);
+    let mut transactor = Transactor::from(vm);
+
+    let tx = TransactionBuilder::script(script.into_iter().collect(), vec![])
+        .script_gas_limit(1_000_000)
+        .add_random_fee_input()
+        .finalize_checked(Default::default());
+    transactor.transact(tx);
+
+    let panic = transactor
+        .receipts()
+        .expect("Expected receipts")
+        .iter()
+        .find(|r| matches!(r, Receipt::Panic { .. }))
+        .expect("Expected a panic receipt");
+    let source = transactor
+        .interpreter()
+        .debug_info()
+        .expect("Debug info was set")
+        .resolve_receipt(panic);
+    assert_eq!(
+        source,
+        Some(&SourceLocation::new("main.sw", 2).with_function("main"))
+    );
+
+    let backtrace = transactor.backtrace().expect("Expected a backtrace");
+    let expected_location = InstructionLocation::new(None, 4);
+    assert_eq!(backtrace.location(), expected_location);
+    assert_eq!(backtrace.locations(), vec![expected_location]);
+    assert!(backtrace
+        .to_string()
+        .contains("Location(script, offset=4) at main.sw:2 in main"));
+}
@@ -0,0 +1,186 @@
+#![allow(non_snake_case)]
+
+use alloc::vec;
+use core::num::NonZeroUsize;
+
+use crate::{
+    block_executor::BlockExecutor,
+    checked_transaction::CheckedTransaction,
+    interpreter::InterpreterParams,
+    prelude::*,
+    tests::test_helpers::{
+        call_script,
+        counter_program,
+    },
+};
+use fuel_asm::{
+    op,
+    RegId,
+};
+use fuel_tx::{
+    ConsensusParameters,
+    Contract,
+    Finalizable,
+    Output,
+    TransactionBuilder,
+};
+use fuel_types::Salt;
+
+fn call(test_context: &mut TestBuilder, contract_id: ContractId) -> CheckedTransaction {
+    let mut script = call_script(contract_id);
+    script.push(op::ret(RegId::ONE));
+
+    test_context
+        .start_script(script, vec![])
+        .script_gas_limit(1_000_000)
+        .contract_input(contract_id)
+        .fee_input()
+        .contract_output(&contract_id)
+        .build()
+        .into()
+}
+
+fn deploy(program: Vec<Instruction>) -> (ContractId, CheckedTransaction) {
+    let program: Vec<u8> = program.into_iter().collect();
+    let contract = Contract::from(program.clone());
+    let salt = Salt::zeroed();
+    let state_root = Contract::default_state_root();
+    let contract_id = contract.id(&salt, &contract.root(), &state_root);
+    let tx = TransactionBuilder::create(program.into(), salt, vec![])
+        .add_random_fee_input()
+        .add_output(Output::contract_created(contract_id, state_root))
+        .finalize()
+        .into_checked(Default::default(), &ConsensusParameters::standard())
+        .expect("failed to check tx");
+    (contract_id, tx.into())
+}
+
+/// Executes the transactions one after another.
+fn execute_sequentially(
+    storage: MemoryStorage,
+    transactions: Vec<CheckedTransaction>,
+) -> (MemoryStorage, Vec<Vec<Receipt>>) {
+    let mut transactor = Transactor::<_, _, Script>::new(
+        MemoryInstance::new(),
+        storage,
+        InterpreterParams::new(0, &ConsensusParameters::standard()),
+    );
+    let receipts = transactions
+        .into_iter()
+        .map(|tx| match tx {
+            CheckedTransaction::Script(tx) => {
+                transactor.transact(tx).receipts().unwrap().to_vec()
+            }
+            CheckedTransaction::Create(tx) => {
+                transactor.deploy(tx).expect("failed to deploy");
+                vec![]
+            }
+            _ => unreachable!(),
+        })
+        .collect();
+    (
+        AsRef::<MemoryStorage>::as_ref(&transactor).clone(),
+        receipts,
+    )
+}
+
+fn assert_same_state(a: &MemoryStorage, b: &MemoryStorage, contracts: &[ContractId]) {
+    assert_eq!(
+        a.all_contract_state().collect::<Vec<_>>(),
+        b.all_contract_state().collect::<Vec<_>>()
+    );
+    for contract in contracts {
+        assert_eq!(
+            a.storage_contract(contract).unwrap(),
+            b.storage_contract(contract).unwrap()
+        );
+    }
+}
+
+fn block(test_context: &mut TestBuilder) -> (Vec<ContractId>, Vec<CheckedTransaction>) {
+    let a = test_context
+        .setup_contract(counter_program(), None, None)
+        .contract_id;
+    let b = test_context
+        .setup_contract(counter_program(), None, None)
+        .contract_id;
+    let (c, deploy_c) =
+        deploy(vec![op::log(0x10, 0x11, 0x12, 0x13), op::ret(RegId::ONE)]);
+
+    let transactions = vec![
+        call(test_context, a),
+        call(test_context, b),
+        call(test_context, a),
+        deploy_c,
+        call(test_context, b),
+        call(test_context, a),
+    ];
+    (vec![a, b, c], transactions)
+}
+
+#[test]
+fn execute__matches_the_sequential_execution() {
+    for threads in [1, 2, 4] {
+        let mut test_context = TestBuilder::new(2322u64);
+        let (contracts, transactions) = block(&mut test_context);
+        let (expected_storage, expected_receipts) = execute_sequentially(
+            test_context.get_storage().clone(),
+            transactions.clone(),
+        );
+
+        let executor = BlockExecutor::new(InterpreterParams::new(
+            0,
+            &ConsensusParameters::standard(),
+        ))
+        .with_threads(NonZeroUsize::new(threads).unwrap());
+        let mut storage = test_context.get_storage().clone();
+        let execution = executor
+            .execute(&mut storage, transactions)
+            .expect("storage failure");
+
+        let receipts: Vec<_> = execution
+            .transactions
+            .into_iter()
+            .map(|result| result.expect("failed to execute").receipts)
+            .collect();
+        assert_eq!(receipts, expected_receipts);
+        assert_same_state(&storage, &expected_storage, &contracts);
+        assert!(storage.storage_contract_exists(&contracts[2]).unwrap());
+    }
+}
+
+#[test]
+fn execute__reexecutes_only_the_conflicting_transactions() {
+    let mut test_context = TestBuilder::new(2322u64);
+    let (_, transactions) = block(&mut test_context);
+
+    let executor =
+        BlockExecutor::new(InterpreterParams::new(0, &ConsensusParameters::standard()))
+            .with_threads(NonZeroUsize::new(2).unwrap());
+    let mut storage = test_context.get_storage().clone();
+    let execution = executor
+        .execute(&mut storage, transactions)
+        .expect("storage failure");
+
+    assert_eq!(execution.reexecuted, vec![2, 4, 5]);
+}
+
+#[test]
+fn execute__keeps_the_error_of_invalid_transactions() {
+    let mut test_context = TestBuilder::new(2322u64);
+    let (_, mut transactions) = block(&mut test_context);
+    let (_, deploy_twice) = deploy(vec![op::ret(RegId::ONE)]);
+    transactions.push(deploy_twice.clone());
+    transactions.push(deploy_twice);
+
+    let executor =
+        BlockExecutor::new(InterpreterParams::new(0, &ConsensusParameters::standard()));
+    let mut storage = test_context.get_storage().clone();
+    let execution = executor
+        .execute(&mut storage, transactions)
+        .expect("storage failure");
+
+    let results = &execution.transactions;
+    assert!(results[..results.len() - 1].iter().all(Result::is_ok));
+    assert!(results.last().unwrap().is_err());
+}
@@ -26,6 +26,22 @@ use std::sync::{
 
 const HALF_WORD_SIZE: u64 = (WORD_SIZE as u64) / 2;
 
+#[derive(Clone, Default)]
+struct ProfilingOutput {
+    data: Arc<Mutex<Option<ProfilingData>>>,
+}
+
+impl ProfileReceiver for ProfilingOutput {
+    fn on_transaction(
+        &mut self,
+        _state: Result<&ProgramState, InterpreterError<String>>,
+        data: &ProfilingData,
+    ) {
+        let mut guard = self.data.lock().unwrap();
+        *guard = Some(data.clone());
+    }
+}
+
 #[test]
 fn code_coverage() {
     let rng = &mut StdRng::seed_from_u64(2322u64);
@@ -57,22 +73,6 @@ fn code_coverage() {
         .maturity(maturity)
         .finalize_checked(height);
 
-    #[derive(Clone, Default)]
-    struct ProfilingOutput {
-        data: Arc<Mutex<Option<ProfilingData>>>,
-    }
-
-    impl ProfileReceiver for ProfilingOutput {
-        fn on_transaction(
-            &mut self,
-            _state: Result<&ProgramState, InterpreterError<String>>,
-            data: &ProfilingData,
-        ) {
-            let mut guard = self.data.lock().unwrap();
-            *guard = Some(data.clone());
-        }
-    }
-
     let output = ProfilingOutput::default();
 
     let mut vm = Interpreter::<_, _, _>::with_memory_storage();
@@ -104,3 +104,86 @@ fn code_coverage() {
         );
     }
 }
+
+#[test]
+fn coverage_report_accumulates_runs() {
+    let rng = &mut StdRng::seed_from_u64(2322u64);
+
+    let script_code = vec![
+        op::gtf_args(0x10, 0x00, GTFArgs::ScriptData),
+        op::lw(0x11, 0x10, 0),
+        op::jnzi(0x11, 4), // Skip next if the script data is not zero
+        op::noop(),
+        op::ret(RegId::ONE),
+    ];
+    let script: Vec<u8> = script_code.into_iter().collect();
+
+    let mut run = |report: &mut CoverageReport, flag: Word| {
+        let tx_script =
+            TransactionBuilder::script(script.clone(), flag.to_be_bytes().to_vec())
+                .add_unsigned_coin_input(
+                    SecretKey::random(rng),
+                    rng.gen(),
+                    2,
+                    Default::default(),
+                    rng.gen(),
+                )
+                .script_gas_limit(1_000_000)
+                .finalize_checked(Default::default());
+
+        let output = ProfilingOutput::default();
+        let mut vm = Interpreter::<_, _, _>::with_memory_storage();
+        vm.with_profiler(output.clone());
+        let mut client = MemoryClient::from_txtor(vm.into());
+        let receipts = client.transact(tx_script);
+        assert!(matches!(
+            receipts.last(),
+            Some(Receipt::ScriptResult {
+                result: ScriptExecutionResult::Success,
+                ..
+            })
+        ));
+
+        let guard = output.data.lock().unwrap();
+        report.add_run(Some(&script), guard.as_ref().unwrap().coverage());
+    };
+
+    let mut report = CoverageReport::new();
+    run(&mut report, 0);
+    let summary = report.summary();
+    assert_eq!(summary.programs.len(), 1);
+    assert_eq!(summary.total.instructions, 5);
+    assert_eq!(summary.total.instructions_hit, 5);
+    assert_eq!(summary.total.branches, 2);
+    assert_eq!(summary.total.branches_hit, 1);
+
+    run(&mut report, 1);
+    run(&mut report, 1);
+    let summary = report.summary();
+    assert_eq!(summary.programs.len(), 1);
+    assert_eq!(summary.total.instructions_hit, 5);
+    assert_eq!(summary.total.branches_hit, 2);
+
+    let expected = format!(
+        "TN:\n\
+         SF:script:{}\n\
+         BRDA:3,0,0,2\n\
+         BRDA:3,0,1,1\n\
+         BRF:2\n\
+         BRH:2\n\
+         DA:1,1\n\
+         DA:2,1\n\
+         DA:3,1\n\
+         DA:4,1\n\
+         DA:5,1\n\
+         LF:5\n\
+         LH:5\n\
+         end_of_record\n",
+        Hasher::hash(&script)
+    );
+    assert_eq!(report.lcov(), expected);
+
+    let json = serde_json::to_value(&summary).unwrap();
+    assert_eq!(json["total"]["branches_hit"], 2);
+    assert_eq!(json["programs"][0]["instructions"], 5);
+}
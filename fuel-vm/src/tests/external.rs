@@ -1,21 +1,38 @@
-use alloc::vec;
+#![allow(non_snake_case)]
+
+use alloc::{
+    string::ToString,
+    vec,
+    vec::Vec,
+};
 use fuel_asm::{
     op,
     GTFArgs,
+    Instruction,
     RegId,
     Word,
 };
 use fuel_tx::{
     ConsensusParameters,
+    DependentCost,
     Finalizable,
+    GasCosts,
+    PanicContext,
     PanicReason,
     Receipt,
     Script,
     ScriptExecutionResult,
     TransactionBuilder,
 };
-use fuel_vm::prelude::*;
+use fuel_vm::{
+    interpreter::EcalRegistry,
+    prelude::*,
+};
 use itertools::Itertools;
+use std::sync::{
+    Arc,
+    Mutex,
+};
 use test_case::test_case;
 
 use crate::tests::test_helpers::{
@@ -225,3 +242,293 @@ fn complex_ecal_fn(val: u32, result: PanicReason) {
 
     assert_panics(receipts, result);
 }
+
+fn run_registry_script(
+    registry: EcalRegistry,
+    script: Vec<Instruction>,
+    script_data: Vec<u8>,
+) -> Vec<Receipt> {
+    let vm: Interpreter<_, _, Script, EcalRegistry> =
+        Interpreter::with_memory_storage_and_ecal(registry);
+
+    let mut client = MemoryClient::from_txtor(vm.into());
+    let consensus_params = ConsensusParameters::standard();
+    let tx = TransactionBuilder::script(script.into_iter().collect(), script_data)
+        .script_gas_limit(1_000_000)
+        .maturity(Default::default())
+        .add_random_fee_input()
+        .finalize()
+        .into_checked(Default::default(), &consensus_params)
+        .expect("failed to generate a checked tx");
+    client.transact(tx);
+    client.receipts().expect("Expected receipts").to_vec()
+}
+
+#[test]
+fn ecal_registry__dispatches_on_function_id_with_decoded_args() {
+    let registry = EcalRegistry::new()
+        .with_function(1, 1, |ctx, (result, lhs, rhs): (RegId, Word, Word)| {
+            ctx.set_register(result, lhs * rhs)
+        })
+        .with_function(2, 1, |ctx, (result, id): (RegId, Bytes32)| {
+            ctx.set_register(result, u64::from(id[31]))
+        });
+    assert!(registry.contains(1));
+    assert!(!registry.contains(3));
+
+    let mut script_data = vec![0u8; 32];
+    script_data[31] = 42;
+    let script = vec![
+        op::movi(0x10, 1),
+        op::movi(0x21, 6),
+        op::movi(0x22, 7),
+        op::ecal(0x10, 0x20, 0x21, 0x22),
+        op::movi(0x10, 2),
+        op::gtf_args(0x22, 0x00, GTFArgs::ScriptData),
+        op::ecal(0x10, 0x21, 0x22, RegId::ZERO),
+        op::log(0x20, 0x21, RegId::ZERO, RegId::ZERO),
+        op::ret(RegId::ONE),
+    ];
+    let receipts = run_registry_script(registry, script, script_data);
+
+    let Receipt::Log { ra, rb, .. } = receipts.first().unwrap() else {
+        panic!("Expected a log receipt");
+    };
+    assert_eq!(*ra, 42);
+    assert_eq!(*rb, 42);
+}
+
+#[test]
+fn ecal_registry__unknown_function_panics() {
+    let registry = EcalRegistry::new().with_function(1, 1, |_, (): ()| Ok(()));
+    let script = vec![
+        op::movi(0x10, 2),
+        op::ecal(0x10, RegId::ZERO, RegId::ZERO, RegId::ZERO),
+        op::ret(RegId::ONE),
+    ];
+    let receipts = run_registry_script(registry, script, vec![]);
+
+    assert_panics(&receipts, PanicReason::EcalError);
+}
+
+#[test]
+fn ecal_registry__charges_the_registered_gas() {
+    let gas_used = |gas: Word| {
+        let registry = EcalRegistry::new().with_function(1, gas, |_, (): ()| Ok(()));
+        let script = vec![
+            op::movi(0x10, 1),
+            op::ecal(0x10, RegId::ZERO, RegId::ZERO, RegId::ZERO),
+            op::ret(RegId::ONE),
+        ];
+        let receipts = run_registry_script(registry, script, vec![]);
+        let Receipt::ScriptResult { gas_used, .. } = receipts.last().unwrap() else {
+            panic!("Expected a result receipt");
+        };
+        *gas_used
+    };
+
+    assert_eq!(gas_used(1000) - gas_used(0), 1000);
+}
+
+#[test]
+fn ecal_registry__debug_functions_write_to_the_output() {
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let sink = output.clone();
+    let registry = EcalRegistry::new()
+        .with_debug_functions(move |line| sink.lock().unwrap().push(line.to_string()));
+
+    let message = b"hello";
+    let script = vec![
+        op::gtf_args(0x20, 0x00, GTFArgs::ScriptData),
+        op::movi(0x21, message.len() as u32),
+        op::movi(0x10, EcalRegistry::PRINT_STRING as u32),
+        op::ecal(0x10, 0x20, 0x21, RegId::ZERO),
+        op::movi(0x10, EcalRegistry::PRINT_REGISTER as u32),
+        op::ecal(0x10, 0x21, RegId::ZERO, RegId::ZERO),
+        op::movi(0x10, EcalRegistry::DUMP_MEMORY as u32),
+        op::ecal(0x10, 0x20, 0x21, RegId::ZERO),
+        op::movi(0x10, EcalRegistry::ASSERT as u32),
+        op::ecal(0x10, 0x21, RegId::ZERO, RegId::ZERO),
+        op::ecal(0x10, RegId::ZERO, RegId::ZERO, RegId::ZERO),
+        op::ret(RegId::ONE),
+    ];
+    let receipts = run_registry_script(registry, script, message.to_vec());
+
+    assert_panics(&receipts, PanicReason::EcalError);
+    let output = output.lock().unwrap();
+    assert_eq!(output.len(), 4);
+    assert_eq!(output[0], "hello");
+    assert_eq!(output[1], "r33 = 5");
+    assert!(output[2].ends_with(": 68 65 6c 6c 6f\n"));
+    assert!(output[3].starts_with("assertion failed at pc "));
+}
+
+#[test_case(EcalRegistry::DUMP_MEMORY; "dump memory")]
+#[test_case(EcalRegistry::PRINT_STRING; "print string")]
+fn ecal_registry__debug_functions_charge_per_printed_byte(id: Word) {
+    let gas_used = |len: u32| {
+        let registry = EcalRegistry::new().with_debug_functions(|_| {});
+        let script = vec![
+            op::movi(0x10, id as u32),
+            op::movi(0x20, 1000),
+            op::aloc(0x20),
+            op::movi(0x21, len),
+            op::ecal(0x10, RegId::HP, 0x21, RegId::ZERO),
+            op::ret(RegId::ONE),
+        ];
+        let receipts = run_registry_script(registry, script, vec![]);
+        let Receipt::ScriptResult { gas_used, .. } = receipts.last().unwrap() else {
+            panic!("Expected a result receipt");
+        };
+        *gas_used
+    };
+
+    let logd = GasCosts::default().logd();
+    assert_eq!(
+        gas_used(1000) - gas_used(0),
+        logd.resolve(1000) - logd.resolve(0)
+    );
+}
+
+/// An ECAL handler using the helper API: `$rA` selects the operation.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HelperEcal;
+
+impl ::fuel_vm::interpreter::EcalHandler for HelperEcal {
+    fn ecal<M, S, Tx>(
+        vm: &mut ::fuel_vm::prelude::Interpreter<M, S, Tx, Self>,
+        a: RegId,
+        b: RegId,
+        c: RegId,
+        _d: RegId,
+    ) -> ::fuel_vm::error::SimpleResult<()>
+    where
+        M: ::fuel_vm::interpreter::Memory,
+    {
+        let (b, c) = (vm.registers()[b], vm.registers()[c]);
+        match vm.registers()[a] {
+            // Charge for `$rB` units.
+            0 => vm.dependent_gas_charge(
+                DependentCost::HeavyOperation {
+                    base: 0,
+                    gas_per_unit: 10,
+                },
+                b,
+            ),
+            // Fill `$rC` bytes at `$rB` with ones.
+            1 => {
+                vm.memory_write(b, c)?.fill(1);
+                Ok(())
+            }
+            // Build the error with the code `$rB`, but recover from it.
+            3 => {
+                let _ = vm.ecal_error(b);
+                Ok(())
+            }
+            // Fail with the code `$rB`.
+            _ => Err(vm.ecal_error(b)),
+        }
+    }
+}
+
+fn run_helper_script(script: Vec<Instruction>) -> Vec<Receipt> {
+    let mut client = MemoryClient::<_, HelperEcal>::new(
+        MemoryInstance::new(),
+        fuel_vm::prelude::MemoryStorage::default(),
+        Default::default(),
+    );
+    let consensus_params = ConsensusParameters::standard();
+    let tx = TransactionBuilder::script(script.into_iter().collect(), vec![])
+        .script_gas_limit(1_000_000)
+        .maturity(Default::default())
+        .add_random_fee_input()
+        .finalize()
+        .into_checked(Default::default(), &consensus_params)
+        .expect("failed to generate a checked tx");
+    client.transact(tx);
+    client.receipts().expect("Expected receipts").to_vec()
+}
+
+#[test]
+fn ecal_helpers__dependent_gas_charge_charges_per_unit() {
+    let gas_used = |units: u32| {
+        let receipts = run_helper_script(vec![
+            op::movi(0x20, units),
+            op::ecal(RegId::ZERO, 0x20, RegId::ZERO, RegId::ZERO),
+            op::ret(RegId::ONE),
+        ]);
+        let Receipt::ScriptResult { gas_used, .. } = receipts.last().unwrap() else {
+            panic!("Expected a result receipt");
+        };
+        *gas_used
+    };
+
+    assert_eq!(gas_used(7) - gas_used(0), 70);
+}
+
+#[test]
+fn ecal_helpers__memory_write_checks_ownership() {
+    let receipts = run_helper_script(vec![
+        op::movi(0x10, 1),
+        op::movi(0x21, 4),
+        op::aloc(0x21),
+        op::ecal(0x10, RegId::HP, 0x21, RegId::ZERO),
+        op::logd(RegId::ZERO, RegId::ZERO, RegId::HP, 0x21),
+        op::ecal(0x10, RegId::ZERO, 0x21, RegId::ZERO),
+        op::ret(RegId::ONE),
+    ]);
+
+    let Receipt::LogData { data, .. } = receipts.first().unwrap() else {
+        panic!("Expected a data log receipt");
+    };
+    assert_eq!(data.as_deref(), Some(&[1u8; 4][..]));
+    assert_panics(&receipts, PanicReason::MemoryOwnership);
+}
+
+#[test]
+fn ecal_helpers__error_code_ends_up_in_the_panic_receipt() {
+    let receipts = run_helper_script(vec![
+        op::movi(0x10, 2),
+        op::movi(0x20, 42),
+        op::ecal(0x10, 0x20, RegId::ZERO, RegId::ZERO),
+        op::ret(RegId::ONE),
+    ]);
+
+    assert_panics(&receipts, PanicReason::EcalError);
+    let context = receipts.iter().find_map(Receipt::panic_context).unwrap();
+    assert_eq!(context, &PanicContext::Ecal { code: 42 });
+}
+
+#[test]
+fn ecal_helpers__recovered_error_code_does_not_leak_into_a_later_panic() {
+    let receipts = run_helper_script(vec![
+        op::movi(0x10, 3),
+        op::movi(0x20, 42),
+        op::ecal(0x10, 0x20, RegId::ZERO, RegId::ZERO),
+        op::movi(0x10, 1),
+        op::movi(0x21, 4),
+        op::ecal(0x10, RegId::ZERO, 0x21, RegId::ZERO),
+        op::ret(RegId::ONE),
+    ]);
+
+    assert_panics(&receipts, PanicReason::MemoryOwnership);
+    let context = receipts.iter().find_map(Receipt::panic_context).unwrap();
+    assert!(!matches!(context, PanicContext::Ecal { .. }));
+}
+
+#[test]
+fn ecal_registry__error_code_ends_up_in_the_panic_receipt() {
+    let registry = EcalRegistry::new()
+        .with_function(1, 1, |ctx, (code,): (Word,)| Err(ctx.error(code)));
+    let script = vec![
+        op::movi(0x10, 1),
+        op::movi(0x20, 7),
+        op::ecal(0x10, 0x20, RegId::ZERO, RegId::ZERO),
+        op::ret(RegId::ONE),
+    ];
+    let receipts = run_registry_script(registry, script, vec![]);
+
+    assert_panics(&receipts, PanicReason::EcalError);
+    let context = receipts.iter().find_map(Receipt::panic_context).unwrap();
+    assert_eq!(context, &PanicContext::Ecal { code: 7 });
+}
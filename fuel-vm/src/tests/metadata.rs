@@ -360,6 +360,83 @@ fn get_metadata_tx_start() {
     }
 }
 
+#[test]
+fn get_metadata_state_usage() {
+    let contract_id = ContractId::from([7; 32]);
+    let program: Vec<u8> = [
+        op::gm_args(0x10, GMArgs::StateSlotCount),
+        op::gm_args(0x11, GMArgs::StateByteSize),
+        op::log(0x10, 0x11, RegId::ZERO, RegId::ZERO),
+        op::move_(0x12, RegId::SSP),
+        op::cfei(32),
+        op::sww(0x12, 0x13, RegId::ONE),
+        op::gm_args(0x10, GMArgs::StateSlotCount),
+        op::gm_args(0x11, GMArgs::StateByteSize),
+        op::log(0x10, 0x11, RegId::ZERO, RegId::ZERO),
+        op::ret(RegId::ONE),
+    ]
+    .into_iter()
+    .collect();
+
+    let mut storage = MemoryStorage::default();
+    storage
+        .storage_contract_insert(&contract_id, &program.into())
+        .unwrap();
+    storage
+        .contract_state_insert(&contract_id, &[1; 32].into(), &[1; 10])
+        .unwrap();
+    storage
+        .contract_state_insert(&contract_id, &[2; 32].into(), &[2; 32])
+        .unwrap();
+
+    // The call parameters are followed by the zeroed asset id sent with the call.
+    let mut script = vec![
+        op::movi(
+            0x10,
+            (ContractId::LEN + WORD_SIZE * 2 + AssetId::LEN) as Immediate18,
+        ),
+        op::aloc(0x10),
+        op::addi(
+            0x11,
+            RegId::HP,
+            (ContractId::LEN + WORD_SIZE * 2) as Immediate12,
+        ),
+    ];
+    contract_id.iter().enumerate().for_each(|(i, b)| {
+        script.push(op::movi(0x10, *b as Immediate18));
+        script.push(op::sb(RegId::HP, 0x10, i as Immediate12));
+    });
+    script.push(op::call(RegId::HP, RegId::ZERO, 0x11, RegId::CGAS));
+    script.push(op::gm_args(0x10, GMArgs::StateSlotCount));
+    script.push(op::ret(RegId::ONE));
+
+    let script = TestBuilder::new(2322u64)
+        .start_script(script, vec![])
+        .script_gas_limit(1_000_000)
+        .contract_input(contract_id)
+        .fee_input()
+        .contract_output(&contract_id)
+        .build();
+
+    let receipts = Transactor::<_, _, _>::new(
+        MemoryInstance::new(),
+        &mut storage,
+        InterpreterParams::new(0, &ConsensusParameters::standard()),
+    )
+    .transact(script)
+    .receipts()
+    .expect("Failed to transact")
+    .to_owned();
+
+    assert!(matches!(receipts[1], Receipt::Log { ra: 2, rb: 42, .. }));
+    assert!(matches!(receipts[2], Receipt::Log { ra: 3, rb: 74, .. }));
+    // The usage is only available to contracts.
+    assert!(matches!(
+        receipts[4],
+        Receipt::Panic { reason, .. } if *reason.reason() == PanicReason::ExpectedInternalContext
+    ));
+}
+
 #[test]
 fn get_transaction_fields() {
     let rng = &mut StdRng::seed_from_u64(2322u64);
@@ -4,10 +4,13 @@ use futures as _;
 use tokio as _;
 use tokio_rayon as _;
 
-mod test_helpers;
+pub(crate) mod test_helpers;
 
+mod access_list;
 mod alu;
 mod backtrace;
+#[cfg(feature = "std")]
+mod block_executor;
 mod blockchain;
 mod cgas;
 mod code_coverage;
@@ -24,6 +27,7 @@ mod log;
 mod memory;
 mod metadata;
 mod outputs;
+mod panic_context;
 mod predicate;
 mod profile_gas;
 mod receipts;
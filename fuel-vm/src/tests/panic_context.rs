@@ -0,0 +1,238 @@
+#![allow(non_snake_case)]
+
+use alloc::{
+    vec,
+    vec::Vec,
+};
+
+use fuel_asm::{
+    op,
+    Instruction,
+    PanicReason,
+    RegId,
+};
+use fuel_tx::PanicContext;
+use fuel_vm::{
+    consts::{
+        VM_MAX_RAM,
+        WORD_SIZE,
+    },
+    prelude::*,
+};
+
+use super::test_helpers::{
+    run_script,
+    set_full_word,
+};
+
+fn panic_of(script: Vec<Instruction>) -> (PanicReason, PanicContext) {
+    let receipts = run_script(script);
+    let panic = receipts
+        .iter()
+        .find(|receipt| matches!(receipt, Receipt::Panic { .. }))
+        .expect("Expected a panic receipt");
+    (
+        *panic.reason().expect("Panic receipt has a reason").reason(),
+        panic
+            .panic_context()
+            .expect("Panic receipt has a context")
+            .clone(),
+    )
+}
+
+#[test]
+fn panic_context__memory_overflow_reports_the_accessed_range() {
+    let mut script = set_full_word(0x10, VM_MAX_RAM - 4);
+    script.extend([op::lw(0x11, 0x10, 0), op::ret(RegId::ONE)]);
+
+    let (reason, context) = panic_of(script);
+
+    assert_eq!(reason, PanicReason::MemoryOverflow);
+    assert_eq!(
+        context,
+        PanicContext::MemoryRange {
+            start: VM_MAX_RAM - 4,
+            len: 8,
+        }
+    );
+}
+
+/// Calls a contract with the `code` and returns the reason and the context of its
+/// panic.
+fn contract_panic_of(code: Vec<Instruction>) -> (PanicReason, PanicContext) {
+    let mut test_context = TestBuilder::new(2322u64);
+    let contract_id = test_context.setup_contract(code, None, None).contract_id;
+
+    let mut script = vec![
+        op::movi(0x10, (ContractId::LEN + WORD_SIZE * 2) as Immediate18),
+        op::aloc(0x10),
+    ];
+    contract_id.iter().enumerate().for_each(|(i, b)| {
+        script.push(op::movi(0x10, *b as Immediate18));
+        script.push(op::sb(RegId::HP, 0x10, i as Immediate12));
+    });
+    script.push(op::call(RegId::HP, RegId::ZERO, RegId::ZERO, RegId::CGAS));
+    script.push(op::ret(RegId::ONE));
+
+    let tx = test_context
+        .start_script(script, vec![])
+        .script_gas_limit(1_000_000)
+        .contract_input(contract_id)
+        .fee_input()
+        .contract_output(&contract_id)
+        .build();
+    let receipts = test_context.execute_tx(tx).unwrap().receipts().to_vec();
+    let panic = receipts
+        .iter()
+        .find(|receipt| matches!(receipt, Receipt::Panic { .. }))
+        .expect("Expected a panic receipt");
+    (
+        *panic.reason().expect("Panic receipt has a reason").reason(),
+        panic
+            .panic_context()
+            .expect("Panic receipt has a context")
+            .clone(),
+    )
+}
+
+#[test]
+fn panic_context__code_copy_reports_the_destination_range() {
+    let mut script = set_full_word(0x10, VM_MAX_RAM - 4);
+    script.extend([
+        op::movi(0x11, 16),
+        op::ccp(0x10, RegId::ZERO, RegId::ZERO, 0x11),
+        op::ret(RegId::ONE),
+    ]);
+
+    let (reason, context) = panic_of(script);
+
+    assert_eq!(reason, PanicReason::MemoryOverflow);
+    assert_eq!(
+        context,
+        PanicContext::MemoryRange {
+            start: VM_MAX_RAM - 4,
+            len: 16,
+        }
+    );
+}
+
+#[test]
+fn panic_context__state_read_reports_the_destination_range() {
+    let mut code = set_full_word(0x10, VM_MAX_RAM - 16);
+    code.extend([
+        op::srwq(0x10, 0x11, RegId::ZERO, RegId::ONE),
+        op::ret(RegId::ONE),
+    ]);
+
+    let (reason, context) = contract_panic_of(code);
+
+    assert_eq!(reason, PanicReason::MemoryOverflow);
+    assert_eq!(
+        context,
+        PanicContext::MemoryRange {
+            start: VM_MAX_RAM - 16,
+            len: 32,
+        }
+    );
+}
+
+#[test]
+fn panic_context__memory_ownership_reports_the_written_range() {
+    let script = vec![
+        op::movi(0x10, 64),
+        op::sw(0x10, RegId::ONE, 1),
+        op::ret(RegId::ONE),
+    ];
+
+    let (reason, context) = panic_of(script);
+
+    assert_eq!(reason, PanicReason::MemoryOwnership);
+    assert_eq!(context, PanicContext::MemoryRange { start: 72, len: 8 });
+}
+
+#[test]
+fn panic_context__not_enough_balance_reports_the_asset_and_amount() {
+    let script = vec![
+        op::movi(0x10, 200_000),
+        op::smo(RegId::ZERO, RegId::ZERO, RegId::ZERO, 0x10),
+        op::ret(RegId::ONE),
+    ];
+
+    let (reason, context) = panic_of(script);
+
+    assert_eq!(reason, PanicReason::NotEnoughBalance);
+    assert_eq!(
+        context,
+        PanicContext::Balance {
+            asset_id: AssetId::BASE,
+            amount: 200_000,
+        }
+    );
+}
+
+#[test]
+fn panic_context__arithmetic_overflow_reports_the_operands() {
+    let script = vec![
+        op::not(0x10, RegId::ZERO),
+        op::addi(0x11, 0x10, 2),
+        op::ret(RegId::ONE),
+    ];
+
+    let (reason, context) = panic_of(script);
+
+    assert_eq!(reason, PanicReason::ArithmeticOverflow);
+    assert_eq!(
+        context,
+        PanicContext::Arithmetic {
+            lhs: Word::MAX,
+            rhs: 2,
+        }
+    );
+}
+
+#[test]
+fn panic_context__is_none_for_panics_without_context() {
+    let script = vec![
+        op::movi(0x10, 1),
+        op::rvrt(RegId::ZERO),
+        op::ret(RegId::ONE),
+    ];
+    let receipts = run_script(script);
+    assert!(receipts
+        .iter()
+        .all(|receipt| receipt.panic_context().is_none()));
+
+    let script = vec![op::div(0x10, RegId::ONE, RegId::ZERO), op::ret(RegId::ONE)];
+    let (reason, context) = panic_of(script);
+
+    assert_eq!(reason, PanicReason::ArithmeticError);
+    assert_eq!(context, PanicContext::None);
+}
+
+#[test]
+fn panic_context__is_none_for_instructions_without_context() {
+    // `LDC` with an out of bounds contract id
+    let mut script = set_full_word(0x10, VM_MAX_RAM);
+    script.extend([op::ldc(0x10, RegId::ZERO, RegId::ONE), op::ret(RegId::ONE)]);
+    let (reason, context) = panic_of(script);
+    assert_eq!(reason, PanicReason::MemoryOverflow);
+    assert_eq!(context, PanicContext::None);
+
+    // Wide integer instructions with an out of bounds destination
+    let mut script = set_full_word(0x10, VM_MAX_RAM - 8);
+    script.extend([
+        op::wqam(0x10, RegId::ZERO, RegId::ZERO, RegId::ZERO),
+        op::ret(RegId::ONE),
+    ]);
+    let (reason, context) = panic_of(script);
+    assert_eq!(reason, PanicReason::MemoryOverflow);
+    assert_eq!(context, PanicContext::None);
+
+    // ECAL handlers
+    let script = vec![
+        op::ecal(RegId::ZERO, RegId::ZERO, RegId::ZERO, RegId::ZERO),
+        op::ret(RegId::ONE),
+    ];
+    let (_, context) = panic_of(script);
+    assert_eq!(context, PanicContext::None);
+}
@@ -5,7 +5,11 @@ use fuel_asm::{
     RegId,
 };
 use fuel_tx::TransactionBuilder;
-use fuel_vm::prelude::*;
+use fuel_vm::{
+    consts::WORD_SIZE,
+    interpreter::InterpreterParams,
+    prelude::*,
+};
 use rand::{
     rngs::StdRng,
     Rng,
@@ -100,3 +104,108 @@ fn profile_gas() {
     assert!(items0[0] == items1[0] && items0[0] == items2[0]);
     assert!(items0[1] == items1[1] && items0[1] == items2[1]);
 }
+
+#[test]
+fn profile_gas_per_source_location() {
+    let line1 = SourceLocation::new("main.sw", 1);
+    let line2 = SourceLocation::new("main.sw", 2);
+
+    let mut debug_info = DebugInfo::new();
+    debug_info.set_script(
+        [(0, line1.clone()), (8, line2.clone())]
+            .into_iter()
+            .collect(),
+    );
+
+    let mut gas = GasProfilingData::default();
+    gas.add(InstructionLocation::new(None, 0), 1);
+    gas.add(InstructionLocation::new(None, 4), 2);
+    gas.add(InstructionLocation::new(None, 8), 3);
+    gas.add(InstructionLocation::new(None, 12), 4);
+    gas.add(
+        InstructionLocation::new(Some(ContractId::from([1; 32])), 0),
+        5,
+    );
+
+    let per_source = gas.per_source_location(&debug_info);
+    assert_eq!(per_source.len(), 3);
+    assert_eq!(per_source[&Some(&line1)], 3);
+    assert_eq!(per_source[&Some(&line2)], 7);
+    assert_eq!(per_source[&None], 5);
+}
+
+#[test]
+fn profile_gas_per_call_stack() {
+    let mut test_context = TestBuilder::new(2322u64);
+
+    let contract_id = test_context
+        .setup_contract(
+            vec![op::noop(), op::noop(), op::ret(RegId::ONE)],
+            None,
+            None,
+        )
+        .contract_id;
+
+    let script = vec![
+        op::gtf_args(0x10, 0x00, GTFArgs::ScriptData),
+        op::call(0x10, RegId::ZERO, RegId::ZERO, RegId::CGAS),
+        op::ret(RegId::ONE),
+    ];
+    let script_data = contract_id
+        .iter()
+        .copied()
+        .chain([0u8; WORD_SIZE * 2])
+        .collect();
+
+    let tx = test_context
+        .start_script(script, script_data)
+        .script_gas_limit(1_000_000)
+        .contract_input(contract_id)
+        .fee_input()
+        .contract_output(&contract_id)
+        .build()
+        .into_ready(
+            0,
+            test_context.get_gas_costs(),
+            test_context.get_fee_params(),
+        )
+        .expect("failed to finalize tx");
+
+    let output = FlamegraphReceiver::new();
+    let mut vm = Interpreter::<_, _, _>::with_storage(
+        MemoryInstance::new(),
+        test_context.get_storage().clone(),
+        InterpreterParams::new(0, &ConsensusParameters::standard()),
+    );
+    vm.with_profiler(output.clone());
+    let state = vm.transact(tx).expect("failed to execute script");
+    assert_eq!(state.state(), &ProgramState::Return(1));
+
+    let data = output.data();
+    let call_site = InstructionLocation::new(None, 4);
+    let callee = |offset| InstructionLocation::new(Some(contract_id), offset);
+
+    let stacks: std::collections::HashMap<_, _> = data.stacks().collect();
+    assert_eq!(stacks.len(), 6);
+    assert!(stacks.contains_key([InstructionLocation::new(None, 0)].as_slice()));
+    assert!(stacks.contains_key([call_site].as_slice()));
+    for offset in [0, 4, 8] {
+        assert!(stacks.contains_key([call_site, callee(offset)].as_slice()));
+    }
+    let per_stack: u64 = stacks.values().sum();
+    assert_eq!(per_stack, data.values().sum::<u64>());
+
+    let mut folded = Vec::new();
+    output.write_folded(&mut folded).unwrap();
+    let folded = String::from_utf8(folded).unwrap();
+    let nested = format!("script:4;{contract_id}:8 ");
+    assert!(
+        folded.lines().any(|line| line.starts_with(&nested)),
+        "missing {nested:?} in {folded}"
+    );
+
+    let mut pprof = Vec::new();
+    output.write_pprof(&mut pprof).unwrap();
+    // The profile starts with the `gas` sample type.
+    assert_eq!(pprof[..6], [0x0a, 0x04, 0x08, 0x01, 0x10, 0x01]);
+}
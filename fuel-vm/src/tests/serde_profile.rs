@@ -19,8 +19,17 @@ fn test_profile_serde() {
         gas.add(InstructionLocation::new(None, 1), 4);
         gas.add(InstructionLocation::new(None, 1), 4);
         gas.add(InstructionLocation::new(None, 2), 2);
+        gas.add_stack(
+            &[
+                InstructionLocation::new(None, 1),
+                InstructionLocation::new(Some(Default::default()), 2),
+            ],
+            2,
+        );
     }
 
     let json = serde_json::to_vec(&data).expect("Serialization failed");
-    let _: ProfilingData = serde_json::from_slice(&json).expect("Deserialization failed");
+    let data: ProfilingData =
+        serde_json::from_slice(&json).expect("Deserialization failed");
+    assert_eq!(data.gas().stacks().count(), 3);
 }
@@ -8,9 +8,19 @@ use fuel_asm::{
     Instruction,
 };
 use fuel_crypto::SecretKey;
-use fuel_tx::ConsensusParameters;
+use fuel_tx::{
+    ConsensusParameters,
+    Contract,
+};
 use fuel_vm::prelude::*;
 
+use crate::{
+    checked_transaction::Checked,
+    consts::WORD_SIZE,
+    prelude::TestBuilder,
+    storage::ContractsStateData,
+};
+
 /// Set a register `r` to a Word-sized number value using left-shifts
 pub fn set_full_word(r: RegisterId, v: Word) -> Vec<Instruction> {
     let r = u8::try_from(r).unwrap();
@@ -87,3 +97,72 @@ pub fn assert_panics(receipts: &[Receipt], reason: PanicReason) {
         unreachable!("No script receipt for a paniced tx");
     }
 }
+
+/// A contract incrementing the counter stored in the slot 0 and logging it.
+pub fn counter_program() -> Vec<Instruction> {
+    vec![
+        op::move_(0x12, RegId::SSP),
+        op::cfei(32),
+        op::srw(0x10, 0x11, 0x12),
+        op::addi(0x10, 0x10, 1),
+        op::sww(0x12, 0x11, 0x10),
+        op::log(0x10, RegId::ZERO, RegId::ZERO, RegId::ZERO),
+        op::ret(RegId::ONE),
+    ]
+}
+
+/// The [`counter_program`] as a contract.
+pub fn counter() -> Contract {
+    let program: Vec<u8> = counter_program().into_iter().collect();
+    program.into()
+}
+
+/// The id of the [`counter`] in the storages built by the tests.
+pub fn contract_id() -> ContractId {
+    ContractId::from([7; 32])
+}
+
+/// The value of the counter slot holding `counter`.
+pub fn counter_value(counter: Word) -> ContractsStateData {
+    let mut value = [0; 32];
+    value[..WORD_SIZE].copy_from_slice(&counter.to_be_bytes());
+    value.to_vec().into()
+}
+
+/// Instructions calling `contract_id` without coins.
+pub fn call_script(contract_id: ContractId) -> Vec<Instruction> {
+    const PARAMS_LEN: usize = ContractId::LEN + WORD_SIZE * 2;
+
+    // The call parameters are followed by the zeroed asset id sent with the call.
+    let mut script = vec![
+        op::movi(
+            0x10,
+            Immediate18::try_from(PARAMS_LEN + AssetId::LEN).unwrap(),
+        ),
+        op::aloc(0x10),
+        op::addi(0x11, RegId::HP, Immediate12::try_from(PARAMS_LEN).unwrap()),
+    ];
+    for (i, b) in contract_id.iter().enumerate() {
+        script.push(op::movi(0x10, Immediate18::from(*b)));
+        script.push(op::sb(RegId::HP, 0x10, Immediate12::try_from(i).unwrap()));
+    }
+    script.push(op::call(RegId::HP, RegId::ZERO, 0x11, RegId::CGAS));
+    script
+}
+
+/// A script calling the [`counter`] at [`contract_id`], followed by `then`.
+pub fn call_counter<I>(then: I) -> Checked<Script>
+where
+    I: IntoIterator<Item = Instruction>,
+{
+    let mut script = call_script(contract_id());
+    script.extend(then);
+
+    TestBuilder::new(2322u64)
+        .start_script(script, vec![])
+        .script_gas_limit(1_000_000)
+        .contract_input(contract_id())
+        .fee_input()
+        .contract_output(&contract_id())
+        .build()
+}
@@ -0,0 +1,504 @@
+//! Execution tracing.
+//!
+//! A [`TraceReceiver`] installed with [`Interpreter::with_tracer`] is notified before
+//! and after every executed instruction. After the instruction, it receives a
+//! [`TraceStep`] with the registers and the memory regions changed by the instruction,
+//! and the gas charged for it. Instructions that fail to decode are not traced, as
+//! they are never executed.
+//!
+//! The traces can be written with the [`JsonLinesSink`], which writes one JSON object
+//! per instruction, or the more compact [`BinarySink`], whose records can be read back
+//! with [`TraceStep::read_binary`]. Both make it easy to compare the execution of the
+//! same transaction by different versions of the VM.
+//!
+//! Tracing compares the registers and the whole memory of the VM after every
+//! instruction, and so has a considerable overhead.
+//!
+//! [`Interpreter::with_tracer`]: crate::interpreter::Interpreter::with_tracer
+
+use alloc::{
+    boxed::Box,
+    string::String,
+    sync::Arc,
+    vec::Vec,
+};
+use core::fmt;
+use std::{
+    io::{
+        self,
+        Read,
+        Write,
+    },
+    sync::{
+        Mutex,
+        PoisonError,
+    },
+};
+
+use dyn_clone::DynClone;
+use fuel_asm::{
+    Instruction,
+    RawInstruction,
+    RegId,
+};
+use fuel_types::Word;
+use serde::Serialize;
+
+use crate::{
+    consts::VM_REGISTER_COUNT,
+    error::InterpreterError,
+    interpreter::{
+        diff::memory_writes,
+        MemoryInstance,
+    },
+    state::ProgramState,
+};
+
+/// Used to receive the execution trace from the interpreter
+pub trait TraceReceiver: DynClone {
+    /// Called before an instruction is executed
+    fn before_instruction(&mut self, _pc: Word, _instruction: &Instruction) {}
+
+    /// Called after an instruction is executed, including instructions that panicked
+    fn after_instruction(&mut self, step: &TraceStep);
+
+    /// Called after a transaction has completed
+    fn on_transaction(
+        &mut self,
+        _state: Result<&ProgramState, InterpreterError<String>>,
+    ) {
+    }
+}
+
+dyn_clone::clone_trait_object!(TraceReceiver);
+
+/// The effects of an executed instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    /// Value of `$pc` before the instruction.
+    pub pc: Word,
+    /// The executed instruction.
+    pub instruction: Instruction,
+    /// Registers changed by the instruction, with their new values.
+    pub registers: Vec<(RegId, Word)>,
+    /// Memory regions changed by the instruction.
+    pub memory: Vec<MemoryWrite>,
+    /// Gas charged for the instruction.
+    pub gas: Word,
+}
+
+/// A memory region changed by an instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryWrite {
+    /// Start of the region.
+    pub address: Word,
+    /// New content of the region.
+    pub bytes: Vec<u8>,
+}
+
+impl TraceStep {
+    /// Write the step in the binary format of the [`BinarySink`].
+    ///
+    /// All integers are big-endian. A step is encoded as `$pc` (8 bytes), the raw
+    /// instruction (4 bytes) and the charged gas (8 bytes), followed by the number of
+    /// changed registers (1 byte) with the id (1 byte) and the value (8 bytes) of each,
+    /// and the number of changed memory regions (4 bytes) with the address (8 bytes),
+    /// the length (4 bytes) and the content of each.
+    pub fn write_binary<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        let raw: RawInstruction = self.instruction.into();
+        writer.write_all(&self.pc.to_be_bytes())?;
+        writer.write_all(&raw.to_be_bytes())?;
+        writer.write_all(&self.gas.to_be_bytes())?;
+
+        let registers = u8::try_from(self.registers.len()).map_err(invalid_input)?;
+        writer.write_all(&[registers])?;
+        for (register, value) in &self.registers {
+            writer.write_all(&[register.to_u8()])?;
+            writer.write_all(&value.to_be_bytes())?;
+        }
+
+        let regions = u32::try_from(self.memory.len()).map_err(invalid_input)?;
+        writer.write_all(&regions.to_be_bytes())?;
+        for region in &self.memory {
+            let len = u32::try_from(region.bytes.len()).map_err(invalid_input)?;
+            writer.write_all(&region.address.to_be_bytes())?;
+            writer.write_all(&len.to_be_bytes())?;
+            writer.write_all(&region.bytes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read a step written by [`TraceStep::write_binary`]. Returns `None` if the
+    /// reader is at its end.
+    pub fn read_binary<R: Read + ?Sized>(reader: &mut R) -> io::Result<Option<Self>> {
+        let mut pc = [0u8; 8];
+        match reader.read_exact(&mut pc) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let pc = Word::from_be_bytes(pc);
+
+        let raw = RawInstruction::from_be_bytes(read_array(reader)?);
+        let instruction = Instruction::try_from(raw).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidData, "invalid instruction")
+        })?;
+        let gas = Word::from_be_bytes(read_array(reader)?);
+
+        let [registers] = read_array(reader)?;
+        let registers = (0..registers)
+            .map(|_| {
+                let [register] = read_array(reader)?;
+                let value = Word::from_be_bytes(read_array(reader)?);
+                Ok((RegId::new(register), value))
+            })
+            .collect::<io::Result<_>>()?;
+
+        let regions = u32::from_be_bytes(read_array(reader)?);
+        let memory = (0..regions)
+            .map(|_| {
+                let address = Word::from_be_bytes(read_array(reader)?);
+                let len = u32::from_be_bytes(read_array(reader)?);
+                let mut bytes = Vec::new();
+                reader.take(len.into()).read_to_end(&mut bytes)?;
+                if bytes.len() != len as usize {
+                    return Err(io::ErrorKind::UnexpectedEof.into())
+                }
+                Ok(MemoryWrite { address, bytes })
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(Some(Self {
+            pc,
+            instruction,
+            registers,
+            memory,
+            gas,
+        }))
+    }
+}
+
+fn read_array<R: Read + ?Sized, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid_input<E>(_: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "trace step is too large")
+}
+
+/// The writer of a sink, shared by its clones.
+struct SharedWriter<W> {
+    inner: Arc<Mutex<WriterState<W>>>,
+}
+
+struct WriterState<W> {
+    writer: W,
+    /// The first error returned by the writer, after which nothing is written.
+    error: Option<io::Error>,
+}
+
+impl<W> Clone for SharedWriter<W> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<W: Write> SharedWriter<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(WriterState {
+                writer,
+                error: None,
+            })),
+        }
+    }
+
+    fn write(&self, f: impl FnOnce(&mut W) -> io::Result<()>) {
+        let mut state = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.error.is_none() {
+            state.error = f(&mut state.writer).err();
+        }
+    }
+
+    fn with_writer<R>(&self, f: impl FnOnce(&mut W) -> R) -> R {
+        f(&mut self
+            .inner
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .writer)
+    }
+
+    fn take_error(&self) -> Option<io::Error> {
+        self.inner
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .error
+            .take()
+    }
+}
+
+/// Writes the trace as JSON lines, one object per executed instruction:
+///
+/// ```text
+/// {"pc":12,"instruction":"add r18 r16 r17","gas":2,"registers":[["$pc",16],["r18",3]],"memory":[]}
+/// ```
+///
+/// Memory regions are written as `{"address":...,"data":"<hex>"}` objects. The sink
+/// can be cloned, and all the clones write to the same writer.
+pub struct JsonLinesSink<W> {
+    writer: SharedWriter<W>,
+}
+
+#[derive(Serialize)]
+struct JsonStep {
+    pc: Word,
+    instruction: String,
+    gas: Word,
+    registers: Vec<(String, Word)>,
+    memory: Vec<JsonMemoryWrite>,
+}
+
+#[derive(Serialize)]
+struct JsonMemoryWrite {
+    address: Word,
+    data: String,
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    /// Create a sink writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: SharedWriter::new(writer),
+        }
+    }
+
+    /// Access the underlying writer.
+    pub fn with_writer<R>(&self, f: impl FnOnce(&mut W) -> R) -> R {
+        self.writer.with_writer(f)
+    }
+
+    /// Take the first error returned by the writer. Nothing is written after an
+    /// error until it is taken.
+    pub fn take_error(&self) -> Option<io::Error> {
+        self.writer.take_error()
+    }
+}
+
+impl<W> Clone for JsonLinesSink<W> {
+    fn clone(&self) -> Self {
+        Self {
+            writer: self.writer.clone(),
+        }
+    }
+}
+
+impl<W: Write> TraceReceiver for JsonLinesSink<W> {
+    fn after_instruction(&mut self, step: &TraceStep) {
+        use core::fmt::Write as _;
+
+        let step = JsonStep {
+            pc: step.pc,
+            instruction: step.instruction.to_string(),
+            gas: step.gas,
+            registers: step
+                .registers
+                .iter()
+                .map(|(register, value)| (register.to_string(), *value))
+                .collect(),
+            memory: step
+                .memory
+                .iter()
+                .map(|region| JsonMemoryWrite {
+                    address: region.address,
+                    data: region.bytes.iter().fold(String::new(), |mut data, b| {
+                        let _ = write!(data, "{b:02x}");
+                        data
+                    }),
+                })
+                .collect(),
+        };
+
+        self.writer.write(|writer| {
+            serde_json::to_writer(&mut *writer, &step)?;
+            writer.write_all(b"\n")
+        });
+    }
+
+    fn on_transaction(
+        &mut self,
+        _state: Result<&ProgramState, InterpreterError<String>>,
+    ) {
+        self.writer.write(|writer| writer.flush());
+    }
+}
+
+/// Writes the trace in a compact binary format, see [`TraceStep::write_binary`]. The
+/// sink can be cloned, and all the clones write to the same writer.
+pub struct BinarySink<W> {
+    writer: SharedWriter<W>,
+}
+
+impl<W: Write> BinarySink<W> {
+    /// Create a sink writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: SharedWriter::new(writer),
+        }
+    }
+
+    /// Access the underlying writer.
+    pub fn with_writer<R>(&self, f: impl FnOnce(&mut W) -> R) -> R {
+        self.writer.with_writer(f)
+    }
+
+    /// Take the first error returned by the writer. Nothing is written after an
+    /// error until it is taken.
+    pub fn take_error(&self) -> Option<io::Error> {
+        self.writer.take_error()
+    }
+}
+
+impl<W> Clone for BinarySink<W> {
+    fn clone(&self) -> Self {
+        Self {
+            writer: self.writer.clone(),
+        }
+    }
+}
+
+impl<W: Write> TraceReceiver for BinarySink<W> {
+    fn after_instruction(&mut self, step: &TraceStep) {
+        self.writer.write(|writer| step.write_binary(writer));
+    }
+
+    fn on_transaction(
+        &mut self,
+        _state: Result<&ProgramState, InterpreterError<String>>,
+    ) {
+        self.writer.write(|writer| writer.flush());
+    }
+}
+
+/// Tracer
+#[derive(Default, Clone)]
+pub struct Tracer {
+    /// Receiver of the trace
+    receiver: Option<Box<dyn TraceReceiver + Send + Sync>>,
+    /// Memory of the VM after the last traced instruction
+    memory: Option<MemoryInstance>,
+    /// The instruction being executed
+    pending: Option<PendingStep>,
+}
+
+/// The state before the instruction being executed.
+#[derive(Clone)]
+struct PendingStep {
+    pc: Word,
+    instruction: Instruction,
+    registers: [Word; VM_REGISTER_COUNT],
+}
+
+impl Tracer {
+    /// Sets the trace receiver
+    pub fn set_receiver(&mut self, receiver: Box<dyn TraceReceiver + Send + Sync>) {
+        self.receiver = Some(receiver);
+        self.memory = None;
+        self.pending = None;
+    }
+
+    /// Called by the VM before an instruction
+    pub(crate) fn before_instruction(
+        &mut self,
+        registers: &[Word; VM_REGISTER_COUNT],
+        memory: &MemoryInstance,
+        raw: RawInstruction,
+    ) {
+        let Some(receiver) = &mut self.receiver else {
+            return
+        };
+        let Ok(instruction) = Instruction::try_from(raw) else {
+            return
+        };
+
+        let pc = registers[RegId::PC];
+        receiver.before_instruction(pc, &instruction);
+        if self.memory.is_none() {
+            self.memory = Some(memory.clone());
+        }
+        self.pending = Some(PendingStep {
+            pc,
+            instruction,
+            registers: *registers,
+        });
+    }
+
+    /// Called by the VM after an instruction, send its effects to the receiver
+    pub(crate) fn after_instruction(
+        &mut self,
+        registers: &[Word; VM_REGISTER_COUNT],
+        memory: &MemoryInstance,
+    ) {
+        let (Some(receiver), Some(pending), Some(snapshot)) =
+            (&mut self.receiver, self.pending.take(), &mut self.memory)
+        else {
+            return
+        };
+
+        let step = TraceStep {
+            pc: pending.pc,
+            instruction: pending.instruction,
+            registers: pending
+                .registers
+                .iter()
+                .zip(registers)
+                .enumerate()
+                .filter(|(_, (before, after))| before != after)
+                .filter_map(|(i, (_, after))| {
+                    Some((RegId::new(i.try_into().ok()?), *after))
+                })
+                .collect(),
+            memory: memory_writes(snapshot, memory)
+                .into_iter()
+                .map(|(address, bytes)| MemoryWrite {
+                    address: address as Word,
+                    bytes,
+                })
+                .collect(),
+            gas: pending.registers[RegId::GGAS].saturating_sub(registers[RegId::GGAS]),
+        };
+        receiver.after_instruction(&step);
+    }
+
+    /// Called by the VM after a transaction
+    pub fn on_transaction(
+        &mut self,
+        state_result: Result<&ProgramState, InterpreterError<String>>,
+    ) {
+        self.memory = None;
+        self.pending = None;
+        if let Some(r) = &mut self.receiver {
+            r.on_transaction(state_result);
+        }
+    }
+}
+
+impl fmt::Debug for Tracer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Tracer(receiver={})",
+            match self.receiver {
+                Some(_) => "enabled",
+                None => "disabled",
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,179 @@
+#![allow(non_snake_case)]
+
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+use fuel_asm::op;
+use fuel_tx::{
+    ConsensusParameters,
+    Finalizable,
+    Script,
+    TransactionBuilder,
+};
+
+use super::*;
+use crate::{
+    checked_transaction::{
+        IntoChecked,
+        Ready,
+    },
+    interpreter::{
+        Interpreter,
+        NotSupportedEcal,
+    },
+    storage::MemoryStorage,
+};
+
+type Vm = Interpreter<MemoryInstance, MemoryStorage, Script, NotSupportedEcal>;
+
+#[derive(Clone, Default)]
+struct Collector {
+    started: Arc<Mutex<Vec<(Word, Instruction)>>>,
+    steps: Arc<Mutex<Vec<TraceStep>>>,
+}
+
+impl TraceReceiver for Collector {
+    fn before_instruction(&mut self, pc: Word, instruction: &Instruction) {
+        self.started.lock().unwrap().push((pc, *instruction));
+    }
+
+    fn after_instruction(&mut self, step: &TraceStep) {
+        self.steps.lock().unwrap().push(step.clone());
+    }
+}
+
+fn script_tx() -> Ready<Script> {
+    let script = [
+        op::movi(0x10, 0x2a),
+        op::cfei(8),
+        op::sw(RegId::SSP, 0x10, 0),
+        op::ret(RegId::ONE),
+    ]
+    .into_iter()
+    .collect();
+
+    let consensus_params = ConsensusParameters::standard();
+    TransactionBuilder::script(script, vec![])
+        .script_gas_limit(1_000_000)
+        .add_random_fee_input()
+        .finalize()
+        .into_checked(Default::default(), &consensus_params)
+        .expect("failed to generate checked tx")
+        .into_ready(
+            0,
+            consensus_params.gas_costs(),
+            consensus_params.fee_params(),
+        )
+        .expect("failed to finalize tx")
+}
+
+fn trace<T>(receiver: T) -> Vm
+where
+    T: TraceReceiver + Send + Sync + 'static,
+{
+    let mut vm = Vm::with_memory_storage();
+    vm.with_tracer(receiver);
+    let state = vm.transact(script_tx()).expect("failed to execute script");
+    assert_eq!(state.state(), &ProgramState::Return(1));
+    vm
+}
+
+#[test]
+fn after_instruction__reports_changed_registers_memory_and_gas() {
+    let collector = Collector::default();
+    let vm = trace(collector.clone());
+
+    let steps = collector.steps.lock().unwrap().clone();
+    let started = collector.started.lock().unwrap().clone();
+    assert_eq!(steps.len(), 4);
+    assert_eq!(
+        started,
+        steps
+            .iter()
+            .map(|step| (step.pc, step.instruction))
+            .collect::<Vec<_>>()
+    );
+
+    let is = vm.registers()[RegId::IS];
+    assert_eq!(steps[0].pc, is);
+    assert_eq!(steps[0].instruction, op::movi(0x10, 0x2a));
+    let changed = steps[0]
+        .registers
+        .iter()
+        .map(|(register, _)| *register)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changed,
+        vec![RegId::PC, RegId::GGAS, RegId::CGAS, RegId::new(0x10)]
+    );
+    assert!(steps[0].registers.contains(&(RegId::PC, is + 4)));
+    assert!(steps[0].registers.contains(&(RegId::new(0x10), 0x2a)));
+    assert!(steps[0].memory.is_empty());
+
+    let ggas = |step: &TraceStep| {
+        step.registers
+            .iter()
+            .find(|(register, _)| *register == RegId::GGAS)
+            .map(|(_, value)| *value)
+            .unwrap()
+    };
+    assert!(steps[0].gas > 0);
+    assert_eq!(ggas(&steps[1]), ggas(&steps[0]) - steps[1].gas);
+
+    let ssp = vm.registers()[RegId::SSP];
+    assert_eq!(steps[2].instruction, op::sw(RegId::SSP, 0x10, 0));
+    assert_eq!(
+        steps[2].memory,
+        vec![MemoryWrite {
+            address: ssp + 7,
+            bytes: vec![0x2a],
+        }]
+    );
+}
+
+#[test]
+fn json_lines_sink__writes_one_object_per_instruction() {
+    let sink = JsonLinesSink::new(Vec::new());
+    trace(sink.clone());
+    assert!(sink.take_error().is_none());
+
+    let output = sink.with_writer(|output| String::from_utf8(output.clone()).unwrap());
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+
+    let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(first["instruction"], "movi r16 42");
+    assert_eq!(first["registers"][3], serde_json::json!(["r16", 42]));
+    assert_eq!(first["memory"], serde_json::json!([]));
+
+    let store: serde_json::Value = serde_json::from_str(lines[2]).unwrap();
+    assert_eq!(store["instruction"], "sw $ssp r16 0");
+    assert_eq!(store["memory"][0]["data"], "2a");
+}
+
+#[test]
+fn binary_sink__steps_can_be_read_back() {
+    let collector = Collector::default();
+    trace(collector.clone());
+
+    let sink = BinarySink::new(Vec::new());
+    trace(sink.clone());
+    assert!(sink.take_error().is_none());
+
+    let output = sink.with_writer(|output| output.clone());
+    let mut reader = output.as_slice();
+    let mut steps = Vec::new();
+    while let Some(step) = TraceStep::read_binary(&mut reader).unwrap() {
+        steps.push(step);
+    }
+    assert_eq!(steps, *collector.steps.lock().unwrap());
+
+    let truncated = &output[..output.len() - 1];
+    let mut reader = truncated;
+    let error = core::iter::from_fn(|| TraceStep::read_binary(&mut reader).transpose())
+        .find_map(Result::err)
+        .expect("truncated trace must fail to read");
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
@@ -22,7 +22,14 @@ use crate::{
         StateTransition,
         StateTransitionRef,
     },
-    storage::InterpreterStorage,
+    storage::{
+        AccessList,
+        AccessRecorder,
+        ExecutionWitness,
+        InterpreterStorage,
+        WitnessRecorder,
+        WitnessStorage,
+    },
 };
 use fuel_tx::{
     Create,
@@ -199,6 +206,87 @@ where
     }
 }
 
+impl<M, S, Tx, Ecal> Transactor<M, AccessRecorder<S>, Tx, Ecal>
+where
+    S: InterpreterStorage,
+{
+    /// The storage entries accessed since the last call to
+    /// [`Self::take_access_list`].
+    pub fn access_list(&self) -> AccessList {
+        self.interpreter.as_ref().access_list()
+    }
+
+    /// Return the storage entries accessed since the last call and start a new
+    /// recording.
+    ///
+    /// Called after [`Self::transact`], it returns the read and write sets of the
+    /// transaction.
+    pub fn take_access_list(&mut self) -> AccessList {
+        self.interpreter.as_mut().take_access_list()
+    }
+}
+
+impl<M, S, Tx, Ecal> Transactor<M, WitnessRecorder<S>, Tx, Ecal>
+where
+    S: InterpreterStorage,
+{
+    /// The witness of the storage values read since the last call to
+    /// [`Self::take_witness`].
+    pub fn witness(&self) -> Result<ExecutionWitness, S::DataError> {
+        self.interpreter
+            .as_ref()
+            .witness(self.interpreter.interpreter_params())
+    }
+
+    /// Return the witness of the storage values read since the last call and start a
+    /// new recording.
+    ///
+    /// Called after [`Self::transact`], it returns the witness needed to replay the
+    /// transaction with [`Transactor::from_witness`].
+    pub fn take_witness(&mut self) -> Result<ExecutionWitness, S::DataError> {
+        let interpreter_params = self.interpreter.interpreter_params().clone();
+        self.interpreter.as_mut().take_witness(&interpreter_params)
+    }
+}
+
+impl<M, Tx, Ecal> Transactor<M, WitnessStorage, Tx, Ecal>
+where
+    Tx: ExecutableTransaction,
+    Ecal: EcalHandler + Default,
+{
+    /// Replay an execution with the storage values and the interpreter parameters
+    /// recorded in the `witness`.
+    pub fn from_witness(memory: M, witness: ExecutionWitness) -> Self {
+        let interpreter_params = witness.interpreter_params.clone();
+        Self::new(memory, witness.into(), interpreter_params)
+    }
+}
+
+#[cfg(feature = "replay")]
+impl<M, S, Ecal> Transactor<M, WitnessRecorder<S>, Script, Ecal>
+where
+    M: Memory,
+    S: InterpreterStorage,
+    Ecal: EcalHandler,
+{
+    /// Execute a script and record a [`ReplayFile`](crate::replay::ReplayFile) of the
+    /// execution.
+    ///
+    /// The witness recorded before the call is discarded, so the replay file only
+    /// holds the storage values read by this transaction.
+    pub fn record_replay(
+        &mut self,
+        tx: Checked<Script>,
+    ) -> Result<crate::replay::ReplayFile, S::DataError> {
+        use fuel_types::canonical::Serialize;
+
+        let transaction = fuel_tx::Transaction::from(tx.transaction().clone()).to_bytes();
+        self.take_witness()?;
+        self.transact(tx);
+        crate::replay::ReplayFile::record(self, transaction)
+    }
+}
+
 impl<M, S, Tx, Ecal> Transactor<M, S, Tx, Ecal>
 where
     S: InterpreterStorage,